@@ -1,27 +1,89 @@
 //! Tauri 命令模块
 //! 定义前端可调用的所有 IPC 命令
 
-use crate::config::{AppConfig, Hotkey, LLMConfig};
-use crate::database::{HistoryResult, PerformanceStats};
-use crate::hotkey::HotkeyManager;
-use crate::llm::LLMClient;
-use crate::state::AppState;
+use crate::config::{
+    archive_legacy_config, known_language_code, legacy_config_candidates, load_legacy_config,
+    mask_api_key, AppConfig, ExcludedApp, Hotkey, HotkeyConfig, LLMConfig, LogLevel,
+    NamedLLMConfig, ProfileList, PromptValidation, ValidationIssue,
+};
+use crate::database::{
+    retention_cutoff, AdjacentIds, BackupInfo, CostStats, DailySeries, DbInfo, HistoryCursor,
+    HistoryFacets, HistoryFilter, HistoryPage, HistoryResult, ImportSummary, MetricRecord,
+    ModelStats, PerformanceStats, RecentTranslation, TranslationMetadata, TranslationRecord,
+};
+use crate::error::{CommandError, ErrorCode};
+use crate::error_log::ErrorLogEntry;
+use crate::hotkey::{ConflictReport, HotkeyConflict, HotkeyIssue, HotkeyManager};
+use crate::key_listener::{CaptureEvent, KeyListener, ListenerStatus};
+use crate::languages::{search_languages, LanguageInfo};
+use crate::llm::{
+    build_messages, estimate_translation, with_prompt_override, ConnectionReport, LLMClient,
+    Message, TokenEstimate,
+};
+use crate::login_item;
+use crate::redaction::{self, Masked, Screening};
+use crate::state::{AppState, LastTranslation};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tauri::Emitter;
 use tauri::State;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
+
+/// 热键录制超时时间（秒）
+const HOTKEY_CAPTURE_TIMEOUT_SECS: u64 = 10;
+
+/// 历史记录每页最多条数
+const MAX_HISTORY_PAGE_SIZE: i64 = 500;
+
+/// 每日统计最多查询的天数，与原始指标的保留天数一致
+const MAX_DAILY_STATS_DAYS: u32 = 90;
 
 /// 获取应用配置
+///
+/// API Key 以掩码返回，通过 `set_api_key` 修改。
 #[tauri::command]
-pub async fn get_config(state: State<'_, Arc<AppState>>) -> Result<AppConfig, String> {
+pub async fn get_config(state: State<'_, Arc<AppState>>) -> Result<AppConfig, CommandError> {
     debug!("Getting config");
-    Ok(state.get_config().await)
+    let mut config = state.get_config().await.with_masked_api_key();
+    // 用户可能在系统设置中修改了登录项，以系统的实际状态为准
+    match login_item::is_enabled() {
+        Ok(enabled) => {
+            if enabled != config.launch_at_login {
+                debug!("Launch at login is {} in system settings", enabled);
+            }
+            config.launch_at_login = enabled;
+        }
+        Err(e) => debug!("Failed to get launch at login status: {}", e),
+    }
+    Ok(config)
+}
+
+/// 修改当前方案的 API Key，有系统凭据存储时保存在凭据存储中，返回新 API Key 的掩码
+#[tauri::command]
+pub async fn set_api_key(
+    api_key: String,
+    state: State<'_, Arc<AppState>>,
+    app: tauri::AppHandle,
+) -> Result<String, CommandError> {
+    info!("Updating API key");
+    let api_key = api_key.trim().to_string();
+    let masked = mask_api_key(&api_key);
+    state
+        .update_config(|config| {
+            config.llm.api_key = api_key;
+            Ok::<(), CommandError>(())
+        })
+        .await?;
+    if let Err(e) = app.emit("config-updated", ()) {
+        error!("Failed to emit config-updated event: {}", e);
+    }
+    Ok(masked)
 }
 
 /// 获取当前启用状态
 #[tauri::command]
-pub async fn get_enabled_status(state: State<'_, Arc<AppState>>) -> Result<bool, String> {
+pub async fn get_enabled_status(state: State<'_, Arc<AppState>>) -> Result<bool, CommandError> {
     Ok(*state.is_enabled.read().await)
 }
 
@@ -31,54 +93,204 @@ pub async fn set_enabled_status(
     enabled: bool,
     state: State<'_, Arc<AppState>>,
     app: tauri::AppHandle,
-) -> Result<(), String> {
-    *state.is_enabled.write().await = enabled;
-    info!("Translation monitoring {}", if enabled { "enabled" } else { "disabled" });
-    
-    // 更新托盘菜单
-    #[cfg(desktop)]
-    {
-        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
-        
-        if let Ok(new_menu) = crate::build_tray_menu(&app, &state).await {
-            if let Some(tray) = app.tray_by_id("main") {
-                let _ = tray.set_menu(None::<tauri::menu::Menu<tauri::Wry>>);
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                if let Err(e) = tray.set_menu(Some(new_menu)) {
-                    error!("Failed to update tray menu: {}", e);
-                }
-            }
+) -> Result<(), CommandError> {
+    state.set_enabled(enabled).await;
+    crate::notify_enabled_status(&app, &state, enabled).await;
+    Ok(())
+}
+
+/// 规范化并校验要保存的配置，返回不阻止保存的警告
+///
+/// 有 `Error` 级别的问题时返回 `Validation` 错误，全部问题序列化后放在 details 中。
+fn validate_config(config: &mut AppConfig) -> Result<Vec<ValidationIssue>, CommandError> {
+    config.hotkey.normalize();
+    let warnings = reject_errors(config.validate())?;
+    config.language.validate()?;
+    Ok(warnings)
+}
+
+/// 将开机启动设置同步到系统，与系统状态一致时不做处理，失败时返回警告
+fn apply_launch_at_login(enabled: bool) -> Option<ValidationIssue> {
+    if login_item::is_enabled().ok() == Some(enabled) {
+        return None;
+    }
+    match login_item::set_enabled(enabled) {
+        Ok(()) => {
+            info!(
+                "Launch at login {}",
+                if enabled { "enabled" } else { "disabled" }
+            );
+            None
+        }
+        Err(e) => {
+            warn!("Failed to update launch at login: {}", e);
+            Some(ValidationIssue::warning("launch_at_login", e.to_string()))
         }
     }
-    
-    // 发送事件通知前端
-    app.emit("enabled-status-changed", enabled)
-        .map_err(|e| format!("Failed to emit event: {}", e))?;
-    
+}
+
+/// 按过滤规则检查要发送给 LLM 的原文，取消翻译时返回 `Validation` 错误
+fn screen_text(config: &AppConfig, text: &str) -> Result<Option<Masked>, CommandError> {
+    match redaction::screen(config.redaction_mode, &config.redaction_patterns, text)? {
+        Screening::Clean => Ok(None),
+        Screening::Blocked { matches } => Err(CommandError::validation(format!(
+            "原文包含 {} 处不能发送的内容，已取消翻译",
+            matches
+        ))),
+        Screening::Masked(masked) => Ok(Some(masked)),
+    }
+}
+
+/// 有 `Error` 级别的问题时返回 `Validation` 错误，否则返回剩余的警告
+fn reject_errors(issues: Vec<ValidationIssue>) -> Result<Vec<ValidationIssue>, CommandError> {
+    if !issues.iter().any(ValidationIssue::is_error) {
+        return Ok(issues);
+    }
+    warn!("Rejected invalid config: {:?}", issues);
+    let message = join_messages(
+        issues
+            .iter()
+            .filter(|issue| issue.is_error())
+            .map(|issue| issue.message.as_str()),
+    );
+    Err(CommandError::validation(message).with_details(serde_json::to_string(&issues)?))
+}
+
+/// 将多条校验问题合并为一条可展示的错误信息
+fn join_messages<'a>(messages: impl Iterator<Item = &'a str>) -> String {
+    messages.collect::<Vec<_>>().join("；")
+}
+
+/// 校验 User Prompt 模板，返回阻止保存的错误和仅作提示的警告
+#[tauri::command]
+pub async fn validate_prompt_template(template: String) -> Result<PromptValidation, CommandError> {
+    Ok(crate::config::validate_prompt_template(&template))
+}
+
+/// 导出当前配置到文件，include_secrets 为 false 时不导出 API Key
+#[tauri::command]
+pub async fn export_config(
+    path: String,
+    include_secrets: bool,
+    state: State<'_, Arc<AppState>>,
+) -> Result<(), CommandError> {
+    info!("Exporting config to {}", path);
+    let content = state.get_config().await.to_export_json(include_secrets)?;
+    std::fs::write(&path, content).map_err(CommandError::from)
+}
+
+/// 从文件导入配置，缺少的字段使用默认值
+///
+/// 导入文件没有 API Key 时保留当前的 API Key。文件无效时不修改当前配置。
+#[tauri::command]
+pub async fn import_config(
+    path: String,
+    state: State<'_, Arc<AppState>>,
+    app: tauri::AppHandle,
+) -> Result<(), CommandError> {
+    info!("Importing config from {}", path);
+    let content = std::fs::read_to_string(&path)?;
+    let mut config = AppConfig::from_import_json(&content, &state.get_config().await)?;
+    validate_config(&mut config)?;
+    state.save_config(&config).await?;
+
+    crate::reregister_hotkeys(&app).await;
+    crate::refresh_tray_menu(&app, &state).await;
+    if let Err(e) = app.emit("config-updated", ()) {
+        error!("Failed to emit config-updated event: {}", e);
+    }
     Ok(())
 }
 
-/// 保存应用配置
+/// 恢复默认设置，返回旧配置文件的备份路径
+///
+/// keep_api_key 保留 API 地址、API Key 和模型，keep_languages 保留语言设置。
+#[tauri::command]
+pub async fn reset_config(
+    keep_api_key: bool,
+    keep_languages: bool,
+    state: State<'_, Arc<AppState>>,
+    app: tauri::AppHandle,
+) -> Result<Option<String>, CommandError> {
+    info!("Resetting config to defaults");
+    let backup = state.backup_config_file()?;
+    let config = state
+        .get_config()
+        .await
+        .reset_from(keep_api_key, keep_languages);
+    state.save_config(&config).await?;
+
+    crate::reregister_hotkeys(&app).await;
+    crate::refresh_tray_menu(&app, &state).await;
+    if let Err(e) = app.emit("config-updated", ()) {
+        error!("Failed to emit config-updated event: {}", e);
+    }
+    Ok(backup.map(|path| path.display().to_string()))
+}
+
+/// 从旧版本的配置文件导入设置，返回导入的文件路径
+///
+/// 导入前备份当前配置，导入后旧配置文件重命名为 `.bak`。数据库位置和历史加密状态保持不变。
+#[tauri::command]
+pub async fn import_legacy_config(
+    state: State<'_, Arc<AppState>>,
+    app: tauri::AppHandle,
+) -> Result<String, CommandError> {
+    let config_path = state.config_path();
+    let sources = config_path
+        .parent()
+        .and_then(Path::parent)
+        .map(|config_dir| legacy_config_candidates(config_dir, config_path))
+        .unwrap_or_default();
+    let (mut config, source) = load_legacy_config(&sources)
+        .ok_or_else(|| CommandError::not_found("未找到旧版本的配置文件"))?;
+    info!("Importing legacy config from {:?}", source);
+
+    let current = state.get_config().await;
+    config.keep_database_settings(&current);
+    validate_config(&mut config)?;
+    state.backup_config_file()?;
+    state.save_config(&config).await?;
+    if let Err(e) = archive_legacy_config(&source) {
+        warn!("Failed to rename imported legacy config: {}", e);
+    }
+
+    crate::reregister_hotkeys(&app).await;
+    crate::refresh_tray_menu(&app, &state).await;
+    if let Err(e) = app.emit("config-updated", ()) {
+        error!("Failed to emit config-updated event: {}", e);
+    }
+    Ok(source.display().to_string())
+}
+
+/// 保存应用配置，返回不阻止保存的警告
+///
+/// 配置有错误时不保存，返回 `Validation` 错误，details 为序列化的 `ValidationIssue` 列表。
 #[tauri::command]
 pub async fn save_config(
-    config: AppConfig,
+    mut config: AppConfig,
     state: State<'_, Arc<AppState>>,
     app: tauri::AppHandle,
-) -> Result<(), String> {
+) -> Result<Vec<ValidationIssue>, CommandError> {
     info!("Saving config");
-    state
-        .save_config(&config)
-        .await
-        .map_err(|e| e.to_string())?;
+    let mut warnings = validate_config(&mut config)?;
+
+    let current = state.get_config().await;
+    config.restore_api_keys(&current);
+    config.language.track_previous_target(&current.language);
+    config.keep_database_settings(&current);
+    state.save_config(&config).await?;
+    crate::reregister_hotkeys(&app).await;
+    warnings.extend(apply_launch_at_login(config.launch_at_login));
 
-    // 清理历史记录（如果超过限制）
+    // 清理历史记录（如果超过条数限制或保留天数）
     state
         .database
-        .cleanup_history(config.history_limit)
+        .apply_history_retention(config.history_limit, config.history_retention_days)
         .await
         .map_err(|e| {
             error!("Failed to cleanup history: {}", e);
-            e.to_string()
+            e
         })?;
 
     // 更新托盘菜单
@@ -108,18 +320,121 @@ pub async fn save_config(
         error!("Failed to emit config-updated event: {}", e);
     }
 
-    Ok(())
+    Ok(warnings)
 }
 
 /// 测试 LLM 连接
+///
+/// profile 为配置所属的方案，未指定时为当前方案，用于恢复掩码形式的 API Key。
 #[tauri::command]
-pub async fn test_llm_connection(config: LLMConfig) -> Result<String, String> {
+pub async fn test_llm_connection(
+    mut config: LLMConfig,
+    profile: Option<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<String, CommandError> {
     info!("Testing LLM connection");
-    let client = LLMClient::new().map_err(|e| e.to_string())?;
+    restore_profile_api_key(&mut config, profile.as_deref(), &state).await;
+    let client = LLMClient::new()?;
     client
         .test_connection(&config)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
+}
+
+/// 测试 LLM 连接，返回请求地址、状态码、耗时、模型、usage 和失败阶段等诊断信息
+#[tauri::command]
+pub async fn test_llm_connection_detailed(
+    mut config: LLMConfig,
+    sample_text: Option<String>,
+    profile: Option<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ConnectionReport, CommandError> {
+    info!("Testing LLM connection (detailed)");
+    restore_profile_api_key(&mut config, profile.as_deref(), &state).await;
+    let client = LLMClient::new()?;
+    Ok(client
+        .test_connection_detailed(&config, sample_text.as_deref())
+        .await)
+}
+
+/// 前端传入的 API Key 仍是掩码时恢复方案中实际的 API Key，未指定方案时使用当前方案
+async fn restore_profile_api_key(config: &mut LLMConfig, profile: Option<&str>, state: &AppState) {
+    let current = state.get_config().await;
+    if let Some(existing) = current.profile(profile.unwrap_or(&current.active_profile)) {
+        config.restore_api_key(&existing.llm.api_key);
+    }
+}
+
+/// 获取 LLM 配置方案列表，API Key 以掩码返回
+#[tauri::command]
+pub async fn list_profiles(state: State<'_, Arc<AppState>>) -> Result<ProfileList, CommandError> {
+    let config = state.get_config().await.with_masked_api_key();
+    Ok(ProfileList {
+        active_profile: config.active_profile,
+        profiles: config.profiles,
+    })
+}
+
+/// 切换当前使用的 LLM 配置方案
+#[tauri::command]
+pub async fn set_active_profile(
+    name: String,
+    state: State<'_, Arc<AppState>>,
+    app: tauri::AppHandle,
+) -> Result<(), CommandError> {
+    info!("Switching LLM profile to: {}", name);
+    state
+        .update_config(|config| config.set_active_profile(&name))
+        .await?;
+    profiles_changed(&app, &state).await;
+    Ok(())
+}
+
+/// 新增或更新 LLM 配置方案，同名方案会被替换，返回不阻止保存的警告
+///
+/// API Key 仍是同名方案的掩码时保留原 API Key。
+#[tauri::command]
+pub async fn save_profile(
+    mut profile: NamedLLMConfig,
+    state: State<'_, Arc<AppState>>,
+    app: tauri::AppHandle,
+) -> Result<Vec<ValidationIssue>, CommandError> {
+    info!("Saving LLM profile: {}", profile.name);
+    let warnings = reject_errors(profile.llm.issues("llm"))?;
+    profile.llm.api_key = profile.llm.api_key.trim().to_string();
+    state
+        .update_config(|config| {
+            if let Some(existing) = config.profile(profile.name.trim()) {
+                profile.llm.restore_api_key(&existing.llm.api_key);
+            }
+            config.save_profile(profile).map_err(CommandError::from)
+        })
+        .await?;
+    profiles_changed(&app, &state).await;
+    Ok(warnings)
+}
+
+/// 删除 LLM 配置方案，删除当前方案时切换到剩余的第一个方案，最后一个方案不能删除
+#[tauri::command]
+pub async fn delete_profile(
+    name: String,
+    state: State<'_, Arc<AppState>>,
+    app: tauri::AppHandle,
+) -> Result<(), CommandError> {
+    info!("Deleting LLM profile: {}", name);
+    state
+        .update_config(|config| config.delete_profile(&name))
+        .await?;
+    profiles_changed(&app, &state).await;
+    Ok(())
+}
+
+/// LLM 配置方案变化后更新托盘菜单并通知前端
+async fn profiles_changed(app: &tauri::AppHandle, state: &Arc<AppState>) {
+    crate::refresh_tray_menu(app, state).await;
+    if let Err(e) = app.emit("config-updated", ()) {
+        error!("Failed to emit config-updated event: {}", e);
+    }
 }
 
 /// 获取翻译历史
@@ -127,48 +442,793 @@ pub async fn test_llm_connection(config: LLMConfig) -> Result<String, String> {
 pub async fn get_history(
     page: i64,
     page_size: i64,
+    filter: Option<HistoryFilter>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<HistoryResult, CommandError> {
+    debug!("Getting history: page={}, size={}", page, page_size);
+    if page < 1 {
+        return Err(CommandError::validation(format!(
+            "页码必须大于 0，当前为 {}",
+            page
+        )));
+    }
+    if !(1..=MAX_HISTORY_PAGE_SIZE).contains(&page_size) {
+        return Err(CommandError::validation(format!(
+            "每页条数必须在 1 到 {} 之间，当前为 {}",
+            MAX_HISTORY_PAGE_SIZE, page_size
+        )));
+    }
+    state
+        .database
+        .get_history(page, page_size, &filter.unwrap_or_default())
+        .await
+        .map_err(CommandError::from)
+}
+
+/// 获取翻译历史按模式和目标语言的计数，`search` 与历史列表的搜索条件一致
+#[tauri::command]
+pub async fn get_history_facets(
     search: Option<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<HistoryFacets, CommandError> {
+    state
+        .database
+        .get_history_facets(search.as_deref())
+        .await
+        .map_err(CommandError::from)
+}
+
+/// 按游标分页获取翻译历史，`cursor` 为空时从最新的记录开始
+#[tauri::command]
+pub async fn get_history_cursor(
+    cursor: Option<HistoryCursor>,
+    limit: i64,
+    filter: Option<HistoryFilter>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<HistoryPage, CommandError> {
+    debug!("Getting history after {:?}, limit={}", cursor, limit);
+    if !(1..=MAX_HISTORY_PAGE_SIZE).contains(&limit) {
+        return Err(CommandError::validation(format!(
+            "每页条数必须在 1 到 {} 之间，当前为 {}",
+            MAX_HISTORY_PAGE_SIZE, limit
+        )));
+    }
+
+    state
+        .database
+        .get_history_after(cursor, limit, &filter.unwrap_or_default())
+        .await
+        .map_err(CommandError::from)
+}
+
+/// 获取最近的翻译，原文和译文只保留开头部分，最多 50 条
+#[tauri::command]
+pub async fn get_recent_translations(
+    limit: usize,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<RecentTranslation>, CommandError> {
+    state
+        .database
+        .get_recent(limit)
+        .await
+        .map_err(CommandError::from)
+}
+
+/// 打开独立的历史记录窗口，已打开时聚焦该窗口
+#[tauri::command]
+pub async fn open_history_window(app: tauri::AppHandle) -> Result<(), CommandError> {
+    crate::open_history_window(&app)
+        .map_err(|e| CommandError::other(format!("无法打开历史记录窗口: {}", e)))
+}
+
+/// 按 ID 获取单条翻译记录，用于详情页
+#[tauri::command]
+pub async fn get_translation_by_id(
+    id: i64,
+    state: State<'_, Arc<AppState>>,
+) -> Result<TranslationRecord, CommandError> {
+    state
+        .database
+        .get_translation(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("翻译记录不存在: {}", id)))
+}
+
+/// 获取详情页中相邻记录的 ID，使用与历史列表相同的筛选条件
+#[tauri::command]
+pub async fn get_adjacent_ids(
+    id: i64,
+    filter: Option<HistoryFilter>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<AdjacentIds, CommandError> {
+    state
+        .database
+        .get_adjacent_ids(id, &filter.unwrap_or_default())
+        .await
+        .map_err(CommandError::from)
+}
+
+/// 设置翻译记录的置顶状态
+#[tauri::command]
+pub async fn set_translation_pinned(
+    id: i64,
+    pinned: bool,
+    state: State<'_, Arc<AppState>>,
+) -> Result<(), CommandError> {
+    state
+        .database
+        .set_translation_pinned(id, pinned)
+        .await
+        .map_err(CommandError::from)
+}
+
+/// 删除早于指定时间的翻译历史，`before_ts` 和 `days` 二选一，置顶记录不会被删除
+#[tauri::command]
+pub async fn purge_history(
+    before_ts: Option<i64>,
+    days: Option<u32>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<u64, CommandError> {
+    let before = match (before_ts, days) {
+        (Some(before_ts), None) => before_ts,
+        (None, Some(days)) => retention_cutoff(days),
+        _ => return Err(CommandError::validation("请指定截止时间或保留天数中的一项")),
+    };
+
+    info!("Purging translation history before {}", before);
+    state
+        .database
+        .purge_history_before(before)
+        .await
+        .map_err(CommandError::from)
+}
+
+/// 清空翻译历史，返回删除的记录数
+///
+/// 未指定任何筛选条件时清空全部历史、性能指标和每日汇总；否则只删除匹配的记录。
+/// 两种情况下置顶记录除非 `include_pinned` 为 true 否则都保留。`before` 为 Unix 时间戳（秒，不包含）。
+#[tauri::command]
+pub async fn clear_history(
     mode: Option<String>,
+    target_lang: Option<String>,
+    before: Option<i64>,
+    include_pinned: Option<bool>,
     state: State<'_, Arc<AppState>>,
-) -> Result<HistoryResult, String> {
-    debug!("Getting history: page={}, size={}", page, page_size);
+) -> Result<u64, CommandError> {
+    if mode.is_none() && target_lang.is_none() && before.is_none() {
+        info!("Clearing all translation history");
+        return state
+            .database
+            .clear_all_history(include_pinned.unwrap_or(false))
+            .await
+            .map_err(CommandError::from);
+    }
+
+    let filter = HistoryFilter {
+        mode,
+        target_lang,
+        to_ts: before,
+        ..Default::default()
+    };
+    info!("Clearing translation history matching {:?}", filter);
+    state
+        .database
+        .clear_history_filtered(&filter, include_pinned.unwrap_or(false))
+        .await
+        .map_err(CommandError::from)
+}
+
+/// 从导出的 JSON 文件导入翻译历史
+#[tauri::command]
+pub async fn import_history(
+    path: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ImportSummary, CommandError> {
+    info!("Importing translation history from {}", path);
+    state
+        .database
+        .import_history(Path::new(&path))
+        .await
+        .map_err(CommandError::from)
+}
+
+/// 启用或关闭翻译历史加密，返回转换的记录数
+///
+/// 加密后历史搜索在解密后的内容中进行。
+#[tauri::command]
+pub async fn set_history_encryption(
+    enabled: bool,
+    state: State<'_, Arc<AppState>>,
+) -> Result<u64, CommandError> {
+    info!("Setting history encryption: {}", enabled);
+    let converted = convert_history_encryption(&state, enabled).await?;
+
+    let saved = state
+        .update_config(|config| {
+            config.history_encryption = enabled;
+            Ok::<(), CommandError>(())
+        })
+        .await;
+    if let Err(e) = saved {
+        // 配置未更新时下次启动仍按原来的状态打开数据库，需要转换回去
+        error!("Failed to save history encryption setting: {}", e);
+        if let Err(rollback_err) = convert_history_encryption(&state, !enabled).await {
+            error!("Failed to restore history encryption: {}", rollback_err);
+        }
+        return Err(e);
+    }
+    Ok(converted)
+}
+
+/// 历史记录加密不可用的原因
+///
+/// 配置启用了加密但启动时无法加载密钥（如钥匙串已锁定或密钥丢失）时返回原因，
+/// 此时不保存翻译文本。加密正常或未启用加密时返回 `None`。
+#[tauri::command]
+pub async fn get_history_encryption_error(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Option<String>, CommandError> {
+    Ok(state.database.encryption_error())
+}
+
+/// 按保存的密钥加密或解密所有历史记录，返回转换的记录数
+async fn convert_history_encryption(state: &AppState, enabled: bool) -> crate::error::Result<u64> {
+    if enabled {
+        let cipher = state.history_cipher().await?;
+        state.database.enable_encryption(cipher).await
+    } else {
+        state.database.disable_encryption().await
+    }
+}
+
+/// 获取数据库文件大小和各表行数
+#[tauri::command]
+pub async fn get_db_info(state: State<'_, Arc<AppState>>) -> Result<DbInfo, CommandError> {
+    state
+        .database
+        .get_db_info()
+        .await
+        .map_err(CommandError::from)
+}
+
+/// 压缩数据库，回收已删除记录占用的空间
+#[tauri::command]
+pub async fn compact_database(state: State<'_, Arc<AppState>>) -> Result<DbInfo, CommandError> {
+    info!("Compacting database");
+    state.database.compact().await.map_err(CommandError::from)
+}
+
+/// 列出数据库备份
+#[tauri::command]
+pub async fn list_backups(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<BackupInfo>, CommandError> {
+    state.database.list_backups().map_err(CommandError::from)
+}
+
+/// 从指定序号的备份恢复数据库
+///
+/// 未启用历史记录加密时，包含加密记录的备份会被拒绝，返回 `Crypto` 错误。
+#[tauri::command]
+pub async fn restore_from_backup(
+    index: usize,
+    state: State<'_, Arc<AppState>>,
+) -> Result<(), CommandError> {
+    info!("Restoring database from backup {}", index);
+    state
+        .database
+        .restore_from_backup(index)
+        .await
+        .map_err(CommandError::from)
+}
+
+/// 将数据库移动到新位置，并在配置中记录新路径
+#[tauri::command]
+pub async fn move_database(
+    new_path: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<(), CommandError> {
+    info!("Moving database to {}", new_path);
+    let new_path = PathBuf::from(new_path);
+    if !new_path.is_absolute() {
+        return Err(CommandError::validation(format!(
+            "数据库路径必须是绝对路径: {}",
+            new_path.display()
+        )));
+    }
+
+    let previous = state.database.move_to(&new_path).await?;
+    let saved = state
+        .update_config(|config| {
+            config.database_path = Some(new_path);
+            Ok::<(), CommandError>(())
+        })
+        .await;
+    if let Err(e) = saved {
+        // 配置未更新时下次启动仍打开原位置，需要移回原位置
+        error!("Failed to save database path: {}", e);
+        if let Err(rollback_err) = state.database.move_to(&previous).await {
+            error!("Failed to move database back: {}", rollback_err);
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// 获取费用统计，period 为 "day"、"week"、"month" 或 "lifetime"
+#[tauri::command]
+pub async fn get_cost_stats(
+    period: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<CostStats, CommandError> {
+    debug!("Getting cost stats for period: {}", period);
+    state
+        .database
+        .get_cost_stats(&period)
+        .await
+        .map_err(CommandError::from)
+}
+
+/// 按模型对比平均耗时、输出速率和失败率，period 与 `get_performance_stats` 相同
+#[tauri::command]
+pub async fn get_model_performance_comparison(
+    period: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<ModelStats>, CommandError> {
+    debug!("Getting model comparison for period: {}", period);
     state
         .database
-        .get_history(page, page_size, search.as_deref(), mode.as_deref())
+        .get_model_stats(&period)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
-/// 清空所有翻译历史
+/// 获取最近 `days` 天每天的翻译统计，用于统计页的趋势图
 #[tauri::command]
-pub async fn clear_history(state: State<'_, Arc<AppState>>) -> Result<u64, String> {
-    info!("Clearing all translation history");
+pub async fn get_daily_stats(
+    days: u32,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<DailySeries>, CommandError> {
+    debug!("Getting daily stats for {} days", days);
+    if !(1..=MAX_DAILY_STATS_DAYS).contains(&days) {
+        return Err(CommandError::validation(format!(
+            "天数必须在 1 到 {} 之间，当前为 {}",
+            MAX_DAILY_STATS_DAYS, days
+        )));
+    }
     state
         .database
-        .clear_all_history()
+        .get_daily_series(days)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
-/// 获取性能统计
+/// 取消正在进行的翻译，返回是否有翻译被取消
+///
+/// 翻译任务收到取消信号后停止输入、恢复剪贴板并记录取消的指标。
+#[tauri::command]
+pub async fn cancel_translation(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<bool, CommandError> {
+    let Some(active) = state.cancel_translation() else {
+        debug!("No active translation to cancel");
+        return Ok(false);
+    };
+
+    info!("Cancelling {} translation", active.mode);
+    if let Err(e) = app.emit("translation-cancelled", &active) {
+        error!("Failed to emit translation-cancelled event: {}", e);
+    }
+    Ok(true)
+}
+
+/// 获取性能统计，period 为 "hour"、"day"、"week"、"month" 或 "lifetime"
 #[tauri::command]
 pub async fn get_performance_stats(
     period: String,
     state: State<'_, Arc<AppState>>,
-) -> Result<PerformanceStats, String> {
+) -> Result<PerformanceStats, CommandError> {
     debug!("Getting performance stats for period: {}", period);
     state
         .database
         .get_performance_stats(&period)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
+}
+
+/// 将性能指标导出为 CSV 文件，period 与 `get_performance_stats` 相同，返回导出的行数
+#[tauri::command]
+pub async fn export_metrics(
+    path: String,
+    period: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<u64, CommandError> {
+    info!("Exporting {} metrics to {}", period, path);
+    state
+        .database
+        .export_metrics(Path::new(&path), &period)
+        .await
+        .map_err(CommandError::from)
 }
 
-/// 检查热键冲突
+/// 检查热键与系统快捷键、常见应用快捷键的冲突
 #[tauri::command]
-pub async fn check_hotkey_conflicts(hotkey: Hotkey) -> Result<Vec<String>, String> {
+pub async fn check_hotkey_conflicts(hotkey: Hotkey) -> Result<ConflictReport, CommandError> {
     debug!("Checking hotkey conflicts: {:?}", hotkey);
-    Ok(HotkeyManager::check_system_conflicts(&hotkey))
+    Ok(HotkeyManager::check_conflicts(&hotkey))
+}
+
+/// 检查应用内部热键之间的冲突
+#[tauri::command]
+pub async fn validate_hotkey_config(
+    config: HotkeyConfig,
+) -> Result<Vec<HotkeyConflict>, CommandError> {
+    debug!("Validating hotkey config: {:?}", config);
+    Ok(HotkeyManager::check_internal_conflicts(&config))
+}
+
+/// 校验单个热键，返回带类型代码的问题列表
+/// `mode` 为绑定标识（如 "selected_mode"），其余绑定取当前保存的配置
+#[tauri::command]
+pub async fn validate_hotkey(
+    hotkey: Hotkey,
+    mode: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<HotkeyIssue>, CommandError> {
+    debug!("Validating {} hotkey: {:?}", mode, hotkey);
+    let mut config = state.get_config().await.hotkey;
+    if !config.set_binding(&mode, hotkey) {
+        return Err(CommandError::validation(format!(
+            "未知的热键绑定: {}",
+            mode
+        )));
+    }
+    config.normalize();
+
+    Ok(config
+        .issues()
+        .into_iter()
+        .filter(|issue| issue.involves(&mode))
+        .collect())
+}
+
+/// 开始录制热键
+/// 捕获下一个按下的组合键，通过 hotkey-captured 事件发送给前端；不能用作热键的按键
+/// 通过 hotkey-capture-invalid 事件发送原因，录制继续；
+/// 录制期间暂停连续按键/长按监听，翻译热键也不会触发
+#[tauri::command]
+pub async fn start_hotkey_capture(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<(), CommandError> {
+    // rdev 同一时间只能有一个回调，登记录制会话时会先停止现有的监听器
+    let mut listener = KeyListener::new();
+    let Some((id, mut rx)) = state.start_capture_listener(|| {
+        let rx = listener.start_capture();
+        (listener.handle(), rx)
+    }) else {
+        return Err(CommandError::new(ErrorCode::Conflict, "正在录制热键"));
+    };
+    info!("Hotkey capture started");
+
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn(async move {
+        let deadline =
+            tokio::time::Instant::now() + Duration::from_secs(HOTKEY_CAPTURE_TIMEOUT_SECS);
+        // 不能用作热键的按键只通知前端，继续等待到超时
+        let result = loop {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(CaptureEvent::Invalid(invalid))) => {
+                    info!(
+                        "Captured hotkey {} is invalid: {}",
+                        invalid.hotkey, invalid.reason
+                    );
+                    if let Err(e) = app.emit("hotkey-capture-invalid", invalid) {
+                        error!("Failed to emit hotkey capture event: {}", e);
+                    }
+                }
+                Ok(Some(CaptureEvent::Captured(hotkey))) => break Ok(Some(hotkey)),
+                Ok(None) => break Ok(None),
+                Err(elapsed) => break Err(elapsed),
+            }
+        };
+
+        listener.stop();
+        state.release_key_listener(id);
+
+        let emitted = match result {
+            Ok(Some(hotkey)) => {
+                info!("Hotkey captured: {:?}", hotkey);
+                app.emit("hotkey-captured", hotkey)
+            }
+            Ok(None) => {
+                info!("Hotkey capture cancelled");
+                app.emit("hotkey-capture-cancelled", ())
+            }
+            Err(_) => {
+                info!("Hotkey capture timed out");
+                app.emit("hotkey-capture-timeout", ())
+            }
+        };
+        if let Err(e) = emitted {
+            error!("Failed to emit hotkey capture event: {}", e);
+        }
+
+        // 恢复连续按键/长按监听
+        let config = state.get_config().await;
+        crate::restart_key_listeners(&app, &config.hotkey, state.is_enabled().await);
+    });
+
+    Ok(())
+}
+
+/// 取消热键录制
+#[tauri::command]
+pub async fn cancel_hotkey_capture(state: State<'_, Arc<AppState>>) -> Result<(), CommandError> {
+    state.stop_hotkey_capture();
+    Ok(())
+}
+
+/// 检查输入监控权限（连续按键/长按热键需要）
+#[tauri::command]
+pub async fn check_input_monitoring_permission() -> Result<bool, CommandError> {
+    let granted = crate::check_input_monitoring_permission();
+    debug!("Input monitoring permission granted: {}", granted);
+    Ok(granted)
+}
+
+/// 当前配置使用了连续按键/长按等 rdev 热键但未授予输入监控权限时返回 true
+/// 启动时的检查早于前端加载，前端加载后通过该命令决定是否显示授权提示
+#[tauri::command]
+pub async fn get_input_monitoring_required(
+    state: State<'_, Arc<AppState>>,
+) -> Result<bool, CommandError> {
+    let uses_key_listener = state.get_config().await.hotkey.uses_key_listener();
+    Ok(uses_key_listener && !crate::check_input_monitoring_permission())
+}
+
+/// 获取辅助功能和输入监控权限的授权状态，供引导页面轮询
+#[tauri::command]
+pub async fn get_permission_status() -> Result<crate::PermissionStatus, CommandError> {
+    Ok(crate::permission_status())
+}
+
+/// 打开系统设置中对应的权限页面，仅 macOS 有效
+#[tauri::command]
+pub async fn open_permission_settings(
+    kind: String,
+    app: tauri::AppHandle,
+) -> Result<(), CommandError> {
+    let url = crate::permission_settings_url(&kind)
+        .ok_or_else(|| CommandError::validation(format!("未知的权限类型: {}", kind)))?;
+
+    #[cfg(target_os = "macos")]
+    {
+        use tauri_plugin_shell::ShellExt;
+
+        #[allow(deprecated)]
+        app.shell()
+            .open(url, None)
+            .map_err(|e| CommandError::other(format!("无法打开系统设置: {}", e)))?;
+        info!("Opened permission settings: {}", kind);
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = (url, app);
+        debug!("Permission settings are only available on macOS: {}", kind);
+    }
+
+    Ok(())
+}
+
+/// 获取版本、路径、热键和权限等诊断信息
+#[tauri::command]
+pub async fn get_app_info(
+    state: State<'_, Arc<AppState>>,
+    app: tauri::AppHandle,
+) -> Result<crate::AppInfo, CommandError> {
+    Ok(crate::app_info(&app, &state).await)
+}
+
+/// 获取各热键的注册结果和键盘监听器状态
+#[tauri::command]
+pub async fn get_hotkey_status(
+    state: State<'_, Arc<AppState>>,
+) -> Result<crate::HotkeyStatus, CommandError> {
+    Ok(crate::hotkey_status(&state))
+}
+
+/// 使用示例文本诊断热键翻译流程，返回每一步的耗时和结果
+#[tauri::command]
+pub async fn test_translation_pipeline(
+    sample_text: String,
+    mode: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<crate::PipelineReport, CommandError> {
+    info!("Testing {} translation pipeline", mode);
+    crate::test_translation_pipeline(&state, &sample_text, &mode).await
+}
+
+/// 获取最近的警告和错误日志，从新到旧
+#[tauri::command]
+pub async fn get_error_log(
+    limit: usize,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<ErrorLogEntry>, CommandError> {
+    Ok(state.error_log.recent(limit))
+}
+
+/// 清空内存中的错误日志
+#[tauri::command]
+pub async fn clear_error_log(state: State<'_, Arc<AppState>>) -> Result<(), CommandError> {
+    state.error_log.clear();
+    Ok(())
+}
+
+/// 获取配置中的日志级别
+#[tauri::command]
+pub async fn get_log_level(state: State<'_, Arc<AppState>>) -> Result<LogLevel, CommandError> {
+    Ok(state.get_config().await.log_level)
+}
+
+/// 调整日志级别并保存到配置，level 为 error、warn、info、debug 或 trace
+#[tauri::command]
+pub async fn set_log_level(
+    level: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<(), CommandError> {
+    let level = LogLevel::parse(&level).ok_or_else(|| {
+        CommandError::validation(format!(
+            "无效的日志级别: {}，可选 error、warn、info、debug、trace",
+            level
+        ))
+    })?;
+    state
+        .update_config(|config| {
+            config.log_level = level;
+            Ok::<(), CommandError>(())
+        })
+        .await?;
+    crate::set_log_level(level)?;
+    Ok(())
+}
+
+/// 获取键盘监听器最近一次启动失败的原因
+#[tauri::command]
+pub async fn get_key_listener_error(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Option<String>, CommandError> {
+    Ok(state.key_listener_error().await)
+}
+
+/// 获取键盘监听器的运行状态
+#[tauri::command]
+pub async fn get_listener_status(
+    state: State<'_, Arc<AppState>>,
+) -> Result<ListenerStatus, CommandError> {
+    Ok(state.listener_status())
+}
+
+/// 获取内置的语言列表，按英文名称排序，search 按英文或本地名称的前缀过滤
+#[tauri::command]
+pub async fn get_languages(search: Option<String>) -> Result<Vec<LanguageInfo>, CommandError> {
+    Ok(search_languages(search.as_deref().unwrap_or_default()))
+}
+
+/// 添加常用语言，name 为空时使用语言的本地名称
+#[tauri::command]
+pub async fn add_favorite_language(
+    code: String,
+    name: Option<String>,
+    state: State<'_, Arc<AppState>>,
+    app: tauri::AppHandle,
+) -> Result<(), CommandError> {
+    info!("Adding favorite language: {}", code);
+    state
+        .update_config(|config| {
+            config
+                .language
+                .add_favorite(&code, name.as_deref().unwrap_or_default())
+        })
+        .await?;
+    language_config_changed(&app, &state).await;
+    Ok(())
+}
+
+/// 移除常用语言，移除当前目标语言时自动切换到剩余的第一个常用语言
+#[tauri::command]
+pub async fn remove_favorite_language(
+    code: String,
+    state: State<'_, Arc<AppState>>,
+    app: tauri::AppHandle,
+) -> Result<(), CommandError> {
+    info!("Removing favorite language: {}", code);
+    state
+        .update_config(|config| config.language.remove_favorite(&code))
+        .await?;
+    language_config_changed(&app, &state).await;
+    Ok(())
+}
+
+/// 按给定顺序重新排列常用语言，托盘菜单按此顺序显示
+///
+/// codes 必须恰好包含每个常用语言一次，否则返回 `Validation` 错误，details 为序列化的 `FavoriteOrderError`。
+#[tauri::command]
+pub async fn reorder_favorite_languages(
+    codes: Vec<String>,
+    state: State<'_, Arc<AppState>>,
+    app: tauri::AppHandle,
+) -> Result<(), CommandError> {
+    info!("Reordering favorite languages: {:?}", codes);
+    state
+        .update_config(|config| {
+            config.language.reorder_favorites(&codes).map_err(|error| {
+                warn!("Rejected favorite language order: {:?}", error);
+                CommandError::validation("常用语言排序与当前常用语言不一致")
+                    .with_details(serde_json::to_string(&error).unwrap_or_default())
+            })
+        })
+        .await?;
+    language_config_changed(&app, &state).await;
+    Ok(())
+}
+
+/// 常用语言变化后更新托盘菜单并通知前端
+async fn language_config_changed(app: &tauri::AppHandle, state: &Arc<AppState>) {
+    crate::refresh_tray_menu(app, state).await;
+    if let Err(e) = app.emit("config-updated", ()) {
+        error!("Failed to emit config-updated event: {}", e);
+    }
+}
+
+/// 添加排除应用，前台应用匹配时不触发翻译，name 为空时使用标识
+#[tauri::command]
+pub async fn add_excluded_app(
+    identifier: String,
+    name: Option<String>,
+    state: State<'_, Arc<AppState>>,
+    app: tauri::AppHandle,
+) -> Result<Vec<ExcludedApp>, CommandError> {
+    info!("Adding excluded app: {}", identifier);
+    let config = state
+        .update_config(|config| {
+            config.add_excluded_app(&identifier, name.as_deref().unwrap_or_default())
+        })
+        .await?;
+    excluded_apps_changed(&app);
+    Ok(config.excluded_apps)
+}
+
+/// 移除排除应用，返回剩余的排除列表
+#[tauri::command]
+pub async fn remove_excluded_app(
+    identifier: String,
+    state: State<'_, Arc<AppState>>,
+    app: tauri::AppHandle,
+) -> Result<Vec<ExcludedApp>, CommandError> {
+    info!("Removing excluded app: {}", identifier);
+    let config = state
+        .update_config(|config| config.remove_excluded_app(&identifier))
+        .await?;
+    excluded_apps_changed(&app);
+    Ok(config.excluded_apps)
+}
+
+/// 列出正在运行的应用，供前端选择排除应用，不支持的平台返回空列表
+#[tauri::command]
+pub async fn list_running_apps() -> Result<Vec<ExcludedApp>, CommandError> {
+    tokio::task::spawn_blocking(crate::text_handler::running_apps)
+        .await
+        .map_err(|e| CommandError::other(format!("获取运行中的应用失败: {}", e)))?
+        .map_err(CommandError::from)
+}
+
+/// 排除列表变化后通知前端
+fn excluded_apps_changed(app: &tauri::AppHandle) {
+    if let Err(e) = app.emit("config-updated", ()) {
+        error!("Failed to emit config-updated event: {}", e);
+    }
 }
 
 /// 切换目标语言
@@ -176,16 +1236,205 @@ pub async fn check_hotkey_conflicts(hotkey: Hotkey) -> Result<Vec<String>, Strin
 pub async fn switch_language(
     language_code: String,
     state: State<'_, Arc<AppState>>,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     info!("Switching target language to: {}", language_code);
-    
-    let mut config = state.get_config().await;
-    config.language.current_target = language_code;
-    
+    let target = known_language_code(&language_code)?;
+
     state
-        .save_config(&config)
-        .await
-        .map_err(|e| e.to_string())
+        .update_config(|config| {
+            config.language.set_target(target);
+            Ok::<(), CommandError>(())
+        })
+        .await?;
+    Ok(())
+}
+
+/// 将目标语言切换为最近检测到的原文语言，没有检测到时切换为上一个目标语言，返回新的目标语言
+#[tauri::command]
+pub async fn swap_languages(
+    state: State<'_, Arc<AppState>>,
+    app: tauri::AppHandle,
+) -> Result<String, CommandError> {
+    let source = state.last_source_lang();
+    let config = state
+        .update_config(|config| config.language.swap_target(source).map(|_| ()))
+        .await?;
+    info!(
+        "Swapped target language to {}",
+        config.language.current_target
+    );
+    language_config_changed(&app, &state).await;
+    Ok(config.language.current_target)
+}
+
+/// 使用当前的模型和提示词重新翻译一条历史记录
+///
+/// 默认翻译为原记录的目标语言，可用 `target_language` 指定。译文保存为关联原记录的新记录，
+/// 原记录保持不变。
+#[tauri::command]
+pub async fn retranslate_record(
+    id: i64,
+    target_language: Option<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<TranslationRecord, CommandError> {
+    let record = state
+        .database
+        .get_translation(id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("翻译记录不存在或已被删除: {}", id)))?;
+    let config = state.get_config().await;
+    if !config.history_mode.records_text() {
+        return Err(CommandError::new(
+            ErrorCode::Conflict,
+            "当前历史模式不保存翻译文本，无法保存重新翻译的结果",
+        ));
+    }
+    let target_lang = match target_language {
+        Some(code) => known_language_code(&code)?,
+        None => record.target_lang.clone(),
+    };
+    info!("Retranslating record {} to {}", id, target_lang);
+    let masked = screen_text(&config, &record.original_text)?;
+
+    let start = Instant::now();
+    let mut result = state
+        .llm_client
+        .translate(
+            &with_prompt_override(&config.llm, &config.language.prompt_overrides, &target_lang),
+            masked.as_ref().map_or(&record.original_text, |m| &m.text),
+            &target_lang,
+        )
+        .await;
+    if let (Ok(result), Some(masked)) = (&mut result, &masked) {
+        result.translated_text = masked.restore(&result.translated_text);
+    }
+    let duration_ms = start.elapsed().as_millis() as i64;
+
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            if config.history_mode.records_metrics() {
+                let error_message = e.to_string();
+                let metric = MetricRecord {
+                    operation_type: &record.mode,
+                    duration_ms,
+                    success: false,
+                    error_type: Some(e.metric_type()),
+                    error_message: Some(&error_message),
+                    target_lang: Some(target_lang.as_str()),
+                    model: Some(config.llm.model.as_str()),
+                    stream: Some(false),
+                    ..Default::default()
+                };
+                if let Err(record_err) = state.database.insert_metric(&metric).await {
+                    error!("Failed to record metric: {}", record_err);
+                }
+            }
+            return Err(e.into());
+        }
+    };
+
+    // 不去重，避免译文相同时刷新原记录的时间
+    let new_id = state
+        .database
+        .insert_translation(
+            &record.original_text,
+            &result.translated_text,
+            record.source_lang.as_deref(),
+            &target_lang,
+            &record.mode,
+            &TranslationMetadata {
+                model: Some(config.llm.model.clone()),
+                completion_tokens: result.completion_tokens,
+                duration_ms: Some(duration_ms),
+                source_app: None,
+                dedup_window_secs: 0,
+                retranslated_from: Some(id),
+            },
+        )
+        .await?;
+
+    if config.history_mode.records_metrics() {
+        let metric = MetricRecord {
+            operation_type: &record.mode,
+            duration_ms,
+            success: true,
+            char_count: record.original_text.len() as i64,
+            target_lang: Some(target_lang.as_str()),
+            completion_tokens: result.completion_tokens,
+            tokens_per_second: result.tokens_per_second,
+            model: Some(config.llm.model.as_str()),
+            cost_usd: result.cost_usd,
+            stream: Some(false),
+            ..Default::default()
+        };
+        if let Err(e) = state.database.insert_metric(&metric).await {
+            error!("Failed to record metric: {}", e);
+        }
+    }
+
+    state
+        .database
+        .get_translation(new_id)
+        .await?
+        .ok_or_else(|| CommandError::not_found(format!("翻译记录不存在或已被删除: {}", new_id)))
+}
+
+/// 获取本次运行中最近一次热键翻译的结果
+#[tauri::command]
+pub async fn get_last_translation(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Option<LastTranslation>, CommandError> {
+    Ok(state.last_translation())
+}
+
+/// 将最近一次热键翻译的译文写入剪贴板，返回译文
+#[tauri::command]
+pub async fn copy_last_translation(
+    state: State<'_, Arc<AppState>>,
+) -> Result<String, CommandError> {
+    let last = state
+        .last_translation()
+        .ok_or_else(|| CommandError::not_found("本次运行中还没有翻译结果"))?;
+    state
+        .text_handler
+        .copy_to_clipboard(&last.translated_text)
+        .await?;
+    Ok(last.translated_text)
+}
+
+/// 在本地预估翻译 `text` 的 tokens 数量和费用，不调用 API
+#[tauri::command]
+pub async fn estimate_tokens(
+    text: String,
+    target_language: Option<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<TokenEstimate, CommandError> {
+    let config = state.get_config().await;
+    let target_lang = match target_language {
+        Some(code) => known_language_code(&code)?,
+        None => config.language.current_target.clone(),
+    };
+    let llm = with_prompt_override(&config.llm, &config.language.prompt_overrides, &target_lang);
+    Ok(estimate_translation(&llm, &text, &target_lang))
+}
+
+/// 预览翻译请求发送的消息，与实际翻译使用相同的构建逻辑，不调用 API
+///
+/// 未指定目标语言时使用当前目标语言。
+#[tauri::command]
+pub async fn preview_prompt(
+    text: String,
+    target_language: Option<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<Message>, CommandError> {
+    let config = state.get_config().await;
+    let target_lang = match target_language {
+        Some(code) => known_language_code(&code)?,
+        None => config.language.current_target,
+    };
+    let llm = with_prompt_override(&config.llm, &config.language.prompt_overrides, &target_lang);
+    Ok(build_messages(&llm, &target_lang, &text))
 }
 
 /// 翻译文本（供测试和手动调用）
@@ -193,57 +1442,94 @@ pub async fn switch_language(
 pub async fn translate_text(
     text: String,
     mode: String,
+    target_language: Option<String>,
     state: State<'_, Arc<AppState>>,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     info!("Translating text ({} chars) in {} mode", text.len(), mode);
     
     let start = Instant::now();
     let config = state.get_config().await;
-    
-    let result = state
+    // 指定目标语言时只用于本次翻译，不修改配置
+    let target_lang = match target_language {
+        Some(code) => known_language_code(&code)?,
+        None => config.language.current_target.clone(),
+    };
+
+    let masked = screen_text(&config, &text)?;
+
+    let llm = with_prompt_override(&config.llm, &config.language.prompt_overrides, &target_lang);
+    let llm_text = masked.as_ref().map_or(text.as_str(), |m| m.text.as_str());
+    let mut result = state
         .llm_client
-        .translate(&config.llm, &text, &config.language.current_target)
+        .translate(&llm, llm_text, &target_lang)
         .await;
+    if let (Ok(result), Some(masked)) = (&mut result, &masked) {
+        result.translated_text = masked.restore(&result.translated_text);
+    }
 
     let duration = start.elapsed();
     let duration_ms = duration.as_millis() as i64;
 
     match &result {
         Ok(translation_result) => {
-            // 记录成功的翻译
-            if let Err(e) = state
-                .database
-                .insert_translation(
-                    &text,
-                    &translation_result.translated_text,
-                    None,
-                    &config.language.current_target,
-                    &mode,
-                )
-                .await
-            {
-                error!("Failed to save translation: {}", e);
+            // 记录成功的翻译，隐私模式下不保存文本
+            if config.history_mode.records_text() {
+                if let Err(e) = state
+                    .database
+                    .insert_translation(
+                        &text,
+                        &translation_result.translated_text,
+                        None,
+                        &target_lang,
+                        &mode,
+                        &TranslationMetadata {
+                            model: Some(config.llm.model.clone()),
+                            completion_tokens: translation_result.completion_tokens,
+                            duration_ms: Some(duration_ms),
+                            // 从设置窗口发起的翻译，前台应用是本应用
+                            source_app: None,
+                            dedup_window_secs: config.history_dedup_secs,
+                            retranslated_from: None,
+                        },
+                    )
+                    .await
+                {
+                    error!("Failed to save translation: {}", e);
+                }
             }
 
             // 记录性能指标（包含 token 信息）
+            if config.history_mode.records_metrics() {
+                if let Err(e) = state
+                    .database
+                    .insert_metric(&MetricRecord {
+                        operation_type: &mode,
+                        duration_ms,
+                        success: true,
+                        error_type: None,
+                        error_message: None,
+                        char_count: text.len() as i64,
+                        target_lang: Some(target_lang.as_str()),
+                        completion_tokens: translation_result.completion_tokens,
+                        tokens_per_second: translation_result.tokens_per_second,
+                        model: Some(config.llm.model.as_str()),
+                        cost_usd: translation_result.cost_usd,
+                        // 设置窗口中的翻译始终使用非流式请求
+                        stream: Some(false),
+                        cached: false,
+                    })
+                    .await
+                {
+                    error!("Failed to record metric: {}", e);
+                }
+            }
+
+            // 清理旧的历史记录
             if let Err(e) = state
                 .database
-                .insert_metric(
-                    &mode,
-                    duration_ms,
-                    true,
-                    None,
-                    text.len() as i64,
-                    translation_result.completion_tokens,
-                    translation_result.tokens_per_second,
-                )
+                .apply_history_retention(config.history_limit, config.history_retention_days)
                 .await
             {
-                error!("Failed to record metric: {}", e);
-            }
-
-            // 清理旧的历史记录
-            if let Err(e) = state.database.cleanup_history(config.history_limit).await {
                 error!("Failed to cleanup history: {}", e);
             }
 
@@ -256,19 +1542,29 @@ pub async fn translate_text(
         }
         Err(e) => {
             // 记录失败的指标
-            let error_type = match &e {
-                crate::error::AppError::Network(_) => "network",
-                crate::error::AppError::LlmApi(_) => "api",
-                crate::error::AppError::Config(_) => "config",
-                _ => "other",
-            };
-
-            if let Err(record_err) = state
-                .database
-                .insert_metric(&mode, duration_ms, false, Some(error_type), 0, None, None)
-                .await
-            {
-                error!("Failed to record metric: {}", record_err);
+            if config.history_mode.records_metrics() {
+                let error_message = e.to_string();
+                if let Err(record_err) = state
+                    .database
+                    .insert_metric(&MetricRecord {
+                        operation_type: &mode,
+                        duration_ms,
+                        success: false,
+                        error_type: Some(e.metric_type()),
+                        error_message: Some(&error_message),
+                        char_count: 0,
+                        target_lang: Some(target_lang.as_str()),
+                        completion_tokens: None,
+                        tokens_per_second: None,
+                        model: Some(config.llm.model.as_str()),
+                        cost_usd: None,
+                        stream: Some(false),
+                        cached: false,
+                    })
+                    .await
+                {
+                    error!("Failed to record metric: {}", record_err);
+                }
             }
 
             error!("Translation failed: {}", e);
@@ -277,5 +1573,5 @@ pub async fn translate_text(
 
     result
         .map(|r| r.translated_text)
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
@@ -1,34 +1,1113 @@
 //! 配置模块
 //! 定义应用程序的配置结构和默认值
 
+use crate::error::{AppError, Result};
+use crate::hotkey::{
+    keycode_from_name, normalize_key_name, shortcut_code, HotkeyIssue, HotkeyIssueCode,
+    HotkeyManager,
+};
+use crate::languages;
+use crate::redaction;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// 应用程序全局配置
+///
+/// 所有字段都有默认值，缺少字段的旧版本配置也能正常加载；未知字段保留在
+/// `extra` 中并原样写回，使用旧版本打开新版本的配置不会丢失设置。
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
-    /// LLM 配置
+    /// 配置格式版本，加载时由 `upgrade_config_json` 升级到 `CONFIG_VERSION`
+    pub config_version: u32,
+    /// 当前方案的 LLM 配置
+    ///
+    /// 由 `active_profile` 对应的方案得到，修改后保存时写回该方案，配置文件中不保存此字段。
     pub llm: LLMConfig,
+    /// LLM 配置方案，至少有一个
+    pub profiles: Vec<NamedLLMConfig>,
+    /// 当前使用的方案名称
+    pub active_profile: String,
     /// 热键配置
     pub hotkey: HotkeyConfig,
     /// 语言配置
     pub language: LanguageConfig,
     /// 历史记录保存条数限制
     pub history_limit: usize,
+    /// 与最近一条相同的翻译在该时间窗口（秒）内只保留一条记录，0 表示不去重
+    #[serde(default = "default_history_dedup_secs")]
+    pub history_dedup_secs: u64,
+    /// 翻译历史的保存方式
+    #[serde(default)]
+    pub history_mode: HistoryMode,
+    /// 是否加密保存翻译历史的原文和译文，通过 `set_history_encryption` 命令切换
+    #[serde(default)]
+    pub history_encryption: bool,
+    /// 翻译历史的保留天数，更早的非置顶记录在清理时删除，为空表示不限制；
+    /// 与 `history_limit` 同时生效，启动时也会清理一次
+    #[serde(default)]
+    pub history_retention_days: Option<u32>,
+    /// 数据库文件位置，为空时使用默认的数据目录，通过 `move_database` 命令修改
+    #[serde(default)]
+    pub database_path: Option<PathBuf>,
+    /// 翻译前先查找最近相同原文的历史译文，找到时直接使用，不调用 API
+    #[serde(default)]
+    pub reuse_history: bool,
+    /// 本应用的日志级别，通过 `set_log_level` 命令修改，设置了 RUST_LOG 时启动时以环境变量为准
+    #[serde(default)]
+    pub log_level: LogLevel,
+    /// 翻译结束后剪贴板的处理方式
+    #[serde(default)]
+    pub clipboard: ClipboardConfig,
+    /// 模拟按键和剪贴板操作的等待时间预设，`Custom` 时使用 `timing`
+    #[serde(default)]
+    pub performance_preset: PerformancePreset,
+    /// 自定义的等待时间，只在 `performance_preset` 为 `Custom` 时使用
+    #[serde(default)]
+    pub timing: TimingConfig,
+    /// 登录系统时自动启动，保存配置时同步到系统登录项，读取时以系统的实际状态为准
+    #[serde(default)]
+    pub launch_at_login: bool,
+    /// 不触发翻译的应用，前台应用匹配其中任意一项时忽略翻译热键
+    #[serde(default)]
+    pub excluded_apps: Vec<ExcludedApp>,
+    /// 原文匹配 `redaction_patterns` 时的处理方式
+    #[serde(default)]
+    pub redaction_mode: RedactionMode,
+    /// 不能发送给 LLM 的内容，每项为一个正则表达式
+    #[serde(default = "redaction::default_patterns")]
+    pub redaction_patterns: Vec<String>,
+    /// 当前版本不认识的字段
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
+fn default_history_dedup_secs() -> u64 {
+    DEFAULT_HISTORY_DEDUP_SECS
+}
+
+/// 重复翻译记录的默认去重窗口（秒）
+pub const DEFAULT_HISTORY_DEDUP_SECS: u64 = 60;
+
+/// 复用历史译文时只查找该时间（秒）内的记录
+pub const HISTORY_REUSE_MAX_AGE_SECS: u64 = 30 * 24 * 3600;
+
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
+            config_version: CONFIG_VERSION,
             llm: LLMConfig::default(),
+            profiles: vec![NamedLLMConfig::default()],
+            active_profile: DEFAULT_PROFILE.to_string(),
             hotkey: HotkeyConfig::default(),
             language: LanguageConfig::default(),
             history_limit: 500,
+            history_dedup_secs: DEFAULT_HISTORY_DEDUP_SECS,
+            history_mode: HistoryMode::default(),
+            history_encryption: false,
+            history_retention_days: None,
+            database_path: None,
+            reuse_history: false,
+            log_level: LogLevel::default(),
+            clipboard: ClipboardConfig::default(),
+            performance_preset: PerformancePreset::default(),
+            timing: TimingConfig::default(),
+            launch_at_login: false,
+            excluded_apps: Vec::new(),
+            redaction_mode: RedactionMode::default(),
+            redaction_patterns: redaction::default_patterns(),
+            extra: serde_json::Map::new(),
+        }
+    }
+}
+
+/// 导出配置时代替 API Key 的占位文本
+pub const REDACTED_API_KEY: &str = "<redacted>";
+
+/// 返回给前端显示的 API Key 掩码，只保留开头和结尾几位
+pub fn mask_api_key(api_key: &str) -> String {
+    let chars: Vec<char> = api_key.chars().collect();
+    if chars.is_empty() {
+        return String::new();
+    }
+    if chars.len() <= 12 {
+        return "•".repeat(8);
+    }
+    let head: String = chars[..3].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}••••{}", head, tail)
+}
+
+impl AppConfig {
+    /// 导出为 JSON，`include_secrets` 为 false 时用占位文本代替 API Key
+    ///
+    /// 数据库位置只对本机有效，不会导出。
+    pub fn to_export_json(&self, include_secrets: bool) -> Result<String> {
+        let mut config = self.clone();
+        config.sync_active_profile();
+        if !include_secrets {
+            for profile in &mut config.profiles {
+                if !profile.llm.api_key.is_empty() {
+                    profile.llm.api_key = REDACTED_API_KEY.to_string();
+                }
+            }
+        }
+        config.database_path = None;
+        config.to_file_json()
+    }
+
+    /// 保存到配置文件的 JSON，不包含由当前方案得到的 `llm`
+    pub fn to_file_json(&self) -> Result<String> {
+        // 经过字符串转换，避免 f32 转为 `Value` 时出现多余的小数位
+        let mut value: serde_json::Value = serde_json::from_str(&serde_json::to_string(self)?)?;
+        if let Some(object) = value.as_object_mut() {
+            object.remove("llm");
+        }
+        Ok(serde_json::to_string_pretty(&value)?)
+    }
+
+    /// 返回给前端的配置，所有方案的 API Key 替换为掩码
+    pub fn with_masked_api_key(&self) -> Self {
+        let mut config = self.clone();
+        config.llm.api_key = mask_api_key(&config.llm.api_key);
+        for profile in &mut config.profiles {
+            profile.llm.api_key = mask_api_key(&profile.llm.api_key);
+        }
+        config
+    }
+
+    /// 前端回传的 API Key 仍是掩码时恢复 `current` 中同名方案的 API Key
+    pub fn restore_api_keys(&mut self, current: &AppConfig) {
+        if let Some(existing) = current.profile(&self.active_profile) {
+            self.llm.restore_api_key(&existing.llm.api_key);
+        }
+        for profile in &mut self.profiles {
+            if let Some(existing) = current.profile(&profile.name) {
+                profile.llm.restore_api_key(&existing.llm.api_key);
+            }
+        }
+    }
+
+    /// 校验配置，返回所有问题，有 `Error` 级别的问题时不应保存
+    ///
+    /// 当前方案以 `llm` 为准，只校验 `llm`。
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = self.llm.issues("llm");
+        for (i, profile) in self.profiles.iter().enumerate() {
+            let field = format!("profiles[{}]", i);
+            if profile.name.trim().is_empty() {
+                issues.push(ValidationIssue::error(
+                    format!("{}.name", field),
+                    "方案名称不能为空",
+                ));
+            } else if self.profiles[..i]
+                .iter()
+                .any(|other| other.name == profile.name)
+            {
+                issues.push(ValidationIssue::error(
+                    format!("{}.name", field),
+                    format!("方案名称重复: {}", profile.name),
+                ));
+            }
+            if profile.name != self.active_profile {
+                issues.extend(profile.llm.issues(&field));
+            }
+        }
+
+        if self.history_limit == 0 || self.history_limit > MAX_HISTORY_LIMIT {
+            issues.push(ValidationIssue::error(
+                "history_limit",
+                format!(
+                    "历史记录条数限制必须在 1 到 {} 之间，不保存历史请使用隐私模式",
+                    MAX_HISTORY_LIMIT
+                ),
+            ));
+        }
+        if let Some(days) = self.history_retention_days {
+            if days == 0 || days > MAX_HISTORY_RETENTION_DAYS {
+                issues.push(ValidationIssue::error(
+                    "history_retention_days",
+                    format!(
+                        "历史记录保留天数必须在 1 到 {} 之间，不限制请留空",
+                        MAX_HISTORY_RETENTION_DAYS
+                    ),
+                ));
+            }
+        }
+
+        issues.extend(self.clipboard.issues());
+        if self.performance_preset == PerformancePreset::Custom {
+            issues.extend(self.timing.issues());
+        }
+        for (i, app) in self.excluded_apps.iter().enumerate() {
+            let field = format!("excluded_apps[{}].identifier", i);
+            if app.identifier.trim().is_empty() {
+                issues.push(ValidationIssue::error(field, "排除应用的标识不能为空"));
+            } else if self.excluded_apps[..i]
+                .iter()
+                .any(|other| other.identifier.eq_ignore_ascii_case(&app.identifier))
+            {
+                issues.push(ValidationIssue::warning(
+                    field,
+                    format!("排除应用重复: {}", app.identifier),
+                ));
+            }
+        }
+
+        for (i, pattern) in self.redaction_patterns.iter().enumerate() {
+            let field = format!("redaction_patterns[{}]", i);
+            if pattern.is_empty() {
+                issues.push(ValidationIssue::error(field, "过滤规则不能为空"));
+            } else if let Err(e) = regex::Regex::new(pattern) {
+                issues.push(ValidationIssue::error(
+                    field,
+                    format!("过滤规则不是有效的正则表达式: {}", e),
+                ));
+            }
+        }
+        if self.redaction_mode != RedactionMode::Off && self.redaction_patterns.is_empty() {
+            issues.push(ValidationIssue::warning(
+                "redaction_patterns",
+                "没有设置过滤规则，不会过滤任何内容",
+            ));
+        }
+
+        issues.extend(self.language.issues());
+        for issue in self.hotkey.issues() {
+            let field = format!("hotkey.{}", issue.binding);
+            issues.push(if issue.blocking {
+                ValidationIssue::error(field, issue.message)
+            } else {
+                ValidationIssue::warning(field, issue.message)
+            });
+        }
+        issues
+    }
+
+    /// 前台应用 `app` 匹配的排除应用，匹配规则见 `ExcludedApp::matches`
+    pub fn excluded_app(&self, app: &str) -> Option<&ExcludedApp> {
+        self.excluded_apps
+            .iter()
+            .find(|excluded| excluded.matches(app))
+    }
+
+    /// 添加排除应用，名称为空时使用标识
+    pub fn add_excluded_app(&mut self, identifier: &str, name: &str) -> Result<()> {
+        let identifier = identifier.trim();
+        if identifier.is_empty() {
+            return Err(AppError::Config("排除应用的标识不能为空".to_string()));
+        }
+        if self
+            .excluded_apps
+            .iter()
+            .any(|app| app.identifier.eq_ignore_ascii_case(identifier))
+        {
+            return Err(AppError::Config(format!("{} 已在排除列表中", identifier)));
+        }
+
+        let name = name.trim();
+        self.excluded_apps.push(ExcludedApp {
+            identifier: identifier.to_string(),
+            name: if name.is_empty() { identifier } else { name }.to_string(),
+        });
+        Ok(())
+    }
+
+    /// 移除排除应用，标识不区分大小写
+    pub fn remove_excluded_app(&mut self, identifier: &str) -> Result<()> {
+        let identifier = identifier.trim();
+        let index = self
+            .excluded_apps
+            .iter()
+            .position(|app| app.identifier.eq_ignore_ascii_case(identifier))
+            .ok_or_else(|| AppError::Config(format!("{} 不在排除列表中", identifier)))?;
+        self.excluded_apps.remove(index);
+        Ok(())
+    }
+
+    /// 当前预设对应的等待时间，`Custom` 时为用户设置的 `timing`
+    pub fn effective_timing(&self) -> TimingConfig {
+        self.performance_preset.timing().unwrap_or(self.timing)
+    }
+
+    /// 名称为 `name` 的方案
+    pub fn profile(&self, name: &str) -> Option<&NamedLLMConfig> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    /// 由当前方案得到 `llm`
+    ///
+    /// 没有任何方案时加入默认方案，`active_profile` 不存在时切换到第一个方案。
+    pub fn resolve_active_profile(&mut self) {
+        if self.profiles.is_empty() {
+            self.profiles.push(NamedLLMConfig::default());
+        }
+        let index = match self
+            .profiles
+            .iter()
+            .position(|profile| profile.name == self.active_profile)
+        {
+            Some(index) => index,
+            None => {
+                self.active_profile = self.profiles[0].name.clone();
+                0
+            }
+        };
+        self.llm = self.profiles[index].llm.clone();
+    }
+
+    /// 将 `llm` 写回当前方案，当前方案不存在时新建
+    pub fn sync_active_profile(&mut self) {
+        if self.active_profile.is_empty() {
+            self.active_profile = DEFAULT_PROFILE.to_string();
+        }
+        match self
+            .profiles
+            .iter_mut()
+            .find(|profile| profile.name == self.active_profile)
+        {
+            Some(profile) => profile.llm = self.llm.clone(),
+            None => self.profiles.push(NamedLLMConfig {
+                name: self.active_profile.clone(),
+                llm: self.llm.clone(),
+            }),
+        }
+    }
+
+    /// 切换当前方案
+    pub fn set_active_profile(&mut self, name: &str) -> Result<()> {
+        self.sync_active_profile();
+        let llm = self
+            .profile(name)
+            .map(|profile| profile.llm.clone())
+            .ok_or_else(|| AppError::Config(format!("LLM 配置方案不存在: {}", name)))?;
+        self.active_profile = name.to_string();
+        self.llm = llm;
+        Ok(())
+    }
+
+    /// 新增方案或替换同名方案，替换当前方案时同时更新 `llm`
+    pub fn save_profile(&mut self, mut profile: NamedLLMConfig) -> Result<()> {
+        profile.name = profile.name.trim().to_string();
+        if profile.name.is_empty() {
+            return Err(AppError::Config("方案名称不能为空".to_string()));
+        }
+        self.sync_active_profile();
+        if profile.name == self.active_profile {
+            self.llm = profile.llm.clone();
+        }
+        match self
+            .profiles
+            .iter_mut()
+            .find(|existing| existing.name == profile.name)
+        {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+        Ok(())
+    }
+
+    /// 删除方案，删除当前方案时切换到剩余的第一个方案
+    pub fn delete_profile(&mut self, name: &str) -> Result<()> {
+        self.sync_active_profile();
+        let index = self
+            .profiles
+            .iter()
+            .position(|profile| profile.name == name)
+            .ok_or_else(|| AppError::Config(format!("LLM 配置方案不存在: {}", name)))?;
+        if self.profiles.len() == 1 {
+            return Err(AppError::Config(
+                "至少需要保留一个 LLM 配置方案".to_string(),
+            ));
+        }
+        self.profiles.remove(index);
+        self.resolve_active_profile();
+        Ok(())
+    }
+
+    /// 保留当前的数据库位置和历史加密状态
+    ///
+    /// 这两项与本机数据相关，只能通过 `move_database` 和 `set_history_encryption` 修改，
+    /// 前端保存、导入或恢复的配置中的值可能已经过期，与数据库的实际状态不一致。
+    pub fn keep_database_settings(&mut self, current: &AppConfig) {
+        self.database_path = current.database_path.clone();
+        self.history_encryption = current.history_encryption;
+    }
+
+    /// 生成恢复默认设置后的配置
+    ///
+    /// `keep_api_key` 保留各方案的 API 地址、API Key 和模型，`keep_languages` 保留语言设置。
+    /// 数据库位置和历史加密状态与本机数据相关，始终保留。
+    pub fn reset_from(&self, keep_api_key: bool, keep_languages: bool) -> Self {
+        let mut current = self.clone();
+        current.sync_active_profile();
+        let mut config = AppConfig::default();
+        if keep_api_key {
+            config.profiles = current
+                .profiles
+                .iter()
+                .map(|profile| NamedLLMConfig {
+                    name: profile.name.clone(),
+                    llm: LLMConfig {
+                        base_url: profile.llm.base_url.clone(),
+                        api_key: profile.llm.api_key.clone(),
+                        model: profile.llm.model.clone(),
+                        ..Default::default()
+                    },
+                })
+                .collect();
+            config.active_profile = current.active_profile;
+            config.resolve_active_profile();
+        }
+        if keep_languages {
+            config.language = self.language.clone();
+        }
+        config.keep_database_settings(self);
+        config
+    }
+
+    /// 解析配置文件，先将旧版本的配置升级到当前版本
+    ///
+    /// 没有版本号且没有 `llm` 字段的文件不是当前格式，返回错误，由旧配置迁移处理。
+    pub fn from_json(content: &str) -> Result<Self> {
+        let mut value: serde_json::Value = serde_json::from_str(content)?;
+        let Some(object) = value.as_object_mut() else {
+            return Err(AppError::Config("配置文件格式无效".to_string()));
+        };
+        if !object.contains_key("config_version")
+            && !object.get("llm").is_some_and(|llm| llm.is_object())
+        {
+            return Err(AppError::Config("不是当前格式的配置文件".to_string()));
+        }
+        upgrade_config_json(object);
+        let mut config: AppConfig = serde_json::from_value(value)?;
+        config.resolve_active_profile();
+        Ok(config)
+    }
+
+    /// 解析导入的配置，缺少的字段使用默认值
+    ///
+    /// 导入文件中方案的 API Key 为空或为占位文本时保留 `current` 中同名方案的 API Key；
+    /// 数据库位置和历史加密状态与本机相关，也保持 `current` 中的值。
+    pub fn from_import_json(content: &str, current: &AppConfig) -> Result<Self> {
+        let mut imported: serde_json::Value = serde_json::from_str(content)?;
+        let Some(object) = imported.as_object_mut() else {
+            return Err(AppError::Config("配置文件格式无效".to_string()));
+        };
+        upgrade_config_json(object);
+
+        let mut merged = serde_json::to_value(AppConfig::default())?;
+        merge_config_value(&mut merged, imported, 2);
+        let mut config: AppConfig = serde_json::from_value(merged)?;
+
+        let mut current = current.clone();
+        current.sync_active_profile();
+        for profile in &mut config.profiles {
+            let api_key = &profile.llm.api_key;
+            if api_key.is_empty()
+                || api_key == REDACTED_API_KEY
+                || api_key == crate::secrets::STORED_API_KEY
+            {
+                profile.llm.api_key = current
+                    .profile(&profile.name)
+                    .map(|existing| existing.llm.api_key.clone())
+                    .unwrap_or_default();
+            }
+        }
+        config.resolve_active_profile();
+        config.keep_database_settings(&current);
+        Ok(config)
+    }
+}
+
+/// 将 `overlay` 的字段合并到 `base`，只合并 `depth` 层对象，更深的值整体替换
+///
+/// 热键等枚举值不能逐字段合并，否则可能混入默认值中其他类型的字段。
+fn merge_config_value(base: &mut serde_json::Value, overlay: serde_json::Value, depth: usize) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) if depth > 0 => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_config_value(existing, value, depth - 1),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// 当前的配置格式版本
+///
+/// 修改字段名或字段位置时增加版本号，并在 `CONFIG_UPGRADES` 末尾加入对应的升级函数。
+/// 只新增带默认值的字段不需要增加版本号。
+pub const CONFIG_VERSION: u32 = 3;
+
+/// 配置升级函数，第 i 个函数将版本 i 的配置升级到版本 i + 1
+const CONFIG_UPGRADES: &[fn(&mut serde_json::Map<String, serde_json::Value>)] = &[
+    // 版本 0：没有版本号的配置，结构与版本 1 相同
+    |_| {},
+    upgrade_llm_to_profiles,
+    upgrade_split_stream_mode,
+];
+
+/// 版本 1 → 2：单个 `llm` 配置改为名为 "default" 的方案
+fn upgrade_llm_to_profiles(config: &mut serde_json::Map<String, serde_json::Value>) {
+    let Some(mut llm) = config.remove("llm") else {
+        return;
+    };
+    if let Some(fields) = llm.as_object_mut() {
+        fields.insert("name".to_string(), DEFAULT_PROFILE.into());
+    }
+    config.insert("profiles".to_string(), serde_json::Value::Array(vec![llm]));
+    config.insert("active_profile".to_string(), DEFAULT_PROFILE.into());
+}
+
+/// 版本 2 → 3：各方案的 `stream_mode` 拆分为选中翻译和全文翻译两个开关
+fn upgrade_split_stream_mode(config: &mut serde_json::Map<String, serde_json::Value>) {
+    let Some(profiles) = config.get_mut("profiles").and_then(|p| p.as_array_mut()) else {
+        return;
+    };
+    for profile in profiles.iter_mut().filter_map(|p| p.as_object_mut()) {
+        split_stream_mode(profile);
+    }
+}
+
+/// 将 LLM 配置中旧的 `stream_mode` 同时用于两种翻译模式
+fn split_stream_mode(llm: &mut serde_json::Map<String, serde_json::Value>) {
+    if let Some(stream) = llm.remove("stream_mode") {
+        for field in ["stream_selected", "stream_full"] {
+            llm.entry(field).or_insert_with(|| stream.clone());
+        }
+    }
+}
+
+/// 将配置 JSON 升级到当前版本，返回升级前的版本号
+///
+/// 没有版本号的配置视为版本 0。比当前版本新的配置不做修改，保留其版本号，
+/// 避免使用旧版本保存后再升级时重复执行升级函数。
+pub fn upgrade_config_json(config: &mut serde_json::Map<String, serde_json::Value>) -> u32 {
+    let version = config
+        .get("config_version")
+        .and_then(|version| version.as_u64())
+        .map_or(0, |version| version.min(u32::MAX as u64) as u32);
+    if version > CONFIG_VERSION {
+        tracing::warn!(
+            "Config version {} is newer than supported version {}",
+            version,
+            CONFIG_VERSION
+        );
+        return version;
+    }
+    for upgrade in &CONFIG_UPGRADES[version as usize..] {
+        upgrade(config);
+    }
+    config.insert("config_version".to_string(), CONFIG_VERSION.into());
+    version
+}
+
+/// 旧版本可能使用的配置目录名（位于系统配置目录下），按优先级排序
+const LEGACY_CONFIG_DIRS: &[&str] = &[
+    "QuickTransType",
+    "com.QuickTransType.app",
+    "quick-trans-type",
+    "quicktranstype",
+];
+
+/// 旧版本可能使用的配置文件名
+const LEGACY_CONFIG_FILES: &[&str] = &["config.json", "settings.json", "store.json"];
+
+/// 旧版本平铺在顶层的字段名及其在当前配置中的位置
+const LEGACY_FIELDS: &[(&str, &str)] = &[
+    ("base_url", "/llm/base_url"),
+    ("api_base", "/llm/base_url"),
+    ("api_key", "/llm/api_key"),
+    ("model", "/llm/model"),
+    ("temperature", "/llm/temperature"),
+    ("top_p", "/llm/top_p"),
+    ("system_prompt", "/llm/system_prompt"),
+    ("user_prompt_template", "/llm/user_prompt_template"),
+    ("prompt_template", "/llm/user_prompt_template"),
+    ("stream_mode", "/llm/stream_selected"),
+    ("stream_mode", "/llm/stream_full"),
+    ("current_target", "/language/current_target"),
+    ("target_language", "/language/current_target"),
+    ("favorite_languages", "/language/favorite_languages"),
+    ("selected_hotkey", "/hotkey/selected_mode"),
+    ("full_hotkey", "/hotkey/full_mode"),
+];
+
+/// `config_dir` 下存在的旧版本配置文件，不包含当前的配置文件
+pub fn legacy_config_candidates(config_dir: &Path, current: &Path) -> Vec<PathBuf> {
+    LEGACY_CONFIG_DIRS
+        .iter()
+        .flat_map(|dir| {
+            LEGACY_CONFIG_FILES
+                .iter()
+                .map(move |file| config_dir.join(dir).join(file))
+        })
+        .filter(|path| path != current && path.is_file())
+        .collect()
+}
+
+/// 解析旧版本或部分损坏的配置，无法识别任何字段时返回 `None`
+///
+/// 支持旧版本平铺在顶层的字段和 store 插件 `{"config": {...}}` 形式的文件。
+/// 当前格式的字段逐个合并，无效的字段使用默认值，不会导致整个文件被丢弃。
+pub fn parse_legacy_config(content: &str) -> Option<AppConfig> {
+    let mut legacy: serde_json::Value = serde_json::from_str(content).ok()?;
+    for key in ["config", "settings"] {
+        if legacy.get("llm").is_none() && legacy.get(key).is_some_and(|v| v.is_object()) {
+            legacy = legacy[key].take();
+        }
+    }
+    if let Some(llm) = legacy.get_mut("llm").and_then(|llm| llm.as_object_mut()) {
+        split_stream_mode(llm);
+    }
+    let legacy = legacy.as_object()?;
+
+    let mut merged = serde_json::to_value(AppConfig::default()).ok()?;
+    let mut recognized = 0;
+    let mut apply = |merged: &mut serde_json::Value, candidate: serde_json::Value| {
+        if serde_json::from_value::<AppConfig>(candidate.clone()).is_ok() {
+            *merged = candidate;
+            recognized += 1;
+        }
+    };
+
+    let current_keys: Vec<String> = merged.as_object()?.keys().cloned().collect();
+    for key in current_keys {
+        match legacy.get(&key) {
+            Some(serde_json::Value::Object(section)) if merged[&key].is_object() => {
+                for (field, value) in section {
+                    let mut candidate = merged.clone();
+                    candidate[&key][field] = value.clone();
+                    apply(&mut merged, candidate);
+                }
+            }
+            Some(value) => {
+                let mut candidate = merged.clone();
+                candidate[&key] = value.clone();
+                apply(&mut merged, candidate);
+            }
+            None => {}
+        }
+    }
+    for (legacy_key, pointer) in LEGACY_FIELDS {
+        if let Some(value) = legacy.get(*legacy_key) {
+            let mut candidate = merged.clone();
+            if let Some(slot) = candidate.pointer_mut(pointer) {
+                *slot = value.clone();
+                apply(&mut merged, candidate);
+            }
+        }
+    }
+
+    if recognized == 0 {
+        return None;
+    }
+    let mut config: AppConfig = serde_json::from_value(merged).ok()?;
+    if legacy.contains_key("profiles") {
+        config.resolve_active_profile();
+    } else {
+        config.sync_active_profile();
+    }
+    Some(config)
+}
+
+/// 依次读取 `sources`，返回第一个能识别的旧版本配置及其路径
+pub fn load_legacy_config(sources: &[PathBuf]) -> Option<(AppConfig, PathBuf)> {
+    sources.iter().find_map(|source| {
+        let content = std::fs::read_to_string(source).ok()?;
+        parse_legacy_config(&content).map(|config| (config, source.clone()))
+    })
+}
+
+/// 将已迁移的旧配置文件重命名为 `.bak`，返回新路径
+pub fn archive_legacy_config(source: &Path) -> Result<PathBuf> {
+    let backup = path_with_suffix(source, ".bak");
+    if backup.exists() {
+        std::fs::remove_file(&backup)?;
+    }
+    std::fs::rename(source, &backup)?;
+    Ok(backup)
+}
+
+/// 将无法解析的配置文件移到一旁，避免下次保存时被覆盖，返回新路径
+pub fn stash_broken_config(path: &Path) -> Result<PathBuf> {
+    let suffix = format!(".broken-{}", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    let stashed = path_with_suffix(path, &suffix);
+    std::fs::rename(path, &stashed)?;
+    Ok(stashed)
+}
+
+/// 迁移的结果
+#[derive(Debug, Clone)]
+pub struct LegacyMigration {
+    pub config: AppConfig,
+    /// 迁移来源
+    pub source: PathBuf,
+    /// 来源文件重命名后的路径
+    pub backup: PathBuf,
+}
+
+/// 从 `sources` 中第一个能识别的旧配置迁移，写入 `config_path` 并将来源保留为 `.bak`
+///
+/// 来源可以是 `config_path` 本身（当前位置上的旧格式文件）。没有可迁移的配置时返回 `None`。
+pub fn migrate_legacy_config(
+    sources: &[PathBuf],
+    config_path: &Path,
+) -> Result<Option<LegacyMigration>> {
+    let Some((config, source)) = load_legacy_config(sources) else {
+        return Ok(None);
+    };
+    let backup = archive_legacy_config(&source)?;
+    if let Some(parent) = config_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(config_path, config.to_file_json()?)?;
+    Ok(Some(LegacyMigration {
+        config,
+        source,
+        backup,
+    }))
+}
+
+/// 在文件名后追加后缀，如 config.json → config.json.bak
+fn path_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// 日志级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    #[default]
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    /// 解析 error/warn/info/debug/trace，忽略大小写，其他输入返回 `None`
+    pub fn parse(level: &str) -> Option<Self> {
+        match level.trim().to_ascii_lowercase().as_str() {
+            "error" => Some(Self::Error),
+            "warn" => Some(Self::Warn),
+            "info" => Some(Self::Info),
+            "debug" => Some(Self::Debug),
+            "trace" => Some(Self::Trace),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warn => "warn",
+            Self::Info => "info",
+            Self::Debug => "debug",
+            Self::Trace => "trace",
+        }
+    }
+
+    /// 日志过滤指令，级别只作用于本应用，Tauri 的日志保持 info
+    pub fn filter_directive(self) -> String {
+        format!("quick_trans_type={},tauri=info", self.as_str())
+    }
+}
+
+/// 翻译历史的保存方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryMode {
+    /// 保存原文、译文和性能指标
+    #[default]
+    Full,
+    /// 只保存耗时、字符数等性能指标，不保存任何文本
+    MetricsOnly,
+    /// 不保存任何记录
+    Off,
+}
+
+impl HistoryMode {
+    /// 是否保存原文和译文
+    pub fn records_text(self) -> bool {
+        self == Self::Full
+    }
+
+    /// 是否保存性能指标
+    pub fn records_metrics(self) -> bool {
+        self != Self::Off
+    }
+
+    /// 托盘菜单的隐私模式开关：在完整保存和只保存性能指标之间切换
+    pub fn toggle_privacy(self) -> Self {
+        match self {
+            Self::Full => Self::MetricsOnly,
+            Self::MetricsOnly | Self::Off => Self::Full,
+        }
+    }
+}
+
+/// 原文包含不能发送给 LLM 的内容时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionMode {
+    /// 不检查原文
+    #[default]
+    Off,
+    /// 取消翻译并通知前端
+    Abort,
+    /// 用占位符代替匹配的内容，收到译文后放回原来的内容
+    Mask,
+}
+
+/// 模拟按键和剪贴板操作的等待时间预设
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PerformancePreset {
+    /// 等待时间最短，适合响应快的应用
+    Fast,
+    /// 默认的等待时间
+    #[default]
+    Balanced,
+    /// 等待时间较长，适合复制粘贴反应慢或经常丢字的应用
+    Compatible,
+    /// 使用 `AppConfig::timing` 中的设置
+    Custom,
+}
+
+impl PerformancePreset {
+    /// 预设的等待时间，`Custom` 返回 `None`
+    ///
+    /// 调整预设时修改这里，`test_performance_presets` 中列出了每个预设的全部数值。
+    pub fn timing(self) -> Option<TimingConfig> {
+        Some(match self {
+            Self::Fast => TimingConfig {
+                clipboard_settle_ms: 20,
+                select_all_delay_ms: 80,
+                clipboard_poll_ms: 50,
+                clipboard_retries: 3,
+                key_delay_ms: 20,
+                type_chunk_chars: 200,
+                type_chunk_delay_ms: 5,
+            },
+            Self::Balanced => TimingConfig::default(),
+            Self::Compatible => TimingConfig {
+                clipboard_settle_ms: 100,
+                select_all_delay_ms: 300,
+                clipboard_poll_ms: 200,
+                clipboard_retries: 5,
+                key_delay_ms: 100,
+                type_chunk_chars: 20,
+                type_chunk_delay_ms: 30,
+            },
+            Self::Custom => return None,
+        })
+    }
+}
+
+/// 模拟按键和剪贴板操作的等待时间（毫秒）及逐段输入的分段大小
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimingConfig {
+    /// 写入或清空剪贴板后等待生效的时间
+    pub clipboard_settle_ms: u64,
+    /// 模拟全选后等待选中的时间
+    pub select_all_delay_ms: u64,
+    /// 模拟复制后第一次读取剪贴板前的等待时间，之后每次重试增加一半
+    pub clipboard_poll_ms: u64,
+    /// 模拟复制后等待剪贴板变化的最大重试次数
+    pub clipboard_retries: u32,
+    /// 模拟按键后等待应用响应的时间
+    pub key_delay_ms: u64,
+    /// 逐段输入译文时每段的字符数
+    pub type_chunk_chars: usize,
+    /// 逐段输入时每段之间的等待时间
+    pub type_chunk_delay_ms: u64,
+}
+
+impl Default for TimingConfig {
+    fn default() -> Self {
+        Self {
+            clipboard_settle_ms: 50,
+            select_all_delay_ms: 150,
+            clipboard_poll_ms: 100,
+            clipboard_retries: 3,
+            key_delay_ms: 50,
+            type_chunk_chars: 50,
+            type_chunk_delay_ms: 10,
+        }
+    }
+}
+
+/// 自定义等待时间的上限（毫秒），超过时提示
+const MAX_TIMING_DELAY_MS: u64 = 2000;
+
+impl TimingConfig {
+    pub fn clipboard_settle(&self) -> Duration {
+        Duration::from_millis(self.clipboard_settle_ms)
+    }
+
+    pub fn select_all_delay(&self) -> Duration {
+        Duration::from_millis(self.select_all_delay_ms)
+    }
+
+    /// 第 `attempt` 次（从 0 开始）读取剪贴板前的等待时间
+    pub fn clipboard_poll(&self, attempt: u32) -> Duration {
+        Duration::from_millis(self.clipboard_poll_ms + attempt as u64 * self.clipboard_poll_ms / 2)
+    }
+
+    pub fn key_delay(&self) -> Duration {
+        Duration::from_millis(self.key_delay_ms)
+    }
+
+    pub fn type_chunk_delay(&self) -> Duration {
+        Duration::from_millis(self.type_chunk_delay_ms)
+    }
+
+    /// 校验自定义等待时间，分段大小和重试次数不能为 0
+    pub fn issues(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        if self.type_chunk_chars == 0 {
+            issues.push(ValidationIssue::error(
+                "timing.type_chunk_chars",
+                "每段输入的字符数至少为 1",
+            ));
+        }
+        if self.clipboard_retries == 0 {
+            issues.push(ValidationIssue::error(
+                "timing.clipboard_retries",
+                "读取剪贴板的重试次数至少为 1",
+            ));
+        }
+        for (field, delay) in [
+            ("clipboard_settle_ms", self.clipboard_settle_ms),
+            ("select_all_delay_ms", self.select_all_delay_ms),
+            ("clipboard_poll_ms", self.clipboard_poll_ms),
+            ("key_delay_ms", self.key_delay_ms),
+            ("type_chunk_delay_ms", self.type_chunk_delay_ms),
+        ] {
+            if delay > MAX_TIMING_DELAY_MS {
+                issues.push(ValidationIssue::warning(
+                    format!("timing.{}", field),
+                    format!("等待时间超过 {} 毫秒，翻译会明显变慢", MAX_TIMING_DELAY_MS),
+                ));
+            }
+        }
+        issues
+    }
+}
+
+/// 翻译结束后剪贴板的处理方式
+///
+/// 默认不做处理，剪贴板中保留粘贴译文时写入的内容。同时开启多项时按以下顺序处理：
+/// 翻译到剪贴板始终保留译文；`restore_original` 优先于 `keep_translation_on_clipboard`；
+/// `clear_after_secs` 只清除本次翻译留在剪贴板中的译文，不会清除恢复的原剪贴板内容。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClipboardConfig {
+    /// 翻译结束后恢复翻译前的剪贴板内容
+    pub restore_original: bool,
+    /// 翻译结束后将完整译文留在剪贴板中，流式输出时剪贴板中原本只有最后一个片段
+    pub keep_translation_on_clipboard: bool,
+    /// 译文留在剪贴板中超过该秒数后清除，期间剪贴板内容已改变时不清除，为空表示不清除
+    pub clear_after_secs: Option<u64>,
+}
+
+/// 翻译结束后对剪贴板的操作，由 `ClipboardConfig::action` 得到
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardAction {
+    /// 保持粘贴后的剪贴板内容
+    Leave,
+    /// 写入完整译文
+    KeepTranslation,
+    /// 恢复翻译前的剪贴板内容
+    RestoreOriginal,
+}
+
+impl ClipboardConfig {
+    /// 翻译结束后的剪贴板操作，`to_clipboard` 为翻译到剪贴板模式
+    pub fn action(&self, to_clipboard: bool) -> ClipboardAction {
+        if to_clipboard {
+            ClipboardAction::KeepTranslation
+        } else if self.restore_original {
+            ClipboardAction::RestoreOriginal
+        } else if self.keep_translation_on_clipboard {
+            ClipboardAction::KeepTranslation
+        } else {
+            ClipboardAction::Leave
+        }
+    }
+
+    /// 执行 `action` 后清除剪贴板的等待时间，恢复原剪贴板内容时不清除
+    pub fn clear_after(&self, action: ClipboardAction) -> Option<Duration> {
+        if action == ClipboardAction::RestoreOriginal {
+            return None;
+        }
+        self.clear_after_secs.map(Duration::from_secs)
+    }
+
+    /// 校验清除时间及互相冲突的选项
+    pub fn issues(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        if self.clear_after_secs == Some(0) {
+            issues.push(ValidationIssue::error(
+                "clipboard.clear_after_secs",
+                "清除剪贴板的等待时间至少为 1 秒",
+            ));
+        }
+        if self.restore_original && self.keep_translation_on_clipboard {
+            issues.push(ValidationIssue::warning(
+                "clipboard.keep_translation_on_clipboard",
+                "已开启恢复原剪贴板内容，除翻译到剪贴板外译文不会保留在剪贴板中",
+            ));
+        }
+        issues
+    }
+}
+
+/// 不触发翻译的应用
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExcludedApp {
+    /// 应用标识：macOS 为 bundle id (如 com.apple.Terminal)，Windows 为进程名 (如 slack.exe)
+    pub identifier: String,
+    /// 显示名称
+    pub name: String,
+}
+
+impl ExcludedApp {
+    /// 前台应用 `app` 是否匹配，不区分大小写
+    ///
+    /// 包含 `.` 的标识视为完整的 bundle id 或进程名，必须完全相同；不包含 `.` 的标识
+    /// 视为关键字，出现在 `app` 中即匹配，如 `slack` 匹配 `com.tinyspeck.slackmacgap`。
+    pub fn matches(&self, app: &str) -> bool {
+        let identifier = self.identifier.trim().to_ascii_lowercase();
+        let app = app.trim().to_ascii_lowercase();
+        if identifier.is_empty() {
+            return false;
+        }
+        if identifier.contains('.') {
+            identifier == app
+        } else {
+            app.contains(&identifier)
         }
     }
 }
 
 /// LLM 配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LLMConfig {
     /// API Base URL
     pub base_url: String,
@@ -44,9 +1123,68 @@ pub struct LLMConfig {
     pub system_prompt: String,
     /// User Prompt 模板，支持 {target_language} 和 {text} 变量
     pub user_prompt_template: String,
-    /// 是否使用流式传输模式
+    /// 选中翻译是否使用流式输出，逐字输入译文
     #[serde(default = "default_stream_mode")]
-    pub stream_mode: bool,
+    pub stream_selected: bool,
+    /// 全文翻译是否使用流式输出
+    #[serde(default = "default_stream_mode")]
+    pub stream_full: bool,
+    /// 原文超过该字符数时不使用流式输出，一次性粘贴译文，为空表示不限制
+    #[serde(default)]
+    pub stream_min_chars: Option<usize>,
+    /// 各模型的价格，按模型名称查找，未配置的模型不计算费用
+    #[serde(default)]
+    pub pricing: HashMap<String, ModelPrice>,
+    /// 当前版本不认识的字段
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// 默认方案的名称，旧版本的单个 LLM 配置迁移为该方案
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// 命名的 LLM 配置方案
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NamedLLMConfig {
+    /// 方案名称
+    pub name: String,
+    #[serde(flatten)]
+    pub llm: LLMConfig,
+}
+
+/// LLM 配置方案列表
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileList {
+    /// 当前使用的方案名称
+    pub active_profile: String,
+    pub profiles: Vec<NamedLLMConfig>,
+}
+
+impl Default for NamedLLMConfig {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_PROFILE.to_string(),
+            llm: LLMConfig::default(),
+        }
+    }
+}
+
+/// 模型价格（美元 / 百万 tokens）
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+impl ModelPrice {
+    /// 按 tokens 数量计算费用（美元）
+    pub fn cost_usd(&self, prompt_tokens: u32, completion_tokens: u32) -> f64 {
+        (prompt_tokens as f64 * self.input_per_million
+            + completion_tokens as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
 }
 
 fn default_stream_mode() -> bool {
@@ -66,18 +1204,515 @@ impl Default for LLMConfig {
                     .to_string(),
             user_prompt_template: "将下列文本翻译为{target_language}，保持原有格式：{text}"
                 .to_string(),
-            stream_mode: true,
+            stream_selected: true,
+            stream_full: true,
+            stream_min_chars: None,
+            pricing: HashMap::new(),
+            extra: serde_json::Map::new(),
         }
     }
 }
 
-/// 热键配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HotkeyConfig {
+impl LLMConfig {
+    /// 翻译 `char_count` 个字符的原文时是否使用流式输出
+    ///
+    /// `mode` 为 selected 或 full，其他模式（如翻译到剪贴板）不修改原文，始终不使用流式输出。
+    pub fn use_stream(&self, mode: &str, char_count: usize) -> bool {
+        let enabled = match mode {
+            "selected" => self.stream_selected,
+            "full" => self.stream_full,
+            _ => false,
+        };
+        enabled
+            && self
+                .stream_min_chars
+                .is_none_or(|limit| char_count <= limit)
+    }
+
+    /// 校验数值范围、API 地址和 User Prompt 模板，`field` 为问题路径的前缀
+    pub fn issues(&self, field: &str) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        if !(0.0..=2.0).contains(&self.temperature) {
+            issues.push(ValidationIssue::error(
+                format!("{}.temperature", field),
+                "Temperature 必须在 0 到 2 之间",
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.top_p) {
+            issues.push(ValidationIssue::error(
+                format!("{}.top_p", field),
+                "Top P 必须在 0 到 1 之间",
+            ));
+        }
+        if self.model.trim().is_empty() {
+            issues.push(ValidationIssue::error(
+                format!("{}.model", field),
+                "模型名称不能为空",
+            ));
+        }
+        if self.stream_min_chars == Some(0) {
+            issues.push(ValidationIssue::error(
+                format!("{}.stream_min_chars", field),
+                "流式输出的字符数上限至少为 1，不使用流式输出请关闭对应模式的开关",
+            ));
+        }
+
+        let base_url_field = format!("{}.base_url", field);
+        let mut local = false;
+        match reqwest::Url::parse(self.base_url.trim()) {
+            Ok(url) if !matches!(url.scheme(), "http" | "https") => {
+                issues.push(ValidationIssue::error(
+                    base_url_field,
+                    "API 地址必须以 http:// 或 https:// 开头",
+                ));
+            }
+            Ok(url) => {
+                local = url.host_str().is_some_and(is_local_host);
+                if url.scheme() == "http" && !local {
+                    issues.push(ValidationIssue::warning(
+                        base_url_field,
+                        "API 地址未使用 HTTPS，API Key 将以明文传输",
+                    ));
+                }
+            }
+            Err(e) => {
+                issues.push(ValidationIssue::error(
+                    base_url_field,
+                    format!("API 地址无效: {}", e),
+                ));
+            }
+        }
+        if self.api_key.trim().is_empty() && !local {
+            issues.push(ValidationIssue::warning(
+                format!("{}.api_key", field),
+                "未设置 API Key",
+            ));
+        }
+
+        let prompt = validate_prompt_template(&self.user_prompt_template);
+        let template_field = format!("{}.user_prompt_template", field);
+        for issue in prompt.errors {
+            issues.push(ValidationIssue::error(&template_field, issue.message));
+        }
+        for issue in prompt.warnings {
+            issues.push(ValidationIssue::warning(&template_field, issue.message));
+        }
+        issues
+    }
+
+    /// 前端回传的 API Key 仍是 `current` 的掩码时恢复实际的 API Key
+    pub fn restore_api_key(&mut self, current: &str) {
+        if self.api_key == mask_api_key(current) {
+            self.api_key = current.to_string();
+        }
+    }
+
+    /// 当前模型的价格，未配置时为 `None`
+    pub fn price(&self) -> Option<ModelPrice> {
+        self.pricing.get(&self.model).copied()
+    }
+}
+
+/// User Prompt 模板支持的变量
+pub const PROMPT_PLACEHOLDERS: &[&str] = &["text", "target_language"];
+
+/// User Prompt 模板的最大长度（字符）
+pub const MAX_PROMPT_TEMPLATE_CHARS: usize = 4000;
+
+/// 提示模板校验问题的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptIssueCode {
+    /// 缺少 {text}，译文请求中不会包含原文
+    MissingText,
+    /// {text} 出现多次，原文会被重复发送
+    DuplicateText,
+    /// 缺少 {target_language}
+    MissingTargetLanguage,
+    /// 不支持的变量，会按原样发送
+    UnknownPlaceholder,
+    /// 模板过长
+    TooLong,
+}
+
+impl PromptIssueCode {
+    /// 是否阻止保存配置；缺少目标语言和未知变量只作提示
+    pub fn is_blocking(self) -> bool {
+        !matches!(
+            self,
+            PromptIssueCode::MissingTargetLanguage | PromptIssueCode::UnknownPlaceholder
+        )
+    }
+}
+
+/// 提示模板校验发现的问题
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptIssue {
+    /// 问题类型
+    pub code: PromptIssueCode,
+    /// 问题描述
+    pub message: String,
+    /// 未知变量的名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+}
+
+impl PromptIssue {
+    fn new(code: PromptIssueCode, message: String) -> Self {
+        Self {
+            code,
+            message,
+            placeholder: None,
+        }
+    }
+}
+
+/// 提示模板的校验结果，`errors` 中的问题阻止保存，`warnings` 只作提示
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PromptValidation {
+    pub errors: Vec<PromptIssue>,
+    pub warnings: Vec<PromptIssue>,
+}
+
+impl PromptValidation {
+    /// 是否可以保存
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    fn push(&mut self, issue: PromptIssue) {
+        if issue.code.is_blocking() {
+            self.errors.push(issue);
+        } else {
+            self.warnings.push(issue);
+        }
+    }
+}
+
+/// 校验 User Prompt 模板的变量和长度
+pub fn validate_prompt_template(template: &str) -> PromptValidation {
+    let mut result = PromptValidation::default();
+    let placeholders = template_placeholders(template);
+    let count = |name: &str| placeholders.iter().filter(|p| p.as_str() == name).count();
+
+    match count("text") {
+        0 => result.push(PromptIssue::new(
+            PromptIssueCode::MissingText,
+            "模板中缺少 {text}，原文不会发送给模型".to_string(),
+        )),
+        1 => {}
+        n => result.push(PromptIssue::new(
+            PromptIssueCode::DuplicateText,
+            format!("{{text}} 出现了 {} 次，只能出现一次", n),
+        )),
+    }
+    if count("target_language") == 0 {
+        result.push(PromptIssue::new(
+            PromptIssueCode::MissingTargetLanguage,
+            "模板中缺少 {target_language}，切换目标语言不会生效".to_string(),
+        ));
+    }
+
+    let mut unknown: Vec<&str> = Vec::new();
+    for name in &placeholders {
+        if !PROMPT_PLACEHOLDERS.contains(&name.as_str()) && !unknown.contains(&name.as_str()) {
+            unknown.push(name);
+        }
+    }
+    for name in unknown {
+        result.push(PromptIssue {
+            code: PromptIssueCode::UnknownPlaceholder,
+            message: format!("不支持的变量 {{{}}}，会按原样发送", name),
+            placeholder: Some(name.to_string()),
+        });
+    }
+
+    let length = template.chars().count();
+    if length > MAX_PROMPT_TEMPLATE_CHARS {
+        result.push(PromptIssue::new(
+            PromptIssueCode::TooLong,
+            format!(
+                "模板长度为 {} 个字符，不能超过 {}",
+                length, MAX_PROMPT_TEMPLATE_CHARS
+            ),
+        ));
+    }
+    result
+}
+
+/// 模板中 `{name}` 形式的变量名，只识别由字母、数字和下划线组成的名称
+fn template_placeholders(template: &str) -> Vec<String> {
+    let mut placeholders = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')) else {
+            break;
+        };
+        if end > 0 && rest[end..].starts_with('}') {
+            placeholders.push(rest[..end].to_string());
+            rest = &rest[end + 1..];
+        }
+    }
+    placeholders
+}
+
+/// 历史记录条数限制的上限
+pub const MAX_HISTORY_LIMIT: usize = 100_000;
+
+/// 历史记录保留天数的上限
+pub const MAX_HISTORY_RETENTION_DAYS: u32 = 3650;
+
+/// 配置问题的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSeverity {
+    /// 阻止保存配置
+    Error,
+    /// 仅作提示，不影响保存
+    Warning,
+}
+
+/// 配置校验发现的问题
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue {
+    /// 问题所在字段的路径，如 "llm.temperature"、"profiles[1].base_url"
+    pub field: String,
+    pub severity: IssueSeverity,
+    /// 问题描述
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn error(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            severity: IssueSeverity::Error,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn warning(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            severity: IssueSeverity::Warning,
+            message: message.into(),
+        }
+    }
+
+    /// 是否阻止保存配置
+    pub fn is_error(&self) -> bool {
+        self.severity == IssueSeverity::Error
+    }
+}
+
+/// 是否为本机地址，本机服务（如 Ollama）不需要 HTTPS 和 API Key
+fn is_local_host(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost") || host.starts_with("127.") || host == "[::1]"
+}
+
+/// 热键配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HotkeyConfig {
     /// 选中翻译模式的热键
     pub selected_mode: Hotkey,
     /// 全文翻译模式的热键
     pub full_mode: Hotkey,
+    /// 连续按键触发后如何处理已经输入的触发键
+    /// 新配置默认删除，旧版本配置缺少该字段时保留，与升级前的行为一致
+    #[serde(default = "legacy_trigger_keys")]
+    pub trigger_keys: TriggerKeyHandling,
+    /// 翻译选中文本并将译文写入剪贴板的热键，不修改原文，未设置时不注册
+    #[serde(default)]
+    pub clipboard_mode: Option<Hotkey>,
+    /// 切换启用/暂停状态的热键，未设置时不注册
+    #[serde(default)]
+    pub toggle_enabled: Option<Hotkey>,
+    /// 设置窗口获得焦点时是否仍然响应热键，默认暂停以免在设置中误触发
+    #[serde(default)]
+    pub active_in_settings: bool,
+    /// 连续按键按输入的字符还是按物理按键位置匹配
+    #[serde(default)]
+    pub key_matching: KeyMatching,
+    /// 同一热键重复触发的去抖窗口（毫秒），窗口内的重复触发会被忽略，0 表示不去抖
+    #[serde(default = "default_debounce_ms")]
+    pub debounce_ms: u64,
+    /// 当前版本不认识的字段
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+fn default_debounce_ms() -> u64 {
+    DEFAULT_DEBOUNCE_MS
+}
+
+fn legacy_trigger_keys() -> TriggerKeyHandling {
+    TriggerKeyHandling::Keep
+}
+
+/// 连续按键的匹配方式
+///
+/// AZERTY、Dvorak 等布局下物理按键与输入的字符不一致。按字符匹配时以当前布局
+/// 实际输入的字符为准；修饰键、回车等不产生字符的按键始终按物理按键匹配。
+/// 组合键由全局快捷键插件按物理按键注册，不受此设置影响。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyMatching {
+    /// 按当前键盘布局输入的字符匹配
+    #[default]
+    Character,
+    /// 按 QWERTY 布局下的物理按键位置匹配
+    Physical,
+}
+
+/// 连续按键触发键的处理方式
+///
+/// 连续按键（如三击空格）会把按键内容输入到当前文本中，触发后可以选择删除。
+/// 通过 `rdev::grab` 拦截按键需要额外的权限，暂不提供。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TriggerKeyHandling {
+    /// 翻译前发送相同次数的退格，删除输入的触发键
+    #[default]
+    Backspace,
+    /// 保留输入的触发键
+    Keep,
+}
+
+impl HotkeyConfig {
+    /// 规范化热键配置中的数值参数
+    pub fn normalize(&mut self) {
+        self.selected_mode.clamp_interval();
+        self.full_mode.clamp_interval();
+        for hotkey in [&mut self.clipboard_mode, &mut self.toggle_enabled]
+            .into_iter()
+            .flatten()
+        {
+            hotkey.clamp_interval();
+        }
+    }
+
+    /// 验证热键配置：各热键必须使用不同的触发方式
+    pub fn validate(&self) -> Result<()> {
+        match self.issues().into_iter().find(|issue| issue.blocking) {
+            Some(issue) => Err(AppError::Hotkey(issue.message)),
+            None => Ok(()),
+        }
+    }
+
+    /// 检查所有热键绑定，返回发现的问题
+    ///
+    /// 包括按键设置、全局快捷键支持、修饰键要求、内部冲突、系统快捷键冲突和
+    /// 常见应用快捷键冲突，其中系统和应用快捷键冲突不阻止保存。
+    pub fn issues(&self) -> Vec<HotkeyIssue> {
+        let mut issues = Vec::new();
+
+        for (id, label, hotkey) in self.bindings() {
+            // 选中翻译和翻译到剪贴板作用于选中文本，不能占用普通按键
+            let needs_modifier = matches!(id, "selected_mode" | "clipboard_mode");
+            if needs_modifier && !hotkey.validate_for_selected_mode() {
+                issues.push(HotkeyIssue::new(
+                    id,
+                    HotkeyIssueCode::ModifierRequired,
+                    format!("{}的组合键必须包含修饰键", label),
+                ));
+            }
+
+            if let Some(reason) = hotkey.key_error() {
+                issues.push(HotkeyIssue::new(
+                    id,
+                    HotkeyIssueCode::InvalidKey,
+                    format!("{}热键无效: {}", label, reason),
+                ));
+            } else if let Hotkey::Combination { key, .. }
+            | Hotkey::Chord {
+                first: KeyCombination { key, .. },
+                ..
+            } = hotkey
+            {
+                if shortcut_code(key).is_none() {
+                    issues.push(HotkeyIssue::new(
+                        id,
+                        HotkeyIssueCode::UnsupportedKey,
+                        format!("{}热键无效: 全局快捷键不支持按键 {}", label, key),
+                    ));
+                }
+            }
+        }
+
+        issues.extend(
+            HotkeyManager::check_internal_conflicts(self)
+                .into_iter()
+                .map(HotkeyIssue::from),
+        );
+
+        for (id, label, hotkey) in self.bindings() {
+            if hotkey.key().is_some_and(is_caps_lock_key) {
+                issues.push(HotkeyIssue::new(
+                    id,
+                    HotkeyIssueCode::CapsLockToggle,
+                    format!(
+                        "{}使用大写锁定键触发时仍会切换大小写状态，如需避免请在系统设置中停用该键",
+                        label
+                    ),
+                ));
+            }
+
+            for name in HotkeyManager::check_system_conflicts(hotkey) {
+                issues.push(HotkeyIssue::new(
+                    id,
+                    HotkeyIssueCode::SystemConflict,
+                    format!("{}与系统快捷键冲突: {}", label, name),
+                ));
+            }
+
+            for warning in HotkeyManager::check_app_conflicts(hotkey) {
+                issues.push(HotkeyIssue::new(
+                    id,
+                    HotkeyIssueCode::AppConflict,
+                    format!("{}{}", label, warning),
+                ));
+            }
+        }
+
+        issues
+    }
+
+    /// 按标识替换单个热键绑定，标识未知时返回 false
+    pub fn set_binding(&mut self, id: &str, hotkey: Hotkey) -> bool {
+        match id {
+            "selected_mode" => self.selected_mode = hotkey,
+            "full_mode" => self.full_mode = hotkey,
+            "clipboard_mode" => self.clipboard_mode = Some(hotkey),
+            "toggle_enabled" => self.toggle_enabled = Some(hotkey),
+            _ => return false,
+        }
+        true
+    }
+
+    /// 列出所有热键绑定：(标识, 显示名称, 热键)
+    /// 新增热键（如语言切换热键）时需要加入此列表，才能参与内部冲突检测
+    pub fn bindings(&self) -> Vec<(&'static str, &'static str, &Hotkey)> {
+        let mut bindings = vec![
+            ("selected_mode", "选中翻译", &self.selected_mode),
+            ("full_mode", "全文翻译", &self.full_mode),
+        ];
+        if let Some(clipboard) = &self.clipboard_mode {
+            bindings.push(("clipboard_mode", "翻译到剪贴板", clipboard));
+        }
+        if let Some(toggle) = &self.toggle_enabled {
+            bindings.push(("toggle_enabled", "暂停/启用", toggle));
+        }
+        bindings
+    }
+
+    /// 是否有热键需要 rdev 键盘监听（依赖 macOS 输入监控权限）
+    pub fn uses_key_listener(&self) -> bool {
+        self.bindings()
+            .iter()
+            .any(|(_, _, hotkey)| hotkey.uses_key_listener())
+    }
 }
 
 impl Default for HotkeyConfig {
@@ -93,6 +1728,52 @@ impl Default for HotkeyConfig {
                 modifiers: vec!["Control".to_string()],
                 key: "j".to_string(),
             },
+            trigger_keys: TriggerKeyHandling::default(),
+            clipboard_mode: None,
+            toggle_enabled: None,
+            active_in_settings: false,
+            key_matching: KeyMatching::default(),
+            debounce_ms: DEFAULT_DEBOUNCE_MS,
+            extra: serde_json::Map::new(),
+        }
+    }
+}
+
+/// 热键重复触发的默认去抖窗口（毫秒）
+pub const DEFAULT_DEBOUNCE_MS: u64 = 400;
+/// 连续按键默认间隔（毫秒）
+pub const DEFAULT_CONSECUTIVE_INTERVAL_MS: u64 = 300;
+/// 连续按键允许的最小间隔（毫秒）
+pub const MIN_CONSECUTIVE_INTERVAL_MS: u64 = 100;
+/// 连续按键允许的最大间隔（毫秒）
+pub const MAX_CONSECUTIVE_INTERVAL_MS: u64 = 2000;
+/// 两步组合键默认等待第二步按键的时长（毫秒）
+pub const DEFAULT_CHORD_TIMEOUT_MS: u64 = 1000;
+/// 两步组合键允许的最短等待时长（毫秒）
+pub const MIN_CHORD_TIMEOUT_MS: u64 = 300;
+/// 两步组合键允许的最长等待时长（毫秒）
+pub const MAX_CHORD_TIMEOUT_MS: u64 = 3000;
+
+fn default_chord_timeout_ms() -> u64 {
+    DEFAULT_CHORD_TIMEOUT_MS
+}
+
+/// 组合键的修饰键和主键，用作两步组合键的第一步
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyCombination {
+    /// 修饰键列表 (Meta, Control, Alt, Shift)
+    pub modifiers: Vec<String>,
+    /// 主键
+    pub key: String,
+}
+
+impl KeyCombination {
+    /// 转换为对应的组合键热键
+    pub fn to_hotkey(&self) -> Hotkey {
+        Hotkey::Combination {
+            modifiers: self.modifiers.clone(),
+            key: self.key.clone(),
         }
     }
 }
@@ -114,49 +1795,327 @@ pub enum Hotkey {
         key: String,
         /// 按键次数
         count: u8,
+        /// 两次按键的最大间隔（毫秒），未设置时使用默认值
+        #[serde(default)]
+        interval_ms: Option<u64>,
+    },
+    /// 长按组合键 (如 按住 Ctrl+Space 600ms)
+    Hold {
+        /// 修饰键列表 (Meta, Control, Alt, Shift)
+        modifiers: Vec<String>,
+        /// 主键
+        key: String,
+        /// 需要按住的时长（毫秒）
+        hold_ms: u64,
+    },
+    /// 两步组合键 (如 Ctrl+K 之后按 L)
+    Chord {
+        /// 第一步的组合键，注册为全局快捷键
+        first: KeyCombination,
+        /// 第二步按键，由 rdev 监听器检测
+        second_key: String,
+        /// 第一步之后等待第二步按键的时长（毫秒）
+        #[serde(default = "default_chord_timeout_ms")]
+        timeout_ms: u64,
+    },
+    /// 鼠标按键连击 (如 双击鼠标侧键 4)
+    MouseButton {
+        /// 按键编号：1 左键、2 右键、3 中键、4/5 侧键
+        button: u8,
+        /// 点击次数
+        clicks: u8,
+        /// 两次点击的最大间隔（毫秒）
+        interval_ms: u64,
     },
 }
 
 impl Hotkey {
-    /// 验证选中模式热键是否有效（必须包含修饰键）
+    /// 验证选中模式热键是否有效（组合键必须包含修饰键）
     pub fn validate_for_selected_mode(&self) -> bool {
         match self {
-            Hotkey::Combination { modifiers, .. } => !modifiers.is_empty(),
-            Hotkey::Consecutive { .. } => false, // 选中模式不支持连续按键
+            Hotkey::Combination { modifiers, key } => {
+                !modifiers.is_empty() || is_dedicated_trigger_key(key)
+            }
+            Hotkey::Consecutive { .. } | Hotkey::MouseButton { .. } => true,
+            Hotkey::Hold { modifiers, .. } => !modifiers.is_empty(),
+            Hotkey::Chord { first, .. } => first.to_hotkey().validate_for_selected_mode(),
+        }
+    }
+
+    /// 热键使用的键盘按键，鼠标按键热键返回 None
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            Hotkey::Combination { key, .. }
+            | Hotkey::Consecutive { key, .. }
+            | Hotkey::Hold { key, .. } => Some(key),
+            Hotkey::Chord { first, .. } => Some(&first.key),
+            Hotkey::MouseButton { .. } => None,
+        }
+    }
+
+    /// 检查热键的按键设置，返回不合法的原因
+    pub fn key_error(&self) -> Option<&'static str> {
+        match self {
+            Hotkey::Consecutive { key, count, .. } => {
+                if key.is_empty() {
+                    Some("连续按键不能为空")
+                } else if *count < 2 {
+                    Some("连续按键次数至少为 2")
+                } else {
+                    None
+                }
+            }
+            Hotkey::Combination { key, .. } | Hotkey::Hold { key, .. } => {
+                if is_modifier_key_name(key) {
+                    // 修饰键单独作为主键时无法注册，只能用于连续按键
+                    Some("修饰键只能用作连续按键的触发键")
+                } else if keycode_from_name(key).is_none() {
+                    Some("不支持的按键")
+                } else {
+                    None
+                }
+            }
+            Hotkey::Chord {
+                first, second_key, ..
+            } => {
+                if let Some(reason) = first.to_hotkey().key_error() {
+                    Some(reason)
+                } else if second_key.is_empty() {
+                    Some("第二步按键不能为空")
+                } else if is_modifier_key_name(second_key) {
+                    Some("第二步按键不能是修饰键")
+                } else {
+                    None
+                }
+            }
+            Hotkey::MouseButton { button, clicks, .. } => {
+                if *button == 0 {
+                    Some("鼠标按键编号无效")
+                } else if *clicks == 0 {
+                    Some("鼠标点击次数至少为 1")
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// 是否通过 rdev 监听触发（连续按键、长按、鼠标按键和两步组合键的第二步）
+    pub fn uses_key_listener(&self) -> bool {
+        matches!(
+            self,
+            Hotkey::Consecutive { .. }
+                | Hotkey::Hold { .. }
+                | Hotkey::MouseButton { .. }
+                | Hotkey::Chord { .. }
+        )
+    }
+
+    /// 获取连续按键的有效间隔（毫秒），限制在允许范围内
+    pub fn consecutive_interval_ms(&self) -> Option<u64> {
+        match self {
+            Hotkey::Consecutive { interval_ms, .. } => Some(
+                interval_ms
+                    .unwrap_or(DEFAULT_CONSECUTIVE_INTERVAL_MS)
+                    .clamp(MIN_CONSECUTIVE_INTERVAL_MS, MAX_CONSECUTIVE_INTERVAL_MS),
+            ),
+            _ => None,
+        }
+    }
+
+    /// 将连续按键、鼠标连击间隔和两步组合键的等待时长限制在允许范围内
+    pub fn clamp_interval(&mut self) {
+        let clamped = self.consecutive_interval_ms();
+        match self {
+            Hotkey::Consecutive { interval_ms, .. } => *interval_ms = clamped,
+            Hotkey::MouseButton { interval_ms, .. } => {
+                *interval_ms =
+                    (*interval_ms).clamp(MIN_CONSECUTIVE_INTERVAL_MS, MAX_CONSECUTIVE_INTERVAL_MS);
+            }
+            Hotkey::Chord { timeout_ms, .. } => {
+                *timeout_ms = (*timeout_ms).clamp(MIN_CHORD_TIMEOUT_MS, MAX_CHORD_TIMEOUT_MS);
+            }
+            _ => {}
+        }
+    }
+
+    /// 判断两个热键是否由同一按键触发
+    /// 组合键、长按和两步组合键的第一步使用相同组合时视为相同（第一步只能注册一次）；连续按键只比较按键本身，鼠标只比较按键编号
+    pub fn same_trigger(&self, other: &Hotkey) -> bool {
+        match (self.chord(), other.chord()) {
+            (Some((mods1, key1)), Some((mods2, key2))) => {
+                HotkeyManager::hotkeys_match(mods1, key1, mods2, key2)
+            }
+            _ => match (self, other) {
+                (Hotkey::Consecutive { key: key1, .. }, Hotkey::Consecutive { key: key2, .. }) => {
+                    normalize_key_name(key1) == normalize_key_name(key2)
+                }
+                (
+                    Hotkey::MouseButton {
+                        button: button1, ..
+                    },
+                    Hotkey::MouseButton {
+                        button: button2, ..
+                    },
+                ) => button1 == button2,
+                _ => false,
+            },
+        }
+    }
+
+    /// 获取组合键部分（修饰键和主键），两步组合键取第一步
+    fn chord(&self) -> Option<(&[String], &str)> {
+        match self {
+            Hotkey::Combination { modifiers, key } | Hotkey::Hold { modifiers, key, .. } => {
+                Some((modifiers, key))
+            }
+            Hotkey::Chord { first, .. } => Some((&first.modifiers, &first.key)),
+            Hotkey::Consecutive { .. } | Hotkey::MouseButton { .. } => None,
         }
     }
 
     /// 格式化热键显示
     pub fn format(&self) -> String {
         match self {
+            Hotkey::Combination { modifiers, key } if modifiers.is_empty() => key.to_uppercase(),
             Hotkey::Combination { modifiers, key } => {
-                let mod_str = modifiers
-                    .iter()
-                    .map(|m| match m.as_str() {
-                        "Meta" => "Cmd",
-                        "Control" => "Ctrl",
-                        "Alt" => "Option",
-                        other => other,
-                    })
-                    .collect::<Vec<_>>()
-                    .join(" + ");
-                format!("{} + {}", mod_str, key.to_uppercase())
-            }
-            Hotkey::Consecutive { key, count } => {
+                format!("{} + {}", format_modifiers(modifiers), key.to_uppercase())
+            }
+            Hotkey::Consecutive { key, count, .. } => {
+                if let Some(symbol) = modifier_symbol(key) {
+                    return format!("{} × {}", symbol, count);
+                }
                 let key_name = if key == " " { "Space" } else { key };
                 format!("{} × {}", key_name.to_uppercase(), count)
             }
+            Hotkey::Hold {
+                modifiers,
+                key,
+                hold_ms,
+            } => {
+                let key_name = if key == " " { "Space" } else { key };
+                format!(
+                    "{} + {} (长按 {}ms)",
+                    format_modifiers(modifiers),
+                    key_name.to_uppercase(),
+                    hold_ms
+                )
+            }
+            Hotkey::Chord {
+                first, second_key, ..
+            } => {
+                let key_name = if second_key == " " {
+                    "Space"
+                } else {
+                    second_key
+                };
+                format!(
+                    "{}, {}",
+                    first.to_hotkey().format(),
+                    key_name.to_uppercase()
+                )
+            }
+            Hotkey::MouseButton { button, clicks, .. } => {
+                if *clicks > 1 {
+                    format!("Mouse{} × {}", button, clicks)
+                } else {
+                    format!("Mouse{}", button)
+                }
+            }
         }
     }
 }
 
+/// 不需要修饰键、可以单独作为组合键的专用按键（扩展键盘上的 F13–F19）
+/// 这些按键没有系统默认用途，不会与其他应用冲突
+pub const DEDICATED_TRIGGER_KEYS: &[&str] = &["F13", "F14", "F15", "F16", "F17", "F18", "F19"];
+
+/// 判断按键是否为专用触发键
+pub fn is_dedicated_trigger_key(key: &str) -> bool {
+    DEDICATED_TRIGGER_KEYS
+        .iter()
+        .any(|k| k.eq_ignore_ascii_case(key.trim()))
+}
+
+/// 可以作为连续按键触发键的修饰键名称
+/// 通用名称（如 "Shift"）匹配左右两侧，带方向的名称只匹配对应一侧
+pub const MODIFIER_TRIGGER_KEYS: &[&str] = &[
+    "Shift",
+    "ShiftLeft",
+    "ShiftRight",
+    "Control",
+    "ControlLeft",
+    "ControlRight",
+    "Alt",
+    "AltLeft",
+    "AltRight",
+    "Meta",
+    "MetaLeft",
+    "MetaRight",
+    "Fn",
+];
+
+/// 判断按键名称是否为修饰键
+pub fn is_modifier_key_name(key: &str) -> bool {
+    MODIFIER_TRIGGER_KEYS.contains(&key)
+}
+
+/// 判断按键名称是否为大写锁定键：单击切换大小写状态，不产生输入
+pub fn is_caps_lock_key(key: &str) -> bool {
+    normalize_key_name(key) == "capslock"
+}
+
+/// 修饰键作为触发键时的显示符号
+fn modifier_symbol(key: &str) -> Option<String> {
+    let (side, base) = match key.strip_suffix("Right") {
+        Some(base) => ("右", base),
+        None => ("", key.strip_suffix("Left").unwrap_or(key)),
+    };
+    let symbol = match base {
+        "Shift" => "⇧",
+        "Control" => "⌃",
+        "Alt" => "⌥",
+        "Meta" => "⌘",
+        "Fn" => "fn",
+        _ => return None,
+    };
+    Some(format!("{}{}", side, symbol))
+}
+
+/// 格式化修饰键列表
+fn format_modifiers(modifiers: &[String]) -> String {
+    modifiers
+        .iter()
+        .map(|m| match m.as_str() {
+            "Meta" => "Cmd",
+            "Control" => "Ctrl",
+            "Alt" => "Option",
+            other => other,
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
 /// 语言配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LanguageConfig {
     /// 当前目标语言
     pub current_target: String,
     /// 常用语言列表
     pub favorite_languages: Vec<Language>,
+    /// 切换前的目标语言，互换语言时切回该语言
+    #[serde(default)]
+    pub previous_target: Option<String>,
+    /// 按目标语言代码设置的提示词，代替全局提示词
+    #[serde(default)]
+    pub prompt_overrides: HashMap<String, PromptOverride>,
+    /// 按检测到的原文语言选择目标语言的规则，按顺序使用第一条匹配的规则
+    #[serde(default)]
+    pub language_pairs: Vec<LanguagePair>,
+    /// 当前版本不认识的字段
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 impl Default for LanguageConfig {
@@ -189,64 +2148,2061 @@ impl Default for LanguageConfig {
                     name: "Español".to_string(),
                 },
             ],
+            previous_target: None,
+            prompt_overrides: HashMap::new(),
+            language_pairs: Vec::new(),
+            extra: serde_json::Map::new(),
         }
     }
 }
 
-/// 语言信息
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Language {
-    /// 语言代码 (如 en-US)
-    pub code: String,
-    /// 语言名称 (如 English)
-    pub name: String,
-}
+impl LanguageConfig {
+    /// 校验常用语言、目标语言、各语言的提示词和原文语言规则
+    pub fn issues(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        if self.favorite_languages.is_empty() {
+            issues.push(ValidationIssue::error(
+                "language.favorite_languages",
+                "常用语言不能为空",
+            ));
+        }
+        let mut favorites = Vec::new();
+        for (i, language) in self.favorite_languages.iter().enumerate() {
+            let field = format!("language.favorite_languages[{}].code", i);
+            match known_language_code(&language.code) {
+                Ok(code) if favorites.contains(&code) => {
+                    issues.push(ValidationIssue::warning(
+                        field,
+                        format!("常用语言重复: {}", code),
+                    ));
+                }
+                Ok(code) => favorites.push(code),
+                Err(e) => issues.push(ValidationIssue::error(field, e.to_string())),
+            }
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        match known_language_code(&self.current_target) {
+            Ok(code) if !favorites.is_empty() && !favorites.contains(&code) => {
+                issues.push(ValidationIssue::error(
+                    "language.current_target",
+                    "当前目标语言不在常用语言中",
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => issues.push(ValidationIssue::error(
+                "language.current_target",
+                e.to_string(),
+            )),
+        }
 
-    #[test]
-    fn test_hotkey_format() {
-        let hotkey = Hotkey::Combination {
-            modifiers: vec!["Meta".to_string(), "Shift".to_string()],
-            key: "t".to_string(),
-        };
-        assert_eq!(hotkey.format(), "Cmd + Shift + T");
+        let mut codes: Vec<&String> = self.prompt_overrides.keys().collect();
+        codes.sort();
+        for code in codes {
+            let field = format!("language.prompt_overrides.{}", code);
+            if let Err(e) = known_language_code(code) {
+                issues.push(ValidationIssue::error(&field, e.to_string()));
+            }
+            let template = self.prompt_overrides[code]
+                .user_prompt_template
+                .as_deref()
+                .filter(|template| !template.trim().is_empty());
+            if let Some(template) = template {
+                let prompt = validate_prompt_template(template);
+                let field = format!("{}.user_prompt_template", field);
+                for issue in prompt.errors {
+                    issues.push(ValidationIssue::error(&field, issue.message));
+                }
+                for issue in prompt.warnings {
+                    issues.push(ValidationIssue::warning(&field, issue.message));
+                }
+            }
+        }
 
-        let hotkey = Hotkey::Consecutive {
-            key: " ".to_string(),
-            count: 3,
-        };
-        assert_eq!(hotkey.format(), "SPACE × 3");
+        for (i, pair) in self.language_pairs.iter().enumerate() {
+            let field = format!("language.language_pairs[{}]", i);
+            let source = known_language_code(&pair.source);
+            let target = known_language_code(&pair.target);
+            if let Err(e) = &source {
+                issues.push(ValidationIssue::error(
+                    format!("{}.source", field),
+                    e.to_string(),
+                ));
+            }
+            if let Err(e) = &target {
+                issues.push(ValidationIssue::error(
+                    format!("{}.target", field),
+                    e.to_string(),
+                ));
+            }
+            let (Ok(source), Ok(target)) = (source, target) else {
+                continue;
+            };
+            if same_language(&source, &target) {
+                issues.push(ValidationIssue::warning(
+                    format!("{}.target", field),
+                    format!("原文语言与目标语言相同: {}", target),
+                ));
+            } else if self.language_pairs[..i]
+                .iter()
+                .any(|other| same_language(&other.source, &source))
+            {
+                issues.push(ValidationIssue::warning(
+                    format!("{}.source", field),
+                    format!("{} 已有规则，只使用第一条", source),
+                ));
+            }
+        }
+        issues
     }
 
-    #[test]
-    fn test_hotkey_validation() {
-        let valid = Hotkey::Combination {
-            modifiers: vec!["Meta".to_string()],
-            key: "t".to_string(),
-        };
-        assert!(valid.validate_for_selected_mode());
+    /// 本次翻译的目标语言
+    ///
+    /// `detected` 为检测到的原文语言，匹配某条规则时使用该规则的目标语言，未检测到或
+    /// 没有匹配的规则时使用 `current_target`。不修改 `current_target`。
+    pub fn effective_target(&self, detected: Option<&str>) -> &str {
+        detected
+            .and_then(|source| {
+                self.language_pairs
+                    .iter()
+                    .find(|pair| same_language(&pair.source, source))
+            })
+            .map_or(&self.current_target, |pair| &pair.target)
+    }
 
-        let invalid = Hotkey::Combination {
-            modifiers: vec![],
-            key: "t".to_string(),
-        };
-        assert!(!invalid.validate_for_selected_mode());
+    /// 校验当前目标语言、常用语言和提示词的语言都在内置语言列表中，并将代码统一为列表中的写法
+    pub fn validate(&mut self) -> Result<()> {
+        self.current_target = known_language_code(&self.current_target)?;
+        for language in &mut self.favorite_languages {
+            language.code = known_language_code(&language.code)?;
+        }
+        // 上一个目标语言只用于互换，无效时直接丢弃
+        self.previous_target = self
+            .previous_target
+            .as_deref()
+            .and_then(|code| known_language_code(code).ok());
+        self.prompt_overrides = std::mem::take(&mut self.prompt_overrides)
+            .into_iter()
+            .map(|(code, prompt)| Ok((known_language_code(&code)?, prompt)))
+            .collect::<Result<_>>()?;
+        for pair in &mut self.language_pairs {
+            pair.source = known_language_code(&pair.source)?;
+            pair.target = known_language_code(&pair.target)?;
+        }
+        Ok(())
+    }
 
-        let consecutive = Hotkey::Consecutive {
-            key: " ".to_string(),
-            count: 3,
-        };
-        assert!(!consecutive.validate_for_selected_mode());
+    /// 切换目标语言，并记住切换前的语言
+    pub fn set_target(&mut self, code: String) {
+        if code != self.current_target {
+            self.previous_target = Some(std::mem::replace(&mut self.current_target, code));
+        }
     }
 
-    #[test]
-    fn test_default_config() {
-        let config = AppConfig::default();
-        assert_eq!(config.llm.model, "gpt-4o-mini");
-        assert_eq!(config.history_limit, 500);
+    /// 与当前生效的配置比较，目标语言发生变化时记住原来的目标语言
+    ///
+    /// 前端保存配置时不一定带上 `previous_target`，未提供时沿用当前配置中的值。
+    pub fn track_previous_target(&mut self, current: &LanguageConfig) {
+        if self.current_target != current.current_target {
+            self.previous_target = Some(current.current_target.clone());
+        } else if self.previous_target.is_none() {
+            self.previous_target = current.previous_target.clone();
+        }
+    }
+
+    /// 将目标语言切换为检测到的原文语言，返回新的目标语言
+    ///
+    /// 没有检测到原文语言或原文语言就是当前目标语言时，切换为上一个目标语言，
+    /// 因此连续互换会在原文语言和原来的目标语言之间来回切换。
+    pub fn swap_target(&mut self, source: Option<&str>) -> Result<String> {
+        let target = source
+            .filter(|code| !same_language(code, &self.current_target))
+            .map(str::to_string)
+            .or_else(|| {
+                self.previous_target
+                    .clone()
+                    .filter(|code| *code != self.current_target)
+            })
+            .ok_or_else(|| AppError::Config("还没有可以互换的语言".to_string()))?;
+        self.set_target(target);
+        Ok(self.current_target.clone())
+    }
+
+    /// 添加常用语言，名称为空时使用内置列表中的本地名称
+    pub fn add_favorite(&mut self, code: &str, name: &str) -> Result<()> {
+        let info = languages::find_language(code)
+            .ok_or_else(|| AppError::Config(format!("不支持的语言代码: {}", code)))?;
+        if self
+            .favorite_languages
+            .iter()
+            .any(|language| language.code == info.code)
+        {
+            return Err(AppError::Config(format!("{} 已在常用语言中", info.code)));
+        }
+
+        let name = name.trim();
+        self.favorite_languages.push(Language {
+            code: info.code.clone(),
+            name: if name.is_empty() {
+                info.native_name.clone()
+            } else {
+                name.to_string()
+            },
+        });
+        Ok(())
+    }
+
+    /// 移除常用语言，移除的是当前目标语言时切换到剩余的第一个语言
+    ///
+    /// 至少保留一个常用语言。
+    pub fn remove_favorite(&mut self, code: &str) -> Result<()> {
+        let index = self
+            .favorite_languages
+            .iter()
+            .position(|language| language.code.eq_ignore_ascii_case(code))
+            .ok_or_else(|| AppError::Config(format!("{} 不在常用语言中", code)))?;
+        if self.favorite_languages.len() == 1 {
+            return Err(AppError::Config("至少需要保留一个常用语言".to_string()));
+        }
+
+        let removed = self.favorite_languages.remove(index);
+        if removed.code == self.current_target {
+            self.current_target = self.favorite_languages[0].code.clone();
+        }
+        Ok(())
+    }
+
+    /// 按 `codes` 的顺序重新排列常用语言，`codes` 必须恰好包含每个常用语言一次
+    pub fn reorder_favorites(
+        &mut self,
+        codes: &[String],
+    ) -> std::result::Result<(), FavoriteOrderError> {
+        let mut error = FavoriteOrderError::default();
+        let mut used = vec![false; self.favorite_languages.len()];
+        let mut order = Vec::with_capacity(codes.len());
+        for code in codes {
+            match self
+                .favorite_languages
+                .iter()
+                .position(|language| language.code.eq_ignore_ascii_case(code))
+            {
+                None => error.unknown.push(code.clone()),
+                Some(index) if used[index] => error.duplicate.push(code.clone()),
+                Some(index) => {
+                    used[index] = true;
+                    order.push(index);
+                }
+            }
+        }
+        error.missing = self
+            .favorite_languages
+            .iter()
+            .zip(&used)
+            .filter(|(_, used)| !**used)
+            .map(|(language, _)| language.code.clone())
+            .collect();
+        if !error.is_empty() {
+            return Err(error);
+        }
+
+        self.favorite_languages = order
+            .into_iter()
+            .map(|index| self.favorite_languages[index].clone())
+            .collect();
+        Ok(())
+    }
+}
+
+/// 常用语言排序列表的问题，序列化后返回给前端
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FavoriteOrderError {
+    /// 不在常用语言中的代码
+    pub unknown: Vec<String>,
+    /// 列表中缺少的常用语言
+    pub missing: Vec<String>,
+    /// 重复出现的代码
+    pub duplicate: Vec<String>,
+}
+
+impl FavoriteOrderError {
+    fn is_empty(&self) -> bool {
+        self.unknown.is_empty() && self.missing.is_empty() && self.duplicate.is_empty()
+    }
+}
+
+/// 返回内置语言列表中对应的语言代码，未知代码返回错误
+pub fn known_language_code(code: &str) -> Result<String> {
+    languages::find_language(code)
+        .map(|language| language.code.clone())
+        .ok_or_else(|| AppError::Config(format!("不支持的语言代码: {}", code)))
+}
+
+/// 两个语言代码是否为同一语言，只比较主代码，如 en-GB 与 en-US 视为同一语言
+///
+/// 本地检测无法区分同一语言的地区变体，规则按主代码匹配检测结果。
+fn same_language(a: &str, b: &str) -> bool {
+    fn primary(code: &str) -> &str {
+        code.split(['-', '_']).next().unwrap_or_default()
+    }
+    primary(a).eq_ignore_ascii_case(primary(b))
+}
+
+/// 按原文语言选择目标语言的规则，如原文为英语时翻译为中文
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LanguagePair {
+    /// 原文语言代码
+    pub source: String,
+    /// 原文为 `source` 时使用的目标语言代码
+    pub target: String,
+}
+
+/// 某个目标语言的提示词，未设置或为空的部分使用全局提示词
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptOverride {
+    /// 代替全局的 System Prompt
+    pub system_prompt: Option<String>,
+    /// 代替全局的 User Prompt 模板
+    pub user_prompt_template: Option<String>,
+}
+
+/// 语言信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Language {
+    /// 语言代码 (如 en-US)
+    pub code: String,
+    /// 语言名称 (如 English)
+    pub name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hotkey_format() {
+        let hotkey = Hotkey::Combination {
+            modifiers: vec!["Meta".to_string(), "Shift".to_string()],
+            key: "t".to_string(),
+        };
+        assert_eq!(hotkey.format(), "Cmd + Shift + T");
+
+        let hotkey = Hotkey::Consecutive {
+            key: " ".to_string(),
+            count: 3,
+            interval_ms: None,
+        };
+        assert_eq!(hotkey.format(), "SPACE × 3");
+
+        let hotkey = Hotkey::Consecutive {
+            key: "Shift".to_string(),
+            count: 3,
+            interval_ms: None,
+        };
+        assert_eq!(hotkey.format(), "⇧ × 3");
+
+        let hotkey = Hotkey::Consecutive {
+            key: "MetaRight".to_string(),
+            count: 2,
+            interval_ms: None,
+        };
+        assert_eq!(hotkey.format(), "右⌘ × 2");
+
+        let hotkey = Hotkey::Hold {
+            modifiers: vec!["Control".to_string()],
+            key: " ".to_string(),
+            hold_ms: 600,
+        };
+        assert_eq!(hotkey.format(), "Ctrl + SPACE (长按 600ms)");
+
+        let hotkey = Hotkey::MouseButton {
+            button: 4,
+            clicks: 2,
+            interval_ms: 300,
+        };
+        assert_eq!(hotkey.format(), "Mouse4 × 2");
+    }
+
+    #[test]
+    fn test_hotkey_validation() {
+        let valid = Hotkey::Combination {
+            modifiers: vec!["Meta".to_string()],
+            key: "t".to_string(),
+        };
+        assert!(valid.validate_for_selected_mode());
+
+        let invalid = Hotkey::Combination {
+            modifiers: vec![],
+            key: "t".to_string(),
+        };
+        assert!(!invalid.validate_for_selected_mode());
+
+        // 选中模式同样支持连续按键
+        let consecutive = Hotkey::Consecutive {
+            key: " ".to_string(),
+            count: 3,
+            interval_ms: None,
+        };
+        assert!(consecutive.validate_for_selected_mode());
+    }
+
+    #[test]
+    fn test_hotkey_config_requires_distinct_triggers() {
+        assert!(HotkeyConfig::default().validate().is_ok());
+
+        let same = HotkeyConfig {
+            selected_mode: Hotkey::Combination {
+                modifiers: vec!["Control".to_string(), "Shift".to_string()],
+                key: "k".to_string(),
+            },
+            full_mode: Hotkey::Combination {
+                modifiers: vec!["Shift".to_string(), "Control".to_string()],
+                key: "K".to_string(),
+            },
+            trigger_keys: TriggerKeyHandling::Backspace,
+            clipboard_mode: None,
+            toggle_enabled: None,
+            active_in_settings: false,
+            key_matching: KeyMatching::default(),
+            debounce_ms: DEFAULT_DEBOUNCE_MS,
+            extra: serde_json::Map::new(),
+        };
+        assert!(same.validate().is_err());
+
+        let consecutive = HotkeyConfig {
+            selected_mode: Hotkey::Consecutive {
+                key: "ShiftRight".to_string(),
+                count: 3,
+                interval_ms: None,
+            },
+            full_mode: Hotkey::Consecutive {
+                key: " ".to_string(),
+                count: 3,
+                interval_ms: None,
+            },
+            trigger_keys: TriggerKeyHandling::Backspace,
+            clipboard_mode: None,
+            toggle_enabled: None,
+            active_in_settings: false,
+            key_matching: KeyMatching::default(),
+            debounce_ms: DEFAULT_DEBOUNCE_MS,
+            extra: serde_json::Map::new(),
+        };
+        assert!(consecutive.validate().is_ok());
+
+        let same_key = HotkeyConfig {
+            selected_mode: Hotkey::Consecutive {
+                key: " ".to_string(),
+                count: 2,
+                interval_ms: None,
+            },
+            full_mode: Hotkey::Consecutive {
+                key: " ".to_string(),
+                count: 3,
+                interval_ms: None,
+            },
+            trigger_keys: TriggerKeyHandling::Backspace,
+            clipboard_mode: None,
+            toggle_enabled: None,
+            active_in_settings: false,
+            key_matching: KeyMatching::default(),
+            debounce_ms: DEFAULT_DEBOUNCE_MS,
+            extra: serde_json::Map::new(),
+        };
+        assert!(same_key.validate().is_err());
+
+        // 暂停热键同样参与冲突检测
+        let mut toggle = HotkeyConfig::default();
+        toggle.toggle_enabled = Some(toggle.full_mode.clone());
+        assert!(toggle.validate().is_err());
+        toggle.toggle_enabled = Some(Hotkey::Combination {
+            modifiers: vec!["Control".to_string(), "Alt".to_string()],
+            key: "p".to_string(),
+        });
+        assert!(toggle.validate().is_ok());
+
+        // 翻译到剪贴板与选中翻译一样需要修饰键
+        let mut clipboard = HotkeyConfig::default();
+        clipboard.clipboard_mode = Some(Hotkey::Combination {
+            modifiers: vec![],
+            key: "c".to_string(),
+        });
+        assert!(clipboard.validate().is_err());
+        clipboard.clipboard_mode = Some(Hotkey::Combination {
+            modifiers: vec!["Control".to_string(), "Shift".to_string()],
+            key: "c".to_string(),
+        });
+        assert!(clipboard.validate().is_ok());
+    }
+
+    #[test]
+    fn test_modifier_trigger_validation() {
+        let mut config = HotkeyConfig::default();
+        config.full_mode = Hotkey::Consecutive {
+            key: "ShiftRight".to_string(),
+            count: 3,
+            interval_ms: None,
+        };
+        assert!(config.validate().is_ok());
+
+        config.full_mode = Hotkey::Combination {
+            modifiers: vec!["Control".to_string()],
+            key: "Shift".to_string(),
+        };
+        assert!(config.validate().is_err());
+
+        config.full_mode = Hotkey::Consecutive {
+            key: "Shift".to_string(),
+            count: 1,
+            interval_ms: None,
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_key_name_validation() {
+        let hotkey = |key: &str| Hotkey::Combination {
+            modifiers: vec!["Meta".to_string()],
+            key: key.to_string(),
+        };
+
+        for key in [
+            "t", "T", " ", "F5", "Keypad1", "Enter", "Dot", "[", "PageUp",
+        ] {
+            assert_eq!(hotkey(key).key_error(), None, "{}", key);
+        }
+        assert_eq!(hotkey("Foo").key_error(), Some("不支持的按键"));
+        assert_eq!(hotkey("").key_error(), Some("不支持的按键"));
+    }
+
+    #[test]
+    fn test_chord_hotkey() {
+        let chord = |second_key: &str| Hotkey::Chord {
+            first: KeyCombination {
+                modifiers: vec!["Control".to_string()],
+                key: "k".to_string(),
+            },
+            second_key: second_key.to_string(),
+            timeout_ms: DEFAULT_CHORD_TIMEOUT_MS,
+        };
+
+        assert_eq!(chord("l").format(), "Ctrl + K, L");
+        assert!(chord("l").validate_for_selected_mode());
+        assert!(chord("l").uses_key_listener());
+        assert_eq!(chord("").key_error(), Some("第二步按键不能为空"));
+        assert_eq!(chord("Shift").key_error(), Some("第二步按键不能是修饰键"));
+
+        // 第一步与组合键相同即视为冲突，第二步不同的两步组合键也不能共用第一步
+        let combination = Hotkey::Combination {
+            modifiers: vec!["Control".to_string()],
+            key: "k".to_string(),
+        };
+        assert!(chord("l").same_trigger(&combination));
+        assert!(chord("l").same_trigger(&chord("m")));
+
+        let mut config = HotkeyConfig::default();
+        config.selected_mode = chord("l");
+        assert!(config.validate().is_ok());
+
+        // 等待时长限制在允许范围内
+        let mut hotkey = Hotkey::Chord {
+            first: KeyCombination {
+                modifiers: vec!["Control".to_string()],
+                key: "k".to_string(),
+            },
+            second_key: "l".to_string(),
+            timeout_ms: 10,
+        };
+        hotkey.clamp_interval();
+        assert!(matches!(
+            hotkey,
+            Hotkey::Chord {
+                timeout_ms: MIN_CHORD_TIMEOUT_MS,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_caps_lock_warning_does_not_block() {
+        let mut config = HotkeyConfig::default();
+        config.full_mode = Hotkey::Consecutive {
+            key: "CapsLock".to_string(),
+            count: 2,
+            interval_ms: None,
+        };
+
+        let issues = config.issues();
+        let warning = issues
+            .iter()
+            .find(|issue| issue.code == HotkeyIssueCode::CapsLockToggle)
+            .expect("CapsLock trigger should produce a warning");
+        assert_eq!(warning.binding, "full_mode");
+        assert!(!warning.blocking);
+        assert!(config.validate().is_ok());
+
+        // 其他非字符按键不提示
+        config.full_mode = Hotkey::Consecutive {
+            key: "Escape".to_string(),
+            count: 2,
+            interval_ms: None,
+        };
+        assert!(!config
+            .issues()
+            .iter()
+            .any(|issue| issue.code == HotkeyIssueCode::CapsLockToggle));
+    }
+
+    #[test]
+    fn test_dedicated_trigger_keys() {
+        let f13 = Hotkey::Combination {
+            modifiers: vec![],
+            key: "F13".to_string(),
+        };
+        assert!(f13.validate_for_selected_mode());
+        assert_eq!(f13.format(), "F13");
+        assert!(HotkeyManager::check_system_conflicts(&f13).is_empty());
+
+        let mut config = HotkeyConfig::default();
+        config.selected_mode = f13;
+        assert!(config.validate().is_ok());
+
+        // 其他按键单独使用仍需修饰键
+        let f5 = Hotkey::Combination {
+            modifiers: vec![],
+            key: "F5".to_string(),
+        };
+        assert!(!f5.validate_for_selected_mode());
+    }
+
+    #[test]
+    fn test_hotkey_issue_codes() {
+        let mut config = HotkeyConfig::default();
+        config.selected_mode = Hotkey::Combination {
+            modifiers: vec![],
+            key: "Fn".to_string(),
+        };
+        config.full_mode = Hotkey::Combination {
+            modifiers: vec!["Meta".to_string()],
+            key: "§".to_string(),
+        };
+        let issues = config.issues();
+        let codes = |binding: &str| -> Vec<HotkeyIssueCode> {
+            issues
+                .iter()
+                .filter(|issue| issue.binding == binding)
+                .map(|issue| issue.code)
+                .collect()
+        };
+
+        assert_eq!(
+            codes("selected_mode"),
+            vec![
+                HotkeyIssueCode::ModifierRequired,
+                HotkeyIssueCode::InvalidKey
+            ]
+        );
+        // 按键表中有，但全局快捷键插件不支持
+        assert_eq!(codes("full_mode"), vec![HotkeyIssueCode::UnsupportedKey]);
+        assert!(config.validate().is_err());
+
+        // 系统快捷键冲突只作为提示
+        let mut spotlight = HotkeyConfig::default();
+        spotlight.full_mode = Hotkey::Combination {
+            modifiers: vec!["Meta".to_string()],
+            key: " ".to_string(),
+        };
+        let issues = spotlight.issues();
+        assert!(issues
+            .iter()
+            .any(|issue| issue.code == HotkeyIssueCode::SystemConflict && !issue.blocking));
+        assert!(spotlight.validate().is_ok());
+    }
+
+    #[test]
+    fn test_uses_key_listener() {
+        let mut config = HotkeyConfig::default();
+        config.selected_mode = Hotkey::Combination {
+            modifiers: vec!["Meta".to_string()],
+            key: "T".to_string(),
+        };
+        config.full_mode = Hotkey::Combination {
+            modifiers: vec!["Meta".to_string()],
+            key: "F".to_string(),
+        };
+        assert!(!config.uses_key_listener());
+
+        config.full_mode = Hotkey::Hold {
+            modifiers: vec!["Control".to_string()],
+            key: "Space".to_string(),
+            hold_ms: 600,
+        };
+        assert!(config.uses_key_listener());
+    }
+
+    #[test]
+    fn test_trigger_key_handling_default() {
+        // 旧版本配置没有 trigger_keys 字段
+        let json = r#"{
+            "selected_mode": {"type":"Combination","modifiers":["Control"],"key":"k"},
+            "full_mode": {"type":"Consecutive","key":" ","count":3}
+        }"#;
+        let config: HotkeyConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.trigger_keys, TriggerKeyHandling::Keep);
+        assert!(config.clipboard_mode.is_none());
+        assert!(config.toggle_enabled.is_none());
+        assert!(!config.active_in_settings);
+        assert_eq!(config.key_matching, KeyMatching::Character);
+
+        let json = r#"{
+            "selected_mode": {"type":"Combination","modifiers":["Control"],"key":"k"},
+            "full_mode": {"type":"Consecutive","key":" ","count":3},
+            "trigger_keys": "keep"
+        }"#;
+        let config: HotkeyConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.trigger_keys, TriggerKeyHandling::Keep);
+
+        // 新配置默认删除触发键
+        assert_eq!(
+            HotkeyConfig::default().trigger_keys,
+            TriggerKeyHandling::Backspace
+        );
+        let json = serde_json::to_string(&HotkeyConfig::default()).unwrap();
+        let config: HotkeyConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(config.trigger_keys, TriggerKeyHandling::Backspace);
+    }
+
+    #[test]
+    fn test_consecutive_interval_backward_compat() {
+        // 旧版本配置没有 interval_ms 字段
+        let json = r#"{"type":"Consecutive","key":" ","count":3}"#;
+        let hotkey: Hotkey = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            hotkey,
+            Hotkey::Consecutive {
+                interval_ms: None,
+                ..
+            }
+        ));
+        assert_eq!(
+            hotkey.consecutive_interval_ms(),
+            Some(DEFAULT_CONSECUTIVE_INTERVAL_MS)
+        );
+
+        let json = r#"{"type":"Consecutive","key":" ","count":3,"interval_ms":450}"#;
+        let hotkey: Hotkey = serde_json::from_str(json).unwrap();
+        assert_eq!(hotkey.consecutive_interval_ms(), Some(450));
+    }
+
+    #[test]
+    fn test_consecutive_interval_clamp() {
+        let mut config = HotkeyConfig {
+            selected_mode: Hotkey::Consecutive {
+                key: ".".to_string(),
+                count: 3,
+                interval_ms: Some(20),
+            },
+            full_mode: Hotkey::Consecutive {
+                key: " ".to_string(),
+                count: 3,
+                interval_ms: Some(10_000),
+            },
+            trigger_keys: TriggerKeyHandling::Backspace,
+            clipboard_mode: None,
+            toggle_enabled: None,
+            active_in_settings: false,
+            key_matching: KeyMatching::default(),
+            debounce_ms: DEFAULT_DEBOUNCE_MS,
+            extra: serde_json::Map::new(),
+        };
+        config.normalize();
+        assert_eq!(
+            config.selected_mode.consecutive_interval_ms(),
+            Some(MIN_CONSECUTIVE_INTERVAL_MS)
+        );
+        assert!(matches!(
+            config.full_mode,
+            Hotkey::Consecutive {
+                interval_ms: Some(MAX_CONSECUTIVE_INTERVAL_MS),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_default_config() {
+        let config = AppConfig::default();
+        assert_eq!(config.llm.model, "gpt-4o-mini");
+        assert_eq!(config.history_limit, 500);
         assert_eq!(config.language.current_target, "en-US");
+        assert_eq!(config.history_mode, HistoryMode::Full);
+    }
+
+    #[test]
+    fn test_language_config_validation() {
+        let mut config = LanguageConfig::default();
+        assert!(config.validate().is_ok());
+
+        config.current_target = "zh-cn".to_string();
+        config.validate().unwrap();
+        assert_eq!(config.current_target, "zh-CN");
+
+        config.favorite_languages.push(Language {
+            code: "klingon".to_string(),
+            name: "tlhIngan Hol".to_string(),
+        });
+        assert!(config.validate().is_err());
+        assert!(known_language_code("").is_err());
+    }
+
+    #[test]
+    fn test_add_favorite_language() {
+        let mut config = LanguageConfig::default();
+        config.add_favorite("de-de", "").unwrap();
+        let added = config.favorite_languages.last().unwrap();
+        assert_eq!(added.code, "de-DE");
+        assert_eq!(added.name, "Deutsch");
+
+        // 重复添加（包括大小写不同）被拒绝
+        assert!(config.add_favorite("de-DE", "German").is_err());
+        assert!(config.add_favorite("EN-us", "").is_err());
+        assert!(config.add_favorite("xx-XX", "").is_err());
+        assert_eq!(config.favorite_languages.len(), 7);
+
+        config.add_favorite("it-IT", " Italiano (IT) ").unwrap();
+        assert_eq!(
+            config.favorite_languages.last().unwrap().name,
+            "Italiano (IT)"
+        );
+    }
+
+    #[test]
+    fn test_remove_favorite_language() {
+        let mut config = LanguageConfig::default();
+        config.remove_favorite("ja-JP").unwrap();
+        assert!(config.favorite_languages.iter().all(|l| l.code != "ja-JP"));
+        assert_eq!(config.current_target, "en-US");
+        assert!(config.remove_favorite("ja-JP").is_err());
+
+        // 移除当前目标语言时切换到剩余的第一个
+        config.remove_favorite("en-US").unwrap();
+        assert_eq!(config.current_target, "zh-CN");
+
+        while config.favorite_languages.len() > 1 {
+            let code = config.favorite_languages[0].code.clone();
+            config.remove_favorite(&code).unwrap();
+        }
+        assert_eq!(config.current_target, config.favorite_languages[0].code);
+        let last = config.favorite_languages[0].code.clone();
+        assert!(config.remove_favorite(&last).is_err());
+    }
+
+    #[test]
+    fn test_swap_target_language() {
+        let mut config = LanguageConfig::default();
+        assert!(config.swap_target(None).is_err());
+
+        config.set_target("zh-CN".to_string());
+        assert_eq!(config.previous_target.as_deref(), Some("en-US"));
+        // 切换到相同的语言不影响上一个目标语言
+        config.set_target("zh-CN".to_string());
+        assert_eq!(config.previous_target.as_deref(), Some("en-US"));
+
+        assert_eq!(config.swap_target(None).unwrap(), "en-US");
+        assert_eq!(config.previous_target.as_deref(), Some("zh-CN"));
+        assert_eq!(config.swap_target(None).unwrap(), "zh-CN");
+
+        // 优先切换为检测到的原文语言，原文语言就是目标语言时切换回上一个目标语言
+        assert_eq!(config.swap_target(Some("ja-JP")).unwrap(), "ja-JP");
+        assert_eq!(config.previous_target.as_deref(), Some("zh-CN"));
+        assert_eq!(config.swap_target(Some("ja-JP")).unwrap(), "zh-CN");
+        assert_eq!(config.swap_target(Some("ja-JP")).unwrap(), "ja-JP");
+        assert_eq!(config.swap_target(None).unwrap(), "zh-CN");
+
+        // 前端保存时没有带上 previous_target
+        let current = config.clone();
+        let mut saved = LanguageConfig {
+            previous_target: None,
+            ..current.clone()
+        };
+        saved.track_previous_target(&current);
+        assert_eq!(saved.previous_target.as_deref(), Some("en-US"));
+        saved.current_target = "ja-JP".to_string();
+        saved.track_previous_target(&current);
+        assert_eq!(saved.previous_target.as_deref(), Some("zh-CN"));
+
+        saved.previous_target = Some("xx-XX".to_string());
+        saved.validate().unwrap();
+        assert_eq!(saved.previous_target, None);
+    }
+
+    #[test]
+    fn test_reorder_favorite_languages() {
+        let mut config = LanguageConfig::default();
+        let codes = |config: &LanguageConfig| -> Vec<String> {
+            config
+                .favorite_languages
+                .iter()
+                .map(|language| language.code.clone())
+                .collect()
+        };
+        let mut order = codes(&config);
+        order.rotate_right(2);
+        config.reorder_favorites(&order).unwrap();
+        assert_eq!(codes(&config), order);
+        assert_eq!(config.favorite_languages[0].name, "Français");
+
+        let before = codes(&config);
+        let invalid: Vec<String> = ["fr-FR", "fr-FR", "xx-XX", "ja-JP", "en-US", "zh-CN"]
+            .iter()
+            .map(|code| code.to_string())
+            .collect();
+        let error = config.reorder_favorites(&invalid).unwrap_err();
+        assert_eq!(error.unknown, ["xx-XX"]);
+        assert_eq!(error.duplicate, ["fr-FR"]);
+        assert_eq!(error.missing, ["es-ES", "ko-KR"]);
+        // 失败时保持原来的顺序
+        assert_eq!(codes(&config), before);
+    }
+
+    #[test]
+    fn test_config_export_import() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = "sk-secret".to_string();
+        config.llm.model = "gpt-4o".to_string();
+        config.database_path = Some(PathBuf::from("/data/quicktranstype.db"));
+
+        let exported = config.to_export_json(false).unwrap();
+        assert!(!exported.contains("sk-secret"));
+        assert!(exported.contains(REDACTED_API_KEY));
+        assert!(config.to_export_json(true).unwrap().contains("sk-secret"));
+
+        // 导入到另一台机器时保留本机的 API Key 和数据库位置
+        let mut current = AppConfig::default();
+        current.llm.api_key = "sk-local".to_string();
+        let imported = AppConfig::from_import_json(&exported, &current).unwrap();
+        assert_eq!(imported.llm.api_key, "sk-local");
+        assert_eq!(imported.llm.model, "gpt-4o");
+        assert_eq!(imported.database_path, None);
+
+        let with_key = config.to_export_json(true).unwrap();
+        let imported = AppConfig::from_import_json(&with_key, &current).unwrap();
+        assert_eq!(imported.llm.api_key, "sk-secret");
+    }
+
+    #[test]
+    fn test_language_pairs() {
+        let pair = |source: &str, target: &str| LanguagePair {
+            source: source.to_string(),
+            target: target.to_string(),
+        };
+        let mut language = LanguageConfig {
+            language_pairs: vec![pair("en-US", "zh-CN"), pair("zh-CN", "ja-JP")],
+            ..Default::default()
+        };
+
+        // 只对本次翻译生效，不修改当前目标语言
+        assert_eq!(language.effective_target(Some("en-US")), "zh-CN");
+        assert_eq!(language.effective_target(Some("en-GB")), "zh-CN");
+        assert_eq!(language.effective_target(Some("zh-TW")), "ja-JP");
+        assert_eq!(language.effective_target(Some("ko-KR")), "en-US");
+        // 无法检测原文语言时不使用规则
+        assert_eq!(language.effective_target(None), "en-US");
+        assert_eq!(language.current_target, "en-US");
+        assert!(language.issues().is_empty());
+
+        // 规则中的语言代码统一为内置列表中的写法
+        language.language_pairs.push(pair("fr-fr", "EN-US"));
+        language.validate().unwrap();
+        assert_eq!(language.language_pairs[2], pair("fr-FR", "en-US"));
+
+        language.language_pairs.push(pair("en-GB", "ko-KR"));
+        language.language_pairs.push(pair("de-DE", "de-DE"));
+        language.language_pairs.push(pair("xx-XX", "zh-CN"));
+        let fields = |severity: IssueSeverity| -> Vec<String> {
+            language
+                .issues()
+                .into_iter()
+                .filter(|issue| issue.severity == severity)
+                .map(|issue| issue.field)
+                .collect()
+        };
+        assert_eq!(
+            fields(IssueSeverity::Warning),
+            [
+                "language.language_pairs[3].source",
+                "language.language_pairs[4].target"
+            ]
+        );
+        assert_eq!(
+            fields(IssueSeverity::Error),
+            ["language.language_pairs[5].source"]
+        );
+        assert!(language.validate().is_err());
+    }
+
+    #[test]
+    fn test_prompt_overrides() {
+        let mut config = AppConfig::default();
+        config.language.prompt_overrides.insert(
+            "ja-jp".to_string(),
+            PromptOverride {
+                system_prompt: Some("です/ます調で翻訳してください。".to_string()),
+                user_prompt_template: None,
+            },
+        );
+        // 语言代码统一为内置列表中的写法
+        config.language.validate().unwrap();
+        assert!(config.language.prompt_overrides.contains_key("ja-JP"));
+
+        // 导出和导入时保留各语言的提示词
+        let exported = config.to_export_json(false).unwrap();
+        let imported = AppConfig::from_import_json(&exported, &AppConfig::default()).unwrap();
+        assert_eq!(
+            imported.language.prompt_overrides,
+            config.language.prompt_overrides
+        );
+
+        config
+            .language
+            .prompt_overrides
+            .insert("xx-XX".to_string(), PromptOverride::default());
+        assert!(config.language.validate().is_err());
+    }
+
+    /// `expected` 中的每个值都出现在 `actual` 的相同位置
+    fn assert_contains(actual: &serde_json::Value, expected: &serde_json::Value, path: &str) {
+        match expected {
+            serde_json::Value::Object(fields) => {
+                for (key, value) in fields {
+                    let path = format!("{}/{}", path, key);
+                    let actual = actual
+                        .get(key)
+                        .unwrap_or_else(|| panic!("missing {}", path));
+                    assert_contains(actual, value, &path);
+                }
+            }
+            serde_json::Value::Array(items) => {
+                let actual = actual.as_array().unwrap();
+                assert_eq!(actual.len(), items.len(), "{}", path);
+                for (i, (actual, item)) in actual.iter().zip(items).enumerate() {
+                    assert_contains(actual, item, &format!("{}/{}", path, i));
+                }
+            }
+            _ => assert_eq!(actual, expected, "{}", path),
+        }
+    }
+
+    #[test]
+    fn test_config_version_fixtures() {
+        assert_eq!(CONFIG_UPGRADES.len(), CONFIG_VERSION as usize);
+
+        // 单个 LLM 配置迁移为默认方案
+        let content = std::fs::read_to_string(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/config/v1.json"),
+        )
+        .unwrap();
+        let config = AppConfig::from_json(&content).unwrap();
+        assert_eq!(config.active_profile, DEFAULT_PROFILE);
+        assert_eq!(config.profiles.len(), 1);
+        assert_eq!(config.profiles[0].llm.model, "gpt-4o");
+        assert_eq!(config.llm.model, "gpt-4o");
+
+        // 旧的流式开关同时用于两种翻译模式
+        for (name, profile, stream) in [
+            ("v0-initial.json", "default", false),
+            ("v1.json", "default", true),
+            ("v2.json", "work", true),
+            ("v2.json", "ollama", false),
+        ] {
+            let content = std::fs::read_to_string(
+                Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join("tests/data/config")
+                    .join(name),
+            )
+            .unwrap();
+            let config = AppConfig::from_json(&content).unwrap();
+            let llm = &config.profile(profile).unwrap().llm;
+            assert_eq!(llm.stream_selected, stream, "{}", name);
+            assert_eq!(llm.stream_full, stream, "{}", name);
+            assert_eq!(llm.stream_min_chars, None, "{}", name);
+            assert!(llm.extra.is_empty(), "{}", name);
+        }
+
+        for name in [
+            "v0-initial.json",
+            "v0-privacy.json",
+            "v1.json",
+            "v2.json",
+            "v3.json",
+        ] {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/data/config")
+                .join(name);
+            let content = std::fs::read_to_string(path).unwrap();
+            let config = AppConfig::from_json(&content).unwrap();
+            assert_eq!(config.config_version, CONFIG_VERSION, "{}", name);
+            assert!(config.extra.is_empty(), "{}", name);
+
+            let active = config.profile(&config.active_profile).unwrap();
+            assert_eq!(config.llm.model, active.llm.model, "{}", name);
+
+            // 保存后再加载，原有的设置都保留，字段移动到升级后的位置
+            let saved: serde_json::Value =
+                serde_json::from_str(&config.to_file_json().unwrap()).unwrap();
+            assert!(saved.get("llm").is_none(), "{}", name);
+            let mut expected: serde_json::Value = serde_json::from_str(&content).unwrap();
+            upgrade_config_json(expected.as_object_mut().unwrap());
+            assert_contains(&saved, &expected, name);
+            let reloaded = AppConfig::from_json(&saved.to_string()).unwrap();
+            assert_eq!(
+                reloaded.to_file_json().unwrap(),
+                config.to_file_json().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_unknown_fields_are_kept() {
+        // 较新版本写入的配置，包含当前版本不认识的字段
+        let content = r#"{
+            "config_version": 99,
+            "profiles": [{"name": "default", "model": "gpt-4o", "reasoning_effort": "low"}],
+            "hotkey": {"selected_mode": {"type": "Combination", "modifiers": ["Control"], "key": "k"}, "repeat_mode": null},
+            "language": {"current_target": "de-DE", "auto_detect": true},
+            "glossary": [{"source": "crate", "target": "包"}]
+        }"#;
+        let config = AppConfig::from_json(content).unwrap();
+        assert_eq!(config.config_version, 99);
+        assert_eq!(config.llm.model, "gpt-4o");
+        assert_eq!(config.llm.base_url, LLMConfig::default().base_url);
+        assert_eq!(config.language.current_target, "de-DE");
+        assert_eq!(config.history_limit, 500);
+
+        let saved = serde_json::to_value(&config).unwrap();
+        assert_eq!(saved["config_version"], 99);
+        assert_eq!(saved["profiles"][0]["reasoning_effort"], "low");
+        assert!(saved["hotkey"]["repeat_mode"].is_null());
+        assert!(saved["hotkey"].get("repeat_mode").is_some());
+        assert_eq!(saved["language"]["auto_detect"], true);
+        assert_eq!(saved["glossary"][0]["target"], "包");
+
+        // 没有版本号的旧格式文件交给旧配置迁移处理
+        assert!(AppConfig::from_json(r#"{"api_key": "sk-test"}"#).is_err());
+        assert!(AppConfig::from_json("[]").is_err());
+        let unversioned = AppConfig::from_json(r#"{"llm": {}}"#).unwrap();
+        assert_eq!(unversioned.config_version, CONFIG_VERSION);
+    }
+
+    #[test]
+    fn test_config_import_fills_missing_fields() {
+        let current = AppConfig::default();
+        let imported = AppConfig::from_import_json(
+            r#"{"llm": {"model": "deepseek-chat"}, "history_limit": 100}"#,
+            &current,
+        )
+        .unwrap();
+        assert_eq!(imported.llm.model, "deepseek-chat");
+        assert_eq!(imported.llm.base_url, current.llm.base_url);
+        assert_eq!(imported.history_limit, 100);
+        assert_eq!(imported.language.current_target, "en-US");
+
+        assert!(AppConfig::from_import_json("not json", &current).is_err());
+        assert!(AppConfig::from_import_json("[]", &current).is_err());
+        assert!(AppConfig::from_import_json(r#"{"history_limit": "many"}"#, &current).is_err());
+    }
+
+    #[test]
+    fn test_parse_legacy_config() {
+        // 旧版本平铺在顶层的字段
+        let config = parse_legacy_config(
+            r#"{"base_url": "https://api.deepseek.com/v1", "api_key": "sk-old", "model": "deepseek-chat",
+                "target_language": "ja-JP", "prompt_template": "To {target_language}: {text}",
+                "history_limit": 200, "selected_hotkey": "Cmd+T", "stream_mode": false}"#,
+        )
+        .unwrap();
+        assert_eq!(config.llm.base_url, "https://api.deepseek.com/v1");
+        assert_eq!(config.llm.api_key, "sk-old");
+        assert_eq!(config.llm.model, "deepseek-chat");
+        assert_eq!(
+            config.llm.user_prompt_template,
+            "To {target_language}: {text}"
+        );
+        assert_eq!(config.language.current_target, "ja-JP");
+        assert_eq!(config.history_limit, 200);
+        assert!(!config.llm.stream_selected && !config.llm.stream_full);
+        // 无法识别的热键格式使用默认值
+        assert_eq!(
+            config.hotkey.selected_mode.format(),
+            HotkeyConfig::default().selected_mode.format()
+        );
+
+        // store 插件保存的文件
+        let config = parse_legacy_config(
+            r#"{"config": {"llm": {"api_key": "sk-store", "stream_mode": false}}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.llm.api_key, "sk-store");
+        assert!(!config.llm.stream_selected && !config.llm.stream_full);
+        assert!(config.llm.extra.is_empty());
+
+        // 部分字段无效的当前格式，其余字段保留
+        let config = parse_legacy_config(
+            r#"{"llm": {"model": "gpt-4o", "temperature": "warm"}, "history_limit": -1}"#,
+        )
+        .unwrap();
+        assert_eq!(config.llm.model, "gpt-4o");
+        assert_eq!(config.llm.temperature, LLMConfig::default().temperature);
+        assert_eq!(config.history_limit, AppConfig::default().history_limit);
+
+        assert!(parse_legacy_config("not json").is_none());
+        assert!(parse_legacy_config(r#"{"unrelated": true}"#).is_none());
+    }
+
+    #[test]
+    fn test_migrate_legacy_config_files() {
+        let config_dir = std::env::temp_dir().join(format!(
+            "quick_trans_type_{}_legacy_config",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&config_dir);
+        let config_path = config_dir.join("QuickTransType").join("config.json");
+        assert!(legacy_config_candidates(&config_dir, &config_path).is_empty());
+
+        let legacy_path = config_dir.join("quick-trans-type").join("settings.json");
+        std::fs::create_dir_all(legacy_path.parent().unwrap()).unwrap();
+        std::fs::write(&legacy_path, r#"{"api_key": "sk-old"}"#).unwrap();
+        let sources = legacy_config_candidates(&config_dir, &config_path);
+        assert_eq!(sources, [legacy_path.clone()]);
+
+        let migration = migrate_legacy_config(&sources, &config_path)
+            .unwrap()
+            .unwrap();
+        assert_eq!(migration.source, legacy_path);
+        assert_eq!(migration.config.llm.api_key, "sk-old");
+        assert!(!legacy_path.exists());
+        assert_eq!(
+            std::fs::read_to_string(&migration.backup).unwrap(),
+            r#"{"api_key": "sk-old"}"#
+        );
+        let saved = AppConfig::from_json(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
+        assert_eq!(saved.llm.api_key, "sk-old");
+        assert!(legacy_config_candidates(&config_dir, &config_path).is_empty());
+
+        // 无法识别的文件移到一旁，不会被覆盖
+        std::fs::write(&config_path, "{ broken").unwrap();
+        assert!(migrate_legacy_config(&[config_path.clone()], &config_path)
+            .unwrap()
+            .is_none());
+        let stashed = stash_broken_config(&config_path).unwrap();
+        assert!(!config_path.exists());
+        assert_eq!(std::fs::read_to_string(&stashed).unwrap(), "{ broken");
+
+        let _ = std::fs::remove_dir_all(&config_dir);
+    }
+
+    #[test]
+    fn test_use_stream() {
+        let mut llm = LLMConfig::default();
+        // 默认两种模式都使用流式输出，不限制长度
+        assert!(llm.use_stream("selected", 100_000));
+        assert!(llm.use_stream("full", 100_000));
+        assert!(!llm.use_stream("clipboard", 10));
+
+        llm.stream_full = false;
+        assert!(llm.use_stream("selected", 10));
+        assert!(!llm.use_stream("full", 10));
+
+        llm.stream_min_chars = Some(100);
+        assert!(llm.use_stream("selected", 100));
+        assert!(!llm.use_stream("selected", 101));
+
+        assert!(llm.issues("llm").iter().all(|issue| !issue.is_error()));
+        llm.stream_min_chars = Some(0);
+        assert!(llm
+            .issues("llm")
+            .iter()
+            .any(|issue| issue.is_error() && issue.field == "llm.stream_min_chars"));
+    }
+
+    #[test]
+    fn test_log_level() {
+        assert_eq!(LogLevel::parse(" WARN "), Some(LogLevel::Warn));
+        assert_eq!(LogLevel::parse("trace"), Some(LogLevel::Trace));
+        assert_eq!(LogLevel::parse("verbose"), None);
+        assert_eq!(LogLevel::parse("debug,hyper=trace"), None);
+        assert_eq!(
+            LogLevel::Info.filter_directive(),
+            "quick_trans_type=info,tauri=info"
+        );
+
+        // 旧配置没有该字段时使用默认级别
+        let mut value = serde_json::to_value(AppConfig::default()).unwrap();
+        value.as_object_mut().unwrap().remove("log_level");
+        let config: AppConfig = serde_json::from_value(value).unwrap();
+        assert_eq!(config.log_level, LogLevel::Debug);
+        assert_eq!(serde_json::to_value(LogLevel::Warn).unwrap(), "warn");
+    }
+
+    #[test]
+    fn test_mask_api_key() {
+        assert_eq!(mask_api_key(""), "");
+        assert_eq!(mask_api_key("sk-short"), "••••••••");
+        assert_eq!(mask_api_key("sk-proj-abcdefgh1234"), "sk-••••1234");
+
+        let mut current = AppConfig::default();
+        current.llm.api_key = "sk-proj-abcdefgh1234".to_string();
+        let masked = current.with_masked_api_key();
+        assert!(!serde_json::to_string(&masked).unwrap().contains("abcdefgh"));
+
+        // 未修改的掩码恢复为实际的 API Key，修改后的值原样保留
+        let mut saved = masked.clone();
+        saved.llm.restore_api_key(&current.llm.api_key);
+        assert_eq!(saved.llm.api_key, current.llm.api_key);
+        let mut saved = masked;
+        saved.llm.api_key = "sk-new".to_string();
+        saved.llm.restore_api_key(&current.llm.api_key);
+        assert_eq!(saved.llm.api_key, "sk-new");
+    }
+
+    #[test]
+    fn test_llm_profiles() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = "sk-work-abcdefgh1234".to_string();
+        let ollama = NamedLLMConfig {
+            name: " ollama ".to_string(),
+            llm: LLMConfig {
+                base_url: "http://localhost:11434/v1".to_string(),
+                model: "qwen2.5:7b".to_string(),
+                ..Default::default()
+            },
+        };
+        config.save_profile(ollama).unwrap();
+        // 新增方案前先保存当前方案的修改
+        assert_eq!(config.profiles[0].llm.api_key, "sk-work-abcdefgh1234");
+        assert_eq!(config.profiles[1].name, "ollama");
+        assert_eq!(config.llm.model, "gpt-4o-mini");
+
+        config.set_active_profile("ollama").unwrap();
+        assert_eq!(config.llm.model, "qwen2.5:7b");
+        assert!(config.set_active_profile("missing").is_err());
+        assert_eq!(config.active_profile, "ollama");
+
+        // 保存当前方案时同时更新 llm
+        let mut updated = config.profile("ollama").unwrap().clone();
+        updated.llm.model = "llama3.1".to_string();
+        config.save_profile(updated).unwrap();
+        assert_eq!(config.llm.model, "llama3.1");
+        assert!(config
+            .save_profile(NamedLLMConfig {
+                name: " ".to_string(),
+                ..Default::default()
+            })
+            .is_err());
+
+        // 前端回传的掩码按方案名称恢复
+        let mut saved = config.with_masked_api_key();
+        assert_eq!(saved.profiles[0].llm.api_key, "sk-••••1234");
+        saved.restore_api_keys(&config);
+        assert_eq!(saved.profiles[0].llm.api_key, "sk-work-abcdefgh1234");
+
+        // 删除当前方案后切换到剩余的第一个方案
+        config.delete_profile("ollama").unwrap();
+        assert_eq!(config.active_profile, DEFAULT_PROFILE);
+        assert_eq!(config.llm.api_key, "sk-work-abcdefgh1234");
+        assert!(config.delete_profile(DEFAULT_PROFILE).is_err());
+        assert!(config.delete_profile("missing").is_err());
+    }
+
+    /// 指定严重程度的问题所在字段
+    ///
+    /// 不含热键的问题，默认热键在部分平台上会有与其他应用冲突的提示。
+    fn issue_fields(issues: &[ValidationIssue], severity: IssueSeverity) -> Vec<&str> {
+        issues
+            .iter()
+            .filter(|issue| issue.severity == severity && !issue.field.starts_with("hotkey."))
+            .map(|issue| issue.field.as_str())
+            .collect()
+    }
+
+    /// 除热键外没有任何问题
+    fn no_issues(issues: &[ValidationIssue]) -> bool {
+        issue_fields(issues, IssueSeverity::Error).is_empty()
+            && issue_fields(issues, IssueSeverity::Warning).is_empty()
+    }
+
+    #[test]
+    fn test_validate_default_config() {
+        let mut config = AppConfig::default();
+        let issues = config.validate();
+        assert!(issue_fields(&issues, IssueSeverity::Error).is_empty());
+        // 默认配置只缺少 API Key
+        assert_eq!(
+            issue_fields(&issues, IssueSeverity::Warning),
+            ["llm.api_key"]
+        );
+
+        config.llm.api_key = "sk-test".to_string();
+        assert!(no_issues(&config.validate()));
+    }
+
+    #[test]
+    fn test_validate_llm_ranges() {
+        let mut llm = LLMConfig {
+            api_key: "sk-test".to_string(),
+            ..Default::default()
+        };
+        for temperature in [0.0, 2.0] {
+            llm.temperature = temperature;
+            assert!(llm.issues("llm").is_empty());
+        }
+        for temperature in [-0.1, 2.1, 7.0, f32::NAN] {
+            llm.temperature = temperature;
+            assert_eq!(
+                issue_fields(&llm.issues("llm"), IssueSeverity::Error),
+                ["llm.temperature"]
+            );
+        }
+        llm.temperature = 0.3;
+
+        llm.top_p = 1.5;
+        assert_eq!(
+            issue_fields(&llm.issues("llm"), IssueSeverity::Error),
+            ["llm.top_p"]
+        );
+        llm.top_p = 1.0;
+
+        llm.model = " ".to_string();
+        assert_eq!(
+            issue_fields(&llm.issues("llm"), IssueSeverity::Error),
+            ["llm.model"]
+        );
+    }
+
+    #[test]
+    fn test_validate_base_url() {
+        let issues = |base_url: &str| {
+            LLMConfig {
+                base_url: base_url.to_string(),
+                api_key: "sk-test".to_string(),
+                ..Default::default()
+            }
+            .issues("llm")
+        };
+        assert!(issues("https://api.deepseek.com/v1").is_empty());
+        // 没有协议的地址会被解析为其他协议
+        for base_url in [
+            "api.openai.com/v1",
+            "localhost:11434/v1",
+            "ftp://example.com",
+            "",
+        ] {
+            assert_eq!(
+                issue_fields(&issues(base_url), IssueSeverity::Error),
+                ["llm.base_url"],
+                "{}",
+                base_url
+            );
+        }
+        // 远程地址使用 http 时提示，本机地址不提示
+        assert_eq!(
+            issue_fields(&issues("http://example.com/v1"), IssueSeverity::Warning),
+            ["llm.base_url"]
+        );
+        assert!(issues("http://localhost:11434/v1").is_empty());
+        assert!(issues("http://127.0.0.1:8080/v1").is_empty());
+
+        // 本机服务不需要 API Key
+        let local = LLMConfig {
+            base_url: "http://localhost:11434/v1".to_string(),
+            ..Default::default()
+        };
+        assert!(local.issues("llm").is_empty());
+    }
+
+    #[test]
+    fn test_validate_prompt_templates() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = "sk-test".to_string();
+        config.llm.user_prompt_template = "Translate: {txt}".to_string();
+        assert_eq!(
+            issue_fields(&config.validate(), IssueSeverity::Error),
+            ["llm.user_prompt_template"]
+        );
+
+        config.llm.user_prompt_template = "Translate: {text}".to_string();
+        assert_eq!(
+            issue_fields(&config.validate(), IssueSeverity::Warning),
+            ["llm.user_prompt_template"]
+        );
+
+        config.language.prompt_overrides.insert(
+            "ja-JP".to_string(),
+            PromptOverride {
+                system_prompt: None,
+                user_prompt_template: Some("日本語に翻訳してください".to_string()),
+            },
+        );
+        assert_eq!(
+            issue_fields(&config.validate(), IssueSeverity::Error),
+            ["language.prompt_overrides.ja-JP.user_prompt_template"]
+        );
+    }
+
+    #[test]
+    fn test_validate_languages() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = "sk-test".to_string();
+
+        config.language.current_target = "de-DE".to_string();
+        assert_eq!(
+            issue_fields(&config.validate(), IssueSeverity::Error),
+            ["language.current_target"]
+        );
+        config.language.current_target = "xx-XX".to_string();
+        assert_eq!(
+            issue_fields(&config.validate(), IssueSeverity::Error),
+            ["language.current_target"]
+        );
+        config.language.current_target = "en-US".to_string();
+
+        let favorites = config.language.favorite_languages.clone();
+        config.language.favorite_languages.clear();
+        assert_eq!(
+            issue_fields(&config.validate(), IssueSeverity::Error),
+            ["language.favorite_languages"]
+        );
+
+        config.language.favorite_languages = favorites;
+        config.language.favorite_languages.push(Language {
+            code: "EN-us".to_string(),
+            name: "English".to_string(),
+        });
+        config.language.favorite_languages.push(Language {
+            code: "xx-XX".to_string(),
+            name: "?".to_string(),
+        });
+        let issues = config.validate();
+        assert_eq!(
+            issue_fields(&issues, IssueSeverity::Warning),
+            ["language.favorite_languages[6].code"]
+        );
+        assert_eq!(
+            issue_fields(&issues, IssueSeverity::Error),
+            ["language.favorite_languages[7].code"]
+        );
+        config.language.favorite_languages.truncate(6);
+
+        config
+            .language
+            .prompt_overrides
+            .insert("xx-XX".to_string(), PromptOverride::default());
+        assert_eq!(
+            issue_fields(&config.validate(), IssueSeverity::Error),
+            ["language.prompt_overrides.xx-XX"]
+        );
+    }
+
+    #[test]
+    fn test_validate_history_limits() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = "sk-test".to_string();
+        for limit in [1, MAX_HISTORY_LIMIT] {
+            config.history_limit = limit;
+            assert!(no_issues(&config.validate()));
+        }
+        for limit in [0, MAX_HISTORY_LIMIT + 1] {
+            config.history_limit = limit;
+            assert_eq!(
+                issue_fields(&config.validate(), IssueSeverity::Error),
+                ["history_limit"]
+            );
+        }
+        config.history_limit = 500;
+
+        for days in [0, MAX_HISTORY_RETENTION_DAYS + 1] {
+            config.history_retention_days = Some(days);
+            assert_eq!(
+                issue_fields(&config.validate(), IssueSeverity::Error),
+                ["history_retention_days"]
+            );
+        }
+        for days in [1, 14, MAX_HISTORY_RETENTION_DAYS] {
+            config.history_retention_days = Some(days);
+            assert!(no_issues(&config.validate()));
+        }
+    }
+
+    #[test]
+    fn test_clipboard_config() {
+        use std::time::Duration;
+
+        // 默认保持粘贴后的剪贴板内容，与之前的行为一致
+        let mut clipboard = ClipboardConfig::default();
+        assert_eq!(clipboard.action(false), ClipboardAction::Leave);
+        assert_eq!(clipboard.action(true), ClipboardAction::KeepTranslation);
+        assert_eq!(clipboard.clear_after(ClipboardAction::Leave), None);
+
+        clipboard.keep_translation_on_clipboard = true;
+        assert_eq!(clipboard.action(false), ClipboardAction::KeepTranslation);
+
+        // 恢复原内容优先于保留译文，翻译到剪贴板始终保留译文
+        clipboard.restore_original = true;
+        assert_eq!(clipboard.action(false), ClipboardAction::RestoreOriginal);
+        assert_eq!(clipboard.action(true), ClipboardAction::KeepTranslation);
+
+        // 定时清除只作用于留在剪贴板中的译文
+        clipboard.clear_after_secs = Some(30);
+        assert_eq!(
+            clipboard.clear_after(ClipboardAction::RestoreOriginal),
+            None
+        );
+        assert_eq!(
+            clipboard.clear_after(clipboard.action(true)),
+            Some(Duration::from_secs(30))
+        );
+
+        let mut config = AppConfig::default();
+        config.llm.api_key = "sk-test".to_string();
+        config.clipboard = clipboard;
+        assert_eq!(
+            issue_fields(&config.validate(), IssueSeverity::Warning),
+            ["clipboard.keep_translation_on_clipboard"]
+        );
+        config.clipboard.keep_translation_on_clipboard = false;
+        config.clipboard.clear_after_secs = Some(0);
+        assert_eq!(
+            issue_fields(&config.validate(), IssueSeverity::Error),
+            ["clipboard.clear_after_secs"]
+        );
+
+        // 缺少的字段使用默认值
+        let config =
+            AppConfig::from_json(r#"{"config_version": 3, "clipboard": {"clear_after_secs": 10}}"#)
+                .unwrap();
+        assert_eq!(
+            config.clipboard,
+            ClipboardConfig {
+                clear_after_secs: Some(10),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_performance_presets() {
+        use std::time::Duration;
+
+        // (预设, [写入剪贴板, 全选, 读取剪贴板, 重试次数, 按键, 每段字符数, 分段间隔])
+        let cases = [
+            (PerformancePreset::Fast, [20, 80, 50, 3, 20, 200, 5]),
+            (PerformancePreset::Balanced, [50, 150, 100, 3, 50, 50, 10]),
+            (
+                PerformancePreset::Compatible,
+                [100, 300, 200, 5, 100, 20, 30],
+            ),
+        ];
+        for (preset, [settle, select_all, poll, retries, key, chunk, chunk_delay]) in cases {
+            assert_eq!(
+                preset.timing(),
+                Some(TimingConfig {
+                    clipboard_settle_ms: settle,
+                    select_all_delay_ms: select_all,
+                    clipboard_poll_ms: poll,
+                    clipboard_retries: retries as u32,
+                    key_delay_ms: key,
+                    type_chunk_chars: chunk as usize,
+                    type_chunk_delay_ms: chunk_delay,
+                }),
+                "{:?}",
+                preset
+            );
+        }
+        assert_eq!(PerformancePreset::Custom.timing(), None);
+
+        // 默认预设与之前写死的等待时间一致，之后每次重试增加一半
+        let mut config = AppConfig::default();
+        config.llm.api_key = "sk-test".to_string();
+        assert_eq!(config.performance_preset, PerformancePreset::Balanced);
+        let timing = config.effective_timing();
+        assert_eq!(timing, TimingConfig::default());
+        assert_eq!(timing.clipboard_poll(0), Duration::from_millis(100));
+        assert_eq!(timing.clipboard_poll(2), Duration::from_millis(200));
+
+        // 使用预设时忽略并且不校验自定义的等待时间
+        config.timing.type_chunk_chars = 0;
+        config.timing.key_delay_ms = 5000;
+        assert!(no_issues(&config.validate()));
+        assert_eq!(config.effective_timing(), TimingConfig::default());
+
+        config.performance_preset = PerformancePreset::Custom;
+        assert_eq!(config.effective_timing(), config.timing);
+        let issues = config.validate();
+        assert_eq!(
+            issue_fields(&issues, IssueSeverity::Error),
+            ["timing.type_chunk_chars"]
+        );
+        assert_eq!(
+            issue_fields(&issues, IssueSeverity::Warning),
+            ["timing.key_delay_ms"]
+        );
+
+        // 缺少的字段使用默认值
+        let config = AppConfig::from_json(
+            r#"{"config_version": 3, "performance_preset": "custom", "timing": {"key_delay_ms": 80}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.performance_preset, PerformancePreset::Custom);
+        assert_eq!(
+            config.effective_timing(),
+            TimingConfig {
+                key_delay_ms: 80,
+                ..TimingConfig::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_redaction_patterns() {
+        // 默认带有过滤规则，但不开启过滤
+        let mut config = AppConfig::default();
+        config.llm.api_key = "sk-test".to_string();
+        assert_eq!(config.redaction_mode, RedactionMode::Off);
+        assert_eq!(config.redaction_patterns, redaction::default_patterns());
+        assert!(no_issues(&config.validate()));
+
+        config.redaction_mode = RedactionMode::Mask;
+        config.redaction_patterns = vec![r"\d+".to_string(), "(".to_string(), String::new()];
+        let issues = config.validate();
+        assert_eq!(
+            issue_fields(&issues, IssueSeverity::Error),
+            ["redaction_patterns[1]", "redaction_patterns[2]"]
+        );
+        // 保存时的错误信息包含正则表达式的错误
+        let invalid = issues
+            .iter()
+            .find(|issue| issue.field == "redaction_patterns[1]")
+            .unwrap();
+        assert!(
+            invalid.message.contains("unclosed group"),
+            "{}",
+            invalid.message
+        );
+
+        config.redaction_patterns.clear();
+        assert_eq!(
+            issue_fields(&config.validate(), IssueSeverity::Warning),
+            ["redaction_patterns"]
+        );
+
+        // 缺少字段时使用默认规则，显式设置为空时不过滤
+        let config =
+            AppConfig::from_json(r#"{"config_version": 3, "redaction_mode": "abort"}"#).unwrap();
+        assert_eq!(config.redaction_mode, RedactionMode::Abort);
+        assert_eq!(config.redaction_patterns, redaction::default_patterns());
+        let config =
+            AppConfig::from_json(r#"{"config_version": 3, "redaction_patterns": []}"#).unwrap();
+        assert!(config.redaction_patterns.is_empty());
+    }
+
+    #[test]
+    fn test_excluded_app_matching() {
+        let app = |identifier: &str| ExcludedApp {
+            identifier: identifier.to_string(),
+            name: String::new(),
+        };
+
+        // 完整的 bundle id 和进程名必须完全相同，不区分大小写
+        assert!(app("com.apple.Terminal").matches("com.apple.Terminal"));
+        assert!(app("com.apple.terminal").matches("COM.APPLE.TERMINAL"));
+        assert!(!app("com.apple.Terminal").matches("com.apple.TerminalHelper"));
+        assert!(!app("com.apple").matches("com.apple.Terminal"));
+        assert!(app("slack.exe").matches("Slack.exe"));
+        assert!(!app("slack.exe").matches("slack.exe.bak"));
+
+        // 不包含 . 的标识作为关键字匹配
+        assert!(app("slack").matches("com.tinyspeck.slackmacgap"));
+        assert!(app("1Password").matches("com.1password.1password"));
+        assert!(!app("slack").matches("com.apple.Terminal"));
+
+        // 空标识不匹配任何应用
+        assert!(!app("  ").matches("com.apple.Terminal"));
+        assert!(!app("").matches(""));
+    }
+
+    #[test]
+    fn test_excluded_apps() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = "sk-test".to_string();
+        config.add_excluded_app(" com.apple.Terminal ", "").unwrap();
+        config.add_excluded_app("slack", "Slack").unwrap();
+        assert_eq!(
+            config.excluded_apps,
+            [
+                ExcludedApp {
+                    identifier: "com.apple.Terminal".to_string(),
+                    name: "com.apple.Terminal".to_string(),
+                },
+                ExcludedApp {
+                    identifier: "slack".to_string(),
+                    name: "Slack".to_string(),
+                },
+            ]
+        );
+        assert!(config.add_excluded_app("COM.APPLE.TERMINAL", "").is_err());
+        assert!(config.add_excluded_app(" ", "Empty").is_err());
+
+        assert_eq!(
+            config
+                .excluded_app("com.tinyspeck.slackmacgap")
+                .map(|app| app.name.as_str()),
+            Some("Slack")
+        );
+        assert!(config.excluded_app("com.apple.Safari").is_none());
+
+        // 排除列表随配置保存和加载
+        let loaded = AppConfig::from_json(&config.to_file_json().unwrap()).unwrap();
+        assert_eq!(loaded.excluded_apps, config.excluded_apps);
+        assert!(AppConfig::from_json(r#"{"config_version": 2}"#)
+            .unwrap()
+            .excluded_apps
+            .is_empty());
+
+        config.remove_excluded_app("Slack").unwrap();
+        assert!(config.remove_excluded_app("slack").is_err());
+        assert_eq!(config.excluded_apps.len(), 1);
+        assert!(no_issues(&config.validate()));
+
+        config.excluded_apps.push(ExcludedApp {
+            identifier: "com.apple.terminal".to_string(),
+            name: "Terminal".to_string(),
+        });
+        config.excluded_apps.push(ExcludedApp::default());
+        assert_eq!(
+            issue_fields(&config.validate(), IssueSeverity::Warning),
+            ["excluded_apps[1].identifier"]
+        );
+        assert_eq!(
+            issue_fields(&config.validate(), IssueSeverity::Error),
+            ["excluded_apps[2].identifier"]
+        );
+    }
+
+    #[test]
+    fn test_validate_hotkeys() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = "sk-test".to_string();
+        config.hotkey.full_mode = config.hotkey.selected_mode.clone();
+        let errors: Vec<ValidationIssue> = config
+            .validate()
+            .into_iter()
+            .filter(ValidationIssue::is_error)
+            .collect();
+        assert!(!errors.is_empty());
+        assert!(errors
+            .iter()
+            .all(|issue| issue.field.starts_with("hotkey.")));
+
+        // 不阻止保存的热键问题作为警告返回
+        let mut config = AppConfig::default();
+        config.llm.api_key = "sk-test".to_string();
+        config.hotkey.full_mode = Hotkey::Consecutive {
+            key: "CapsLock".to_string(),
+            count: 2,
+            interval_ms: None,
+        };
+        let issues = config.validate();
+        assert!(!issues.iter().any(ValidationIssue::is_error));
+        assert!(issues.iter().any(|issue| issue.field == "hotkey.full_mode"
+            && issue.severity == IssueSeverity::Warning
+            && issue.message.contains("大写锁定键")));
+    }
+
+    #[test]
+    fn test_validate_profiles() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = "sk-test".to_string();
+        config.sync_active_profile();
+        config.profiles.push(NamedLLMConfig {
+            name: "ollama".to_string(),
+            llm: LLMConfig {
+                base_url: "http://localhost:11434/v1".to_string(),
+                temperature: 3.0,
+                ..Default::default()
+            },
+        });
+        assert_eq!(
+            issue_fields(&config.validate(), IssueSeverity::Error),
+            ["profiles[1].temperature"]
+        );
+
+        config.profiles[1].llm.temperature = 0.3;
+        config.profiles.push(NamedLLMConfig {
+            name: "ollama".to_string(),
+            llm: config.profiles[1].llm.clone(),
+        });
+        config.profiles.push(NamedLLMConfig {
+            name: " ".to_string(),
+            llm: config.profiles[1].llm.clone(),
+        });
+        assert_eq!(
+            issue_fields(&config.validate(), IssueSeverity::Error),
+            ["profiles[2].name", "profiles[3].name"]
+        );
+
+        // 当前方案以 llm 为准
+        config.profiles.truncate(2);
+        config.profiles[0].llm.temperature = 9.0;
+        assert!(no_issues(&config.validate()));
+    }
+
+    #[test]
+    fn test_reset_config() {
+        let mut current = AppConfig::default();
+        current.llm.api_key = "sk-secret".to_string();
+        current.llm.model = "gpt-4o".to_string();
+        current.llm.system_prompt = "broken".to_string();
+        current.language.current_target = "ja-JP".to_string();
+        current.history_limit = 10;
+        current.history_encryption = true;
+
+        let reset = current.reset_from(true, false);
+        assert_eq!(reset.llm.api_key, "sk-secret");
+        assert_eq!(reset.llm.model, "gpt-4o");
+        assert_eq!(
+            reset.llm.system_prompt,
+            AppConfig::default().llm.system_prompt
+        );
+        assert_eq!(reset.language.current_target, "en-US");
+        assert_eq!(reset.history_limit, 500);
+        assert!(reset.history_encryption);
+
+        let reset = current.reset_from(false, true);
+        assert!(reset.llm.api_key.is_empty());
+        assert_eq!(reset.llm.model, AppConfig::default().llm.model);
+        assert_eq!(reset.language.current_target, "ja-JP");
+    }
+
+    #[test]
+    fn test_keep_database_settings() {
+        let mut current = AppConfig::default();
+        current.database_path = Some(PathBuf::from("/data/history.db"));
+        current.history_encryption = true;
+
+        // 前端保存的配置中加密状态已经过期
+        let mut saved = current.clone();
+        saved.history_encryption = false;
+        saved.database_path = None;
+        saved.history_limit = 20;
+        saved.keep_database_settings(&current);
+        assert!(saved.history_encryption);
+        assert_eq!(saved.database_path, current.database_path);
+        assert_eq!(saved.history_limit, 20);
+
+        current.history_encryption = false;
+        let mut saved = current.clone();
+        saved.history_encryption = true;
+        saved.keep_database_settings(&current);
+        assert!(!saved.history_encryption);
+    }
+
+    #[test]
+    fn test_prompt_template_default_is_valid() {
+        let result = validate_prompt_template(&LLMConfig::default().user_prompt_template);
+        assert!(result.is_valid());
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_prompt_template_text_placeholder() {
+        let result = validate_prompt_template("翻译为{target_language}");
+        assert!(!result.is_valid());
+        assert_eq!(result.errors[0].code, PromptIssueCode::MissingText);
+
+        let result = validate_prompt_template("{text} 翻译为{target_language}：{text}");
+        assert_eq!(result.errors[0].code, PromptIssueCode::DuplicateText);
+    }
+
+    #[test]
+    fn test_prompt_template_target_language_is_warning() {
+        let result = validate_prompt_template("Translate to English: {text}");
+        assert!(result.is_valid());
+        assert_eq!(
+            result.warnings[0].code,
+            PromptIssueCode::MissingTargetLanguage
+        );
+    }
+
+    #[test]
+    fn test_prompt_template_unknown_placeholders() {
+        let result = validate_prompt_template(
+            "Translate {source_language} to {target_language}: {text} {source_language} {tone}",
+        );
+        assert!(result.is_valid());
+        let names: Vec<_> = result
+            .warnings
+            .iter()
+            .map(|issue| issue.placeholder.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, ["source_language", "tone"]);
+
+        // JSON 示例和未闭合的括号不算变量
+        let result = validate_prompt_template(r#"{target_language} {"a": 1} {text} {unclosed"#);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_prompt_template_length_limit() {
+        let long = format!(
+            "{{target_language}}{{text}}{}",
+            "字".repeat(MAX_PROMPT_TEMPLATE_CHARS)
+        );
+        let result = validate_prompt_template(&long);
+        assert_eq!(result.errors[0].code, PromptIssueCode::TooLong);
+    }
+
+    #[test]
+    fn test_history_mode() {
+        assert!(HistoryMode::Full.records_text());
+        assert!(HistoryMode::Full.records_metrics());
+        // 隐私模式下不保存任何文本
+        assert!(!HistoryMode::MetricsOnly.records_text());
+        assert!(HistoryMode::MetricsOnly.records_metrics());
+        assert!(!HistoryMode::Off.records_text());
+        assert!(!HistoryMode::Off.records_metrics());
+
+        assert_eq!(HistoryMode::Full.toggle_privacy(), HistoryMode::MetricsOnly);
+        assert_eq!(HistoryMode::MetricsOnly.toggle_privacy(), HistoryMode::Full);
+        assert_eq!(HistoryMode::Off.toggle_privacy(), HistoryMode::Full);
+
+        // 旧配置没有该字段时默认完整保存
+        let mut value = serde_json::to_value(AppConfig::default()).unwrap();
+        value.as_object_mut().unwrap().remove("history_mode");
+        let config: AppConfig = serde_json::from_value(value).unwrap();
+        assert_eq!(config.history_mode, HistoryMode::Full);
+
+        let mode: HistoryMode = serde_json::from_str("\"metrics_only\"").unwrap();
+        assert_eq!(mode, HistoryMode::MetricsOnly);
+    }
+
+    #[test]
+    fn test_model_pricing() {
+        let mut config = LLMConfig::default();
+        assert_eq!(config.price(), None);
+
+        let price = ModelPrice {
+            input_per_million: 0.15,
+            output_per_million: 0.6,
+        };
+        config.pricing.insert("gpt-4o-mini".to_string(), price);
+        let price = config.price().unwrap();
+        assert!((price.cost_usd(1_000_000, 500_000) - 0.45).abs() < 1e-9);
+
+        // 旧配置没有价格表
+        let mut value = serde_json::to_value(LLMConfig::default()).unwrap();
+        value.as_object_mut().unwrap().remove("pricing");
+        let config: LLMConfig = serde_json::from_value(value).unwrap();
+        assert!(config.pricing.is_empty());
     }
 }
@@ -0,0 +1,370 @@
+//! 加密模块
+//! 使用 AES-256-GCM 加密翻译历史中的原文和译文
+//!
+//! 密钥在首次启用加密时随机生成，保存在系统凭据存储中；没有凭据存储的平台上
+//! 保存在配置目录下的密钥文件中，仅当前用户可读。
+//! 密文格式为 `enc:v1:` 前缀加 base64 编码的 nonce、密文和认证标签。
+//! 查找相同原文用的哈希使用从同一密钥派生的 HMAC-SHA256 密钥。
+
+use crate::error::{AppError, Result};
+use crate::secrets::{SecretStore, HISTORY_KEY_ACCOUNT};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// 密文前缀，用于区分加密和未加密的字段
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+/// AES-256 密钥长度（字节）
+const KEY_LEN: usize = 32;
+/// 派生哈希密钥时使用的标签，与加密密钥区分用途
+const HASH_KEY_LABEL: &[u8] = b"QuickTransType history hash v1";
+
+/// 字段是否为加密后的密文
+pub fn is_encrypted(text: &str) -> bool {
+    text.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// 二进制字段是否为 `encrypt_bytes` 生成的密文
+pub fn is_encrypted_bytes(data: &[u8]) -> bool {
+    data.starts_with(ENCRYPTED_PREFIX.as_bytes())
+}
+
+/// 文本字段加密器
+pub struct FieldCipher {
+    key: LessSafeKey,
+    /// 计算原文哈希的 HMAC 密钥
+    hash_key: hmac::Key,
+    rng: SystemRandom,
+}
+
+impl FieldCipher {
+    /// 使用指定密钥创建加密器
+    pub fn new(key: &[u8]) -> Result<Self> {
+        let hash_key = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), HASH_KEY_LABEL);
+        let key = UnboundKey::new(&AES_256_GCM, key)
+            .map_err(|_| AppError::Crypto("密钥长度无效".to_string()))?;
+        Ok(Self {
+            key: LessSafeKey::new(key),
+            hash_key: hmac::Key::new(hmac::HMAC_SHA256, hash_key.as_ref()),
+            rng: SystemRandom::new(),
+        })
+    }
+
+    /// 默认密钥文件路径，没有凭据存储时使用，旧版本也将密钥保存在这里
+    pub fn default_key_path() -> Result<PathBuf> {
+        let config_dir =
+            dirs::config_dir().ok_or_else(|| AppError::Config("无法获取配置目录".to_string()))?;
+        Ok(config_dir.join("QuickTransType").join("history.key"))
+    }
+
+    /// 读取密钥，不存在时生成新密钥并保存
+    ///
+    /// 有凭据存储时密钥保存在凭据存储中，`path` 处旧版本留下的密钥文件会移入凭据存储；
+    /// 没有凭据存储时保存在 `path`。`allow_create` 为 false 时不生成新密钥：
+    /// 翻译历史中已有加密记录时，新密钥无法解密这些记录。
+    pub fn load_or_create(
+        store: Option<&dyn SecretStore>,
+        path: &Path,
+        allow_create: bool,
+    ) -> Result<Self> {
+        if let Some(key) = load_key(store, path)? {
+            return Self::new(&key);
+        }
+        if !allow_create {
+            return Err(AppError::Crypto(format!(
+                "找不到历史加密密钥，已加密的翻译历史无法解密。请恢复密钥（{}）后重试",
+                path.display()
+            )));
+        }
+
+        let rng = SystemRandom::new();
+        let mut key = [0u8; KEY_LEN];
+        rng.fill(&mut key)
+            .map_err(|_| AppError::Crypto("无法生成密钥".to_string()))?;
+
+        match store {
+            Some(store) => {
+                store.set(HISTORY_KEY_ACCOUNT, &STANDARD.encode(key))?;
+                info!("Created history encryption key in the credential store");
+            }
+            None => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                write_key_file(path, &key)?;
+                info!("Created history encryption key at {}", path.display());
+            }
+        }
+
+        Self::new(&key)
+    }
+
+    /// 加密文本
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let payload = self.seal(plaintext.as_bytes())?;
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, STANDARD.encode(payload)))
+    }
+
+    /// 解密 `encrypt` 生成的密文
+    pub fn decrypt(&self, ciphertext: &str) -> Result<String> {
+        let encoded = ciphertext
+            .strip_prefix(ENCRYPTED_PREFIX)
+            .ok_or_else(|| AppError::Crypto("不是加密的内容".to_string()))?;
+        let payload = STANDARD
+            .decode(encoded)
+            .map_err(|_| AppError::Crypto("密文格式无效".to_string()))?;
+
+        String::from_utf8(self.open(&payload)?)
+            .map_err(|_| AppError::Crypto("解密后的内容不是有效文本".to_string()))
+    }
+
+    /// 加密二进制数据，密文以同样的前缀开头，不做 base64 编码
+    pub fn encrypt_bytes(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut encrypted = ENCRYPTED_PREFIX.as_bytes().to_vec();
+        encrypted.extend_from_slice(&self.seal(data)?);
+        Ok(encrypted)
+    }
+
+    /// 解密 `encrypt_bytes` 生成的密文
+    pub fn decrypt_bytes(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let payload = data
+            .strip_prefix(ENCRYPTED_PREFIX.as_bytes())
+            .ok_or_else(|| AppError::Crypto("不是加密的内容".to_string()))?;
+        self.open(payload)
+    }
+
+    /// 数据的 HMAC-SHA256，不知道密钥时无法通过猜测原文来验证
+    pub fn keyed_hash(&self, data: &[u8]) -> Vec<u8> {
+        hmac::sign(&self.hash_key, data).as_ref().to_vec()
+    }
+
+    /// 加密数据，返回 nonce、密文和认证标签
+    fn seal(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| AppError::Crypto("无法生成随机数".to_string()))?;
+
+        let mut data = data.to_vec();
+        self.key
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+            .map_err(|_| AppError::Crypto("加密失败".to_string()))?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&data);
+        Ok(payload)
+    }
+
+    /// 解密 `seal` 生成的数据
+    fn open(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        if payload.len() < NONCE_LEN {
+            return Err(AppError::Crypto("密文格式无效".to_string()));
+        }
+
+        let (nonce, data) = payload.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| AppError::Crypto("密文格式无效".to_string()))?;
+        let mut data = data.to_vec();
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::empty(), &mut data)
+            .map_err(|_| AppError::Crypto("解密失败，密钥不匹配或内容已损坏".to_string()))?;
+        Ok(plaintext.to_vec())
+    }
+}
+
+/// 读取已保存的密钥，凭据存储和密钥文件中都没有时返回 `None`
+///
+/// 读取凭据存储失败时返回错误，不能当作密钥不存在。
+fn load_key(store: Option<&dyn SecretStore>, path: &Path) -> Result<Option<Vec<u8>>> {
+    let Some(store) = store else {
+        return if path.exists() {
+            Ok(Some(std::fs::read(path)?))
+        } else {
+            Ok(None)
+        };
+    };
+
+    if let Some(encoded) = store.get(HISTORY_KEY_ACCOUNT)? {
+        let key = STANDARD
+            .decode(encoded)
+            .map_err(|_| AppError::Crypto("凭据存储中的密钥格式无效".to_string()))?;
+        return Ok(Some(key));
+    }
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    // 旧版本的密钥文件移入凭据存储后删除，写入失败时继续使用密钥文件
+    let key = std::fs::read(path)?;
+    if key.len() == KEY_LEN {
+        match store.set(HISTORY_KEY_ACCOUNT, &STANDARD.encode(&key)) {
+            Ok(()) => match std::fs::remove_file(path) {
+                Ok(()) => info!("Moved history encryption key into the credential store"),
+                Err(e) => warn!("Failed to remove migrated history key file: {}", e),
+            },
+            Err(e) => warn!(
+                "Failed to move history encryption key into the credential store: {}",
+                e
+            ),
+        }
+    }
+    Ok(Some(key))
+}
+
+/// 写入密钥文件，Unix 上仅当前用户可读写
+fn write_key_file(path: &Path, key: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(key)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::MemoryStore;
+
+    fn test_cipher() -> FieldCipher {
+        FieldCipher::new(&[7u8; KEY_LEN]).unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let cipher = test_cipher();
+        let encrypted = cipher.encrypt("机密文档的内容").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.contains("机密"));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "机密文档的内容");
+
+        // 每次加密使用不同的 nonce
+        assert_ne!(cipher.encrypt("机密文档的内容").unwrap(), encrypted);
+        assert!(!is_encrypted("普通文本"));
+    }
+
+    #[test]
+    fn test_decrypt_rejects_tampered_or_foreign_data() {
+        let cipher = test_cipher();
+        let encrypted = cipher.encrypt("hello").unwrap();
+
+        let mut tampered = encrypted.clone();
+        let last = tampered.pop().unwrap();
+        tampered.push(if last == 'A' { 'B' } else { 'A' });
+        assert!(cipher.decrypt(&tampered).is_err());
+
+        let other = FieldCipher::new(&[8u8; KEY_LEN]).unwrap();
+        assert!(other.decrypt(&encrypted).is_err());
+        assert!(cipher.decrypt("hello").is_err());
+        assert!(cipher.decrypt("enc:v1:AAAA").is_err());
+        assert!(FieldCipher::new(&[0u8; 16]).is_err());
+    }
+
+    #[test]
+    fn test_encrypt_bytes_round_trip() {
+        let cipher = test_cipher();
+        let data = [0x78, 0x9c, 0x00, 0xff, 0x10];
+        let encrypted = cipher.encrypt_bytes(&data).unwrap();
+        assert!(is_encrypted_bytes(&encrypted));
+        assert!(!is_encrypted_bytes(&data));
+        assert_eq!(cipher.decrypt_bytes(&encrypted).unwrap(), data);
+        assert!(cipher.decrypt_bytes(&data).is_err());
+    }
+
+    #[test]
+    fn test_keyed_hash() {
+        let cipher = test_cipher();
+        assert_eq!(cipher.keyed_hash(b"OK"), cipher.keyed_hash(b"OK"));
+        assert_ne!(cipher.keyed_hash(b"OK"), cipher.keyed_hash(b"ok"));
+
+        let other = FieldCipher::new(&[8u8; KEY_LEN]).unwrap();
+        assert_ne!(other.keyed_hash(b"OK"), cipher.keyed_hash(b"OK"));
+        let plain = ring::digest::digest(&ring::digest::SHA256, b"OK");
+        assert_ne!(cipher.keyed_hash(b"OK"), plain.as_ref());
+    }
+
+    fn temp_key_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "quick_trans_type_{}_{}.key",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_load_or_create_key() {
+        let path = temp_key_path("file");
+
+        let encrypted = FieldCipher::load_or_create(None, &path, true)
+            .unwrap()
+            .encrypt("hello")
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap().len(), KEY_LEN);
+        // 再次加载使用同一个密钥
+        let reloaded = FieldCipher::load_or_create(None, &path, false).unwrap();
+        assert_eq!(reloaded.decrypt(&encrypted).unwrap(), "hello");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_load_or_create_key_in_store() {
+        let path = temp_key_path("store");
+        let store = MemoryStore::default();
+
+        let encrypted = FieldCipher::load_or_create(Some(&store), &path, true)
+            .unwrap()
+            .encrypt("hello")
+            .unwrap();
+        assert!(!path.exists());
+        assert!(store.get(HISTORY_KEY_ACCOUNT).unwrap().is_some());
+        let reloaded = FieldCipher::load_or_create(Some(&store), &path, false).unwrap();
+        assert_eq!(reloaded.decrypt(&encrypted).unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_migrate_key_file_into_store() {
+        let path = temp_key_path("migrate");
+        let encrypted = FieldCipher::load_or_create(None, &path, true)
+            .unwrap()
+            .encrypt("hello")
+            .unwrap();
+
+        let store = MemoryStore::default();
+        let migrated = FieldCipher::load_or_create(Some(&store), &path, false).unwrap();
+        assert_eq!(migrated.decrypt(&encrypted).unwrap(), "hello");
+        assert!(!path.exists());
+        assert!(store.get(HISTORY_KEY_ACCOUNT).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_missing_key_not_created_when_not_allowed() {
+        let path = temp_key_path("missing");
+        let store = MemoryStore::default();
+
+        assert!(FieldCipher::load_or_create(None, &path, false).is_err());
+        assert!(FieldCipher::load_or_create(Some(&store), &path, false).is_err());
+        assert!(!path.exists());
+        assert!(store.get(HISTORY_KEY_ACCOUNT).unwrap().is_none());
+
+        // 凭据存储无法读取时不当作密钥不存在
+        let locked = MemoryStore {
+            fail: true,
+            ..Default::default()
+        };
+        assert!(FieldCipher::load_or_create(Some(&locked), &path, true).is_err());
+        assert_eq!(*locked.writes.lock().unwrap(), 0);
+    }
+}
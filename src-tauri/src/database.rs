@@ -1,18 +1,88 @@
 //! 数据库模块
 //! 管理 SQLite 数据库连接和操作
 
+use crate::crypto::{is_encrypted, is_encrypted_bytes, FieldCipher, ENCRYPTED_PREFIX};
 use crate::error::{AppError, Result};
-use chrono::Utc;
+use chrono::{DateTime, Datelike, Local, NaiveDate, SecondsFormat, TimeZone, Utc};
+use flate2::read::ZlibDecoder;
+use futures_util::future::BoxFuture;
+use futures_util::TryStreamExt;
+use ring::digest;
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Row, Sqlite};
-use std::path::PathBuf;
-use tracing::{debug, info};
+use sqlx::sqlite::{
+    SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteRow, SqliteSynchronous,
+};
+use sqlx::{Pool, QueryBuilder, Row, Sqlite, SqliteConnection};
+use std::collections::HashMap;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::sync::RwLock;
+use tracing::{debug, error, info, warn};
 
 /// 数据库管理器
 pub struct Database {
-    pool: Pool<Sqlite>,
+    /// 从备份恢复时会替换为新的连接池
+    pool: Mutex<Pool<Sqlite>>,
+    /// 数据库文件路径，内存数据库为 `None`
+    path: Mutex<Option<PathBuf>>,
+    /// 写操作持有读锁，压缩数据库时持有写锁，保证 VACUUM 不与写入同时进行
+    maintenance: RwLock<()>,
+    /// 启用加密时用于加解密原文和译文
+    cipher: Mutex<Option<Arc<FieldCipher>>>,
+    /// 配置要求加密但无法加载密钥的原因，此时不保存任何原文和译文
+    encryption_error: Mutex<Option<String>>,
 }
 
+/// 参与行数统计的数据表
+const COUNTED_TABLES: &[&str] = &["translations", "metrics", "daily_stats"];
+
+/// 原始性能指标的保留天数，更早的指标只保留每日汇总
+const METRICS_RETENTION_DAYS: i64 = 90;
+
+/// 保留的自动备份份数
+const BACKUP_KEEP: usize = 5;
+
+/// 两次自动备份之间的最小间隔
+const BACKUP_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+
+/// 数据库被其他连接锁定时的最长等待时间
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 错误信息保存的最大字符数
+const ERROR_MESSAGE_MAX_CHARS: usize = 500;
+
+/// 性能统计中每种错误类型保留的最近错误条数
+const RECENT_ERRORS_PER_TYPE: i64 = 5;
+
+/// 疑似 API Key 的最小长度，密钥前缀明确时不受此限制
+const SECRET_MIN_LEN: usize = 32;
+
+/// 超过该长度（字节）的原文或译文压缩保存
+const COMPRESS_THRESHOLD_BYTES: usize = 4096;
+
+/// 压缩保存的文本在文本列中保留的预览字符数，未加密时搜索只匹配预览
+const PREVIEW_CHARS: usize = 500;
+
+/// 压缩列第一个字节的格式标记：之后是 zlib 数据，只用于读取之前写入的内容
+const BLOB_FORMAT_ZLIB: u8 = 1;
+
+/// 压缩列第一个字节的格式标记：之后是 zstd 数据，新写入的内容使用此格式
+const BLOB_FORMAT_ZSTD: u8 = 2;
+
+/// zstd 压缩级别，与 zstd 命令行工具的默认级别相同
+const ZSTD_LEVEL: i32 = 3;
+
+/// zlib 数据的第一个字节，用于识别加入格式标记之前写入的压缩内容
+const ZLIB_HEADER: u8 = 0x78;
+
+/// 每日序列的预聚合粒度（秒），所有时区偏移都是 15 分钟的整数倍
+const SERIES_SLOT_SECS: i64 = 900;
+
+/// 模型对比中单独列出的最少请求次数，更少的模型合并为 "other"
+const MODEL_STATS_MIN_COUNT: i64 = 5;
+
 /// 翻译记录
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranslationRecord {
@@ -23,458 +93,5480 @@ pub struct TranslationRecord {
     pub target_lang: String,
     pub mode: String,
     pub timestamp: i64,
+    /// 生成译文的模型，旧记录为空
+    pub model: Option<String>,
+    /// 译文的 completion tokens
+    pub completion_tokens: Option<i64>,
+    /// 翻译耗时（毫秒）
+    pub duration_ms: Option<i64>,
+    /// 是否已置顶，置顶记录不会被自动清理
+    pub pinned: bool,
+    /// 翻译时的前台应用（macOS 上为 bundle id），未检测到时为空
+    pub source_app: Option<String>,
+    /// 重新翻译时对应的原记录 ID
+    pub retranslated_from: Option<i64>,
 }
 
-/// 查询历史记录的结果
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HistoryResult {
-    pub records: Vec<TranslationRecord>,
-    pub total: i64,
+/// 翻译记录附带的模型、tokens 和耗时信息
+#[derive(Debug, Clone, Default)]
+pub struct TranslationMetadata {
+    pub model: Option<String>,
+    pub completion_tokens: Option<u32>,
+    pub duration_ms: Option<i64>,
+    /// 翻译时的前台应用
+    pub source_app: Option<String>,
+    /// 去重窗口（秒），0 表示不去重
+    pub dedup_window_secs: u64,
+    /// 重新翻译时对应的原记录 ID
+    pub retranslated_from: Option<i64>,
 }
 
-impl Database {
-    /// 创建数据库连接
-    pub async fn new() -> Result<Self> {
-        let db_path = Self::get_db_path()?;
-        
-        // 确保目录存在
-        if let Some(parent) = db_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
-        let db_url = format!("sqlite:{}?mode=rwc", db_path.display());
-        debug!("Connecting to database: {}", db_url);
+/// 一次翻译操作的性能指标
+#[derive(Debug, Clone, Default)]
+pub struct MetricRecord<'a> {
+    /// 操作模式："selected"、"full" 或 "clipboard"
+    pub operation_type: &'a str,
+    pub duration_ms: i64,
+    pub success: bool,
+    pub error_type: Option<&'a str>,
+    /// 失败原因，保存前会隐藏疑似 API Key 的内容并截断
+    pub error_message: Option<&'a str>,
+    pub char_count: i64,
+    /// 翻译的目标语言
+    pub target_lang: Option<&'a str>,
+    /// 非流式或失败的请求没有 tokens 信息
+    pub completion_tokens: Option<u32>,
+    pub tokens_per_second: Option<f64>,
+    /// 使用的模型
+    pub model: Option<&'a str>,
+    /// 请求费用（美元），未配置价格时为空
+    pub cost_usd: Option<f64>,
+    /// 是否使用流式输出
+    pub stream: Option<bool>,
+    /// 是否复用了历史中的译文，没有调用 API
+    pub cached: bool,
+}
 
-        let pool = SqlitePoolOptions::new()
-            .max_connections(5)
-            .connect(&db_url)
-            .await?;
+/// 导入的单条翻译记录，字段与导出的 `TranslationRecord` 一致，`id` 被忽略
+#[derive(Debug, Clone, Deserialize)]
+struct ImportedRecord {
+    original_text: String,
+    translated_text: String,
+    #[serde(default)]
+    source_lang: Option<String>,
+    target_lang: String,
+    mode: String,
+    timestamp: i64,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    completion_tokens: Option<i64>,
+    #[serde(default)]
+    duration_ms: Option<i64>,
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default)]
+    source_app: Option<String>,
+}
 
-        let db = Self { pool };
-        db.run_migrations().await?;
-        
-        info!("Database initialized successfully");
-        Ok(db)
+impl ImportedRecord {
+    /// 检查必填字段是否有效
+    fn is_valid(&self) -> bool {
+        !self.original_text.is_empty()
+            && !self.translated_text.is_empty()
+            && !self.target_lang.is_empty()
+            && !self.mode.is_empty()
+            && self.timestamp > 0
     }
+}
 
-    /// 获取数据库文件路径
-    fn get_db_path() -> Result<PathBuf> {
-        let data_dir = dirs::data_dir()
-            .ok_or_else(|| AppError::Config("无法获取数据目录".to_string()))?;
-        Ok(data_dir.join("QuickTransType").join("quicktranstype.db"))
+/// 导入历史记录的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportSummary {
+    /// 成功导入的记录数
+    pub imported: u64,
+    /// 与已有记录完全相同而跳过的记录数
+    pub skipped: u64,
+    /// 字段缺失或无效的记录数
+    pub invalid: u64,
+}
+
+/// 查询历史记录的筛选条件，所有条件同时生效
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryFilter {
+    /// 在原文和译文中搜索，压缩保存的长文本在未加密时只匹配预览部分
+    pub search: Option<String>,
+    pub mode: Option<String>,
+    pub target_lang: Option<String>,
+    /// 翻译时的前台应用
+    pub source_app: Option<String>,
+    /// 只返回置顶记录
+    pub pinned_only: bool,
+    /// 起始时间（包含），Unix 时间戳（秒）
+    pub from_ts: Option<i64>,
+    /// 结束时间（不包含），Unix 时间戳（秒）
+    pub to_ts: Option<i64>,
+}
+
+impl HistoryFilter {
+    /// 将筛选条件连同参数写入查询，返回的 `Conditions` 可继续追加条件
+    fn push_where<'q, 'b>(&self, builder: &'b mut QueryBuilder<'q, Sqlite>) -> Conditions<'q, 'b> {
+        let mut conditions = Conditions::new(builder);
+        if let Some(search) = &self.search {
+            let pattern = like_pattern(search);
+            conditions
+                .push("(original_text LIKE ")
+                .push_bind(pattern.clone())
+                .push(" ESCAPE '\\' OR translated_text LIKE ")
+                .push_bind(pattern)
+                .push(" ESCAPE '\\')");
+        }
+        if let Some(mode) = &self.mode {
+            conditions.push("mode = ").push_bind(mode.clone());
+        }
+        if let Some(target_lang) = &self.target_lang {
+            conditions
+                .push("target_lang = ")
+                .push_bind(target_lang.clone());
+        }
+        if let Some(source_app) = &self.source_app {
+            conditions
+                .push("source_app = ")
+                .push_bind(source_app.clone());
+        }
+        if self.pinned_only {
+            conditions.push("pinned = 1");
+        }
+        if let Some(from_ts) = self.from_ts {
+            conditions.push("timestamp >= ").push_bind(from_ts);
+        }
+        if let Some(to_ts) = self.to_ts {
+            conditions.push("timestamp < ").push_bind(to_ts);
+        }
+        conditions
     }
+}
 
-    /// 运行数据库迁移
-    async fn run_migrations(&self) -> Result<()> {
-        debug!("Running database migrations...");
+/// 包含 `search` 的 LIKE 模式，`%`、`_` 和转义符本身按字面匹配
+fn like_pattern(search: &str) -> String {
+    let mut pattern = String::with_capacity(search.len() + 2);
+    pattern.push('%');
+    for c in search.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
 
-        // 创建翻译记录表
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS translations (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                original_text TEXT NOT NULL,
-                translated_text TEXT NOT NULL,
-                source_lang TEXT,
-                target_lang TEXT NOT NULL,
-                mode TEXT NOT NULL,
-                timestamp INTEGER NOT NULL
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
+/// 以 AND 连接的查询条件，第一个条件前写入 WHERE
+struct Conditions<'q, 'b> {
+    builder: &'b mut QueryBuilder<'q, Sqlite>,
+    empty: bool,
+}
 
-        // 创建索引
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_translations_timestamp ON translations(timestamp DESC)",
-        )
-        .execute(&self.pool)
-        .await?;
+impl<'q, 'b> Conditions<'q, 'b> {
+    fn new(builder: &'b mut QueryBuilder<'q, Sqlite>) -> Self {
+        Self {
+            builder,
+            empty: true,
+        }
+    }
 
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_translations_lang ON translations(target_lang, source_lang)",
-        )
-        .execute(&self.pool)
-        .await?;
+    /// 开始一个新条件，返回查询以继续写入条件内容和参数
+    fn push(&mut self, sql: &str) -> &mut QueryBuilder<'q, Sqlite> {
+        self.builder
+            .push(if self.empty { " WHERE " } else { " AND " });
+        self.empty = false;
+        self.builder.push(sql)
+    }
+}
 
-        // 创建性能指标表
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS metrics (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                timestamp INTEGER NOT NULL,
-                operation_type TEXT NOT NULL,
-                duration_ms INTEGER NOT NULL,
-                success INTEGER NOT NULL,
-                error_type TEXT,
-                char_count INTEGER NOT NULL,
-                completion_tokens INTEGER,
-                tokens_per_second REAL
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
+/// 数据库文件信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbInfo {
+    /// 数据库文件大小（字节）
+    pub file_size: u64,
+    pub page_size: u64,
+    pub page_count: u64,
+    /// 空闲页数，压缩后可回收
+    pub freelist_pages: u64,
+    /// 压缩可回收的空间（字节）
+    pub reclaimable_bytes: u64,
+    /// 各数据表的行数
+    pub tables: Vec<TableRowCount>,
+}
 
-        // 添加新字段（如果表已存在）
-        sqlx::query("ALTER TABLE metrics ADD COLUMN completion_tokens INTEGER")
-            .execute(&self.pool)
-            .await
-            .ok(); // 忽略错误（列可能已存在）
-        
-        sqlx::query("ALTER TABLE metrics ADD COLUMN tokens_per_second REAL")
-            .execute(&self.pool)
-            .await
-            .ok(); // 忽略错误
+/// 数据表行数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableRowCount {
+    pub name: String,
+    pub rows: i64,
+}
 
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_metrics_timestamp ON metrics(timestamp DESC)",
-        )
-        .execute(&self.pool)
-        .await?;
+/// 数据库备份文件信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    /// 备份序号，1 为最新
+    pub index: usize,
+    pub path: String,
+    pub file_size: u64,
+    /// 备份时间（Unix 时间戳）
+    pub created_at: i64,
+}
 
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_metrics_operation ON metrics(operation_type)",
-        )
-        .execute(&self.pool)
-        .await?;
+/// 加密启用时加密要保存的文本
+fn seal_text(cipher: Option<&FieldCipher>, text: &str) -> Result<String> {
+    match cipher {
+        Some(cipher) => cipher.encrypt(text),
+        None => Ok(text.to_string()),
+    }
+}
 
-        debug!("Database migrations completed");
-        Ok(())
+/// 解密数据库中的文本，未加密的记录原样返回
+fn open_text(cipher: Option<&FieldCipher>, text: String) -> Result<String> {
+    if !is_encrypted(&text) {
+        return Ok(text);
+    }
+    match cipher {
+        Some(cipher) => cipher.decrypt(&text),
+        None => Err(AppError::Crypto(
+            "翻译历史已加密，请先启用历史记录加密".to_string(),
+        )),
     }
+}
 
-    /// 插入翻译记录
-    pub async fn insert_translation(
-        &self,
-        original_text: &str,
-        translated_text: &str,
-        source_lang: Option<&str>,
-        target_lang: &str,
-        mode: &str,
-    ) -> Result<i64> {
-        let timestamp = Utc::now().timestamp();
+/// 解密数据库中的二进制内容，未加密的内容原样返回
+fn open_blob(cipher: Option<&FieldCipher>, blob: Vec<u8>) -> Result<Vec<u8>> {
+    if !is_encrypted_bytes(&blob) {
+        return Ok(blob);
+    }
+    match cipher {
+        Some(cipher) => cipher.decrypt_bytes(&blob),
+        None => Err(AppError::Crypto(
+            "翻译历史已加密，请先启用历史记录加密".to_string(),
+        )),
+    }
+}
 
-        let result = sqlx::query(
-            r#"
-            INSERT INTO translations (original_text, translated_text, source_lang, target_lang, mode, timestamp)
-            VALUES (?, ?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(original_text)
-        .bind(translated_text)
-        .bind(source_lang)
-        .bind(target_lang)
-        .bind(mode)
-        .bind(timestamp)
-        .execute(&self.pool)
-        .await?;
+/// 写入文本列和压缩列的内容
+struct StoredText {
+    /// 完整文本，压缩保存时为预览
+    text: String,
+    /// 压缩后的完整文本
+    blob: Option<Vec<u8>>,
+}
 
-        Ok(result.last_insert_rowid())
+/// 准备要保存的文本，超过 `COMPRESS_THRESHOLD_BYTES` 时压缩保存，加密启用时两列都加密
+fn store_text(cipher: Option<&FieldCipher>, text: &str) -> Result<StoredText> {
+    if text.len() <= COMPRESS_THRESHOLD_BYTES {
+        return Ok(StoredText {
+            text: seal_text(cipher, text)?,
+            blob: None,
+        });
     }
 
-    /// 查询翻译历史
-    pub async fn get_history(
-        &self,
-        page: i64,
-        page_size: i64,
-        search: Option<&str>,
-        mode: Option<&str>,
-    ) -> Result<HistoryResult> {
-        let offset = (page - 1) * page_size;
+    let preview: String = text.chars().take(PREVIEW_CHARS).collect();
+    let compressed = compress_text(text)?;
+    let blob = match cipher {
+        Some(cipher) => cipher.encrypt_bytes(&compressed)?,
+        None => compressed,
+    };
+    Ok(StoredText {
+        text: seal_text(cipher, &preview)?,
+        blob: Some(blob),
+    })
+}
 
-        // 构建查询条件
-        let mut conditions = Vec::new();
-        if search.is_some() {
-            conditions.push("(original_text LIKE ? OR translated_text LIKE ?)");
-        }
-        if mode.is_some() {
-            conditions.push("mode = ?");
-        }
+/// 读取保存的文本，有压缩内容时返回解压后的完整文本
+fn load_text(cipher: Option<&FieldCipher>, text: String, blob: Option<Vec<u8>>) -> Result<String> {
+    match blob {
+        Some(blob) => decompress_text(&open_blob(cipher, blob)?),
+        None => open_text(cipher, text),
+    }
+}
 
-        let where_clause = if conditions.is_empty() {
-            String::new()
-        } else {
-            format!("WHERE {}", conditions.join(" AND "))
-        };
+/// 使用 zstd 压缩文本，结果以格式标记开头
+fn compress_text(text: &str) -> Result<Vec<u8>> {
+    let mut compressed = vec![BLOB_FORMAT_ZSTD];
+    compressed.extend(zstd::bulk::compress(text.as_bytes(), ZSTD_LEVEL)?);
+    Ok(compressed)
+}
 
-        // 查询总数
-        let count_query = format!("SELECT COUNT(*) as count FROM translations {}", where_clause);
-        let mut count_builder = sqlx::query(&count_query);
-        
-        if let Some(s) = search {
-            let pattern = format!("%{}%", s);
-            count_builder = count_builder.bind(pattern.clone()).bind(pattern);
+/// 按格式标记解压文本，之前写入的 zlib 内容仍可读取
+fn decompress_text(data: &[u8]) -> Result<String> {
+    let mut text = String::new();
+    match data.first() {
+        Some(&BLOB_FORMAT_ZSTD) => {
+            zstd::stream::read::Decoder::new(&data[1..])?.read_to_string(&mut text)?;
         }
-        if let Some(m) = mode {
-            count_builder = count_builder.bind(m);
+        Some(&BLOB_FORMAT_ZLIB) => {
+            ZlibDecoder::new(&data[1..]).read_to_string(&mut text)?;
         }
-
-        let total: i64 = count_builder
-            .fetch_one(&self.pool)
-            .await?
-            .get("count");
-
-        // 查询记录
-        let data_query = format!(
-            "SELECT * FROM translations {} ORDER BY timestamp DESC LIMIT ? OFFSET ?",
-            where_clause
-        );
-        let mut data_builder = sqlx::query(&data_query);
-        
-        if let Some(s) = search {
-            let pattern = format!("%{}%", s);
-            data_builder = data_builder.bind(pattern.clone()).bind(pattern);
+        Some(&ZLIB_HEADER) => {
+            ZlibDecoder::new(data).read_to_string(&mut text)?;
         }
-        if let Some(m) = mode {
-            data_builder = data_builder.bind(m);
+        format => {
+            return Err(AppError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("未知的压缩格式: {:?}", format),
+            )))
         }
-        
-        data_builder = data_builder.bind(page_size).bind(offset);
-
-        let rows = data_builder.fetch_all(&self.pool).await?;
+    }
+    Ok(text)
+}
 
-        let records: Vec<TranslationRecord> = rows
-            .iter()
-            .map(|row| TranslationRecord {
-                id: row.get("id"),
-                original_text: row.get("original_text"),
-                translated_text: row.get("translated_text"),
-                source_lang: row.get("source_lang"),
-                target_lang: row.get("target_lang"),
-                mode: row.get("mode"),
-                timestamp: row.get("timestamp"),
-            })
-            .collect();
+/// 用于查找相同原文的规范化文本：去掉首尾空白并统一换行符
+fn normalize_for_hash(text: &str) -> String {
+    text.trim().replace("\r\n", "\n")
+}
 
-        Ok(HistoryResult { records, total })
-    }
+/// 原文的哈希（十六进制），规范化后相同的文本哈希相同，只能用来判断两段原文是否相同
+///
+/// 启用加密时使用密钥派生的 HMAC，否则为 SHA-256。加密时如果保存普通哈希，
+/// 拿到数据库文件就能逐个哈希猜测的原文来确认记录内容，短文本可以直接查表。
+fn text_hash(cipher: Option<&FieldCipher>, text: &str) -> String {
+    let normalized = normalize_for_hash(text);
+    let hash = match cipher {
+        Some(cipher) => cipher.keyed_hash(normalized.as_bytes()),
+        None => digest::digest(&digest::SHA256, normalized.as_bytes())
+            .as_ref()
+            .to_vec(),
+    };
+    hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
 
-    /// 清理超出限制的历史记录
-    pub async fn cleanup_history(&self, limit: usize) -> Result<u64> {
-        let result = sqlx::query(
-            r#"
-            DELETE FROM translations 
-            WHERE id NOT IN (
-                SELECT id FROM translations 
-                ORDER BY timestamp DESC 
-                LIMIT ?
-            )
-            "#,
-        )
-        .bind(limit as i64)
-        .execute(&self.pool)
-        .await?;
+/// 读取查询结果中的完整原文和译文
+fn texts_from_row(row: &SqliteRow, cipher: Option<&FieldCipher>) -> Result<(String, String)> {
+    Ok((
+        load_text(cipher, row.get("original_text"), row.get("original_blob"))?,
+        load_text(
+            cipher,
+            row.get("translated_text"),
+            row.get("translated_blob"),
+        )?,
+    ))
+}
 
-        let deleted = result.rows_affected();
-        if deleted > 0 {
-            debug!("Cleaned up {} old translation records", deleted);
+/// 隐藏错误信息中疑似 API Key 的内容，并截断到 `ERROR_MESSAGE_MAX_CHARS`
+pub(crate) fn sanitize_error_message(message: &str) -> String {
+    let mut sanitized = String::with_capacity(message.len());
+    let mut token = String::new();
+    for c in message.chars().chain(std::iter::once(' ')) {
+        if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+            token.push(c);
+            continue;
         }
-        Ok(deleted)
+        if looks_like_secret(&token) {
+            sanitized.push_str("[REDACTED]");
+        } else {
+            sanitized.push_str(&token);
+        }
+        token.clear();
+        sanitized.push(c);
     }
+    sanitized.pop();
 
-    /// 清空所有翻译历史和性能指标
-    pub async fn clear_all_history(&self) -> Result<u64> {
-        // 清空翻译历史
-        let translations_result = sqlx::query("DELETE FROM translations")
-            .execute(&self.pool)
-            .await?;
-        let translations_deleted = translations_result.rows_affected();
-
-        // 清空性能指标
-        let metrics_result = sqlx::query("DELETE FROM metrics")
-            .execute(&self.pool)
-            .await?;
-        let metrics_deleted = metrics_result.rows_affected();
+    if sanitized.chars().count() <= ERROR_MESSAGE_MAX_CHARS {
+        return sanitized;
+    }
+    let mut truncated: String = sanitized.chars().take(ERROR_MESSAGE_MAX_CHARS).collect();
+    truncated.push('…');
+    truncated
+}
 
-        info!(
-            "Cleared all history: {} translations, {} metrics", 
-            translations_deleted, 
-            metrics_deleted
-        );
-        Ok(translations_deleted)
+/// `sk-` 开头的片段，或同时包含字母和数字的长片段
+fn looks_like_secret(token: &str) -> bool {
+    if token.starts_with("sk-") && token.len() > "sk-".len() {
+        return true;
     }
+    token.len() >= SECRET_MIN_LEN
+        && token.chars().any(|c| c.is_ascii_digit())
+        && token.chars().any(|c| c.is_ascii_alphabetic())
+}
 
-    /// 记录性能指标
-    pub async fn record_metric(
-        &self,
-        operation_type: &str,
-        duration_ms: i64,
-        success: bool,
-        error_type: Option<&str>,
-        char_count: i64,
-    ) -> Result<()> {
-        let timestamp = Utc::now().timestamp();
+/// 从查询结果构建翻译记录，并解密原文和译文
+fn record_from_row(row: &SqliteRow, cipher: Option<&FieldCipher>) -> Result<TranslationRecord> {
+    let (original_text, translated_text) = texts_from_row(row, cipher)?;
+    Ok(TranslationRecord {
+        id: row.get("id"),
+        original_text,
+        translated_text,
+        source_lang: row.get("source_lang"),
+        target_lang: row.get("target_lang"),
+        mode: row.get("mode"),
+        timestamp: row.get("timestamp"),
+        model: row.get("model"),
+        completion_tokens: row.get("completion_tokens"),
+        duration_ms: row.get("duration_ms"),
+        pinned: row.get("pinned"),
+        source_app: row.get("source_app"),
+        retranslated_from: row.get("retranslated_from"),
+    })
+}
 
-        sqlx::query(
-            r#"
-            INSERT INTO metrics (timestamp, operation_type, duration_ms, success, error_type, char_count)
-            VALUES (?, ?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(timestamp)
-        .bind(operation_type)
-        .bind(duration_ms)
-        .bind(success)
-        .bind(error_type)
-        .bind(char_count)
-        .execute(&self.pool)
-        .await?;
+/// 查询历史记录的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryResult {
+    pub records: Vec<TranslationRecord>,
+    pub total: i64,
+}
 
-        Ok(())
-    }
+/// 历史记录按筛选项的计数，用于在筛选控件旁显示数量
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryFacets {
+    pub total: i64,
+    pub by_mode: Vec<FacetCount>,
+    pub by_target_lang: Vec<FacetCount>,
+}
 
-    /// 插入带有 tokens 信息的性能指标
-    pub async fn insert_metric(
-        &self,
-        operation_type: &str,
-        duration_ms: i64,
-        success: bool,
-        error_type: Option<&str>,
-        char_count: i64,
-        completion_tokens: Option<u32>,
-        tokens_per_second: Option<f64>,
-    ) -> Result<()> {
-        let timestamp = Utc::now().timestamp();
+/// 单个筛选项的记录数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
 
-        sqlx::query(
-            r#"
-            INSERT INTO metrics (timestamp, operation_type, duration_ms, success, error_type, char_count, completion_tokens, tokens_per_second)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(timestamp)
-        .bind(operation_type)
-        .bind(duration_ms)
-        .bind(success)
-        .bind(error_type)
-        .bind(char_count as i32)
-        .bind(completion_tokens.map(|t| t as i32))
-        .bind(tokens_per_second)
-        .execute(&self.pool)
-        .await?;
+/// 按数量从多到少排列计数，数量相同时按取值排列
+fn sorted_facets(counts: HashMap<String, i64>) -> Vec<FacetCount> {
+    let mut facets: Vec<FacetCount> = counts
+        .into_iter()
+        .map(|(value, count)| FacetCount { value, count })
+        .collect();
+    facets.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    facets
+}
 
-        Ok(())
-    }
+/// 按游标分页时的位置，指向上一页的最后一条记录
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryCursor {
+    pub timestamp: i64,
+    pub id: i64,
+}
 
-    /// 获取性能统计
-    pub async fn get_performance_stats(&self, period: &str) -> Result<PerformanceStats> {
-        let since = match period {
-            "hour" => Utc::now().timestamp() - 3600,
-            "day" => Utc::now().timestamp() - 86400,
-            "week" => Utc::now().timestamp() - 604800,
-            _ => Utc::now().timestamp() - 86400,
-        };
+/// 按游标分页查询的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryPage {
+    pub records: Vec<TranslationRecord>,
+    /// 下一页的游标，没有更多记录时为空
+    pub next_cursor: Option<HistoryCursor>,
+}
 
-        // 基本统计
-        let stats_row = sqlx::query(
+impl HistoryPage {
+    /// 由多查询一条的结果构建分页，多出的一条说明还有下一页
+    fn from_records(mut records: Vec<TranslationRecord>, limit: usize) -> Self {
+        let has_more = records.len() > limit;
+        records.truncate(limit);
+        let next_cursor = records
+            .last()
+            .filter(|_| has_more)
+            .map(|last| HistoryCursor {
+                timestamp: last.timestamp,
+                id: last.id,
+            });
+        Self {
+            records,
+            next_cursor,
+        }
+    }
+}
+
+/// 最近翻译列表的最大条数
+pub const RECENT_MAX_LIMIT: usize = 50;
+/// 最近翻译列表中原文和译文保留的字符数
+const RECENT_PREVIEW_CHARS: usize = 100;
+
+/// 最近的一条翻译，原文和译文只保留开头部分，供托盘菜单和快捷面板使用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentTranslation {
+    pub id: i64,
+    pub original_text: String,
+    /// 原文是否被截断
+    pub original_truncated: bool,
+    pub translated_text: String,
+    /// 译文是否被截断
+    pub translated_truncated: bool,
+    pub target_lang: String,
+    pub mode: String,
+    pub timestamp: i64,
+    pub pinned: bool,
+    pub source_app: Option<String>,
+}
+
+impl From<TranslationRecord> for RecentTranslation {
+    fn from(record: TranslationRecord) -> Self {
+        let (original_text, original_truncated) =
+            truncate_chars(&record.original_text, RECENT_PREVIEW_CHARS);
+        let (translated_text, translated_truncated) =
+            truncate_chars(&record.translated_text, RECENT_PREVIEW_CHARS);
+        Self {
+            id: record.id,
+            original_text,
+            original_truncated,
+            translated_text,
+            translated_truncated,
+            target_lang: record.target_lang,
+            mode: record.mode,
+            timestamp: record.timestamp,
+            pinned: record.pinned,
+            source_app: record.source_app,
+        }
+    }
+}
+
+/// 保留文本开头的 `max_chars` 个字符，返回截断后的文本和是否发生了截断
+fn truncate_chars(text: &str, max_chars: usize) -> (String, bool) {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => (text[..end].to_string(), true),
+        None => (text.to_string(), false),
+    }
+}
+
+/// 详情页中相邻记录的 ID，按历史列表的顺序（从新到旧）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AdjacentIds {
+    /// 列表中的上一条（更新的）记录
+    pub previous: Option<i64>,
+    /// 列表中的下一条（更早的）记录
+    pub next: Option<i64>,
+}
+
+impl Database {
+    /// 打开配置的数据库文件，未配置时使用默认位置
+    pub async fn new(configured_path: Option<&Path>) -> Result<Self> {
+        match configured_path {
+            Some(path) => Self::open(path).await,
+            None => Self::open(&Self::default_path()?).await,
+        }
+    }
+
+    /// 打开指定路径的数据库，不存在时创建
+    pub async fn open(db_path: &Path) -> Result<Self> {
+        // 确保目录存在
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let db = Self::from_pool(Self::connect(db_path).await?);
+        *db.path.lock().unwrap() = Some(db_path.to_path_buf());
+        db.run_migrations().await?;
+        
+        info!("Database initialized successfully");
+        Ok(db)
+    }
+
+    /// 创建已完成迁移的内存数据库，单连接保证所有查询访问同一个库
+    #[cfg(test)]
+    pub(crate) async fn new_in_memory() -> Result<Self> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        let db = Self::from_pool(pool);
+        db.run_migrations().await?;
+        Ok(db)
+    }
+
+    fn from_pool(pool: Pool<Sqlite>) -> Self {
+        Self {
+            pool: Mutex::new(pool),
+            path: Mutex::new(None),
+            maintenance: RwLock::new(()),
+            cipher: Mutex::new(None),
+            encryption_error: Mutex::new(None),
+        }
+    }
+
+    /// 连接数据库文件
+    ///
+    /// 使用 WAL 模式，读写可以并发进行；写入冲突时等待 `BUSY_TIMEOUT` 而不是立即失败。
+    async fn connect(db_path: &Path) -> Result<Pool<Sqlite>> {
+        debug!("Connecting to database: {}", db_path.display());
+        let options = SqliteConnectOptions::new()
+            .filename(db_path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(BUSY_TIMEOUT);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect_with(options)
+            .await?;
+        Ok(pool)
+    }
+
+    /// 当前的连接池
+    fn pool(&self) -> Pool<Sqlite> {
+        self.pool.lock().unwrap().clone()
+    }
+
+    /// 将 WAL 中的内容写回数据库文件后关闭连接池，之后可以安全地替换或复制数据库文件
+    async fn checkpoint_and_close(&self) -> Result<()> {
+        let pool = self.pool();
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&pool)
+            .await?;
+        pool.close().await;
+        Ok(())
+    }
+
+    /// 当前的加密器，未启用加密时为 `None`
+    fn cipher(&self) -> Option<Arc<FieldCipher>> {
+        self.cipher.lock().unwrap().clone()
+    }
+
+    /// 是否已启用历史记录加密
+    pub fn encryption_enabled(&self) -> bool {
+        self.cipher().is_some()
+    }
+
+    /// 配置要求加密但无法加载密钥时调用，之后保存原文和译文的操作都会失败，直到成功启用或关闭加密
+    pub fn mark_encryption_unavailable(&self, reason: String) {
+        *self.encryption_error.lock().unwrap() = Some(reason);
+    }
+
+    /// 历史记录加密不可用的原因，加密正常或未启用加密时为 `None`
+    pub fn encryption_error(&self) -> Option<String> {
+        self.encryption_error.lock().unwrap().clone()
+    }
+
+    /// 保存原文和译文前调用，加密不可用时返回错误，不能以明文保存
+    fn ensure_text_writable(&self) -> Result<()> {
+        match self.encryption_error() {
+            Some(reason) => Err(AppError::Crypto(format!(
+                "历史记录加密不可用，翻译文本不会保存: {}",
+                reason
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// 翻译历史中是否有加密的记录
+    pub async fn has_encrypted_history(&self) -> Result<bool> {
+        let encrypted = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM translations WHERE original_text LIKE ? OR translated_text LIKE ?)",
+        )
+        .bind(format!("{}%", ENCRYPTED_PREFIX))
+        .bind(format!("{}%", ENCRYPTED_PREFIX))
+        .fetch_one(&self.pool())
+        .await?;
+        Ok(encrypted)
+    }
+
+    /// 翻译历史中是否有未加密的原文或译文，包括压缩保存的内容
+    pub async fn has_plain_history(&self) -> Result<bool> {
+        let plain = sqlx::query_scalar(
             r#"
-            SELECT 
-                COUNT(*) as total,
-                SUM(CASE WHEN success = 1 THEN 1 ELSE 0 END) as successful,
-                SUM(CASE WHEN success = 0 THEN 1 ELSE 0 END) as failed,
-                AVG(CASE WHEN success = 1 THEN duration_ms ELSE NULL END) as avg_duration,
-                MIN(CASE WHEN success = 1 THEN duration_ms ELSE NULL END) as min_duration,
-                MAX(CASE WHEN success = 1 THEN duration_ms ELSE NULL END) as max_duration,
-                SUM(char_count) as total_chars,
-                SUM(CASE WHEN operation_type = 'selected' THEN 1 ELSE 0 END) as selected_count,
-                SUM(CASE WHEN operation_type = 'full' THEN 1 ELSE 0 END) as full_count,
-                SUM(COALESCE(completion_tokens, 0)) as total_tokens,
-                AVG(CASE WHEN tokens_per_second > 0 THEN tokens_per_second ELSE NULL END) as avg_tps
-            FROM metrics
-            WHERE timestamp > ?
+            SELECT EXISTS(
+                SELECT 1 FROM translations
+                WHERE substr(original_text, 1, ?1) != ?2
+                   OR substr(translated_text, 1, ?1) != ?2
+                   OR substr(original_blob, 1, ?1) != CAST(?2 AS BLOB)
+                   OR substr(translated_blob, 1, ?1) != CAST(?2 AS BLOB)
+            )
             "#,
         )
-        .bind(since)
-        .fetch_one(&self.pool)
+        .bind(ENCRYPTED_PREFIX.len() as i64)
+        .bind(ENCRYPTED_PREFIX)
+        .fetch_one(&self.pool())
         .await?;
+        Ok(plain)
+    }
 
-        // 错误分布
-        let error_rows = sqlx::query(
+    /// 是否有缺少原文哈希的记录，迁移时无法为加密的记录计算哈希
+    async fn has_unhashed_history(&self) -> Result<bool> {
+        let unhashed = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM translations WHERE original_hash IS NULL)",
+        )
+        .fetch_one(&self.pool())
+        .await?;
+        Ok(unhashed)
+    }
+
+    /// 启用历史记录加密，并加密已有的明文记录，返回新加密的记录数
+    ///
+    /// 记录就地改写后原来的明文仍留在空闲页和 WAL 中，加密了记录时重建数据库文件并清空 WAL。
+    /// 每次启动都会调用，所有记录都已加密且有哈希时不逐条读取记录。
+    pub async fn enable_encryption(&self, cipher: FieldCipher) -> Result<u64> {
+        let _guard = self.maintenance.write().await;
+        let cipher = Arc::new(cipher);
+        let encrypted = if self.has_plain_history().await? || self.has_unhashed_history().await? {
+            self.encrypt_plain_history(&cipher).await?
+        } else {
+            0
+        };
+        if encrypted > 0 {
+            self.scrub_free_pages().await?;
+        }
+
+        *self.cipher.lock().unwrap() = Some(cipher);
+        *self.encryption_error.lock().unwrap() = None;
+        info!(
+            "History encryption enabled, {} records encrypted",
+            encrypted
+        );
+        Ok(encrypted)
+    }
+
+    /// 关闭历史记录加密，并将已加密的记录还原为明文，返回解密的记录数
+    ///
+    /// 密钥不可用时无法解密已有的记录，这些记录保持加密，之后的新记录以明文保存。
+    pub async fn disable_encryption(&self) -> Result<u64> {
+        let _guard = self.maintenance.write().await;
+        let Some(cipher) = self.cipher() else {
+            *self.encryption_error.lock().unwrap() = None;
+            return Ok(0);
+        };
+        let decrypted = self
+            .reencode_history(
+                |text| {
+                    if is_encrypted(text) {
+                        cipher.decrypt(text).map(Some)
+                    } else {
+                        Ok(None)
+                    }
+                },
+                |blob| {
+                    if is_encrypted_bytes(blob) {
+                        cipher.decrypt_bytes(blob).map(Some)
+                    } else {
+                        Ok(None)
+                    }
+                },
+                None,
+            )
+            .await?;
+
+        *self.cipher.lock().unwrap() = None;
+        info!(
+            "History encryption disabled, {} records decrypted",
+            decrypted
+        );
+        Ok(decrypted)
+    }
+
+    /// 加密尚未加密的记录，返回新加密的记录数
+    async fn encrypt_plain_history(&self, cipher: &FieldCipher) -> Result<u64> {
+        self.reencode_history(
+            |text| {
+                if is_encrypted(text) {
+                    Ok(None)
+                } else {
+                    cipher.encrypt(text).map(Some)
+                }
+            },
+            |blob| {
+                if is_encrypted_bytes(blob) {
+                    Ok(None)
+                } else {
+                    cipher.encrypt_bytes(blob).map(Some)
+                }
+            },
+            Some(cipher),
+        )
+        .await
+    }
+
+    /// 重建数据库文件并清空 WAL，清除已删除或改写的内容残留在空闲页中的副本
+    ///
+    /// 调用方需持有 `maintenance` 写锁。
+    async fn scrub_free_pages(&self) -> Result<()> {
+        sqlx::query("VACUUM").execute(&self.pool()).await?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&self.pool())
+            .await?;
+        Ok(())
+    }
+
+    /// 在一个事务中转换所有记录的原文和译文，包括压缩保存的内容，转换函数返回 `None` 表示保持不变
+    ///
+    /// 转换过的记录和缺少哈希的记录按转换后的加密状态（`hash_cipher`）重新计算原文哈希。
+    async fn reencode_history<F, G>(
+        &self,
+        convert_text: F,
+        convert_blob: G,
+        hash_cipher: Option<&FieldCipher>,
+    ) -> Result<u64>
+    where
+        F: Fn(&str) -> Result<Option<String>>,
+        G: Fn(&[u8]) -> Result<Option<Vec<u8>>>,
+    {
+        let mut tx = self.pool().begin().await?;
+        let rows = sqlx::query(
+            "SELECT id, original_text, translated_text, original_blob, translated_blob, original_hash FROM translations",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut updated = 0;
+        for row in &rows {
+            let original = convert_text(row.get("original_text"))?;
+            let translated = convert_text(row.get("translated_text"))?;
+            let original_blob = row
+                .get::<Option<Vec<u8>>, _>("original_blob")
+                .map_or(Ok(None), |blob| convert_blob(&blob))?;
+            let translated_blob = row
+                .get::<Option<Vec<u8>>, _>("translated_blob")
+                .map_or(Ok(None), |blob| convert_blob(&blob))?;
+            let converted = original.is_some()
+                || translated.is_some()
+                || original_blob.is_some()
+                || translated_blob.is_some();
+            if !converted && row.get::<Option<String>, _>("original_hash").is_some() {
+                continue;
+            }
+
+            let original_text = load_text(
+                hash_cipher,
+                original.clone().unwrap_or_else(|| row.get("original_text")),
+                original_blob.clone().or_else(|| row.get("original_blob")),
+            )?;
+            sqlx::query(
+                r#"
+                UPDATE translations
+                SET original_text = COALESCE(?, original_text), translated_text = COALESCE(?, translated_text),
+                    original_blob = COALESCE(?, original_blob), translated_blob = COALESCE(?, translated_blob),
+                    original_hash = ?
+                WHERE id = ?
+                "#,
+            )
+            .bind(original)
+            .bind(translated)
+            .bind(original_blob)
+            .bind(translated_blob)
+            .bind(text_hash(hash_cipher, &original_text))
+            .bind(row.get::<i64, _>("id"))
+            .execute(&mut *tx)
+            .await?;
+            if converted {
+                updated += 1;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    /// 默认的数据库文件路径
+    pub fn default_path() -> Result<PathBuf> {
+        let data_dir = dirs::data_dir()
+            .ok_or_else(|| AppError::Config("无法获取数据目录".to_string()))?;
+        Ok(data_dir.join("QuickTransType").join("quicktranstype.db"))
+    }
+
+    /// 运行数据库迁移
+    async fn run_migrations(&self) -> Result<()> {
+        self.apply_migrations(MIGRATIONS).await
+    }
+
+    /// 按顺序应用尚未执行的迁移，每个迁移在单独的事务中执行并记录版本号
+    ///
+    /// 迁移失败时事务回滚且不记录版本，数据库保持在上一个版本。
+    async fn apply_migrations(&self, migrations: &[Migration]) -> Result<()> {
+        debug!("Running database migrations...");
+
+        sqlx::query(
             r#"
-            SELECT error_type, COUNT(*) as count
-            FROM metrics
-            WHERE timestamp > ? AND success = 0 AND error_type IS NOT NULL
-            GROUP BY error_type
+            CREATE TABLE IF NOT EXISTS schema_version (
+                version INTEGER PRIMARY KEY,
+                applied_at INTEGER NOT NULL
+            )
             "#,
         )
-        .bind(since)
-        .fetch_all(&self.pool)
+        .execute(&self.pool())
         .await?;
 
-        let error_distribution: Vec<ErrorDistribution> = error_rows
-            .iter()
-            .map(|row| ErrorDistribution {
-                error_type: row.get("error_type"),
-                count: row.get("count"),
-            })
-            .collect();
+        let current = self.schema_version().await?;
+        if current > migrations.len() as i64 {
+            warn!(
+                "Database schema version {} is newer than supported version {}",
+                current,
+                migrations.len()
+            );
+        }
 
-        Ok(PerformanceStats {
-            total_translations: stats_row.get::<i64, _>("total") as u64,
-            successful_translations: stats_row.get::<i64, _>("successful") as u64,
-            failed_translations: stats_row.get::<i64, _>("failed") as u64,
-            avg_duration_ms: stats_row.get::<Option<f64>, _>("avg_duration").unwrap_or(0.0),
-            min_duration_ms: stats_row.get::<Option<i64>, _>("min_duration").unwrap_or(0) as u64,
-            max_duration_ms: stats_row.get::<Option<i64>, _>("max_duration").unwrap_or(0) as u64,
-            total_chars_translated: stats_row.get::<Option<i64>, _>("total_chars").unwrap_or(0) as u64,
-            selected_mode_count: stats_row.get::<i64, _>("selected_count") as u64,
-            full_mode_count: stats_row.get::<i64, _>("full_count") as u64,
-            total_completion_tokens: stats_row.get::<Option<i64>, _>("total_tokens").unwrap_or(0) as u64,
-            avg_tokens_per_second: stats_row.get::<Option<f64>, _>("avg_tps").unwrap_or(0.0),
-            error_distribution,
-            hourly_data: Vec::new(), // TODO: 实现按小时统计
-        })
+        for (index, migration) in migrations.iter().enumerate() {
+            let version = index as i64 + 1;
+            if version <= current {
+                continue;
+            }
+
+            let mut tx = self.pool().begin().await?;
+            if let Err(e) = migration(&mut tx).await {
+                error!("Database migration v{} failed: {}", version, e);
+                return Err(e);
+            }
+            sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, ?)")
+                .bind(version)
+                .bind(Utc::now().timestamp())
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            info!("Applied database migration v{}", version);
+        }
+
+        debug!("Database migrations completed");
+        Ok(())
     }
 
-    /// 清理旧的性能指标（保留 90 天）
-    pub async fn cleanup_metrics(&self) -> Result<u64> {
-        let cutoff = Utc::now().timestamp() - (90 * 24 * 3600);
-        
-        let result = sqlx::query("DELETE FROM metrics WHERE timestamp < ?")
-            .bind(cutoff)
-            .execute(&self.pool)
+    /// 获取数据库当前的 schema 版本，未执行过迁移时为 0
+    pub async fn schema_version(&self) -> Result<i64> {
+        let version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM schema_version")
+            .fetch_one(&self.pool())
             .await?;
+        Ok(version.unwrap_or(0))
+    }
 
-        let deleted = result.rows_affected();
-        if deleted > 0 {
-            debug!("Cleaned up {} old metric records", deleted);
+    /// 插入翻译记录
+    ///
+    /// 如果去重窗口内最近的一条记录原文、译文和目标语言都相同，只更新它的时间戳并返回其 id。
+    pub async fn insert_translation(
+        &self,
+        original_text: &str,
+        translated_text: &str,
+        source_lang: Option<&str>,
+        target_lang: &str,
+        mode: &str,
+        metadata: &TranslationMetadata,
+    ) -> Result<i64> {
+        let _guard = self.maintenance.read().await;
+        self.ensure_text_writable()?;
+        let timestamp = Utc::now().timestamp();
+        let cipher = self.cipher();
+        // 立即获取写锁，避免去重检查和写入之间被其他写入插入
+        let mut tx = self.pool().begin_with("BEGIN IMMEDIATE").await?;
+
+        if metadata.dedup_window_secs > 0 {
+            let since = timestamp - metadata.dedup_window_secs as i64;
+            let latest = sqlx::query(
+                r#"
+                SELECT id, original_text, translated_text, original_blob, translated_blob, target_lang
+                FROM translations
+                WHERE timestamp >= ?
+                ORDER BY timestamp DESC, id DESC
+                LIMIT 1
+                "#,
+            )
+            .bind(since)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            if let Some(row) = latest {
+                let is_duplicate = row.get::<String, _>("target_lang") == target_lang
+                    && texts_from_row(&row, cipher.as_deref())?
+                        == (original_text.to_string(), translated_text.to_string());
+                if is_duplicate {
+                    let id: i64 = row.get("id");
+                    sqlx::query("UPDATE translations SET timestamp = ? WHERE id = ?")
+                        .bind(timestamp)
+                        .bind(id)
+                        .execute(&mut *tx)
+                        .await?;
+                    tx.commit().await?;
+                    debug!("Skipped duplicate translation, refreshed record {}", id);
+                    return Ok(id);
+                }
+            }
         }
-        Ok(deleted)
+
+        let original = store_text(cipher.as_deref(), original_text)?;
+        let translated = store_text(cipher.as_deref(), translated_text)?;
+        let result = sqlx::query(
+            r#"
+            INSERT INTO translations (original_text, translated_text, original_blob, translated_blob, original_hash, source_lang, target_lang, mode, timestamp, model, completion_tokens, duration_ms, source_app, retranslated_from)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(original.text)
+        .bind(translated.text)
+        .bind(original.blob)
+        .bind(translated.blob)
+        .bind(text_hash(cipher.as_deref(), original_text))
+        .bind(source_lang)
+        .bind(target_lang)
+        .bind(mode)
+        .bind(timestamp)
+        .bind(metadata.model.as_deref())
+        .bind(metadata.completion_tokens.map(i64::from))
+        .bind(metadata.duration_ms)
+        .bind(metadata.source_app.as_deref())
+        .bind(metadata.retranslated_from)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(result.last_insert_rowid())
     }
-}
 
-/// 性能统计
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PerformanceStats {
-    pub total_translations: u64,
-    pub successful_translations: u64,
-    pub failed_translations: u64,
-    pub avg_duration_ms: f64,
-    pub min_duration_ms: u64,
-    pub max_duration_ms: u64,
-    pub total_chars_translated: u64,
-    pub selected_mode_count: u64,
-    pub full_mode_count: u64,
-    pub error_distribution: Vec<ErrorDistribution>,
-    pub hourly_data: Vec<HourlyData>,
-    /// 总 completion tokens
-    pub total_completion_tokens: u64,
-    /// 平均输出速率 (tokens/s)
-    pub avg_tokens_per_second: f64,
-}
+    /// 查找 `max_age_secs` 秒内原文和目标语言都相同的最近一条翻译
+    ///
+    /// 通过原文哈希的索引查找，再比较规范化后的完整原文，排除哈希冲突。
+    pub async fn find_recent_translation(
+        &self,
+        original_text: &str,
+        target_lang: &str,
+        max_age_secs: u64,
+    ) -> Result<Option<TranslationRecord>> {
+        let since = Utc::now().timestamp() - max_age_secs as i64;
+        let cipher = self.cipher();
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM translations
+            WHERE original_hash = ? AND target_lang = ? AND timestamp >= ?
+            ORDER BY timestamp DESC, id DESC
+            "#,
+        )
+        .bind(text_hash(cipher.as_deref(), original_text))
+        .bind(target_lang)
+        .bind(since)
+        .fetch_all(&self.pool())
+        .await?;
 
-/// 错误分布
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ErrorDistribution {
-    pub error_type: String,
-    pub count: i64,
-}
+        let normalized = normalize_for_hash(original_text);
+        for row in &rows {
+            let record = record_from_row(row, cipher.as_deref())?;
+            if normalize_for_hash(&record.original_text) == normalized {
+                return Ok(Some(record));
+            }
+        }
+        Ok(None)
+    }
 
-/// 按小时统计
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HourlyData {
-    pub hour: i32,
-    pub avg_duration: f64,
-    pub count: i64,
+    /// 查询翻译历史
+    pub async fn get_history(
+        &self,
+        page: i64,
+        page_size: i64,
+        filter: &HistoryFilter,
+    ) -> Result<HistoryResult> {
+        let offset = (page - 1) * page_size;
+        let cipher = self.cipher();
+        if let (Some(cipher), Some(search)) = (&cipher, &filter.search) {
+            return self
+                .search_encrypted_history(cipher, search, page_size, offset, filter)
+                .await;
+        }
+
+        // 查询总数
+        let mut count_query = QueryBuilder::new("SELECT COUNT(*) as count FROM translations");
+        filter.push_where(&mut count_query);
+        let total: i64 = count_query
+            .build()
+            .fetch_one(&self.pool())
+            .await?
+            .get("count");
+
+        // 查询记录
+        let mut data_query = QueryBuilder::new("SELECT * FROM translations");
+        filter.push_where(&mut data_query);
+        data_query
+            .push(" ORDER BY timestamp DESC, id DESC LIMIT ")
+            .push_bind(page_size)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let rows = data_query.build().fetch_all(&self.pool()).await?;
+
+        let records = rows
+            .iter()
+            .map(|row| record_from_row(row, cipher.as_deref()))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(HistoryResult { records, total })
+    }
+
+    /// 按游标分页查询翻译历史，从 `cursor` 之后（更早）的记录开始
+    ///
+    /// 与按页码查询相比，深分页不需要跳过前面的记录，翻页期间有新记录写入时也不会重复或遗漏。
+    pub async fn get_history_after(
+        &self,
+        cursor: Option<HistoryCursor>,
+        limit: i64,
+        filter: &HistoryFilter,
+    ) -> Result<HistoryPage> {
+        let limit = limit.max(0) as usize;
+        let cipher = self.cipher();
+        if let (Some(cipher), Some(search)) = (&cipher, &filter.search) {
+            let matched = self
+                .encrypted_search_matches(cipher, search, filter)
+                .await?;
+            let records = matched
+                .into_iter()
+                .filter(|r| cursor.is_none_or(|c| (r.timestamp, r.id) < (c.timestamp, c.id)))
+                .take(limit + 1)
+                .collect();
+            return Ok(HistoryPage::from_records(records, limit));
+        }
+
+        let mut query = QueryBuilder::new("SELECT * FROM translations");
+        let mut conditions = filter.push_where(&mut query);
+        if let Some(cursor) = cursor {
+            conditions
+                .push("(timestamp, id) < (")
+                .push_bind(cursor.timestamp)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
+        }
+        query
+            .push(" ORDER BY timestamp DESC, id DESC LIMIT ")
+            .push_bind(limit as i64 + 1);
+
+        let rows = query.build().fetch_all(&self.pool()).await?;
+        let records = rows
+            .iter()
+            .map(|row| record_from_row(row, cipher.as_deref()))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(HistoryPage::from_records(records, limit))
+    }
+
+    /// 在加密的历史记录中搜索
+    ///
+    /// 密文无法用 LIKE 匹配，先按其他条件查询并解密，再在内存中按原文和译文过滤和分页。
+    async fn search_encrypted_history(
+        &self,
+        cipher: &FieldCipher,
+        search: &str,
+        page_size: i64,
+        offset: i64,
+        filter: &HistoryFilter,
+    ) -> Result<HistoryResult> {
+        let matched = self
+            .encrypted_search_matches(cipher, search, filter)
+            .await?;
+
+        let total = matched.len() as i64;
+        let records = matched
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(page_size.max(0) as usize)
+            .collect();
+        Ok(HistoryResult { records, total })
+    }
+
+    /// 解密并按原文和译文过滤历史记录，按历史列表的顺序返回全部匹配项
+    async fn encrypted_search_matches(
+        &self,
+        cipher: &FieldCipher,
+        search: &str,
+        filter: &HistoryFilter,
+    ) -> Result<Vec<TranslationRecord>> {
+        let filter = HistoryFilter {
+            search: None,
+            ..filter.clone()
+        };
+        let mut query = QueryBuilder::new("SELECT * FROM translations");
+        filter.push_where(&mut query);
+        query.push(" ORDER BY timestamp DESC, id DESC");
+        let rows = query.build().fetch_all(&self.pool()).await?;
+
+        let search = search.to_lowercase();
+        let mut matched = Vec::new();
+        for row in &rows {
+            let record = record_from_row(row, Some(cipher))?;
+            if record.original_text.to_lowercase().contains(&search)
+                || record.translated_text.to_lowercase().contains(&search)
+            {
+                matched.push(record);
+            }
+        }
+        Ok(matched)
+    }
+
+    /// 统计搜索结果中每种模式和目标语言的记录数，与 `get_history` 使用相同的筛选条件
+    pub async fn get_history_facets(&self, search: Option<&str>) -> Result<HistoryFacets> {
+        let filter = HistoryFilter {
+            search: search.map(str::to_string),
+            ..Default::default()
+        };
+
+        let (by_mode, by_target_lang) = match (self.cipher(), search) {
+            (Some(cipher), Some(search)) => {
+                let matched = self
+                    .encrypted_search_matches(&cipher, search, &filter)
+                    .await?;
+                let mut modes = HashMap::new();
+                let mut languages = HashMap::new();
+                for record in matched {
+                    *modes.entry(record.mode).or_insert(0) += 1;
+                    *languages.entry(record.target_lang).or_insert(0) += 1;
+                }
+                (sorted_facets(modes), sorted_facets(languages))
+            }
+            _ => (
+                self.facet_counts("mode", &filter).await?,
+                self.facet_counts("target_lang", &filter).await?,
+            ),
+        };
+
+        Ok(HistoryFacets {
+            total: by_mode.iter().map(|facet| facet.count).sum(),
+            by_mode,
+            by_target_lang,
+        })
+    }
+
+    /// 按 `column` 分组统计符合筛选条件的记录数
+    async fn facet_counts(&self, column: &str, filter: &HistoryFilter) -> Result<Vec<FacetCount>> {
+        let mut query = QueryBuilder::new(format!(
+            "SELECT {} as value, COUNT(*) as count FROM translations",
+            column
+        ));
+        filter.push_where(&mut query);
+        query.push(" GROUP BY value ORDER BY count DESC, value");
+        let rows = query.build().fetch_all(&self.pool()).await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| FacetCount {
+                value: row.get("value"),
+                count: row.get("count"),
+            })
+            .collect())
+    }
+
+    /// 获取最近的 `limit` 条翻译，最多 `RECENT_MAX_LIMIT` 条
+    pub async fn get_recent(&self, limit: usize) -> Result<Vec<RecentTranslation>> {
+        let limit = limit.min(RECENT_MAX_LIMIT);
+        let rows =
+            sqlx::query("SELECT * FROM translations ORDER BY timestamp DESC, id DESC LIMIT ?")
+                .bind(limit as i64)
+                .fetch_all(&self.pool())
+                .await?;
+
+        let cipher = self.cipher();
+        rows.iter()
+            .map(|row| record_from_row(row, cipher.as_deref()).map(RecentTranslation::from))
+            .collect()
+    }
+
+    /// 按 ID 获取单条翻译记录，不存在时返回 `None`
+    pub async fn get_translation(&self, id: i64) -> Result<Option<TranslationRecord>> {
+        let row = sqlx::query("SELECT * FROM translations WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool())
+            .await?;
+
+        row.map(|row| record_from_row(&row, self.cipher().as_deref()))
+            .transpose()
+    }
+
+    /// 获取指定记录在筛选结果中相邻的记录 ID，顺序与 `get_history` 一致
+    ///
+    /// 指定的记录本身不需要满足筛选条件，按它的时间和 ID 在结果中定位。
+    pub async fn get_adjacent_ids(&self, id: i64, filter: &HistoryFilter) -> Result<AdjacentIds> {
+        let position = sqlx::query("SELECT timestamp FROM translations WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool())
+            .await?;
+        let Some(position) = position else {
+            return Err(AppError::Other(format!("翻译记录不存在: {}", id)));
+        };
+        let key = (position.get::<i64, _>("timestamp"), id);
+
+        let cipher = self.cipher();
+        if let (Some(cipher), Some(search)) = (&cipher, &filter.search) {
+            let matched = self
+                .encrypted_search_matches(cipher, search, filter)
+                .await?;
+            let previous = matched.iter().rev().find(|r| (r.timestamp, r.id) > key);
+            let next = matched.iter().find(|r| (r.timestamp, r.id) < key);
+            return Ok(AdjacentIds {
+                previous: previous.map(|r| r.id),
+                next: next.map(|r| r.id),
+            });
+        }
+
+        Ok(AdjacentIds {
+            previous: self.adjacent_id(key, filter, true).await?,
+            next: self.adjacent_id(key, filter, false).await?,
+        })
+    }
+
+    /// 查询紧邻 `key`（时间戳, ID）的记录，`newer` 为真时查更新的一条
+    async fn adjacent_id(
+        &self,
+        (timestamp, id): (i64, i64),
+        filter: &HistoryFilter,
+        newer: bool,
+    ) -> Result<Option<i64>> {
+        let (compare, order) = if newer { (">", "ASC") } else { ("<", "DESC") };
+
+        let mut query = QueryBuilder::new("SELECT id FROM translations");
+        filter
+            .push_where(&mut query)
+            .push(&format!("(timestamp {} ", compare))
+            .push_bind(timestamp)
+            .push(" OR (timestamp = ")
+            .push_bind(timestamp)
+            .push(format!(" AND id {} ", compare))
+            .push_bind(id)
+            .push("))");
+        query.push(format!(
+            " ORDER BY timestamp {}, id {} LIMIT 1",
+            order, order
+        ));
+
+        let row = query.build().fetch_optional(&self.pool()).await?;
+        Ok(row.map(|row| row.get("id")))
+    }
+
+    /// 从导出的 JSON 文件导入翻译历史
+    pub async fn import_history(&self, path: &Path) -> Result<ImportSummary> {
+        let content = std::fs::read_to_string(path)?;
+        self.import_history_json(&content).await
+    }
+
+    /// 导入 JSON 格式的翻译历史，支持记录数组或 `get_history` 返回的 `{ "records": [...] }`
+    ///
+    /// 原文、译文、目标语言和时间戳都相同的记录视为重复并跳过。所有记录在同一个
+    /// 事务中写入，文件格式错误或写入失败时不会导入任何记录。
+    pub async fn import_history_json(&self, content: &str) -> Result<ImportSummary> {
+        let _guard = self.maintenance.read().await;
+        self.ensure_text_writable()?;
+        let value: serde_json::Value = serde_json::from_str(content)?;
+        let items = match value {
+            serde_json::Value::Array(items) => items,
+            serde_json::Value::Object(mut object) => match object.remove("records") {
+                Some(serde_json::Value::Array(items)) => items,
+                _ => return Err(AppError::Other("历史记录文件缺少 records 列表".to_string())),
+            },
+            _ => return Err(AppError::Other("历史记录文件格式无效".to_string())),
+        };
+
+        let mut summary = ImportSummary::default();
+        let cipher = self.cipher();
+        let mut tx = self.pool().begin().await?;
+
+        for item in items {
+            let record = match serde_json::from_value::<ImportedRecord>(item) {
+                Ok(record) if record.is_valid() => record,
+                _ => {
+                    summary.invalid += 1;
+                    continue;
+                }
+            };
+
+            // 加密的记录无法在 SQL 中比较文本，按目标语言和时间戳查出候选记录后解密比较
+            let candidates = sqlx::query(
+                "SELECT original_text, translated_text, original_blob, translated_blob FROM translations WHERE target_lang = ? AND timestamp = ?",
+            )
+            .bind(&record.target_lang)
+            .bind(record.timestamp)
+            .fetch_all(&mut *tx)
+            .await?;
+            let mut exists = false;
+            for row in &candidates {
+                let (original_text, translated_text) = texts_from_row(row, cipher.as_deref())?;
+                if original_text == record.original_text
+                    && translated_text == record.translated_text
+                {
+                    exists = true;
+                    break;
+                }
+            }
+            if exists {
+                summary.skipped += 1;
+                continue;
+            }
+
+            let original = store_text(cipher.as_deref(), &record.original_text)?;
+            let translated = store_text(cipher.as_deref(), &record.translated_text)?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO translations (original_text, translated_text, original_blob, translated_blob, original_hash, source_lang, target_lang, mode, timestamp, model, completion_tokens, duration_ms, pinned, source_app)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(original.text)
+            .bind(translated.text)
+            .bind(original.blob)
+            .bind(translated.blob)
+            .bind(text_hash(cipher.as_deref(), &record.original_text))
+            .bind(&record.source_lang)
+            .bind(&record.target_lang)
+            .bind(&record.mode)
+            .bind(record.timestamp)
+            .bind(&record.model)
+            .bind(record.completion_tokens)
+            .bind(record.duration_ms)
+            .bind(record.pinned)
+            .bind(&record.source_app)
+            .execute(&mut *tx)
+            .await?;
+            summary.imported += 1;
+        }
+
+        tx.commit().await?;
+        info!(
+            "Imported history: {} imported, {} skipped, {} invalid",
+            summary.imported, summary.skipped, summary.invalid
+        );
+        Ok(summary)
+    }
+
+    /// 设置翻译记录的置顶状态
+    pub async fn set_translation_pinned(&self, id: i64, pinned: bool) -> Result<()> {
+        let _guard = self.maintenance.read().await;
+        let result = sqlx::query("UPDATE translations SET pinned = ? WHERE id = ?")
+            .bind(pinned)
+            .bind(id)
+            .execute(&self.pool())
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::Other(format!("翻译记录不存在: {}", id)));
+        }
+        debug!("Set translation {} pinned={}", id, pinned);
+        Ok(())
+    }
+
+    /// 清理超出限制的历史记录，置顶记录不计入限制且不会被删除
+    pub async fn cleanup_history(&self, limit: usize) -> Result<u64> {
+        let _guard = self.maintenance.read().await;
+        let mut conn = self.pool().acquire().await?;
+        delete_history_over_limit(&mut conn, limit).await
+    }
+
+    /// 删除 `before` 之前（不包含）的翻译历史，置顶记录不会被删除，返回删除的记录数
+    pub async fn purge_history_before(&self, before: i64) -> Result<u64> {
+        let _guard = self.maintenance.read().await;
+        let mut conn = self.pool().acquire().await?;
+        delete_history_before(&mut conn, before).await
+    }
+
+    /// 按保留天数和条数限制清理历史记录，返回删除的记录数
+    ///
+    /// 两步清理在同一个事务中完成。
+    pub async fn apply_history_retention(
+        &self,
+        limit: usize,
+        retention_days: Option<u32>,
+    ) -> Result<u64> {
+        let _guard = self.maintenance.read().await;
+        let mut tx = self.pool().begin_with("BEGIN IMMEDIATE").await?;
+        let mut deleted = 0;
+        if let Some(days) = retention_days {
+            deleted += delete_history_before(&mut tx, retention_cutoff(days)).await?;
+        }
+        deleted += delete_history_over_limit(&mut tx, limit).await?;
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    /// 清空所有翻译历史、性能指标和每日汇总，返回删除的翻译记录数
+    ///
+    /// 置顶记录默认保留，`include_pinned` 为 true 时一并删除。
+    pub async fn clear_all_history(&self, include_pinned: bool) -> Result<u64> {
+        let _guard = self.maintenance.read().await;
+        // 三张表在同一个事务中清空，失败时不会只清空一部分
+        let mut tx = self.pool().begin().await?;
+        let translations_deleted = sqlx::query(if include_pinned {
+            "DELETE FROM translations"
+        } else {
+            "DELETE FROM translations WHERE pinned = 0"
+        })
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        let metrics_deleted = sqlx::query("DELETE FROM metrics")
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        sqlx::query("DELETE FROM daily_stats")
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        info!(
+            "Cleared all history: {} translations, {} metrics",
+            translations_deleted, metrics_deleted
+        );
+        Ok(translations_deleted)
+    }
+
+    /// 删除符合筛选条件的翻译历史，返回删除的记录数
+    ///
+    /// 置顶记录默认保留，`include_pinned` 为 true 时一并删除。性能指标不受影响。
+    pub async fn clear_history_filtered(
+        &self,
+        filter: &HistoryFilter,
+        include_pinned: bool,
+    ) -> Result<u64> {
+        let _guard = self.maintenance.read().await;
+        let mut query = QueryBuilder::new("DELETE FROM translations");
+        let mut conditions = filter.push_where(&mut query);
+        if !include_pinned {
+            conditions.push("pinned = 0");
+        }
+
+        let deleted = query.build().execute(&self.pool()).await?.rows_affected();
+        info!("Cleared {} translations matching filter", deleted);
+        Ok(deleted)
+    }
+
+    /// 记录性能指标
+    pub async fn insert_metric(&self, metric: &MetricRecord<'_>) -> Result<()> {
+        let _guard = self.maintenance.read().await;
+        let timestamp = Utc::now().timestamp();
+
+        sqlx::query(
+            r#"
+            INSERT INTO metrics (timestamp, operation_type, duration_ms, success, error_type, error_message, char_count, target_lang, completion_tokens, tokens_per_second, model, cost_usd, stream, cached)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(timestamp)
+        .bind(metric.operation_type)
+        .bind(metric.duration_ms)
+        .bind(metric.success)
+        .bind(metric.error_type)
+        .bind(metric.error_message.map(sanitize_error_message))
+        .bind(metric.char_count)
+        .bind(metric.target_lang)
+        .bind(metric.completion_tokens.map(i64::from))
+        .bind(metric.tokens_per_second)
+        .bind(metric.model)
+        .bind(metric.cost_usd)
+        .bind(metric.stream)
+        .bind(metric.cached)
+        .execute(&self.pool())
+        .await?;
+
+        Ok(())
+    }
+
+    /// 获取性能统计
+    ///
+    /// `lifetime` 的总数、字符数、tokens 和平均耗时包含已归档到每日汇总的指标，
+    /// 其余字段只统计仍保留的原始指标。
+    pub async fn get_performance_stats(&self, period: &str) -> Result<PerformanceStats> {
+        let since = stats_period_start(period);
+
+        // 基本统计
+        let stats_row = sqlx::query(
+            r#"
+            SELECT 
+                COUNT(*) as total,
+                SUM(CASE WHEN success = 1 THEN 1 ELSE 0 END) as successful,
+                SUM(CASE WHEN success = 0 THEN 1 ELSE 0 END) as failed,
+                AVG(CASE WHEN success = 1 AND cached = 0 THEN duration_ms ELSE NULL END) as avg_duration,
+                MIN(CASE WHEN success = 1 AND cached = 0 THEN duration_ms ELSE NULL END) as min_duration,
+                MAX(CASE WHEN success = 1 AND cached = 0 THEN duration_ms ELSE NULL END) as max_duration,
+                SUM(cached) as cached_count,
+                SUM(char_count) as total_chars,
+                SUM(CASE WHEN operation_type = 'selected' THEN 1 ELSE 0 END) as selected_count,
+                SUM(CASE WHEN operation_type = 'full' THEN 1 ELSE 0 END) as full_count,
+                SUM(COALESCE(completion_tokens, 0)) as total_tokens,
+                AVG(completion_tokens) as avg_tokens,
+                AVG(CASE WHEN tokens_per_second > 0 THEN tokens_per_second ELSE NULL END) as avg_tps
+            FROM metrics
+            WHERE timestamp > ?
+            "#,
+        )
+        .bind(since)
+        .fetch_one(&self.pool())
+        .await?;
+
+        // 错误分布
+        let error_rows = sqlx::query(
+            r#"
+            SELECT error_type, COUNT(*) as count
+            FROM metrics
+            WHERE timestamp > ? AND success = 0 AND error_type IS NOT NULL
+            GROUP BY error_type
+            "#,
+        )
+        .bind(since)
+        .fetch_all(&self.pool())
+        .await?;
+
+        let error_distribution: Vec<ErrorDistribution> = error_rows
+            .iter()
+            .map(|row| ErrorDistribution {
+                error_type: row.get("error_type"),
+                count: row.get("count"),
+            })
+            .collect();
+        let recent_errors = self.get_recent_errors(since).await?;
+
+        // 流式和非流式请求的对比，引入该字段之前的记录归入 "unknown"
+        let stream_rows = sqlx::query(
+            r#"
+            SELECT
+                CASE stream WHEN 1 THEN 'stream' WHEN 0 THEN 'non_stream' ELSE 'unknown' END as output,
+                COUNT(*) as count,
+                AVG(CASE WHEN success = 1 THEN duration_ms ELSE NULL END) as avg_duration,
+                AVG(CASE WHEN tokens_per_second > 0 THEN tokens_per_second ELSE NULL END) as avg_tps
+            FROM metrics
+            WHERE timestamp > ?
+            GROUP BY output
+            ORDER BY output
+            "#,
+        )
+        .bind(since)
+        .fetch_all(&self.pool())
+        .await?;
+
+        let by_output_mode: Vec<OutputModeStats> = stream_rows
+            .iter()
+            .map(|row| OutputModeStats {
+                output_mode: row.get("output"),
+                count: row.get("count"),
+                avg_duration_ms: row.get::<Option<f64>, _>("avg_duration").unwrap_or(0.0),
+                avg_tokens_per_second: row.get::<Option<f64>, _>("avg_tps").unwrap_or(0.0),
+            })
+            .collect();
+
+        // 按目标语言统计，未记录语言的旧数据归入 "unknown"
+        let language_rows = sqlx::query(
+            r#"
+            SELECT
+                COALESCE(target_lang, 'unknown') as language,
+                COUNT(*) as count,
+                AVG(CASE WHEN success = 1 THEN duration_ms ELSE NULL END) as avg_duration,
+                SUM(char_count) as total_chars
+            FROM metrics
+            WHERE timestamp > ?
+            GROUP BY language
+            ORDER BY count DESC, language
+            "#,
+        )
+        .bind(since)
+        .fetch_all(&self.pool())
+        .await?;
+
+        let by_language: Vec<LanguageStats> = language_rows
+            .iter()
+            .map(|row| LanguageStats {
+                target_lang: row.get("language"),
+                count: row.get("count"),
+                avg_duration_ms: row.get::<Option<f64>, _>("avg_duration").unwrap_or(0.0),
+                total_chars: row.get::<Option<i64>, _>("total_chars").unwrap_or(0),
+            })
+            .collect();
+
+        // 按时间分段统计：一周、一月及全部时间按天，其余按小时（本地时间）
+        let bucket = if matches!(period, "week" | "month" | "lifetime") {
+            TimeBucket::Day
+        } else {
+            TimeBucket::Hour
+        };
+        let hourly_data = self.get_bucketed_stats(since, bucket).await?;
+
+        let mut stats = PerformanceStats {
+            total_translations: stats_row.get::<i64, _>("total") as u64,
+            successful_translations: stats_row.get::<i64, _>("successful") as u64,
+            failed_translations: stats_row.get::<i64, _>("failed") as u64,
+            avg_duration_ms: stats_row.get::<Option<f64>, _>("avg_duration").unwrap_or(0.0),
+            min_duration_ms: stats_row.get::<Option<i64>, _>("min_duration").unwrap_or(0) as u64,
+            max_duration_ms: stats_row.get::<Option<i64>, _>("max_duration").unwrap_or(0) as u64,
+            total_chars_translated: stats_row.get::<Option<i64>, _>("total_chars").unwrap_or(0) as u64,
+            selected_mode_count: stats_row.get::<i64, _>("selected_count") as u64,
+            full_mode_count: stats_row.get::<i64, _>("full_count") as u64,
+            cached_translations: stats_row.get::<Option<i64>, _>("cached_count").unwrap_or(0) as u64,
+            total_completion_tokens: stats_row.get::<Option<i64>, _>("total_tokens").unwrap_or(0) as u64,
+            avg_tokens_per_translation: stats_row.get::<Option<f64>, _>("avg_tokens").unwrap_or(0.0),
+            avg_tokens_per_second: stats_row.get::<Option<f64>, _>("avg_tps").unwrap_or(0.0),
+            error_distribution,
+            recent_errors,
+            by_output_mode,
+            by_language,
+            hourly_data,
+            bucket,
+        };
+
+        if period == "lifetime" {
+            self.add_daily_stats(&mut stats).await?;
+        }
+        Ok(stats)
+    }
+
+    /// 将时间范围内的原始性能指标导出为 CSV，返回写入的行数
+    ///
+    /// period 与 `get_performance_stats` 相同，已归档到每日汇总的指标不包含在内。
+    /// 逐行读取并写入，不会一次加载整张表。
+    pub async fn export_metrics(&self, path: &Path, period: &str) -> Result<u64> {
+        let result = self
+            .write_metrics_csv(path, stats_period_start(period))
+            .await;
+        if result.is_err() {
+            let _ = std::fs::remove_file(path);
+        }
+        result
+    }
+
+    async fn write_metrics_csv(&self, path: &Path, since: i64) -> Result<u64> {
+        let mut writer = BufWriter::new(std::fs::File::create(path)?);
+        writeln!(
+            writer,
+            "timestamp,operation_type,duration_ms,success,error_type,char_count,completion_tokens,tokens_per_second"
+        )?;
+
+        let pool = self.pool();
+        let mut rows = sqlx::query(
+            r#"
+            SELECT timestamp, operation_type, duration_ms, success, error_type, char_count, completion_tokens, tokens_per_second
+            FROM metrics
+            WHERE timestamp > ?
+            ORDER BY timestamp, id
+            "#,
+        )
+        .bind(since)
+        .fetch(&pool);
+
+        let mut written = 0;
+        while let Some(row) = rows.try_next().await? {
+            let timestamp = Local
+                .timestamp_opt(row.get("timestamp"), 0)
+                .single()
+                .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, false))
+                .unwrap_or_default();
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{}",
+                timestamp,
+                csv_field(row.get("operation_type")),
+                row.get::<i64, _>("duration_ms"),
+                row.get::<bool, _>("success"),
+                csv_field(row.get::<Option<&str>, _>("error_type").unwrap_or_default()),
+                row.get::<i64, _>("char_count"),
+                row.get::<Option<i64>, _>("completion_tokens")
+                    .map(|tokens| tokens.to_string())
+                    .unwrap_or_default(),
+                row.get::<Option<f64>, _>("tokens_per_second")
+                    .map(|tps| format!("{:.2}", tps))
+                    .unwrap_or_default(),
+            )?;
+            written += 1;
+        }
+        writer.flush()?;
+
+        info!("Exported {} metrics to {}", written, path.display());
+        Ok(written)
+    }
+
+    /// 获取费用统计
+    ///
+    /// 总费用和每日费用包含已归档的每日汇总；按模型的统计只包含保留期内的原始指标，
+    /// 且只统计有费用数据的请求，没有价格信息的请求不会被当作零费用。
+    pub async fn get_cost_stats(&self, period: &str) -> Result<CostStats> {
+        let since = cost_period_start(period);
+
+        let daily_rows = sqlx::query(
+            r#"
+            SELECT date, SUM(cost_usd) as cost_usd
+            FROM (
+                SELECT date(timestamp, 'unixepoch', 'localtime') as date, cost_usd
+                FROM metrics
+                WHERE timestamp >= ? AND cost_usd IS NOT NULL
+                UNION ALL
+                SELECT date, cost_usd
+                FROM daily_stats
+                WHERE date >= date(?, 'unixepoch', 'localtime') AND cost_usd > 0
+            )
+            GROUP BY date
+            ORDER BY date
+            "#,
+        )
+        .bind(since)
+        .bind(since)
+        .fetch_all(&self.pool())
+        .await?;
+
+        let daily: Vec<DailyCost> = daily_rows
+            .iter()
+            .map(|row| DailyCost {
+                date: row.get("date"),
+                cost_usd: row.get("cost_usd"),
+            })
+            .collect();
+
+        let model_rows = sqlx::query(
+            r#"
+            SELECT
+                COALESCE(model, 'unknown') as model_name,
+                COUNT(*) as count,
+                SUM(cost_usd) as total_cost,
+                AVG(cost_usd) as avg_cost
+            FROM metrics
+            WHERE timestamp >= ? AND cost_usd IS NOT NULL
+            GROUP BY model_name
+            ORDER BY total_cost DESC, model_name
+            "#,
+        )
+        .bind(since)
+        .fetch_all(&self.pool())
+        .await?;
+
+        let by_model = model_rows
+            .iter()
+            .map(|row| ModelCost {
+                model: row.get("model_name"),
+                count: row.get("count"),
+                total_cost_usd: row.get("total_cost"),
+                avg_cost_usd: row.get("avg_cost"),
+            })
+            .collect();
+
+        Ok(CostStats {
+            total_cost_usd: daily.iter().map(|day| day.cost_usd).sum(),
+            by_model,
+            daily,
+        })
+    }
+
+    /// 按模型对比性能，period 与 `get_performance_stats` 相同
+    ///
+    /// 请求次数少于 `MODEL_STATS_MIN_COUNT` 的模型合并为 "other"，未记录模型的旧数据归入
+    /// "unknown"。只统计原始指标，已归档到每日汇总的数据不包含在内。
+    pub async fn get_model_stats(&self, period: &str) -> Result<Vec<ModelStats>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                CASE
+                    WHEN model IS NULL THEN 'unknown'
+                    WHEN model_count < ? THEN 'other'
+                    ELSE model
+                END as model_name,
+                COUNT(*) as count,
+                SUM(CASE WHEN success = 0 THEN 1 ELSE 0 END) as failed,
+                AVG(CASE WHEN success = 1 AND cached = 0 THEN duration_ms ELSE NULL END) as avg_duration,
+                AVG(CASE WHEN tokens_per_second > 0 THEN tokens_per_second ELSE NULL END) as avg_tps
+            FROM (
+                SELECT *, COUNT(*) OVER (PARTITION BY model) as model_count
+                FROM metrics
+                WHERE timestamp > ?
+            )
+            GROUP BY model_name
+            ORDER BY count DESC, model_name
+            "#,
+        )
+        .bind(MODEL_STATS_MIN_COUNT)
+        .bind(stats_period_start(period))
+        .fetch_all(&self.pool())
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let count: i64 = row.get("count");
+                let failed: i64 = row.get("failed");
+                ModelStats {
+                    model: row.get("model_name"),
+                    count,
+                    failed,
+                    failure_rate: failed as f64 / count as f64,
+                    avg_duration_ms: row.get::<Option<f64>, _>("avg_duration").unwrap_or(0.0),
+                    avg_tokens_per_second: row.get::<Option<f64>, _>("avg_tps").unwrap_or(0.0),
+                }
+            })
+            .collect())
+    }
+
+    /// 每种错误类型最近的几条错误信息，按时间倒序
+    async fn get_recent_errors(&self, since: i64) -> Result<Vec<RecentError>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT error_type, error_message, timestamp FROM (
+                SELECT error_type, error_message, timestamp,
+                    ROW_NUMBER() OVER (PARTITION BY error_type ORDER BY timestamp DESC, id DESC) as rank
+                FROM metrics
+                WHERE timestamp > ? AND success = 0 AND error_type IS NOT NULL AND error_message IS NOT NULL
+            )
+            WHERE rank <= ?
+            ORDER BY error_type, timestamp DESC, rank
+            "#,
+        )
+        .bind(since)
+        .bind(RECENT_ERRORS_PER_TYPE)
+        .fetch_all(&self.pool())
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| RecentError {
+                error_type: row.get("error_type"),
+                message: row.get("error_message"),
+                timestamp: row.get("timestamp"),
+            })
+            .collect())
+    }
+
+    /// 将已归档的每日汇总合并到统计结果中
+    async fn add_daily_stats(&self, stats: &mut PerformanceStats) -> Result<()> {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                COALESCE(SUM(translations), 0) as translations,
+                COALESCE(SUM(failures), 0) as failures,
+                COALESCE(SUM(chars), 0) as chars,
+                COALESCE(SUM(tokens), 0) as tokens,
+                COALESCE(SUM(total_duration_ms), 0) as total_duration_ms
+            FROM daily_stats
+            "#,
+        )
+        .fetch_one(&self.pool())
+        .await?;
+
+        let translations = row.get::<i64, _>("translations") as u64;
+        let failures = row.get::<i64, _>("failures") as u64;
+        let archived_successes = translations - failures;
+        let total_duration_ms = row.get::<i64, _>("total_duration_ms") as f64;
+
+        // 按成功次数加权合并平均耗时
+        let successes = stats.successful_translations + archived_successes;
+        if successes > 0 {
+            stats.avg_duration_ms = (stats.avg_duration_ms * stats.successful_translations as f64
+                + total_duration_ms)
+                / successes as f64;
+        }
+        stats.total_translations += translations;
+        stats.successful_translations = successes;
+        stats.failed_translations += failures;
+        stats.total_chars_translated += row.get::<i64, _>("chars") as u64;
+        stats.total_completion_tokens += row.get::<i64, _>("tokens") as u64;
+        Ok(())
+    }
+
+    /// 按本地时间的小时或天分段统计性能指标，按时间先后排序
+    async fn get_bucketed_stats(&self, since: i64, bucket: TimeBucket) -> Result<Vec<HourlyData>> {
+        // 分段格式和分段标签（小时或日期）的格式
+        let (bucket_format, label_format) = match bucket {
+            TimeBucket::Hour => ("%Y-%m-%d %H:00:00", "%H"),
+            TimeBucket::Day => ("%Y-%m-%d", "%d"),
+        };
+
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                CAST(strftime('%s', bucket, 'utc') AS INTEGER) as bucket_start,
+                CAST(strftime(?, bucket) AS INTEGER) as label,
+                AVG(CASE WHEN success = 1 THEN duration_ms ELSE NULL END) as avg_duration,
+                COUNT(*) as count,
+                SUM(CASE WHEN success = 1 THEN 1 ELSE 0 END) as success_count
+            FROM (
+                SELECT strftime(?, timestamp, 'unixepoch', 'localtime') as bucket, success, duration_ms
+                FROM metrics
+                WHERE timestamp > ?
+            )
+            GROUP BY bucket
+            ORDER BY bucket
+            "#,
+        )
+        .bind(label_format)
+        .bind(bucket_format)
+        .bind(since)
+        .fetch_all(&self.pool())
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| HourlyData {
+                hour: row.get::<i64, _>("label") as i32,
+                avg_duration: row.get::<Option<f64>, _>("avg_duration").unwrap_or(0.0),
+                count: row.get("count"),
+                success_count: row.get("success_count"),
+                bucket_start: row.get("bucket_start"),
+            })
+            .collect())
+    }
+
+    /// 最近 `days` 天（本地时间，包含今天）每天的翻译统计，没有数据的日期为 0
+    pub async fn get_daily_series(&self, days: u32) -> Result<Vec<DailySeries>> {
+        self.daily_series_in(&Local::now(), days).await
+    }
+
+    /// 按 `now` 所在时区的日期边界统计
+    ///
+    /// SQL 先按 15 分钟分段聚合，再在 Rust 中按本地日期合并，夏令时切换的日期也能正确划分。
+    async fn daily_series_in<Tz: TimeZone>(
+        &self,
+        now: &DateTime<Tz>,
+        days: u32,
+    ) -> Result<Vec<DailySeries>> {
+        if days == 0 {
+            return Ok(Vec::new());
+        }
+        let tz = now.timezone();
+        let today = now.date_naive();
+        let first_day = today - chrono::Duration::days(i64::from(days) - 1);
+        let mut series: Vec<DailySeries> = first_day
+            .iter_days()
+            .take(days as usize)
+            .map(|date| DailySeries::empty(date, local_day_start(&tz, date)))
+            .collect();
+
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                timestamp / ? as slot,
+                COUNT(*) as total,
+                SUM(CASE WHEN success = 1 THEN 1 ELSE 0 END) as successes,
+                SUM(CASE WHEN success = 1 THEN duration_ms ELSE 0 END) as success_duration,
+                SUM(char_count) as total_chars
+            FROM metrics
+            WHERE timestamp >= ?
+            GROUP BY slot
+            "#,
+        )
+        .bind(SERIES_SLOT_SECS)
+        .bind(series[0].day_start)
+        .fetch_all(&self.pool())
+        .await?;
+
+        // 成功翻译的耗时总和，用于计算平均值
+        let mut durations = vec![0i64; series.len()];
+        for row in &rows {
+            let slot_start = row.get::<i64, _>("slot") * SERIES_SLOT_SECS;
+            let Some(date) = tz
+                .timestamp_opt(slot_start, 0)
+                .single()
+                .map(|t| t.date_naive())
+            else {
+                continue;
+            };
+            let Ok(index) = usize::try_from((date - first_day).num_days()) else {
+                continue;
+            };
+            let Some(day) = series.get_mut(index) else {
+                continue;
+            };
+            let total: i64 = row.get("total");
+            let successes: i64 = row.get("successes");
+            day.total += total;
+            day.successes += successes;
+            day.failures += total - successes;
+            day.total_chars += row.get::<Option<i64>, _>("total_chars").unwrap_or(0);
+            durations[index] += row.get::<Option<i64>, _>("success_duration").unwrap_or(0);
+        }
+        for (day, duration) in series.iter_mut().zip(durations) {
+            if day.successes > 0 {
+                day.avg_duration_ms = duration as f64 / day.successes as f64;
+            }
+        }
+        Ok(series)
+    }
+
+    /// 获取数据库文件大小、页数和各表行数
+    pub async fn get_db_info(&self) -> Result<DbInfo> {
+        let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+            .fetch_one(&self.pool())
+            .await?;
+        let page_count: i64 = sqlx::query_scalar("PRAGMA page_count")
+            .fetch_one(&self.pool())
+            .await?;
+        let freelist_pages: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+            .fetch_one(&self.pool())
+            .await?;
+
+        // 内存数据库没有文件，按页数估算
+        let file: Option<String> =
+            sqlx::query_scalar("SELECT file FROM pragma_database_list WHERE name = 'main'")
+                .fetch_optional(&self.pool())
+                .await?;
+        let file_size = file
+            .filter(|file| !file.is_empty())
+            .and_then(|file| std::fs::metadata(file).ok())
+            .map(|metadata| metadata.len())
+            .unwrap_or((page_count * page_size) as u64);
+
+        let mut tables = Vec::with_capacity(COUNTED_TABLES.len());
+        for table in COUNTED_TABLES {
+            let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+                .fetch_one(&self.pool())
+                .await?;
+            tables.push(TableRowCount {
+                name: table.to_string(),
+                rows,
+            });
+        }
+
+        Ok(DbInfo {
+            file_size,
+            page_size: page_size as u64,
+            page_count: page_count as u64,
+            freelist_pages: freelist_pages as u64,
+            reclaimable_bytes: (freelist_pages * page_size) as u64,
+            tables,
+        })
+    }
+
+    /// 压缩数据库并回收空闲页，返回压缩后的数据库信息
+    ///
+    /// 压缩期间的写入会等待压缩完成。
+    pub async fn compact(&self) -> Result<DbInfo> {
+        {
+            let _guard = self.maintenance.write().await;
+            sqlx::query("VACUUM").execute(&self.pool()).await?;
+            sqlx::query("PRAGMA optimize").execute(&self.pool()).await?;
+        }
+
+        let info = self.get_db_info().await?;
+        info!("Database compacted: {} bytes", info.file_size);
+        Ok(info)
+    }
+
+    /// 数据库文件路径，内存数据库返回 None
+    pub fn path(&self) -> Option<PathBuf> {
+        self.path.lock().unwrap().clone()
+    }
+
+    /// 数据库文件路径，内存数据库不支持备份
+    fn file_path(&self) -> Result<PathBuf> {
+        self.path()
+            .ok_or_else(|| AppError::Other("内存数据库不支持备份".to_string()))
+    }
+
+    /// 第 `index` 份备份的路径，与数据库文件位于同一目录
+    fn backup_path(db_path: &Path, index: usize) -> PathBuf {
+        let stem = db_path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("quicktranstype");
+        db_path.with_file_name(format!("{}.backup.{}.db", stem, index))
+    }
+
+    /// 备份数据库并轮换旧备份，只保留最近 `BACKUP_KEEP` 份
+    ///
+    /// 先用 `VACUUM INTO` 写入临时文件，成功后再轮换，备份失败时不影响已有备份。
+    pub async fn backup(&self) -> Result<PathBuf> {
+        let _guard = self.maintenance.write().await;
+        let db_path = &self.file_path()?;
+
+        let tmp_path = db_path.with_extension("backup.tmp");
+        let _ = std::fs::remove_file(&tmp_path);
+        sqlx::query("VACUUM INTO ?")
+            .bind(tmp_path.to_string_lossy().into_owned())
+            .execute(&self.pool())
+            .await?;
+
+        let oldest = Self::backup_path(db_path, BACKUP_KEEP);
+        if oldest.exists() {
+            std::fs::remove_file(&oldest)?;
+        }
+        for index in (1..BACKUP_KEEP).rev() {
+            let from = Self::backup_path(db_path, index);
+            if from.exists() {
+                std::fs::rename(&from, Self::backup_path(db_path, index + 1))?;
+            }
+        }
+
+        let latest = Self::backup_path(db_path, 1);
+        std::fs::rename(&tmp_path, &latest)?;
+        info!("Database backed up to {}", latest.display());
+        Ok(latest)
+    }
+
+    /// 距最近一次备份超过 `BACKUP_INTERVAL` 时执行备份，未执行时返回 `None`
+    pub async fn backup_if_due(&self) -> Result<Option<PathBuf>> {
+        let latest = Self::backup_path(&self.file_path()?, 1);
+        let age = std::fs::metadata(&latest)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok());
+        if matches!(age, Some(age) if age < BACKUP_INTERVAL) {
+            debug!("Latest backup is recent, skipping");
+            return Ok(None);
+        }
+
+        self.backup().await.map(Some)
+    }
+
+    /// 列出已有的备份，按从新到旧排列
+    pub fn list_backups(&self) -> Result<Vec<BackupInfo>> {
+        let db_path = &self.file_path()?;
+        let mut backups = Vec::new();
+        for index in 1..=BACKUP_KEEP {
+            let path = Self::backup_path(db_path, index);
+            let Ok(metadata) = std::fs::metadata(&path) else {
+                continue;
+            };
+            let created_at = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |elapsed| elapsed.as_secs() as i64);
+
+            backups.push(BackupInfo {
+                index,
+                path: path.display().to_string(),
+                file_size: metadata.len(),
+                created_at,
+            });
+        }
+        Ok(backups)
+    }
+
+    /// 从第 `index` 份备份恢复数据库
+    ///
+    /// 关闭连接池后用备份替换数据库文件并重新打开，替换前的数据库保存为
+    /// `.db.before-restore`。恢复失败时还原原数据库，无论是否还原成功都重新连接。
+    /// 未启用加密时不能恢复包含加密记录的备份。
+    pub async fn restore_from_backup(&self, index: usize) -> Result<()> {
+        let _guard = self.maintenance.write().await;
+        let db_path = &self.file_path()?;
+        let backup_path = Self::backup_path(db_path, index);
+        if !backup_path.exists() {
+            return Err(AppError::Other(format!("备份 {} 不存在", index)));
+        }
+
+        // 上次恢复留下的数据库不能在这次失败时用来覆盖当前数据
+        let previous = db_path.with_extension("db.before-restore");
+        if previous.exists() {
+            std::fs::remove_file(&previous)?;
+        }
+        self.checkpoint_and_close().await?;
+
+        let mut set_aside = false;
+        let result = async {
+            remove_sidecar_files(db_path)?;
+            std::fs::rename(db_path, &previous)?;
+            set_aside = true;
+            self.swap_in_backup(db_path, &backup_path).await
+        }
+        .await;
+        let Err(e) = result else {
+            info!("Database restored from {}", backup_path.display());
+            return Ok(());
+        };
+
+        error!("Failed to restore backup {}: {}", index, e);
+        self.pool().close().await;
+        if set_aside {
+            let rollback = remove_sidecar_files(db_path)
+                .and_then(|()| std::fs::rename(&previous, db_path).map_err(AppError::from));
+            if let Err(rollback_err) = rollback {
+                error!(
+                    "Failed to put back the database from {}: {}",
+                    previous.display(),
+                    rollback_err
+                );
+            }
+        }
+        match Self::connect(db_path).await {
+            Ok(pool) => *self.pool.lock().unwrap() = pool,
+            Err(reconnect_err) => error!("Failed to reopen database: {}", reconnect_err),
+        }
+        Err(e)
+    }
+
+    /// 复制备份文件到数据库位置并重新连接，备份可能来自旧版本，需要重新迁移
+    async fn swap_in_backup(&self, db_path: &Path, backup_path: &Path) -> Result<()> {
+        std::fs::copy(backup_path, db_path)?;
+
+        *self.pool.lock().unwrap() = Self::connect(db_path).await?;
+        self.run_migrations().await?;
+
+        // 当前启用了加密时，备份中的明文记录同样需要加密，并清除残留的明文；
+        // 未启用时备份中的加密记录无法读取
+        match self.cipher() {
+            Some(cipher) => {
+                if self.encrypt_plain_history(&cipher).await? > 0 {
+                    self.scrub_free_pages().await?;
+                }
+            }
+            None => {
+                if self.has_encrypted_history().await? {
+                    return Err(AppError::Crypto(
+                        "备份中的翻译历史已加密，请先启用历史记录加密".to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 将数据库文件（包括 WAL 和共享内存文件）移动到 `new_path` 并重新打开
+    ///
+    /// 复制或打开新文件失败时删除已复制的文件并重新打开原数据库。
+    /// 成功后删除原位置的文件，自动备份保留在原目录。返回原来的路径。
+    pub async fn move_to(&self, new_path: &Path) -> Result<PathBuf> {
+        let _guard = self.maintenance.write().await;
+        let db_path = self.file_path()?;
+        if new_path == db_path {
+            return Ok(db_path);
+        }
+        if new_path.exists() {
+            return Err(AppError::Other(format!(
+                "目标位置已存在文件: {}",
+                new_path.display()
+            )));
+        }
+        ensure_writable_dir(new_path)?;
+
+        self.checkpoint_and_close().await?;
+        if let Err(e) = self.copy_and_reopen(&db_path, new_path).await {
+            error!("Failed to move database to {}: {}", new_path.display(), e);
+            self.pool().close().await;
+            remove_database_files(new_path)?;
+            *self.pool.lock().unwrap() = Self::connect(&db_path).await?;
+            return Err(e);
+        }
+        *self.path.lock().unwrap() = Some(new_path.to_path_buf());
+
+        if let Err(e) = remove_database_files(&db_path) {
+            warn!("Failed to remove old database {}: {}", db_path.display(), e);
+        }
+        info!("Database moved to {}", new_path.display());
+        Ok(db_path)
+    }
+
+    /// 复制数据库文件到新位置并连接
+    async fn copy_and_reopen(&self, db_path: &Path, new_path: &Path) -> Result<()> {
+        std::fs::copy(db_path, new_path)?;
+        for suffix in SIDECAR_SUFFIXES {
+            let sidecar = sidecar_path(db_path, suffix);
+            if sidecar.exists() {
+                std::fs::copy(&sidecar, sidecar_path(new_path, suffix))?;
+            }
+        }
+
+        *self.pool.lock().unwrap() = Self::connect(new_path).await?;
+        self.run_migrations().await?;
+        Ok(())
+    }
+
+    /// 清理旧的性能指标，删除前按本地日期汇总到 `daily_stats`
+    pub async fn cleanup_metrics(&self) -> Result<u64> {
+        let _guard = self.maintenance.read().await;
+        let cutoff = Utc::now().timestamp() - (METRICS_RETENTION_DAYS * 24 * 3600);
+
+        // 汇总和删除在同一个事务中完成
+        let mut tx = self.pool().begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO daily_stats (date, translations, failures, chars, tokens, total_duration_ms, cost_usd)
+            SELECT
+                date(timestamp, 'unixepoch', 'localtime') as day,
+                COUNT(*),
+                SUM(CASE WHEN success = 0 THEN 1 ELSE 0 END),
+                SUM(char_count),
+                SUM(COALESCE(completion_tokens, 0)),
+                SUM(CASE WHEN success = 1 THEN duration_ms ELSE 0 END),
+                SUM(COALESCE(cost_usd, 0))
+            FROM metrics
+            WHERE timestamp < ?
+            GROUP BY day
+            ON CONFLICT(date) DO UPDATE SET
+                translations = translations + excluded.translations,
+                failures = failures + excluded.failures,
+                chars = chars + excluded.chars,
+                tokens = tokens + excluded.tokens,
+                total_duration_ms = total_duration_ms + excluded.total_duration_ms,
+                cost_usd = cost_usd + excluded.cost_usd
+            "#,
+        )
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?;
+
+        let result = sqlx::query("DELETE FROM metrics WHERE timestamp < ?")
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        let deleted = result.rows_affected();
+        if deleted > 0 {
+            debug!("Cleaned up {} old metric records", deleted);
+        }
+        Ok(deleted)
+    }
+}
+
+/// 删除 `before` 之前的非置顶翻译历史
+async fn delete_history_before(conn: &mut SqliteConnection, before: i64) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM translations WHERE pinned = 0 AND timestamp < ?")
+        .bind(before)
+        .execute(&mut *conn)
+        .await?;
+
+    let deleted = result.rows_affected();
+    if deleted > 0 {
+        info!("Purged {} translation records before {}", deleted, before);
+    }
+    Ok(deleted)
+}
+
+/// 删除超出条数限制的非置顶翻译历史
+async fn delete_history_over_limit(conn: &mut SqliteConnection, limit: usize) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM translations 
+        WHERE pinned = 0 AND id NOT IN (
+            SELECT id FROM translations 
+            WHERE pinned = 0
+            ORDER BY timestamp DESC 
+            LIMIT ?
+        )
+        "#,
+    )
+    .bind(limit as i64)
+    .execute(&mut *conn)
+    .await?;
+
+    let deleted = result.rows_affected();
+    if deleted > 0 {
+        debug!("Cleaned up {} old translation records", deleted);
+    }
+    Ok(deleted)
+}
+
+/// 数据库迁移步骤，`MIGRATIONS` 中的第 N 个步骤将数据库升级到版本 N
+type Migration = for<'c> fn(&'c mut SqliteConnection) -> BoxFuture<'c, Result<()>>;
+
+/// 按版本顺序排列的迁移，已发布的步骤不能修改，只能追加新步骤
+const MIGRATIONS: &[Migration] = &[
+    migrate_v1,
+    migrate_v2,
+    migrate_v3,
+    migrate_v4,
+    migrate_v5,
+    migrate_v6,
+    migrate_v7,
+    migrate_v8,
+    migrate_v9,
+    migrate_v10,
+];
+
+/// 版本 1：翻译记录表和性能指标表
+///
+/// 兼容引入版本号之前创建的数据库，已存在的表只补充缺少的字段。
+fn migrate_v1(conn: &mut SqliteConnection) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        // 创建翻译记录表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS translations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                original_text TEXT NOT NULL,
+                translated_text TEXT NOT NULL,
+                source_lang TEXT,
+                target_lang TEXT NOT NULL,
+                mode TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                model TEXT,
+                completion_tokens INTEGER,
+                duration_ms INTEGER,
+                pinned INTEGER NOT NULL DEFAULT 0
+            )
+            "#,
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        // 为旧版本创建的表添加模型、tokens、耗时和置顶字段
+        add_missing_columns(
+            conn,
+            "translations",
+            &[
+                ("model", "TEXT"),
+                ("completion_tokens", "INTEGER"),
+                ("duration_ms", "INTEGER"),
+                ("pinned", "INTEGER NOT NULL DEFAULT 0"),
+            ],
+        )
+        .await?;
+
+        // 创建索引
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_translations_timestamp ON translations(timestamp DESC)",
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_translations_lang ON translations(target_lang, source_lang)",
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        // 创建性能指标表
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS metrics (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                operation_type TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                success INTEGER NOT NULL,
+                error_type TEXT,
+                char_count INTEGER NOT NULL,
+                completion_tokens INTEGER,
+                tokens_per_second REAL,
+                target_lang TEXT
+            )
+            "#,
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        // 为旧版本创建的表添加 tokens 和目标语言字段
+        add_missing_columns(
+            conn,
+            "metrics",
+            &[
+                ("completion_tokens", "INTEGER"),
+                ("tokens_per_second", "REAL"),
+                ("target_lang", "TEXT"),
+            ],
+        )
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_metrics_timestamp ON metrics(timestamp DESC)")
+            .execute(&mut *conn)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_metrics_operation ON metrics(operation_type)")
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(())
+    })
+}
+
+/// 版本 2：每日汇总表，保存已清理的性能指标
+fn migrate_v2(conn: &mut SqliteConnection) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS daily_stats (
+                date TEXT PRIMARY KEY,
+                translations INTEGER NOT NULL,
+                failures INTEGER NOT NULL,
+                chars INTEGER NOT NULL,
+                tokens INTEGER NOT NULL,
+                total_duration_ms INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    })
+}
+
+/// 版本 3：记录翻译时的前台应用
+fn migrate_v3(conn: &mut SqliteConnection) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        add_missing_columns(conn, "translations", &[("source_app", "TEXT")]).await?;
+        Ok(())
+    })
+}
+
+/// 版本 4：记录每次请求使用的模型和费用
+fn migrate_v4(conn: &mut SqliteConnection) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        add_missing_columns(conn, "metrics", &[("model", "TEXT"), ("cost_usd", "REAL")]).await?;
+        add_missing_columns(
+            conn,
+            "daily_stats",
+            &[("cost_usd", "REAL NOT NULL DEFAULT 0")],
+        )
+        .await?;
+        Ok(())
+    })
+}
+
+/// 版本 5：按时间和 ID 排序的索引，游标分页时不需要额外排序
+fn migrate_v5(conn: &mut SqliteConnection) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_translations_timestamp_id ON translations(timestamp DESC, id DESC)",
+        )
+        .execute(&mut *conn)
+        .await?;
+        // 新索引已覆盖按时间排序的查询
+        sqlx::query("DROP INDEX IF EXISTS idx_translations_timestamp")
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    })
+}
+
+/// 版本 6：失败指标的错误信息
+fn migrate_v6(conn: &mut SqliteConnection) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        add_missing_columns(conn, "metrics", &[("error_message", "TEXT")]).await?;
+        Ok(())
+    })
+}
+
+/// 版本 7：指标是否来自流式请求
+fn migrate_v7(conn: &mut SqliteConnection) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        add_missing_columns(conn, "metrics", &[("stream", "INTEGER")]).await?;
+        Ok(())
+    })
+}
+
+/// 版本 8：长文本压缩保存，已有的未加密长文本在迁移时一次性压缩
+///
+/// 已加密的记录保持原样，读取时不受影响。
+fn migrate_v8(conn: &mut SqliteConnection) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        add_missing_columns(
+            conn,
+            "translations",
+            &[("original_blob", "BLOB"), ("translated_blob", "BLOB")],
+        )
+        .await?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, original_text, translated_text FROM translations
+            WHERE length(CAST(original_text AS BLOB)) > ? OR length(CAST(translated_text AS BLOB)) > ?
+            "#,
+        )
+        .bind(COMPRESS_THRESHOLD_BYTES as i64)
+        .bind(COMPRESS_THRESHOLD_BYTES as i64)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut compressed = 0;
+        for row in &rows {
+            let original: String = row.get("original_text");
+            let translated: String = row.get("translated_text");
+            if is_encrypted(&original) || is_encrypted(&translated) {
+                continue;
+            }
+            let original = store_text(None, &original)?;
+            let translated = store_text(None, &translated)?;
+            sqlx::query(
+                r#"
+                UPDATE translations
+                SET original_text = ?, translated_text = ?, original_blob = ?, translated_blob = ?
+                WHERE id = ?
+                "#,
+            )
+            .bind(original.text)
+            .bind(translated.text)
+            .bind(original.blob)
+            .bind(translated.blob)
+            .bind(row.get::<i64, _>("id"))
+            .execute(&mut *conn)
+            .await?;
+            compressed += 1;
+        }
+        if compressed > 0 {
+            info!("Compressed {} long translation records", compressed);
+        }
+        Ok(())
+    })
+}
+
+/// 版本 9：原文哈希和复用缓存的指标标记，已有的未加密记录在迁移时计算哈希
+fn migrate_v9(conn: &mut SqliteConnection) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        add_missing_columns(conn, "translations", &[("original_hash", "TEXT")]).await?;
+        add_missing_columns(conn, "metrics", &[("cached", "INTEGER NOT NULL DEFAULT 0")]).await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_translations_original_hash ON translations(original_hash, target_lang)",
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        let rows = sqlx::query(
+            "SELECT id, original_text, original_blob FROM translations WHERE original_hash IS NULL",
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        for row in &rows {
+            let text: String = row.get("original_text");
+            let blob: Option<Vec<u8>> = row.get("original_blob");
+            if is_encrypted(&text) || blob.as_deref().is_some_and(is_encrypted_bytes) {
+                continue;
+            }
+            let original = load_text(None, text, blob)?;
+            sqlx::query("UPDATE translations SET original_hash = ? WHERE id = ?")
+                .bind(text_hash(None, &original))
+                .bind(row.get::<i64, _>("id"))
+                .execute(&mut *conn)
+                .await?;
+        }
+        Ok(())
+    })
+}
+
+/// 版本 10：重新翻译的记录关联原记录
+fn migrate_v10(conn: &mut SqliteConnection) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        add_missing_columns(conn, "translations", &[("retranslated_from", "INTEGER")]).await?;
+        Ok(())
+    })
+}
+
+/// 为表添加缺少的字段
+async fn add_missing_columns(
+    conn: &mut SqliteConnection,
+    table: &str,
+    columns: &[(&str, &str)],
+) -> Result<()> {
+    for (column, column_type) in columns {
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?")
+                .bind(table)
+                .bind(column)
+                .fetch_one(&mut *conn)
+                .await?;
+        if count == 0 {
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, column_type
+            ))
+            .execute(&mut *conn)
+            .await?;
+            debug!("Added column {}.{}", table, column);
+        }
+    }
+    Ok(())
+}
+
+/// 性能统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformanceStats {
+    pub total_translations: u64,
+    pub successful_translations: u64,
+    pub failed_translations: u64,
+    pub avg_duration_ms: f64,
+    pub min_duration_ms: u64,
+    pub max_duration_ms: u64,
+    pub total_chars_translated: u64,
+    pub selected_mode_count: u64,
+    pub full_mode_count: u64,
+    /// 复用历史译文的次数，这些记录不参与耗时统计
+    pub cached_translations: u64,
+    pub error_distribution: Vec<ErrorDistribution>,
+    /// 每种错误类型最近的错误信息
+    pub recent_errors: Vec<RecentError>,
+    /// 流式和非流式请求的对比
+    pub by_output_mode: Vec<OutputModeStats>,
+    /// 按目标语言的统计
+    pub by_language: Vec<LanguageStats>,
+    pub hourly_data: Vec<HourlyData>,
+    /// `hourly_data` 的分段方式
+    pub bucket: TimeBucket,
+    /// 总 completion tokens
+    pub total_completion_tokens: u64,
+    /// 每次翻译的平均 completion tokens，没有 tokens 信息的记录不参与平均
+    pub avg_tokens_per_translation: f64,
+    /// 平均输出速率 (tokens/s)
+    pub avg_tokens_per_second: f64,
+}
+
+/// 错误分布
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorDistribution {
+    pub error_type: String,
+    pub count: i64,
+}
+
+/// 最近一次失败的错误信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentError {
+    pub error_type: String,
+    pub message: String,
+    /// 失败时间（Unix 时间戳）
+    pub timestamp: i64,
+}
+
+/// 一种输出方式（流式或非流式）的统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputModeStats {
+    /// "stream"、"non_stream"，未记录时为 "unknown"
+    pub output_mode: String,
+    pub count: i64,
+    /// 成功翻译的平均耗时（毫秒）
+    pub avg_duration_ms: f64,
+    /// 平均输出速率 (tokens/s)
+    pub avg_tokens_per_second: f64,
+}
+
+/// 单个目标语言的统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageStats {
+    /// 目标语言代码，未记录时为 "unknown"
+    pub target_lang: String,
+    pub count: i64,
+    /// 成功翻译的平均耗时（毫秒）
+    pub avg_duration_ms: f64,
+    pub total_chars: i64,
+}
+
+/// 按时间分段的统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HourlyData {
+    /// 分段标签：按小时为本地时间的小时（0–23），按天为日期（1–31）
+    pub hour: i32,
+    /// 成功翻译的平均耗时（毫秒）
+    pub avg_duration: f64,
+    pub count: i64,
+    /// 成功次数
+    pub success_count: i64,
+    /// 分段开始时间（Unix 时间戳）
+    pub bucket_start: i64,
+}
+
+/// 费用统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostStats {
+    /// 总费用（美元）
+    pub total_cost_usd: f64,
+    /// 按模型的费用
+    pub by_model: Vec<ModelCost>,
+    /// 按本地日期的每日费用，按日期先后排序
+    pub daily: Vec<DailyCost>,
+}
+
+/// 单个模型的费用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCost {
+    /// 模型名称，未记录时为 "unknown"
+    pub model: String,
+    /// 有费用数据的请求次数
+    pub count: i64,
+    pub total_cost_usd: f64,
+    pub avg_cost_usd: f64,
+}
+
+/// 单个模型的性能统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelStats {
+    /// 模型名称，请求较少的模型为 "other"，未记录时为 "unknown"
+    pub model: String,
+    pub count: i64,
+    pub failed: i64,
+    /// 失败次数占总请求次数的比例（0–1）
+    pub failure_rate: f64,
+    /// 成功翻译的平均耗时（毫秒），复用历史译文的记录不参与统计
+    pub avg_duration_ms: f64,
+    /// 平均输出速率 (tokens/s)
+    pub avg_tokens_per_second: f64,
+}
+
+/// 一天的费用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyCost {
+    /// 本地日期（YYYY-MM-DD）
+    pub date: String,
+    pub cost_usd: f64,
+}
+
+/// 保留 `days` 天时最早保留的时间（Unix 时间戳）
+pub fn retention_cutoff(days: u32) -> i64 {
+    Utc::now().timestamp() - i64::from(days) * 86400
+}
+
+/// 费用统计的起始时间（Unix 时间戳），默认为本月第一天零点（本地时间）
+fn cost_period_start(period: &str) -> i64 {
+    let now = Local::now();
+    match period {
+        "day" => now.timestamp() - 86400,
+        "week" => now.timestamp() - 604800,
+        "lifetime" => 0,
+        _ => local_month_start(),
+    }
+}
+
+/// 性能统计时间范围的起始时间戳，未知的 period 按最近一天处理
+fn stats_period_start(period: &str) -> i64 {
+    match period {
+        "hour" => Utc::now().timestamp() - 3600,
+        "day" => Utc::now().timestamp() - 86400,
+        "week" => Utc::now().timestamp() - 604800,
+        "month" => local_month_start(),
+        "lifetime" => 0,
+        _ => Utc::now().timestamp() - 86400,
+    }
+}
+
+/// 转义 CSV 字段，包含逗号、引号或换行时加引号
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 本月第一天零点（本地时间）的时间戳
+fn local_month_start() -> i64 {
+    Local::now()
+        .date_naive()
+        .with_day(1)
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .and_then(|start| start.and_local_timezone(Local).earliest())
+        .map_or(0, |start| start.timestamp())
+}
+
+/// 一天的翻译统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailySeries {
+    /// 本地日期，格式为 YYYY-MM-DD
+    pub date: String,
+    /// 当天开始时间（Unix 时间戳）
+    pub day_start: i64,
+    pub total: i64,
+    pub successes: i64,
+    pub failures: i64,
+    /// 成功翻译的平均耗时（毫秒）
+    pub avg_duration_ms: f64,
+    pub total_chars: i64,
+}
+
+impl DailySeries {
+    fn empty(date: NaiveDate, day_start: i64) -> Self {
+        Self {
+            date: date.format("%Y-%m-%d").to_string(),
+            day_start,
+            total: 0,
+            successes: 0,
+            failures: 0,
+            avg_duration_ms: 0.0,
+            total_chars: 0,
+        }
+    }
+}
+
+/// 指定时区中某一天的开始时间，午夜因夏令时不存在时取当天第一个存在的时刻
+fn local_day_start<Tz: TimeZone>(tz: &Tz, date: NaiveDate) -> i64 {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    (0..=24)
+        .find_map(|hour| {
+            tz.from_local_datetime(&(midnight + chrono::Duration::hours(hour)))
+                .earliest()
+        })
+        .map_or_else(|| midnight.and_utc().timestamp(), |start| start.timestamp())
+}
+
+/// 性能统计的时间分段方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeBucket {
+    Hour,
+    Day,
+}
+
+/// 数据库的 WAL 和共享内存文件后缀
+const SIDECAR_SUFFIXES: [&str; 2] = ["-wal", "-shm"];
+
+/// 数据库文件对应的 WAL 或共享内存文件路径
+fn sidecar_path(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut sidecar = db_path.as_os_str().to_owned();
+    sidecar.push(suffix);
+    PathBuf::from(sidecar)
+}
+
+/// 删除数据库的 WAL 和共享内存文件，替换数据库文件前调用，避免残留的日志被应用到新文件
+fn remove_sidecar_files(db_path: &Path) -> Result<()> {
+    for suffix in SIDECAR_SUFFIXES {
+        match std::fs::remove_file(sidecar_path(db_path, suffix)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// 删除数据库文件及其 WAL 和共享内存文件
+fn remove_database_files(db_path: &Path) -> Result<()> {
+    remove_sidecar_files(db_path)?;
+    match std::fs::remove_file(db_path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// 创建数据库文件所在的目录，并确认可以在其中写入文件
+fn ensure_writable_dir(db_path: &Path) -> Result<()> {
+    let dir = db_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .ok_or_else(|| AppError::Config(format!("无效的数据库路径: {}", db_path.display())))?;
+    let not_writable =
+        |e: std::io::Error| AppError::Config(format!("目标目录不可写: {} ({})", dir.display(), e));
+
+    std::fs::create_dir_all(dir).map_err(not_writable)?;
+    let probe = dir.join(".quicktranstype-write-test");
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .map_err(not_writable)?;
+    std::fs::remove_file(&probe)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 创建已完成迁移的内存数据库
+    async fn memory_db() -> Database {
+        Database::new_in_memory().await.unwrap()
+    }
+
+    /// 创建未执行迁移的内存数据库连接池
+    async fn memory_pool() -> Pool<Sqlite> {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    /// 查询第一页的全部历史记录
+    async fn all_history(db: &Database) -> HistoryResult {
+        db.get_history(1, 10, &HistoryFilter::default())
+            .await
+            .unwrap()
+    }
+
+    /// 插入指定时间的性能指标
+    async fn insert_metric_at(db: &Database, timestamp: i64, duration_ms: i64, success: bool) {
+        sqlx::query(
+            "INSERT INTO metrics (timestamp, operation_type, duration_ms, success, char_count) VALUES (?, 'selected', ?, ?, 10)",
+        )
+        .bind(timestamp)
+        .bind(duration_ms)
+        .bind(success)
+        .execute(&db.pool())
+        .await
+        .unwrap();
+    }
+
+    /// 读取原文的文本列和压缩列
+    async fn stored_original(db: &Database, id: i64) -> (String, Option<Vec<u8>>) {
+        let row = sqlx::query("SELECT original_text, original_blob FROM translations WHERE id = ?")
+            .bind(id)
+            .fetch_one(&db.pool())
+            .await
+            .unwrap();
+        (row.get("original_text"), row.get("original_blob"))
+    }
+
+    #[test]
+    fn test_compressed_blob_format() {
+        let text = "压缩格式".repeat(100);
+        let blob = compress_text(&text).unwrap();
+        assert_eq!(blob[0], BLOB_FORMAT_ZSTD);
+        assert!(blob.len() < text.len());
+        assert_eq!(decompress_text(&blob).unwrap(), text);
+
+        // 之前写入的 zlib 数据，包括没有格式标记的
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(vec![BLOB_FORMAT_ZLIB], flate2::Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        let zlib = encoder.finish().unwrap();
+        assert_eq!(decompress_text(&zlib).unwrap(), text);
+        assert_eq!(zlib[1], ZLIB_HEADER);
+        assert_eq!(decompress_text(&zlib[1..]).unwrap(), text);
+
+        assert!(decompress_text(&[9, 1, 2, 3]).is_err());
+        assert!(decompress_text(&[]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_long_text_is_compressed() {
+        let db = memory_db().await;
+        let long = "这是一段很长的文档内容。".repeat(400);
+        let metadata = TranslationMetadata {
+            dedup_window_secs: 60,
+            ..Default::default()
+        };
+        let id = db
+            .insert_translation(&long, "short", None, "en", "full", &metadata)
+            .await
+            .unwrap();
+
+        let (preview, blob) = stored_original(&db, id).await;
+        assert_eq!(preview.chars().count(), PREVIEW_CHARS);
+        assert!(blob.unwrap().len() < long.len());
+        let record = db.get_translation(id).await.unwrap().unwrap();
+        assert_eq!(record.original_text, long);
+        assert_eq!(record.translated_text, "short");
+        assert_eq!(all_history(&db).await.records[0].original_text, long);
+
+        // 去重比较完整文本
+        let again = db
+            .insert_translation(&long, "short", None, "en", "full", &metadata)
+            .await
+            .unwrap();
+        assert_eq!(again, id);
+
+        // 加密和解密时压缩内容一起转换
+        let cipher = FieldCipher::new(&[5u8; 32]).unwrap();
+        db.enable_encryption(cipher).await.unwrap();
+        let (preview, blob) = stored_original(&db, id).await;
+        assert!(is_encrypted(&preview));
+        assert!(is_encrypted_bytes(&blob.unwrap()));
+        let filter = HistoryFilter {
+            search: Some("文档".to_string()),
+            ..Default::default()
+        };
+        let result = db.get_history(1, 10, &filter).await.unwrap();
+        assert_eq!(result.records[0].original_text, long);
+
+        db.disable_encryption().await.unwrap();
+        let (_, blob) = stored_original(&db, id).await;
+        assert!(!is_encrypted_bytes(&blob.unwrap()));
+        assert_eq!(
+            db.get_translation(id).await.unwrap().unwrap().original_text,
+            long
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_recent_translation() {
+        let db = memory_db().await;
+        let metadata = TranslationMetadata::default();
+        let id = db
+            .insert_translation(
+                "Hello\r\nworld",
+                "你好\n世界",
+                None,
+                "zh",
+                "selected",
+                &metadata,
+            )
+            .await
+            .unwrap();
+        db.insert_translation(
+            "Hello\r\nworld",
+            "Bonjour",
+            None,
+            "fr",
+            "selected",
+            &metadata,
+        )
+        .await
+        .unwrap();
+
+        // 首尾空白和换行符不同也能找到
+        let found = db
+            .find_recent_translation("  Hello\nworld\n", "zh", 3600)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.id, id);
+        assert_eq!(found.translated_text, "你好\n世界");
+        assert!(db
+            .find_recent_translation("Hello world", "zh", 3600)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(db
+            .find_recent_translation("Hello\nworld", "de", 3600)
+            .await
+            .unwrap()
+            .is_none());
+
+        // 超过时间范围的记录不复用
+        sqlx::query("UPDATE translations SET timestamp = timestamp - 7200")
+            .execute(&db.pool())
+            .await
+            .unwrap();
+        assert!(db
+            .find_recent_translation("Hello\nworld", "zh", 3600)
+            .await
+            .unwrap()
+            .is_none());
+
+        // 加密后仍能通过哈希找到
+        db.enable_encryption(FieldCipher::new(&[5u8; 32]).unwrap())
+            .await
+            .unwrap();
+        let found = db
+            .find_recent_translation("Hello\nworld", "fr", 86400)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.translated_text, "Bonjour");
+    }
+
+    #[tokio::test]
+    async fn test_find_recent_translation_uses_hash_index() {
+        let db = memory_db().await;
+        let rows = sqlx::query(
+            "EXPLAIN QUERY PLAN SELECT * FROM translations WHERE original_hash = ? AND target_lang = ? AND timestamp >= ? ORDER BY timestamp DESC, id DESC",
+        )
+        .bind("")
+        .bind("en")
+        .bind(0)
+        .fetch_all(&db.pool())
+        .await
+        .unwrap();
+        let plan: Vec<String> = rows.iter().map(|r| r.get("detail")).collect();
+        assert!(plan[0].contains("USING INDEX idx_translations_original_hash"));
+    }
+
+    #[tokio::test]
+    async fn test_migration_backfills_original_hash() {
+        let db = memory_db().await;
+        sqlx::query(
+            "INSERT INTO translations (original_text, translated_text, target_lang, mode, timestamp) VALUES ('旧记录', 'old', 'en', 'full', ?)",
+        )
+        .bind(Utc::now().timestamp())
+        .execute(&db.pool())
+        .await
+        .unwrap();
+        assert!(db
+            .find_recent_translation("旧记录", "en", 3600)
+            .await
+            .unwrap()
+            .is_none());
+
+        let mut conn = db.pool().acquire().await.unwrap();
+        migrate_v9(&mut conn).await.unwrap();
+        drop(conn);
+
+        let found = db
+            .find_recent_translation("旧记录", "en", 3600)
+            .await
+            .unwrap();
+        assert_eq!(found.unwrap().translated_text, "old");
+    }
+
+    #[tokio::test]
+    async fn test_cached_metrics_excluded_from_durations() {
+        let db = memory_db().await;
+        for (duration_ms, cached) in [(1000, false), (3000, false), (0, true)] {
+            let metric = MetricRecord {
+                operation_type: "selected",
+                duration_ms,
+                success: true,
+                char_count: 10,
+                cached,
+                ..Default::default()
+            };
+            db.insert_metric(&metric).await.unwrap();
+        }
+
+        let stats = db.get_performance_stats("day").await.unwrap();
+        assert_eq!(stats.total_translations, 3);
+        assert_eq!(stats.cached_translations, 1);
+        assert_eq!(stats.avg_duration_ms, 2000.0);
+        assert_eq!(stats.min_duration_ms, 1000);
+    }
+
+    #[tokio::test]
+    async fn test_export_metrics_csv() {
+        let db = memory_db().await;
+        let success = MetricRecord {
+            operation_type: "selected",
+            duration_ms: 1200,
+            success: true,
+            char_count: 42,
+            completion_tokens: Some(30),
+            tokens_per_second: Some(25.0),
+            ..Default::default()
+        };
+        let failure = MetricRecord {
+            operation_type: "full",
+            duration_ms: 300,
+            error_type: Some("network"),
+            char_count: 10,
+            ..Default::default()
+        };
+        db.insert_metric(&success).await.unwrap();
+        db.insert_metric(&failure).await.unwrap();
+        // 超出时间范围的指标不导出
+        insert_metric_at(&db, Utc::now().timestamp() - 2 * 86400, 500, true).await;
+
+        let path = std::env::temp_dir().join(format!(
+            "quick_trans_type_metrics_{}.csv",
+            std::process::id()
+        ));
+        assert_eq!(db.export_metrics(&path, "day").await.unwrap(), 2);
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let lines: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0][0], "timestamp");
+        assert_eq!(lines[0].len(), 8);
+        let timestamp = DateTime::parse_from_rfc3339(lines[1][0]).unwrap();
+        assert!((Utc::now().timestamp() - timestamp.timestamp()).abs() < 60);
+        assert_eq!(
+            lines[1][1..].to_vec(),
+            ["selected", "1200", "true", "", "42", "30", "25.00"]
+        );
+        assert_eq!(
+            lines[2][1..].to_vec(),
+            ["full", "300", "false", "network", "10", "", ""]
+        );
+
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+        assert_eq!(csv_field("network"), "network");
+    }
+
+    #[tokio::test]
+    async fn test_migration_compresses_existing_long_text() {
+        let db = memory_db().await;
+        let long = "x".repeat(COMPRESS_THRESHOLD_BYTES + 1);
+        sqlx::query(
+            "INSERT INTO translations (original_text, translated_text, target_lang, mode, timestamp) VALUES (?, 'short', 'en', 'full', 1)",
+        )
+        .bind(&long)
+        .execute(&db.pool())
+        .await
+        .unwrap();
+
+        let mut conn = db.pool().acquire().await.unwrap();
+        migrate_v8(&mut conn).await.unwrap();
+        drop(conn);
+
+        let (preview, blob) = stored_original(&db, 1).await;
+        assert_eq!(preview.len(), PREVIEW_CHARS);
+        assert!(blob.is_some());
+        assert_eq!(all_history(&db).await.records[0].original_text, long);
+    }
+
+    #[tokio::test]
+    async fn test_history_pagination() {
+        let db = memory_db().await;
+        for i in 0..25 {
+            insert_text_at(&db, &format!("记录 {}", i), 1_700_000_000 + i).await;
+        }
+
+        let filter = HistoryFilter::default();
+        let first = db.get_history(1, 10, &filter).await.unwrap();
+        assert_eq!(first.total, 25);
+        assert_eq!(first.records.len(), 10);
+        assert_eq!(first.records[0].original_text, "记录 24");
+
+        let last = db.get_history(3, 10, &filter).await.unwrap();
+        assert_eq!(last.records.len(), 5);
+        assert_eq!(last.records[4].original_text, "记录 0");
+        let beyond = db.get_history(4, 10, &filter).await.unwrap();
+        assert!(beyond.records.is_empty());
+        assert_eq!(beyond.total, 25);
+    }
+
+    #[tokio::test]
+    async fn test_search_matches_like_wildcards_literally() {
+        let db = memory_db().await;
+        for text in [
+            "100% done",
+            "100 items",
+            "file_name",
+            "filename",
+            "C:\\temp",
+        ] {
+            insert_text(&db, text).await;
+        }
+
+        let search = |text: &str| HistoryFilter {
+            search: Some(text.to_string()),
+            ..Default::default()
+        };
+        let found = |result: HistoryResult| -> Vec<String> {
+            result
+                .records
+                .into_iter()
+                .map(|r| r.original_text)
+                .collect()
+        };
+        let result = db.get_history(1, 10, &search("100%")).await.unwrap();
+        assert_eq!(found(result), vec!["100% done"]);
+        let result = db.get_history(1, 10, &search("_")).await.unwrap();
+        assert_eq!(found(result), vec!["file_name"]);
+        let result = db.get_history(1, 10, &search("%")).await.unwrap();
+        assert_eq!(found(result), vec!["100% done"]);
+        let result = db.get_history(1, 10, &search("\\")).await.unwrap();
+        assert_eq!(found(result), vec!["C:\\temp"]);
+        assert_eq!(db.get_history_facets(Some("_")).await.unwrap().total, 1);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_history_limit_boundaries() {
+        let db = memory_db().await;
+        for i in 0..5 {
+            insert_text_at(&db, &format!("记录 {}", i), 1_700_000_000 + i).await;
+        }
+
+        // 刚好等于或少于限制时不删除
+        assert_eq!(db.cleanup_history(6).await.unwrap(), 0);
+        assert_eq!(db.cleanup_history(5).await.unwrap(), 0);
+        // 超出时删除最旧的记录
+        assert_eq!(db.cleanup_history(4).await.unwrap(), 1);
+        let history = all_history(&db).await;
+        assert_eq!(history.total, 4);
+        assert_eq!(history.records[3].original_text, "记录 1");
+
+        assert_eq!(db.cleanup_history(0).await.unwrap(), 4);
+        assert_eq!(all_history(&db).await.total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_performance_stats_math() {
+        let db = memory_db().await;
+        for (operation_type, duration_ms, success, char_count) in [
+            ("selected", 100, true, 10),
+            ("selected", 300, true, 20),
+            ("full", 800, true, 30),
+            ("full", 9000, false, 0),
+        ] {
+            let metric = MetricRecord {
+                operation_type,
+                duration_ms,
+                success,
+                char_count,
+                error_type: (!success).then_some("network"),
+                ..Default::default()
+            };
+            db.insert_metric(&metric).await.unwrap();
+        }
+
+        let stats = db.get_performance_stats("hour").await.unwrap();
+        assert_eq!(stats.total_translations, 4);
+        assert_eq!(stats.successful_translations, 3);
+        assert_eq!(stats.failed_translations, 1);
+        // 耗时只统计成功的翻译
+        assert_eq!(stats.avg_duration_ms, 400.0);
+        assert_eq!(stats.min_duration_ms, 100);
+        assert_eq!(stats.max_duration_ms, 800);
+        assert_eq!(stats.total_chars_translated, 60);
+        assert_eq!(stats.selected_mode_count, 2);
+        assert_eq!(stats.full_mode_count, 2);
+        assert_eq!(stats.error_distribution.len(), 1);
+        assert_eq!(stats.error_distribution[0].count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_metrics_cutoff() {
+        let db = memory_db().await;
+        let cutoff = Utc::now().timestamp() - METRICS_RETENTION_DAYS * 86400;
+        insert_metric_at(&db, cutoff - 60, 100, true).await;
+        insert_metric_at(&db, cutoff + 60, 100, true).await;
+
+        assert_eq!(db.cleanup_metrics().await.unwrap(), 1);
+        let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM metrics")
+            .fetch_one(&db.pool())
+            .await
+            .unwrap();
+        assert_eq!(remaining, 1);
+    }
+
+    #[tokio::test]
+    async fn test_daily_series_fills_gaps() {
+        use chrono::FixedOffset;
+
+        let db = memory_db().await;
+        let tz = FixedOffset::east_opt(8 * 3600).unwrap();
+        let now = tz.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap();
+        let at = |day, hour| {
+            tz.with_ymd_and_hms(2024, 3, day, hour, 0, 0)
+                .unwrap()
+                .timestamp()
+        };
+
+        insert_metric_at(&db, at(8, 9), 100, true).await;
+        insert_metric_at(&db, at(8, 10), 300, true).await;
+        insert_metric_at(&db, at(8, 11), 9000, false).await;
+        insert_metric_at(&db, at(10, 1), 200, true).await;
+        // 统计范围之前的记录不计入
+        insert_metric_at(&db, at(7, 23), 100, true).await;
+
+        let series = db.daily_series_in(&now, 4).await.unwrap();
+        let dates: Vec<&str> = series.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(
+            dates,
+            vec!["2024-03-07", "2024-03-08", "2024-03-09", "2024-03-10"]
+        );
+        assert_eq!(series[0].total, 1);
+        assert_eq!(series[1].total, 3);
+        assert_eq!(series[1].successes, 2);
+        assert_eq!(series[1].failures, 1);
+        assert_eq!(series[1].avg_duration_ms, 200.0);
+        assert_eq!(series[1].total_chars, 30);
+        assert_eq!(series[2].total, 0);
+        assert_eq!(series[2].avg_duration_ms, 0.0);
+        assert_eq!(series[2].day_start, at(9, 0));
+        assert_eq!(series[3].total, 1);
+        assert_eq!(series[3].day_start, at(10, 0));
+
+        assert!(db.daily_series_in(&now, 0).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_daily_series_timezone_boundaries() {
+        use chrono::FixedOffset;
+
+        let db = memory_db().await;
+        // UTC 2024-03-09 15:59:59 和 16:00:00，在 UTC+8 分别是 9 日最后一秒和 10 日零点
+        let midnight = Utc
+            .with_ymd_and_hms(2024, 3, 9, 16, 0, 0)
+            .unwrap()
+            .timestamp();
+        insert_metric_at(&db, midnight - 1, 100, true).await;
+        insert_metric_at(&db, midnight, 100, true).await;
+
+        let east = FixedOffset::east_opt(8 * 3600).unwrap();
+        let now = east.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap();
+        let series = db.daily_series_in(&now, 2).await.unwrap();
+        assert_eq!(series[0].date, "2024-03-09");
+        assert_eq!(series[0].total, 1);
+        assert_eq!(series[1].day_start, midnight);
+        assert_eq!(series[1].total, 1);
+
+        // 同样的记录在 UTC-5 都属于 9 日
+        let west = FixedOffset::west_opt(5 * 3600).unwrap();
+        let now = west.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap();
+        let series = db.daily_series_in(&now, 2).await.unwrap();
+        assert_eq!(series[0].total, 2);
+        assert_eq!(series[1].total, 0);
+
+        // 半小时时区
+        let india = FixedOffset::east_opt(5 * 3600 + 1800).unwrap();
+        let boundary = india
+            .with_ymd_and_hms(2024, 3, 10, 0, 0, 0)
+            .unwrap()
+            .timestamp();
+        insert_metric_at(&db, boundary - 1, 100, false).await;
+        let now = india.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap();
+        let series = db.daily_series_in(&now, 2).await.unwrap();
+        assert_eq!(series[0].failures, 1);
+        assert_eq!(series[1].day_start, boundary);
+    }
+
+    #[tokio::test]
+    async fn test_hourly_stats() {
+        use chrono::{Local, TimeZone, Timelike};
+
+        let db = memory_db().await;
+
+        // 以本地时间的整点为界，在前两个小时中各写入若干记录
+        let now = Local::now();
+        let hour_start = now.timestamp() - (now.minute() * 60 + now.second()) as i64;
+        let previous = hour_start - 3600;
+        let earlier = hour_start - 7200;
+        insert_metric_at(&db, previous + 10, 100, true).await;
+        insert_metric_at(&db, previous + 20, 300, true).await;
+        insert_metric_at(&db, previous + 30, 5000, false).await;
+        insert_metric_at(&db, earlier + 5, 400, true).await;
+        // 超出统计范围
+        insert_metric_at(&db, hour_start - 3 * 86400, 100, true).await;
+
+        let stats = db.get_performance_stats("day").await.unwrap();
+        assert_eq!(stats.bucket, TimeBucket::Hour);
+        assert_eq!(stats.hourly_data.len(), 2);
+
+        let first = &stats.hourly_data[0];
+        assert_eq!(first.bucket_start, earlier);
+        assert_eq!(
+            first.hour,
+            Local.timestamp_opt(earlier, 0).unwrap().hour() as i32
+        );
+        assert_eq!((first.count, first.success_count), (1, 1));
+
+        let second = &stats.hourly_data[1];
+        assert_eq!(second.bucket_start, previous);
+        assert_eq!((second.count, second.success_count), (3, 2));
+        assert_eq!(second.avg_duration, 200.0);
+    }
+
+    #[tokio::test]
+    async fn test_weekly_stats_bucket_by_day() {
+        use chrono::Local;
+
+        let db = memory_db().await;
+
+        let today = Local::now()
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_local_timezone(Local)
+            .unwrap()
+            .timestamp();
+        let yesterday = today - 86400;
+        insert_metric_at(&db, yesterday + 3600, 200, true).await;
+        insert_metric_at(&db, yesterday + 7200, 400, true).await;
+        insert_metric_at(&db, yesterday - 86400 + 60, 100, false).await;
+
+        let stats = db.get_performance_stats("week").await.unwrap();
+        assert_eq!(stats.bucket, TimeBucket::Day);
+        assert_eq!(stats.hourly_data.len(), 2);
+        assert_eq!(stats.hourly_data[0].success_count, 0);
+        assert_eq!(stats.hourly_data[0].avg_duration, 0.0);
+        assert_eq!(stats.hourly_data[1].bucket_start, yesterday);
+        assert_eq!(stats.hourly_data[1].count, 2);
+        assert_eq!(stats.hourly_data[1].avg_duration, 300.0);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_record_survives_cleanup() {
+        let db = memory_db().await;
+
+        let mut ids = Vec::new();
+        for (i, text) in ["一", "二", "三"].iter().enumerate() {
+            let id = db
+                .insert_translation(
+                    text,
+                    text,
+                    None,
+                    "en",
+                    "selected",
+                    &TranslationMetadata::default(),
+                )
+                .await
+                .unwrap();
+            // 保证时间戳先后顺序
+            sqlx::query("UPDATE translations SET timestamp = ? WHERE id = ?")
+                .bind(1_700_000_000 + i as i64)
+                .bind(id)
+                .execute(&db.pool())
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+        db.set_translation_pinned(ids[0], true).await.unwrap();
+
+        assert_eq!(db.cleanup_history(1).await.unwrap(), 1);
+
+        let history = all_history(&db).await;
+        let remaining: Vec<i64> = history.records.iter().map(|r| r.id).collect();
+        assert_eq!(remaining, vec![ids[2], ids[0]]);
+
+        let filter = HistoryFilter {
+            pinned_only: true,
+            ..Default::default()
+        };
+        let pinned = db.get_history(1, 10, &filter).await.unwrap();
+        assert_eq!(pinned.total, 1);
+        assert!(pinned.records[0].pinned);
+
+        assert!(db.set_translation_pinned(9999, true).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_clear_history_filtered() {
+        let db = memory_db().await;
+
+        let mut ids = Vec::new();
+        for (i, (mode, lang)) in [
+            ("selected", "ja-JP"),
+            ("full", "ja-JP"),
+            ("full", "en-US"),
+            ("selected", "en-US"),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let id = db
+                .insert_translation(
+                    "原文",
+                    "译文",
+                    None,
+                    lang,
+                    mode,
+                    &TranslationMetadata::default(),
+                )
+                .await
+                .unwrap();
+            sqlx::query("UPDATE translations SET timestamp = ? WHERE id = ?")
+                .bind(1_700_000_000 + i as i64)
+                .bind(id)
+                .execute(&db.pool())
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+        db.set_translation_pinned(ids[1], true).await.unwrap();
+
+        // 置顶记录默认保留
+        let full = HistoryFilter {
+            mode: Some("full".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(db.clear_history_filtered(&full, false).await.unwrap(), 1);
+        let remaining: Vec<i64> = all_history(&db)
+            .await
+            .records
+            .iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(remaining, vec![ids[3], ids[1], ids[0]]);
+
+        let japanese_before = HistoryFilter {
+            target_lang: Some("ja-JP".to_string()),
+            to_ts: Some(1_700_000_001),
+            ..Default::default()
+        };
+        assert_eq!(
+            db.clear_history_filtered(&japanese_before, true)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(db.clear_history_filtered(&full, true).await.unwrap(), 1);
+        let remaining: Vec<i64> = all_history(&db)
+            .await
+            .records
+            .iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(remaining, vec![ids[3]]);
+    }
+
+    #[tokio::test]
+    async fn test_clear_all_history_keeps_pinned() {
+        let db = memory_db().await;
+        let mut ids = Vec::new();
+        for text in ["置顶", "普通"] {
+            let id = db
+                .insert_translation(
+                    text,
+                    "译文",
+                    None,
+                    "en",
+                    "selected",
+                    &TranslationMetadata::default(),
+                )
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+        db.set_translation_pinned(ids[0], true).await.unwrap();
+        insert_metric_at(&db, Utc::now().timestamp(), 100, true).await;
+        insert_metric_at(&db, Utc::now().timestamp() - 100 * 86400, 100, true).await;
+        db.cleanup_metrics().await.unwrap();
+
+        // 未指定筛选条件时置顶记录也默认保留，性能指标和每日汇总全部清空
+        assert_eq!(db.clear_all_history(false).await.unwrap(), 1);
+        let remaining: Vec<i64> = all_history(&db)
+            .await
+            .records
+            .iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(remaining, vec![ids[0]]);
+        let metrics: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM metrics")
+            .fetch_one(&db.pool())
+            .await
+            .unwrap();
+        assert_eq!(metrics, 0);
+        let daily: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM daily_stats")
+            .fetch_one(&db.pool())
+            .await
+            .unwrap();
+        assert_eq!(daily, 0);
+
+        assert_eq!(db.clear_all_history(true).await.unwrap(), 1);
+        assert!(all_history(&db).await.records.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_import_history() {
+        let db = memory_db().await;
+        db.insert_translation(
+            "已有",
+            "Existing",
+            None,
+            "en",
+            "selected",
+            &TranslationMetadata::default(),
+        )
+        .await
+        .unwrap();
+        let history = all_history(&db).await;
+        let existing = history.records[0].clone();
+
+        let content = serde_json::json!({
+            "records": [
+                existing,
+                {
+                    "id": 99,
+                    "original_text": "你好",
+                    "translated_text": "Hello",
+                    "target_lang": "en",
+                    "mode": "full",
+                    "timestamp": 1_700_000_000,
+                    "model": "gpt-4o-mini"
+                },
+                { "original_text": "", "translated_text": "x", "target_lang": "en", "mode": "full", "timestamp": 1 },
+                { "original_text": "缺少字段" }
+            ]
+        })
+        .to_string();
+
+        let summary = db.import_history_json(&content).await.unwrap();
+        assert_eq!(
+            (summary.imported, summary.skipped, summary.invalid),
+            (1, 1, 2)
+        );
+
+        let filter = HistoryFilter {
+            search: Some("你好".to_string()),
+            ..Default::default()
+        };
+        let history = db.get_history(1, 10, &filter).await.unwrap();
+        assert_eq!(history.total, 1);
+        assert_eq!(history.records[0].model.as_deref(), Some("gpt-4o-mini"));
+
+        // 再次导入时全部跳过
+        let summary = db.import_history_json(&content).await.unwrap();
+        assert_eq!((summary.imported, summary.skipped), (0, 2));
+    }
+
+    #[tokio::test]
+    async fn test_import_corrupt_history_fails_cleanly() {
+        let db = memory_db().await;
+
+        let truncated = "[{\"original_text\": ";
+        assert!(db.import_history_json(truncated).await.is_err());
+        assert!(db.import_history_json("{\"version\": 1}").await.is_err());
+        let history = all_history(&db).await;
+        assert_eq!(history.total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_translation_metadata_round_trip() {
+        let db = memory_db().await;
+
+        let metadata = TranslationMetadata {
+            model: Some("gpt-4o-mini".to_string()),
+            completion_tokens: Some(42),
+            duration_ms: Some(850),
+            ..Default::default()
+        };
+        db.insert_translation("你好", "Hello", None, "en", "selected", &metadata)
+            .await
+            .unwrap();
+
+        let history = all_history(&db).await;
+        let record = &history.records[0];
+        assert_eq!(record.model.as_deref(), Some("gpt-4o-mini"));
+        assert_eq!(record.completion_tokens, Some(42));
+        assert_eq!(record.duration_ms, Some(850));
+    }
+
+    #[tokio::test]
+    async fn test_migration_adds_columns_to_old_table() {
+        let pool = memory_pool().await;
+        sqlx::query(
+            r#"
+            CREATE TABLE translations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                original_text TEXT NOT NULL,
+                translated_text TEXT NOT NULL,
+                source_lang TEXT,
+                target_lang TEXT NOT NULL,
+                mode TEXT NOT NULL,
+                timestamp INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO translations (original_text, translated_text, target_lang, mode, timestamp) VALUES ('a', 'b', 'en', 'full', 1)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let db = Database::from_pool(pool);
+        db.run_migrations().await.unwrap();
+        // 再次运行迁移不会重复添加字段
+        db.run_migrations().await.unwrap();
+
+        let history = all_history(&db).await;
+        assert_eq!(history.total, 1);
+        let record = &history.records[0];
+        assert_eq!(record.model, None);
+        assert_eq!(record.completion_tokens, None);
+        assert_eq!(record.duration_ms, None);
+        assert!(!record.pinned);
+    }
+
+    #[tokio::test]
+    async fn test_token_aggregates() {
+        let db = memory_db().await;
+
+        // 没有 tokens 信息时为 0
+        let stats = db.get_performance_stats("day").await.unwrap();
+        assert_eq!(stats.total_completion_tokens, 0);
+        assert_eq!(stats.avg_tokens_per_second, 0.0);
+
+        let metrics = [
+            MetricRecord {
+                operation_type: "selected",
+                duration_ms: 1000,
+                success: true,
+                char_count: 10,
+                completion_tokens: Some(40),
+                tokens_per_second: Some(40.0),
+                ..Default::default()
+            },
+            MetricRecord {
+                operation_type: "full",
+                duration_ms: 2000,
+                success: true,
+                char_count: 20,
+                completion_tokens: Some(160),
+                tokens_per_second: Some(80.0),
+                ..Default::default()
+            },
+            // 非流式请求和失败请求没有 tokens 信息，不参与速率平均
+            MetricRecord {
+                operation_type: "selected",
+                duration_ms: 500,
+                success: true,
+                char_count: 5,
+                ..Default::default()
+            },
+            MetricRecord {
+                operation_type: "full",
+                duration_ms: 300,
+                error_type: Some("network"),
+                ..Default::default()
+            },
+        ];
+        for metric in &metrics {
+            db.insert_metric(metric).await.unwrap();
+        }
+
+        let stats = db.get_performance_stats("day").await.unwrap();
+        assert_eq!(stats.total_translations, 4);
+        assert_eq!(stats.total_completion_tokens, 200);
+        assert_eq!(stats.avg_tokens_per_second, 60.0);
+        // 没有 tokens 信息的记录不按 0 计入平均值
+        assert_eq!(stats.avg_tokens_per_translation, 100.0);
+
+        // 本月统计不包含上个月以前的记录
+        insert_metric_at(&db, Utc::now().timestamp() - 40 * 86400, 100, true).await;
+        let month = db.get_performance_stats("month").await.unwrap();
+        assert_eq!(month.total_translations, 4);
+        assert_eq!(month.total_completion_tokens, 200);
+        assert_eq!(month.bucket, TimeBucket::Day);
+    }
+
+    #[test]
+    fn test_sanitize_error_message() {
+        assert_eq!(
+            sanitize_error_message("LLM API 错误: Incorrect API key provided: sk-proj-abc123."),
+            "LLM API 错误: Incorrect API key provided: [REDACTED]."
+        );
+        assert_eq!(
+            sanitize_error_message("Bearer a1b2c3d4e5f6a7b8c9d0e1f2a3b4c5d6e7f8 rejected"),
+            "Bearer [REDACTED] rejected"
+        );
+        // 普通的长单词和数字不受影响
+        let message = "context_length_exceeded: maximum context length is 128000 tokens";
+        assert_eq!(sanitize_error_message(message), message);
+
+        let long = "错".repeat(ERROR_MESSAGE_MAX_CHARS + 10);
+        let truncated = sanitize_error_message(&long);
+        assert_eq!(truncated.chars().count(), ERROR_MESSAGE_MAX_CHARS + 1);
+        assert!(truncated.ends_with('…'));
+    }
+
+    #[tokio::test]
+    async fn test_recent_errors() {
+        let db = memory_db().await;
+        for i in 0..(RECENT_ERRORS_PER_TYPE + 2) {
+            let message = format!("rate limited #{}", i);
+            let metric = MetricRecord {
+                operation_type: "selected",
+                error_type: Some("api"),
+                error_message: Some(&message),
+                ..Default::default()
+            };
+            db.insert_metric(&metric).await.unwrap();
+        }
+        let metric = MetricRecord {
+            operation_type: "full",
+            error_type: Some("network"),
+            error_message: Some("connection reset, key sk-secret-value"),
+            ..Default::default()
+        };
+        db.insert_metric(&metric).await.unwrap();
+        // 没有错误信息的旧记录只计入分布
+        let metric = MetricRecord {
+            operation_type: "full",
+            error_type: Some("network"),
+            ..Default::default()
+        };
+        db.insert_metric(&metric).await.unwrap();
+
+        let stats = db.get_performance_stats("day").await.unwrap();
+        let api: Vec<&str> = stats
+            .recent_errors
+            .iter()
+            .filter(|e| e.error_type == "api")
+            .map(|e| e.message.as_str())
+            .collect();
+        assert_eq!(api.len(), RECENT_ERRORS_PER_TYPE as usize);
+        assert_eq!(api[0], "rate limited #6");
+
+        let network: Vec<&str> = stats
+            .recent_errors
+            .iter()
+            .filter(|e| e.error_type == "network")
+            .map(|e| e.message.as_str())
+            .collect();
+        assert_eq!(network, vec!["connection reset, key [REDACTED]"]);
+        let network_count = stats
+            .error_distribution
+            .iter()
+            .find(|e| e.error_type == "network")
+            .unwrap()
+            .count;
+        assert_eq!(network_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_output_mode_stats() {
+        let db = memory_db().await;
+        for (stream, duration_ms, tokens_per_second) in [
+            (Some(true), 1000, Some(40.0)),
+            (Some(true), 3000, Some(60.0)),
+            (Some(false), 800, None),
+        ] {
+            let metric = MetricRecord {
+                operation_type: "selected",
+                duration_ms,
+                success: true,
+                tokens_per_second,
+                stream,
+                ..Default::default()
+            };
+            db.insert_metric(&metric).await.unwrap();
+        }
+        // 引入该字段之前的记录
+        insert_metric_at(&db, Utc::now().timestamp(), 500, true).await;
+
+        let stats = db.get_performance_stats("day").await.unwrap();
+        let modes: Vec<(&str, i64, f64, f64)> = stats
+            .by_output_mode
+            .iter()
+            .map(|m| {
+                (
+                    m.output_mode.as_str(),
+                    m.count,
+                    m.avg_duration_ms,
+                    m.avg_tokens_per_second,
+                )
+            })
+            .collect();
+        assert_eq!(
+            modes,
+            vec![
+                ("non_stream", 1, 800.0, 0.0),
+                ("stream", 2, 2000.0, 50.0),
+                ("unknown", 1, 500.0, 0.0),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_language_stats() {
+        let db = memory_db().await;
+
+        for (target_lang, duration_ms, success) in [
+            (Some("en"), 100, true),
+            (Some("en"), 300, true),
+            (Some("ja"), 200, true),
+            (Some("ja"), 900, false),
+        ] {
+            let metric = MetricRecord {
+                operation_type: "selected",
+                duration_ms,
+                success,
+                char_count: 10,
+                target_lang,
+                ..Default::default()
+            };
+            db.insert_metric(&metric).await.unwrap();
+        }
+        // 旧版本记录没有目标语言
+        insert_metric_at(&db, Utc::now().timestamp(), 400, true).await;
+
+        let stats = db.get_performance_stats("day").await.unwrap();
+        assert_eq!(stats.total_translations, 5);
+
+        let languages: Vec<(&str, i64, f64, i64)> = stats
+            .by_language
+            .iter()
+            .map(|l| {
+                (
+                    l.target_lang.as_str(),
+                    l.count,
+                    l.avg_duration_ms,
+                    l.total_chars,
+                )
+            })
+            .collect();
+        assert_eq!(
+            languages,
+            vec![
+                ("en", 2, 200.0, 20),
+                ("ja", 2, 200.0, 20),
+                ("unknown", 1, 400.0, 10),
+            ]
+        );
+    }
+
+    /// 创建引入版本号之前的数据库文件
+    async fn old_database_file(name: &str) -> (Pool<Sqlite>, PathBuf) {
+        let path = std::env::temp_dir().join(format!(
+            "quick_trans_type_{}_{}.db",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect(&format!("sqlite:{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        for statement in [
+            "CREATE TABLE translations (id INTEGER PRIMARY KEY AUTOINCREMENT, original_text TEXT NOT NULL, translated_text TEXT NOT NULL, source_lang TEXT, target_lang TEXT NOT NULL, mode TEXT NOT NULL, timestamp INTEGER NOT NULL)",
+            "CREATE TABLE metrics (id INTEGER PRIMARY KEY AUTOINCREMENT, timestamp INTEGER NOT NULL, operation_type TEXT NOT NULL, duration_ms INTEGER NOT NULL, success INTEGER NOT NULL, error_type TEXT, char_count INTEGER NOT NULL)",
+            "INSERT INTO translations (original_text, translated_text, target_lang, mode, timestamp) VALUES ('旧', 'old', 'en', 'full', 1)",
+        ] {
+            sqlx::query(statement).execute(&pool).await.unwrap();
+        }
+        (pool, path)
+    }
+
+    #[tokio::test]
+    async fn test_migrations_upgrade_old_database_file() {
+        let (pool, path) = old_database_file("upgrade").await;
+        let db = Database::from_pool(pool);
+
+        assert_eq!(db.schema_version().await.ok(), None);
+        db.run_migrations().await.unwrap();
+        assert_eq!(db.schema_version().await.unwrap(), MIGRATIONS.len() as i64);
+        // 已是最新版本时不再重复执行
+        db.run_migrations().await.unwrap();
+        let applied: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM schema_version")
+            .fetch_one(&db.pool())
+            .await
+            .unwrap();
+        assert_eq!(applied, MIGRATIONS.len() as i64);
+
+        let history = all_history(&db).await;
+        assert_eq!(history.total, 1);
+        assert!(!history.records[0].pinned);
+
+        db.pool().close().await;
+        let _ = std::fs::remove_file(&path);
+    }
+
+    fn failing_migration(conn: &mut SqliteConnection) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            sqlx::query("CREATE TABLE partial (id INTEGER)")
+                .execute(&mut *conn)
+                .await?;
+            sqlx::query("ALTER TABLE missing ADD COLUMN x INTEGER")
+                .execute(&mut *conn)
+                .await?;
+            Ok(())
+        })
+    }
+
+    #[tokio::test]
+    async fn test_failed_migration_keeps_previous_version() {
+        let (pool, path) = old_database_file("failure").await;
+        let db = Database::from_pool(pool);
+
+        let mut migrations = MIGRATIONS.to_vec();
+        migrations.push(failing_migration);
+        assert!(db.apply_migrations(&migrations).await.is_err());
+        assert_eq!(db.schema_version().await.unwrap(), MIGRATIONS.len() as i64);
+
+        // 失败步骤中的修改已回滚
+        let partial: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'partial'",
+        )
+        .fetch_one(&db.pool())
+        .await
+        .unwrap();
+        assert_eq!(partial, 0);
+
+        // 数据库仍可在上一个版本下正常使用
+        db.insert_translation(
+            "新",
+            "new",
+            None,
+            "en",
+            "selected",
+            &TranslationMetadata::default(),
+        )
+        .await
+        .unwrap();
+        let history = all_history(&db).await;
+        assert_eq!(history.total, 2);
+
+        db.pool().close().await;
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_history_date_range() {
+        let db = memory_db().await;
+
+        let day = 86400;
+        let base = 1_700_000_000;
+        for offset in 0..4 {
+            let id = db
+                .insert_translation(
+                    &format!("第{}天", offset),
+                    "text",
+                    None,
+                    "en",
+                    "selected",
+                    &TranslationMetadata::default(),
+                )
+                .await
+                .unwrap();
+            sqlx::query("UPDATE translations SET timestamp = ? WHERE id = ?")
+                .bind(base + offset * day)
+                .bind(id)
+                .execute(&db.pool())
+                .await
+                .unwrap();
+        }
+
+        let range = |from_ts, to_ts| HistoryFilter {
+            from_ts,
+            to_ts,
+            ..Default::default()
+        };
+
+        // 起始时间包含，结束时间不包含
+        let history = db
+            .get_history(1, 10, &range(Some(base + day), Some(base + 3 * day)))
+            .await
+            .unwrap();
+        assert_eq!(history.total, 2);
+        let texts: Vec<&str> = history
+            .records
+            .iter()
+            .map(|r| r.original_text.as_str())
+            .collect();
+        assert_eq!(texts, vec!["第2天", "第1天"]);
+
+        let history = db
+            .get_history(1, 10, &range(Some(base + 2 * day), None))
+            .await
+            .unwrap();
+        assert_eq!(history.total, 2);
+
+        let history = db
+            .get_history(1, 10, &range(None, Some(base)))
+            .await
+            .unwrap();
+        assert_eq!(history.total, 0);
+
+        // 与其他条件组合
+        let filter = HistoryFilter {
+            search: Some("第3".to_string()),
+            ..range(Some(base), None)
+        };
+        let history = db.get_history(1, 10, &filter).await.unwrap();
+        assert_eq!(history.total, 1);
+    }
+
+    #[tokio::test]
+    async fn test_history_combined_filters() {
+        let db = memory_db().await;
+
+        for (text, target_lang, mode) in [
+            ("早上好", "ja", "selected"),
+            ("晚上好", "ja", "full"),
+            ("早上好", "en", "selected"),
+            ("再见", "ja", "selected"),
+        ] {
+            db.insert_translation(
+                text,
+                "text",
+                None,
+                target_lang,
+                mode,
+                &TranslationMetadata::default(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let japanese = HistoryFilter {
+            target_lang: Some("ja".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(db.get_history(1, 10, &japanese).await.unwrap().total, 3);
+
+        let filter = HistoryFilter {
+            search: Some("好".to_string()),
+            mode: Some("selected".to_string()),
+            ..japanese.clone()
+        };
+        let history = db.get_history(1, 10, &filter).await.unwrap();
+        assert_eq!(history.total, 1);
+        assert_eq!(history.records.len(), 1);
+        assert_eq!(history.records[0].original_text, "早上好");
+        assert_eq!(history.records[0].target_lang, "ja");
+
+        // 总数不受分页影响
+        let history = db.get_history(2, 1, &japanese).await.unwrap();
+        assert_eq!(history.total, 3);
+        assert_eq!(history.records.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_translation_refreshes_latest_record() {
+        let db = memory_db().await;
+        let metadata = TranslationMetadata {
+            dedup_window_secs: 60,
+            ..Default::default()
+        };
+
+        let first = db
+            .insert_translation("你好", "Hello", None, "en", "selected", &metadata)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE translations SET timestamp = timestamp - 10 WHERE id = ?")
+            .bind(first)
+            .execute(&db.pool())
+            .await
+            .unwrap();
+
+        // 窗口内完全相同的翻译只更新时间戳
+        let second = db
+            .insert_translation("你好", "Hello", None, "en", "selected", &metadata)
+            .await
+            .unwrap();
+        assert_eq!(second, first);
+        let history = all_history(&db).await;
+        assert_eq!(history.total, 1);
+        assert!(history.records[0].timestamp >= Utc::now().timestamp() - 1);
+
+        // 目标语言不同时正常插入
+        let japanese = db
+            .insert_translation("你好", "こんにちは", None, "ja", "selected", &metadata)
+            .await
+            .unwrap();
+        assert_ne!(japanese, first);
+        assert_eq!(all_history(&db).await.total, 2);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_translation_outside_window_is_inserted() {
+        let db = memory_db().await;
+        let metadata = TranslationMetadata {
+            dedup_window_secs: 60,
+            ..Default::default()
+        };
+
+        let first = db
+            .insert_translation("你好", "Hello", None, "en", "selected", &metadata)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE translations SET timestamp = timestamp - 120 WHERE id = ?")
+            .bind(first)
+            .execute(&db.pool())
+            .await
+            .unwrap();
+        db.insert_translation("你好", "Hello", None, "en", "selected", &metadata)
+            .await
+            .unwrap();
+        assert_eq!(all_history(&db).await.total, 2);
+
+        // 窗口为 0 时不去重
+        let disabled = TranslationMetadata::default();
+        db.insert_translation("你好", "Hello", None, "en", "selected", &disabled)
+            .await
+            .unwrap();
+        assert_eq!(all_history(&db).await.total, 3);
+    }
+
+    #[tokio::test]
+    async fn test_db_info_and_compact() {
+        let db = memory_db().await;
+        let long_text = "长文本".repeat(2000);
+        for _ in 0..20 {
+            db.insert_translation(
+                &long_text,
+                &long_text,
+                None,
+                "en",
+                "full",
+                &TranslationMetadata::default(),
+            )
+            .await
+            .unwrap();
+        }
+        insert_metric_at(&db, Utc::now().timestamp(), 100, true).await;
+
+        let info = db.get_db_info().await.unwrap();
+        let rows: Vec<(&str, i64)> = info
+            .tables
+            .iter()
+            .map(|t| (t.name.as_str(), t.rows))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![("translations", 20), ("metrics", 1), ("daily_stats", 0)]
+        );
+        assert_eq!(info.file_size, info.page_count * info.page_size);
+
+        db.clear_all_history(true).await.unwrap();
+        let cleared = db.get_db_info().await.unwrap();
+        assert!(cleared.freelist_pages > 0);
+        assert_eq!(
+            cleared.reclaimable_bytes,
+            cleared.freelist_pages * cleared.page_size
+        );
+
+        let compacted = db.compact().await.unwrap();
+        assert_eq!(compacted.freelist_pages, 0);
+        assert!(compacted.page_count < cleared.page_count);
+    }
+
+    /// 直接读取数据库中保存的原文
+    async fn stored_original_texts(db: &Database) -> Vec<String> {
+        sqlx::query_scalar("SELECT original_text FROM translations ORDER BY id")
+            .fetch_all(&db.pool())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_history_encryption() {
+        let db = memory_db().await;
+        db.insert_translation(
+            "机密合同",
+            "Confidential contract",
+            None,
+            "en",
+            "selected",
+            &TranslationMetadata::default(),
+        )
+        .await
+        .unwrap();
+
+        // 启用时加密已有记录
+        assert!(!db.has_encrypted_history().await.unwrap());
+        let cipher = FieldCipher::new(&[1u8; 32]).unwrap();
+        assert_eq!(db.enable_encryption(cipher).await.unwrap(), 1);
+        assert!(db.encryption_enabled());
+        assert!(db.has_encrypted_history().await.unwrap());
+        db.insert_translation(
+            "会议纪要",
+            "Meeting notes",
+            None,
+            "en",
+            "full",
+            &TranslationMetadata::default(),
+        )
+        .await
+        .unwrap();
+        let stored = stored_original_texts(&db).await;
+        assert!(stored.iter().all(|text| is_encrypted(text)));
+
+        let history = all_history(&db).await;
+        let texts: Vec<&str> = history
+            .records
+            .iter()
+            .map(|r| r.original_text.as_str())
+            .collect();
+        assert_eq!(texts, vec!["会议纪要", "机密合同"]);
+
+        // 搜索在解密后进行
+        let filter = HistoryFilter {
+            search: Some("contract".to_string()),
+            ..Default::default()
+        };
+        let history = db.get_history(1, 10, &filter).await.unwrap();
+        assert_eq!(history.total, 1);
+        assert_eq!(history.records[0].translated_text, "Confidential contract");
+
+        // 关闭时还原为明文
+        assert_eq!(db.disable_encryption().await.unwrap(), 2);
+        assert!(!db.encryption_enabled());
+        assert_eq!(
+            stored_original_texts(&db).await,
+            vec!["机密合同".to_string(), "会议纪要".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_encryption_refuses_text() {
+        let db = memory_db().await;
+        assert!(!db.has_plain_history().await.unwrap());
+        db.insert_translation(
+            "明文",
+            "plain",
+            None,
+            "en",
+            "selected",
+            &TranslationMetadata::default(),
+        )
+        .await
+        .unwrap();
+        assert!(db.has_plain_history().await.unwrap());
+
+        // 密钥不可用时不以明文保存
+        db.mark_encryption_unavailable("钥匙串已锁定".to_string());
+        assert!(db.encryption_error().is_some());
+        let result = db
+            .insert_translation(
+                "机密",
+                "secret",
+                None,
+                "en",
+                "selected",
+                &TranslationMetadata::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(AppError::Crypto(_))));
+        assert_eq!(stored_original_texts(&db).await, vec!["明文".to_string()]);
+
+        // 成功启用后恢复保存，之后所有记录都已加密
+        db.enable_encryption(FieldCipher::new(&[3u8; 32]).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(db.encryption_error(), None);
+        assert!(!db.has_plain_history().await.unwrap());
+        db.insert_translation(
+            "机密",
+            "secret",
+            None,
+            "en",
+            "selected",
+            &TranslationMetadata::default(),
+        )
+        .await
+        .unwrap();
+        assert!(!db.has_plain_history().await.unwrap());
+    }
+
+    /// 直接读取数据库中保存的原文哈希
+    async fn stored_hashes(db: &Database) -> Vec<String> {
+        sqlx::query_scalar("SELECT original_hash FROM translations ORDER BY id")
+            .fetch_all(&db.pool())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_history_hash_is_keyed() {
+        let db = memory_db().await;
+        let plain_hash: String = digest::digest(&digest::SHA256, "OK".as_bytes())
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        db.insert_translation(
+            "OK",
+            "好的",
+            None,
+            "zh-CN",
+            "selected",
+            &TranslationMetadata::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(stored_hashes(&db).await, vec![plain_hash.clone()]);
+
+        // 启用加密后已有记录和新记录都保存 HMAC，不能通过哈希猜测的原文来确认
+        db.enable_encryption(FieldCipher::new(&[1u8; 32]).unwrap())
+            .await
+            .unwrap();
+        db.insert_translation(
+            "OK",
+            "好的",
+            None,
+            "ja-JP",
+            "selected",
+            &TranslationMetadata::default(),
+        )
+        .await
+        .unwrap();
+        let hashes = stored_hashes(&db).await;
+        assert_eq!(hashes[0], hashes[1]);
+        assert_ne!(hashes[0], plain_hash);
+        for target_lang in ["zh-CN", "ja-JP"] {
+            assert!(db
+                .find_recent_translation("OK", target_lang, 3600)
+                .await
+                .unwrap()
+                .is_some());
+        }
+
+        // 关闭加密后还原为普通哈希
+        db.disable_encryption().await.unwrap();
+        assert_eq!(
+            stored_hashes(&db).await,
+            vec![plain_hash.clone(), plain_hash]
+        );
+        assert!(db
+            .find_recent_translation("OK", "ja-JP", 3600)
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_history_dedup_and_import() {
+        let db = memory_db().await;
+        db.enable_encryption(FieldCipher::new(&[2u8; 32]).unwrap())
+            .await
+            .unwrap();
+        let metadata = TranslationMetadata {
+            dedup_window_secs: 60,
+            ..Default::default()
+        };
+
+        let first = db
+            .insert_translation("你好", "Hello", None, "en", "selected", &metadata)
+            .await
+            .unwrap();
+        let second = db
+            .insert_translation("你好", "Hello", None, "en", "selected", &metadata)
+            .await
+            .unwrap();
+        assert_eq!(first, second);
+
+        let history = all_history(&db).await;
+        let content = serde_json::to_string(&history.records).unwrap();
+        let summary = db.import_history_json(&content).await.unwrap();
+        assert_eq!((summary.imported, summary.skipped), (0, 1));
+
+        // 没有密钥时无法读取已加密的记录
+        let locked = Database::from_pool(db.pool());
+        let result = locked.get_history(1, 10, &HistoryFilter::default()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rollup_preserves_lifetime_totals() {
+        let db = memory_db().await;
+        let now = Utc::now().timestamp();
+        let old = now - 200 * 86400;
+        for (timestamp, duration_ms, success, tokens) in [
+            (old, 100, true, Some(10)),
+            (old + 60, 300, true, None),
+            (old + 120, 5000, false, None),
+            (old - 86400, 200, true, Some(30)),
+            (now - 60, 400, true, Some(5)),
+        ] {
+            sqlx::query(
+                "INSERT INTO metrics (timestamp, operation_type, duration_ms, success, char_count, completion_tokens) VALUES (?, 'selected', ?, ?, 10, ?)",
+            )
+            .bind(timestamp)
+            .bind(duration_ms)
+            .bind(success)
+            .bind(tokens)
+            .execute(&db.pool())
+            .await
+            .unwrap();
+        }
+
+        let before = db.get_performance_stats("lifetime").await.unwrap();
+        assert_eq!(before.total_translations, 5);
+
+        assert_eq!(db.cleanup_metrics().await.unwrap(), 4);
+        // 已汇总的指标不会重复计入
+        assert_eq!(db.cleanup_metrics().await.unwrap(), 0);
+
+        let after = db.get_performance_stats("lifetime").await.unwrap();
+        assert_eq!(after.total_translations, before.total_translations);
+        assert_eq!(
+            after.successful_translations,
+            before.successful_translations
+        );
+        assert_eq!(after.failed_translations, before.failed_translations);
+        assert_eq!(after.total_chars_translated, before.total_chars_translated);
+        assert_eq!(
+            after.total_completion_tokens,
+            before.total_completion_tokens
+        );
+        assert_eq!(after.avg_duration_ms, before.avg_duration_ms);
+        assert_eq!(after.avg_duration_ms, 250.0);
+
+        // 其他时间段只统计保留的原始指标
+        let day = db.get_performance_stats("day").await.unwrap();
+        assert_eq!(day.total_translations, 1);
+
+        let info = db.get_db_info().await.unwrap();
+        let daily = info
+            .tables
+            .iter()
+            .find(|t| t.name == "daily_stats")
+            .unwrap();
+        assert_eq!(daily.rows, 2);
+    }
+
+    /// 位于独立临时目录中的数据库文件，测试备份和恢复
+    async fn file_db(name: &str) -> (Database, PathBuf) {
+        let name = format!("quick_trans_type_{}_{}", name, std::process::id());
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("quicktranstype.db");
+        let db = Database::open(&path).await.unwrap();
+        (db, dir)
+    }
+
+    async fn insert_text(db: &Database, text: &str) {
+        let metadata = TranslationMetadata::default();
+        db.insert_translation(text, text, None, "en", "selected", &metadata)
+            .await
+            .unwrap();
+    }
+
+    /// 数据库目录中所有文件（包括 WAL）是否包含 `needle`
+    fn files_contain(dir: &Path, needle: &[u8]) -> bool {
+        std::fs::read_dir(dir).unwrap().any(|entry| {
+            let data = std::fs::read(entry.unwrap().path()).unwrap();
+            data.windows(needle.len()).any(|window| window == needle)
+        })
+    }
+
+    #[tokio::test]
+    async fn test_enable_encryption_leaves_no_plaintext_in_file() {
+        let (db, dir) = file_db("scrub").await;
+        for i in 0..20 {
+            insert_text(&db, &format!("plaintext-marker-{}", i)).await;
+        }
+        assert!(files_contain(&dir, b"plaintext-marker-"));
+
+        db.enable_encryption(FieldCipher::new(&[4u8; 32]).unwrap())
+            .await
+            .unwrap();
+        assert!(!files_contain(&dir, b"plaintext-marker-"));
+        assert_eq!(all_history(&db).await.total, 20);
+
+        db.pool().close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_writes_do_not_fail() {
+        let (db, dir) = file_db("concurrent").await;
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(&db.pool())
+            .await
+            .unwrap();
+        assert_eq!(journal_mode, "wal");
+
+        let db = Arc::new(db);
+        let tasks: Vec<_> = (0..40)
+            .map(|i| {
+                let db = db.clone();
+                tokio::spawn(async move {
+                    let text = format!("text {}", i);
+                    let metadata = TranslationMetadata {
+                        dedup_window_secs: 60,
+                        ..Default::default()
+                    };
+                    db.insert_translation(&text, &text, None, "en", "selected", &metadata)
+                        .await?;
+                    let metric = MetricRecord {
+                        operation_type: "selected",
+                        success: true,
+                        ..Default::default()
+                    };
+                    db.insert_metric(&metric).await?;
+                    db.apply_history_retention(30, Some(30)).await
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        assert_eq!(all_history(&db).await.total, 30);
+        let stats = db.get_performance_stats("day").await.unwrap();
+        assert_eq!(stats.total_translations, 40);
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_backup_rotation() {
+        let (db, dir) = file_db("backup").await;
+        insert_text(&db, "hello").await;
+
+        let latest = db.backup_if_due().await.unwrap().unwrap();
+        assert!(dir.join("quicktranstype.backup.1.db").exists());
+        assert_eq!(latest, dir.join("quicktranstype.backup.1.db"));
+        // 刚备份过，不会重复备份
+        assert!(db.backup_if_due().await.unwrap().is_none());
+
+        for _ in 0..BACKUP_KEEP {
+            db.backup().await.unwrap();
+        }
+        let backups = db.list_backups().unwrap();
+        let indexes: Vec<usize> = backups.iter().map(|b| b.index).collect();
+        assert_eq!(indexes, (1..=BACKUP_KEEP).collect::<Vec<_>>());
+        assert!(backups.iter().all(|b| b.file_size > 0));
+        let rotated_out = format!("quicktranstype.backup.{}.db", BACKUP_KEEP + 1);
+        assert!(!dir.join(rotated_out).exists());
+        assert!(!dir.join("quicktranstype.backup.tmp").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_restore_from_backup() {
+        let (db, dir) = file_db("restore").await;
+        insert_text(&db, "before backup").await;
+        db.backup().await.unwrap();
+        insert_text(&db, "after backup").await;
+        assert_eq!(all_history(&db).await.total, 2);
+
+        db.restore_from_backup(1).await.unwrap();
+        let history = all_history(&db).await;
+        assert_eq!(history.total, 1);
+        assert_eq!(history.records[0].original_text, "before backup");
+        assert!(dir.join("quicktranstype.db.before-restore").exists());
+
+        // 恢复后数据库可以正常写入
+        insert_text(&db, "after restore").await;
+        assert_eq!(all_history(&db).await.total, 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_failed_restore_keeps_current_database() {
+        let (db, dir) = file_db("restore_failed").await;
+        insert_text(&db, "current").await;
+
+        assert!(db.restore_from_backup(1).await.is_err());
+        std::fs::write(dir.join("quicktranstype.backup.1.db"), "not a database").unwrap();
+        assert!(db.restore_from_backup(1).await.is_err());
+
+        let history = all_history(&db).await;
+        assert_eq!(history.total, 1);
+        assert_eq!(history.records[0].original_text, "current");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_failed_restore_ignores_stale_previous_database() {
+        let (db, dir) = file_db("restore_stale").await;
+        insert_text(&db, "old").await;
+        db.backup().await.unwrap();
+        db.restore_from_backup(1).await.unwrap();
+        // 上次恢复留下的数据库
+        let previous = dir.join("quicktranstype.db.before-restore");
+        assert!(previous.exists());
+
+        insert_text(&db, "current").await;
+        std::fs::write(dir.join("quicktranstype.backup.1.db"), "not a database").unwrap();
+        assert!(db.restore_from_backup(1).await.is_err());
+        assert!(!previous.exists());
+
+        let history = all_history(&db).await;
+        assert_eq!(history.total, 2);
+        assert_eq!(history.records[0].original_text, "current");
+        insert_text(&db, "after failed restore").await;
+        assert_eq!(all_history(&db).await.total, 3);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_restore_rejects_encrypted_backup_without_cipher() {
+        let (db, dir) = file_db("restore_encrypted").await;
+        db.enable_encryption(FieldCipher::new(&[6u8; 32]).unwrap())
+            .await
+            .unwrap();
+        insert_text(&db, "encrypted").await;
+        db.backup().await.unwrap();
+        db.disable_encryption().await.unwrap();
+        insert_text(&db, "plain").await;
+
+        assert!(matches!(
+            db.restore_from_backup(1).await,
+            Err(AppError::Crypto(_))
+        ));
+        let history = all_history(&db).await;
+        assert_eq!(history.total, 2);
+        assert_eq!(history.records[0].original_text, "plain");
+        insert_text(&db, "after rejected restore").await;
+        assert_eq!(all_history(&db).await.total, 3);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_move_database() {
+        let (db, dir) = file_db("move").await;
+        insert_text(&db, "before move").await;
+        let old_path = dir.join("quicktranstype.db");
+        let new_path = dir.join("synced").join("history.db");
+
+        db.move_to(&new_path).await.unwrap();
+        assert!(new_path.exists());
+        assert!(!old_path.exists());
+        let history = all_history(&db).await;
+        assert_eq!(history.total, 1);
+        assert_eq!(history.records[0].original_text, "before move");
+
+        // 移动后的写入和备份都在新位置
+        insert_text(&db, "after move").await;
+        assert_eq!(all_history(&db).await.total, 2);
+        let backup = db.backup().await.unwrap();
+        assert_eq!(backup.parent(), new_path.parent());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_move_database_rejects_existing_target() {
+        let (db, dir) = file_db("move_existing").await;
+        insert_text(&db, "current").await;
+        let target = dir.join("other.db");
+        std::fs::write(&target, "existing").unwrap();
+
+        assert!(db.move_to(&target).await.is_err());
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "existing");
+        assert_eq!(all_history(&db).await.total, 1);
+        // 内存数据库没有文件可以移动
+        let target = dir.join("memory.db");
+        assert!(memory_db().await.move_to(&target).await.is_err());
+        assert!(!target.exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_memory_database_has_no_backups() {
+        let db = memory_db().await;
+        assert!(db.backup().await.is_err());
+        assert!(db.list_backups().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_translation() {
+        let db = memory_db().await;
+        let metadata = TranslationMetadata {
+            model: Some("gpt-4o-mini".to_string()),
+            completion_tokens: Some(12),
+            ..Default::default()
+        };
+        let id = db
+            .insert_translation("你好", "Hello", None, "en", "selected", &metadata)
+            .await
+            .unwrap();
+
+        let record = db.get_translation(id).await.unwrap().unwrap();
+        assert_eq!(record.original_text, "你好");
+        assert_eq!(record.model.as_deref(), Some("gpt-4o-mini"));
+        assert_eq!(record.completion_tokens, Some(12));
+        assert!(db.get_translation(id + 1).await.unwrap().is_none());
+
+        // 加密的记录返回解密后的文本
+        let cipher = FieldCipher::new(&[1u8; 32]).unwrap();
+        db.enable_encryption(cipher).await.unwrap();
+        let record = db.get_translation(id).await.unwrap().unwrap();
+        assert_eq!(record.translated_text, "Hello");
+    }
+
+    #[tokio::test]
+    async fn test_get_recent() {
+        let db = memory_db().await;
+        let long_text = "译".repeat(150);
+        for (i, text) in ["一", "二", long_text.as_str()].iter().enumerate() {
+            let id = db
+                .insert_translation(
+                    text,
+                    text,
+                    None,
+                    "en",
+                    "selected",
+                    &TranslationMetadata::default(),
+                )
+                .await
+                .unwrap();
+            sqlx::query("UPDATE translations SET timestamp = ? WHERE id = ?")
+                .bind(1_700_000_000 + i as i64)
+                .bind(id)
+                .execute(&db.pool())
+                .await
+                .unwrap();
+        }
+
+        let recent = db.get_recent(2).await.unwrap();
+        assert_eq!(recent.len(), 2);
+        // 按字符截断，不拆分多字节字符
+        assert_eq!(recent[0].original_text, "译".repeat(RECENT_PREVIEW_CHARS));
+        assert!(recent[0].original_truncated);
+        assert!(recent[0].translated_truncated);
+        assert_eq!(recent[1].original_text, "二");
+        assert!(!recent[1].original_truncated);
+
+        assert_eq!(db.get_recent(1000).await.unwrap().len(), 3);
+        assert_eq!(truncate_chars("héllo", 2), ("hé".to_string(), true));
+        assert_eq!(truncate_chars("héllo", 5), ("héllo".to_string(), false));
+    }
+
+    #[tokio::test]
+    async fn test_retranslated_record_links_original() {
+        let db = memory_db().await;
+        let id = db
+            .insert_translation(
+                "你好",
+                "Hello",
+                None,
+                "en",
+                "selected",
+                &TranslationMetadata::default(),
+            )
+            .await
+            .unwrap();
+
+        // 重新翻译不去重，即使译文相同也插入新记录
+        let metadata = TranslationMetadata {
+            retranslated_from: Some(id),
+            ..Default::default()
+        };
+        let new_id = db
+            .insert_translation("你好", "Hello", None, "en", "selected", &metadata)
+            .await
+            .unwrap();
+        assert_ne!(new_id, id);
+
+        let record = db.get_translation(new_id).await.unwrap().unwrap();
+        assert_eq!(record.retranslated_from, Some(id));
+        let original = db.get_translation(id).await.unwrap().unwrap();
+        assert_eq!(original.retranslated_from, None);
+    }
+
+    #[tokio::test]
+    async fn test_history_facets() {
+        let db = memory_db().await;
+        let metadata = TranslationMetadata::default();
+        for (text, lang, mode) in [
+            ("苹果", "en", "selected"),
+            ("香蕉", "en", "full"),
+            ("苹果派", "ja", "selected"),
+            ("橙子", "ko", "selected"),
+        ] {
+            db.insert_translation(text, text, None, lang, mode, &metadata)
+                .await
+                .unwrap();
+        }
+
+        let facets = db.get_history_facets(None).await.unwrap();
+        assert_eq!(facets.total, all_history(&db).await.total);
+        let lang_total: i64 = facets.by_target_lang.iter().map(|f| f.count).sum();
+        assert_eq!(lang_total, facets.total);
+        assert_eq!(
+            facets.by_mode,
+            vec![
+                FacetCount {
+                    value: "selected".to_string(),
+                    count: 3
+                },
+                FacetCount {
+                    value: "full".to_string(),
+                    count: 1
+                },
+            ]
+        );
+        assert_eq!(facets.by_target_lang[0].value, "en");
+
+        // 搜索条件下的计数与历史列表一致，加密后结果不变
+        let filter = HistoryFilter {
+            search: Some("苹果".to_string()),
+            ..Default::default()
+        };
+        let expected = db.get_history(1, 10, &filter).await.unwrap().total;
+        let facets = db.get_history_facets(Some("苹果")).await.unwrap();
+        assert_eq!(facets.total, expected);
+        assert_eq!(facets.by_mode.len(), 1);
+        assert_eq!(facets.by_target_lang.len(), 2);
+
+        let cipher = FieldCipher::new(&[3u8; 32]).unwrap();
+        db.enable_encryption(cipher).await.unwrap();
+        let encrypted = db.get_history_facets(Some("苹果")).await.unwrap();
+        assert_eq!(encrypted.total, expected);
+        assert_eq!(encrypted.by_mode, facets.by_mode);
+        assert_eq!(encrypted.by_target_lang, facets.by_target_lang);
+    }
+
+    #[tokio::test]
+    async fn test_adjacent_ids() {
+        let db = memory_db().await;
+        let mut ids = Vec::new();
+        for (text, mode) in [
+            ("一", "selected"),
+            ("二", "full"),
+            ("三", "selected"),
+            ("四", "selected"),
+        ] {
+            let metadata = TranslationMetadata::default();
+            let id = db
+                .insert_translation(text, text, None, "en", mode, &metadata)
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+
+        // 列表从新到旧：四、三、二、一
+        let all = HistoryFilter::default();
+        let adjacent = db.get_adjacent_ids(ids[2], &all).await.unwrap();
+        let expected = AdjacentIds {
+            previous: Some(ids[3]),
+            next: Some(ids[1]),
+        };
+        assert_eq!(adjacent, expected);
+        let newest = db.get_adjacent_ids(ids[3], &all).await.unwrap();
+        assert_eq!(newest.previous, None);
+        let oldest = db.get_adjacent_ids(ids[0], &all).await.unwrap();
+        assert_eq!(oldest.next, None);
+
+        // 跳过不满足筛选条件的记录
+        let selected = HistoryFilter {
+            mode: Some("selected".to_string()),
+            ..Default::default()
+        };
+        let adjacent = db.get_adjacent_ids(ids[2], &selected).await.unwrap();
+        assert_eq!(adjacent.next, Some(ids[0]));
+
+        // 加密后按解密的文本搜索
+        let cipher = FieldCipher::new(&[1u8; 32]).unwrap();
+        db.enable_encryption(cipher).await.unwrap();
+        let search = HistoryFilter {
+            search: Some("一".to_string()),
+            ..Default::default()
+        };
+        let adjacent = db.get_adjacent_ids(ids[3], &search).await.unwrap();
+        let expected = AdjacentIds {
+            previous: None,
+            next: Some(ids[0]),
+        };
+        assert_eq!(adjacent, expected);
+
+        assert!(db.get_adjacent_ids(ids[3] + 1, &all).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_history_source_app() {
+        let db = memory_db().await;
+        let slack = Some("com.tinyspeck.slackmacgap");
+        let pages = Some("com.apple.Pages");
+        for source_app in [slack, pages, None] {
+            let metadata = TranslationMetadata {
+                source_app: source_app.map(str::to_string),
+                ..Default::default()
+            };
+            db.insert_translation("你好", "Hello", None, "en", "selected", &metadata)
+                .await
+                .unwrap();
+        }
+
+        let history = all_history(&db).await;
+        let apps: Vec<Option<&str>> = history
+            .records
+            .iter()
+            .map(|r| r.source_app.as_deref())
+            .collect();
+        assert_eq!(apps, vec![None, pages, slack]);
+
+        let filter = HistoryFilter {
+            source_app: pages.map(str::to_string),
+            ..Default::default()
+        };
+        let history = db.get_history(1, 10, &filter).await.unwrap();
+        assert_eq!(history.total, 1);
+        assert_eq!(history.records[0].source_app.as_deref(), pages);
+    }
+
+    #[tokio::test]
+    async fn test_model_stats() {
+        let db = memory_db().await;
+        let mut metrics = Vec::new();
+        for i in 0..5 {
+            metrics.push((Some("gpt-4o-mini"), 100 + i * 100, i != 4, 50.0));
+        }
+        for i in 0..5 {
+            metrics.push((Some("gpt-4o"), 1000, true, 20.0 + i as f64));
+        }
+        metrics.push((Some("llama-3"), 2000, false, 0.0));
+        metrics.push((Some("qwen-2.5"), 500, true, 10.0));
+        metrics.push((None, 700, true, 0.0));
+
+        for (model, duration_ms, success, tokens_per_second) in metrics {
+            let metric = MetricRecord {
+                operation_type: "selected",
+                duration_ms,
+                success,
+                model,
+                tokens_per_second: Some(tokens_per_second),
+                ..Default::default()
+            };
+            db.insert_metric(&metric).await.unwrap();
+        }
+
+        let stats = db.get_model_stats("hour").await.unwrap();
+        let models: Vec<&str> = stats.iter().map(|s| s.model.as_str()).collect();
+        assert_eq!(models, ["gpt-4o", "gpt-4o-mini", "other", "unknown"]);
+
+        let mini = &stats[1];
+        assert_eq!(mini.count, 5);
+        assert_eq!(mini.failed, 1);
+        assert!((mini.failure_rate - 0.2).abs() < 1e-9);
+        // 耗时只统计成功的翻译
+        assert_eq!(mini.avg_duration_ms, 250.0);
+        assert_eq!(mini.avg_tokens_per_second, 50.0);
+        assert_eq!(stats[0].avg_tokens_per_second, 22.0);
+
+        let other = &stats[2];
+        assert_eq!(other.count, 2);
+        assert_eq!(other.failure_rate, 0.5);
+        assert_eq!(other.avg_duration_ms, 500.0);
+        assert_eq!(stats[3].count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_cost_stats() {
+        let db = memory_db().await;
+        for (model, cost_usd) in [
+            ("gpt-4o-mini", Some(0.001)),
+            ("gpt-4o-mini", Some(0.003)),
+            // 没有价格信息的请求不计入平均值
+            ("gpt-4o-mini", None),
+            ("gpt-4o", Some(0.02)),
+        ] {
+            let metric = MetricRecord {
+                operation_type: "selected",
+                success: true,
+                model: Some(model),
+                cost_usd,
+                ..Default::default()
+            };
+            db.insert_metric(&metric).await.unwrap();
+        }
+
+        let stats = db.get_cost_stats("month").await.unwrap();
+        assert!((stats.total_cost_usd - 0.024).abs() < 1e-9);
+        assert_eq!(stats.daily.len(), 1);
+        assert_eq!(stats.by_model.len(), 2);
+        assert_eq!(stats.by_model[0].model, "gpt-4o");
+        let mini = &stats.by_model[1];
+        assert_eq!(mini.count, 2);
+        assert!((mini.avg_cost_usd - 0.002).abs() < 1e-9);
+
+        // 归档后全部时间的总费用不变
+        let old = Utc::now().timestamp() - (METRICS_RETENTION_DAYS + 10) * 24 * 3600;
+        sqlx::query("UPDATE metrics SET timestamp = ? WHERE model = 'gpt-4o'")
+            .bind(old)
+            .execute(&db.pool())
+            .await
+            .unwrap();
+        db.cleanup_metrics().await.unwrap();
+
+        let stats = db.get_cost_stats("lifetime").await.unwrap();
+        assert!((stats.total_cost_usd - 0.024).abs() < 1e-9);
+        assert_eq!(stats.daily.len(), 2);
+        assert_eq!(stats.by_model.len(), 1);
+        let stats = db.get_cost_stats("month").await.unwrap();
+        assert!((stats.total_cost_usd - 0.004).abs() < 1e-9);
+    }
+
+    /// 在内存中按筛选条件判断记录是否匹配，作为查询结果的参照
+    fn filter_matches(filter: &HistoryFilter, record: &TranslationRecord) -> bool {
+        let search = filter.search.as_deref().is_none_or(|search| {
+            record.original_text.contains(search) || record.translated_text.contains(search)
+        });
+        search
+            && filter.mode.as_ref().is_none_or(|mode| &record.mode == mode)
+            && filter
+                .target_lang
+                .as_ref()
+                .is_none_or(|lang| &record.target_lang == lang)
+            && filter
+                .source_app
+                .as_ref()
+                .is_none_or(|app| record.source_app.as_ref() == Some(app))
+            && (!filter.pinned_only || record.pinned)
+            && filter.from_ts.is_none_or(|from| record.timestamp >= from)
+            && filter.to_ts.is_none_or(|to| record.timestamp < to)
+    }
+
+    #[tokio::test]
+    async fn test_history_filter_matrix() {
+        let db = memory_db().await;
+        let pages = "com.apple.Pages";
+        let notes = "com.apple.Notes";
+        let rows = [
+            ("早上好", "selected", "ja", Some(pages), false, 100),
+            ("晚上好", "full", "ja", None, true, 200),
+            ("早上好", "selected", "en", Some(pages), true, 300),
+            ("再见", "selected", "ja", Some(notes), false, 400),
+            ("你好", "full", "en", Some(pages), false, 200),
+            ("谢谢", "selected", "ja", Some(pages), true, 399),
+        ];
+        for (text, mode, target_lang, source_app, pinned, timestamp) in rows {
+            let metadata = TranslationMetadata {
+                source_app: source_app.map(str::to_string),
+                ..Default::default()
+            };
+            let id = db
+                .insert_translation(text, "text", None, target_lang, mode, &metadata)
+                .await
+                .unwrap();
+            db.set_translation_pinned(id, pinned).await.unwrap();
+            sqlx::query("UPDATE translations SET timestamp = ? WHERE id = ?")
+                .bind(timestamp)
+                .bind(id)
+                .execute(&db.pool())
+                .await
+                .unwrap();
+        }
+        let records = all_history(&db).await.records;
+        assert_eq!(records.len(), rows.len());
+
+        // 每种筛选条件组合的结果都与逐条判断一致
+        for mask in 0..128u32 {
+            let filter = HistoryFilter {
+                search: (mask & 1 != 0).then(|| "好".to_string()),
+                mode: (mask & 2 != 0).then(|| "selected".to_string()),
+                target_lang: (mask & 4 != 0).then(|| "ja".to_string()),
+                source_app: (mask & 8 != 0).then(|| pages.to_string()),
+                pinned_only: mask & 16 != 0,
+                from_ts: (mask & 32 != 0).then_some(200),
+                to_ts: (mask & 64 != 0).then_some(400),
+            };
+            let expected: Vec<i64> = records
+                .iter()
+                .filter(|r| filter_matches(&filter, r))
+                .map(|r| r.id)
+                .collect();
+
+            let history = db.get_history(1, 100, &filter).await.unwrap();
+            let ids: Vec<i64> = history.records.iter().map(|r| r.id).collect();
+            assert_eq!(history.total, expected.len() as i64, "filter {:?}", filter);
+            assert_eq!(ids, expected, "filter {:?}", filter);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_history_cursor_pagination() {
+        let db = memory_db().await;
+        let mut ids = Vec::new();
+        for i in 0..5 {
+            let text = format!("记录 {}", i);
+            let metadata = TranslationMetadata::default();
+            let id = db
+                .insert_translation(&text, "text", None, "en", "selected", &metadata)
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+        let filter = HistoryFilter::default();
+
+        // 时间戳相同的记录按 ID 区分，逐页取完不重复、不遗漏
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = db.get_history_after(cursor, 2, &filter).await.unwrap();
+            seen.extend(page.records.iter().map(|r| r.id));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        ids.reverse();
+        assert_eq!(seen, ids);
+
+        // 翻页期间写入的新记录不影响后续页
+        let first = db.get_history_after(None, 2, &filter).await.unwrap();
+        insert_text(&db, "新记录").await;
+        let second = db
+            .get_history_after(first.next_cursor, 2, &filter)
+            .await
+            .unwrap();
+        let second_ids: Vec<i64> = second.records.iter().map(|r| r.id).collect();
+        assert_eq!(second_ids, ids[2..4].to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_history_cursor_uses_index() {
+        let db = memory_db().await;
+        let rows = sqlx::query(
+            "EXPLAIN QUERY PLAN SELECT * FROM translations WHERE (timestamp, id) < (?, ?) ORDER BY timestamp DESC, id DESC LIMIT ?",
+        )
+        .bind(0)
+        .bind(0)
+        .bind(10)
+        .fetch_all(&db.pool())
+        .await
+        .unwrap();
+        let plan: Vec<String> = rows.iter().map(|r| r.get("detail")).collect();
+
+        // 直接按索引顺序读取，不需要临时排序
+        assert!(plan[0].contains("USING INDEX idx_translations_timestamp_id"));
+        assert!(!plan.iter().any(|step| step.contains("TEMP B-TREE")));
+    }
+
+    /// 插入一条指定时间的翻译记录
+    async fn insert_text_at(db: &Database, text: &str, timestamp: i64) -> i64 {
+        let metadata = TranslationMetadata::default();
+        let id = db
+            .insert_translation(text, text, None, "en", "selected", &metadata)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE translations SET timestamp = ? WHERE id = ?")
+            .bind(timestamp)
+            .bind(id)
+            .execute(&db.pool())
+            .await
+            .unwrap();
+        id
+    }
+
+    #[tokio::test]
+    async fn test_purge_history_before() {
+        let db = memory_db().await;
+        let cutoff = 1_700_000_000;
+        let before = insert_text_at(&db, "之前", cutoff - 1).await;
+        let pinned = insert_text_at(&db, "置顶", cutoff - 100).await;
+        let at_cutoff = insert_text_at(&db, "截止时刻", cutoff).await;
+        db.set_translation_pinned(pinned, true).await.unwrap();
+
+        // 截止时间本身的记录保留，置顶记录不删除
+        assert_eq!(db.purge_history_before(cutoff).await.unwrap(), 1);
+        let history = all_history(&db).await;
+        let remaining: Vec<i64> = history.records.iter().map(|r| r.id).collect();
+        assert_eq!(remaining, vec![at_cutoff, pinned]);
+        assert!(db.get_translation(before).await.unwrap().is_none());
+        assert_eq!(db.purge_history_before(cutoff).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_history_retention_with_limit() {
+        let db = memory_db().await;
+        let now = Utc::now().timestamp();
+        let expired = insert_text_at(&db, "过期", now - 40 * 86400).await;
+        let recent: Vec<i64> = [3, 2, 1].iter().map(|days| now - days * 86400).collect();
+        for timestamp in &recent {
+            insert_text_at(&db, "最近", *timestamp).await;
+        }
+
+        // 不限制天数时只按条数清理
+        assert_eq!(db.apply_history_retention(10, None).await.unwrap(), 0);
+        // 先按天数删除过期记录，再按条数保留最新的两条
+        assert_eq!(db.apply_history_retention(2, Some(30)).await.unwrap(), 2);
+        let history = all_history(&db).await;
+        let timestamps: Vec<i64> = history.records.iter().map(|r| r.timestamp).collect();
+        assert_eq!(timestamps, vec![recent[2], recent[1]]);
+        assert!(db.get_translation(expired).await.unwrap().is_none());
+    }
 }
@@ -1,6 +1,7 @@
 //! 错误处理模块
 //! 定义应用程序的统一错误类型
 
+use serde::Serialize;
 use thiserror::Error;
 
 /// 应用程序统一错误类型
@@ -14,6 +15,14 @@ pub enum AppError {
     #[error("LLM API 错误: {0}")]
     LlmApi(String),
 
+    /// LLM API 认证失败（401/403）
+    #[error("LLM API 认证失败: {0}")]
+    LlmAuth(String),
+
+    /// LLM API 请求过于频繁或额度不足（429）
+    #[error("LLM API 请求受限: {0}")]
+    LlmRateLimit(String),
+
     /// 网络请求错误
     #[error("网络请求失败: {0}")]
     Network(#[from] reqwest::Error),
@@ -42,6 +51,14 @@ pub enum AppError {
     #[error("IO 错误: {0}")]
     Io(#[from] std::io::Error),
 
+    /// 加密或解密错误
+    #[error("加密错误: {0}")]
+    Crypto(String),
+
+    /// 系统凭据存储（钥匙串、凭据管理器）错误
+    #[error("凭据存储错误: {0}")]
+    Keychain(String),
+
     /// 序列化错误
     #[error("序列化错误: {0}")]
     Serialization(#[from] serde_json::Error),
@@ -51,6 +68,130 @@ pub enum AppError {
     Other(String),
 }
 
+impl AppError {
+    /// 性能指标中记录的错误类型
+    pub fn metric_type(&self) -> &'static str {
+        match self {
+            AppError::Network(_) => "network",
+            AppError::LlmApi(_) | AppError::LlmAuth(_) | AppError::LlmRateLimit(_) => "api",
+            AppError::Config(_) => "config",
+            _ => "other",
+        }
+    }
+}
+
+/// 返回给前端的错误类型，前端据此决定提示方式（如跳转设置或重试）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    Config,
+    LlmApi,
+    LlmAuth,
+    LlmRateLimit,
+    Network,
+    Timeout,
+    Database,
+    Clipboard,
+    Hotkey,
+    Permission,
+    Keyboard,
+    Io,
+    Crypto,
+    Keychain,
+    Serialization,
+    /// 参数或配置未通过校验
+    Validation,
+    /// 请求的记录或资源不存在
+    NotFound,
+    /// 操作与当前状态冲突，如正在录制热键
+    Conflict,
+    Other,
+}
+
+/// Tauri 命令返回的结构化错误
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommandError {
+    pub code: ErrorCode,
+    /// 可直接展示给用户的错误信息
+    pub message: String,
+    /// 补充信息，如校验问题列表的 JSON
+    pub details: Option<String>,
+}
+
+impl CommandError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Validation, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn other(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Other, message)
+    }
+
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<&AppError> for ErrorCode {
+    fn from(error: &AppError) -> Self {
+        match error {
+            AppError::Config(_) => ErrorCode::Config,
+            AppError::LlmApi(_) => ErrorCode::LlmApi,
+            AppError::LlmAuth(_) => ErrorCode::LlmAuth,
+            AppError::LlmRateLimit(_) => ErrorCode::LlmRateLimit,
+            AppError::Network(e) if e.is_timeout() => ErrorCode::Timeout,
+            AppError::Network(_) => ErrorCode::Network,
+            AppError::Database(_) => ErrorCode::Database,
+            AppError::Clipboard(_) => ErrorCode::Clipboard,
+            AppError::Hotkey(_) => ErrorCode::Hotkey,
+            AppError::Permission(_) => ErrorCode::Permission,
+            AppError::Keyboard(_) => ErrorCode::Keyboard,
+            AppError::Io(_) => ErrorCode::Io,
+            AppError::Crypto(_) => ErrorCode::Crypto,
+            AppError::Keychain(_) => ErrorCode::Keychain,
+            AppError::Serialization(_) => ErrorCode::Serialization,
+            AppError::Other(_) => ErrorCode::Other,
+        }
+    }
+}
+
+impl From<AppError> for CommandError {
+    fn from(error: AppError) -> Self {
+        Self::new(ErrorCode::from(&error), error.to_string())
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(error: std::io::Error) -> Self {
+        AppError::from(error).into()
+    }
+}
+
+impl From<serde_json::Error> for CommandError {
+    fn from(error: serde_json::Error) -> Self {
+        AppError::from(error).into()
+    }
+}
+
 /// 将 AppError 转换为可序列化的字符串，用于 Tauri IPC
 impl From<AppError> for String {
     fn from(error: AppError) -> Self {
@@ -94,6 +235,78 @@ mod tests {
         assert_eq!(err.to_string(), "配置错误: test error");
     }
 
+    #[test]
+    fn test_error_codes() {
+        let cases = [
+            (AppError::Config(String::new()), ErrorCode::Config),
+            (AppError::LlmApi(String::new()), ErrorCode::LlmApi),
+            (AppError::LlmAuth(String::new()), ErrorCode::LlmAuth),
+            (
+                AppError::LlmRateLimit(String::new()),
+                ErrorCode::LlmRateLimit,
+            ),
+            (
+                AppError::Database(sqlx::Error::RowNotFound),
+                ErrorCode::Database,
+            ),
+            (AppError::Clipboard(String::new()), ErrorCode::Clipboard),
+            (AppError::Hotkey(String::new()), ErrorCode::Hotkey),
+            (AppError::Permission(String::new()), ErrorCode::Permission),
+            (AppError::Keyboard(String::new()), ErrorCode::Keyboard),
+            (AppError::Io(std::io::Error::other("io")), ErrorCode::Io),
+            (AppError::Crypto(String::new()), ErrorCode::Crypto),
+            (AppError::Keychain(String::new()), ErrorCode::Keychain),
+            (
+                AppError::Serialization(serde_json::from_str::<u8>("x").unwrap_err()),
+                ErrorCode::Serialization,
+            ),
+            (AppError::Other(String::new()), ErrorCode::Other),
+        ];
+        for (error, code) in cases {
+            assert_eq!(ErrorCode::from(&error), code, "{:?}", error);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_network_error_codes() {
+        // 端口上没有服务
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let error = AppError::from(reqwest::get(&url).await.unwrap_err());
+        assert_eq!(ErrorCode::from(&error), ErrorCode::Network);
+
+        // 服务接受连接但不响应
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let error = AppError::from(client.get(&url).send().await.unwrap_err());
+        assert_eq!(ErrorCode::from(&error), ErrorCode::Timeout);
+        drop(listener);
+    }
+
+    #[test]
+    fn test_command_error_serialization() {
+        let error = CommandError::from(AppError::Config("API Key 未配置".to_string()));
+        assert_eq!(error.message, "配置错误: API Key 未配置");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "code": "config",
+                "message": "配置错误: API Key 未配置",
+                "details": null,
+            })
+        );
+
+        let error = CommandError::validation("热键配置无效").with_details("[]");
+        assert_eq!(error.code, ErrorCode::Validation);
+        assert_eq!(error.details.as_deref(), Some("[]"));
+        assert_eq!(error.to_string(), "热键配置无效");
+    }
+
     #[test]
     fn test_error_to_string() {
         let err = AppError::LlmApi("API key invalid".to_string());
@@ -0,0 +1,176 @@
+//! 错误日志模块
+//! 在内存中保留最近的警告和错误日志，供诊断面板查看翻译等操作静默失败的原因
+
+use crate::database::sanitize_error_message;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// 最多保留的日志条数，超过后丢弃最旧的记录
+pub const ERROR_LOG_CAPACITY: usize = 200;
+
+/// 一条警告或错误日志
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorLogEntry {
+    /// 记录时间（Unix 时间戳）
+    pub timestamp: i64,
+    /// "WARN" 或 "ERROR"
+    pub level: String,
+    /// 产生日志的模块
+    pub target: String,
+    /// 日志内容，疑似 API Key 的部分已隐藏
+    pub message: String,
+}
+
+/// 最近日志的环形缓冲区，克隆后共享同一份数据
+#[derive(Debug, Clone)]
+pub struct ErrorLog {
+    entries: Arc<Mutex<VecDeque<ErrorLogEntry>>>,
+    capacity: usize,
+}
+
+impl ErrorLog {
+    /// 创建最多保留 `capacity` 条记录的缓冲区
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// 进程共享的错误日志，日志系统初始化时接入
+    pub fn global() -> &'static ErrorLog {
+        static ERROR_LOG: OnceLock<ErrorLog> = OnceLock::new();
+        ERROR_LOG.get_or_init(|| ErrorLog::new(ERROR_LOG_CAPACITY))
+    }
+
+    /// 将 WARN 和 ERROR 日志写入此缓冲区的 `tracing` Layer
+    pub fn layer(&self) -> ErrorLogLayer {
+        ErrorLogLayer { log: self.clone() }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<ErrorLogEntry>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// 追加一条记录，已满时丢弃最旧的记录
+    pub fn push(&self, entry: ErrorLogEntry) {
+        let mut entries = self.lock();
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// 最近的 `limit` 条记录，从新到旧
+    pub fn recent(&self, limit: usize) -> Vec<ErrorLogEntry> {
+        self.lock().iter().rev().take(limit).cloned().collect()
+    }
+
+    /// 清空所有记录
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+}
+
+/// 收集 WARN 和 ERROR 日志的 `tracing` Layer
+pub struct ErrorLogLayer {
+    log: ErrorLog,
+}
+
+impl<S: Subscriber> Layer<S> for ErrorLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let level = *event.metadata().level();
+        if level > Level::WARN {
+            return;
+        }
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        self.log.push(ErrorLogEntry {
+            timestamp: chrono::Utc::now().timestamp(),
+            level: level.to_string(),
+            target: event.metadata().target().to_string(),
+            message: sanitize_error_message(&visitor.message),
+        });
+    }
+}
+
+/// 拼接日志的 message 和其他字段
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.insert_str(0, value);
+        } else {
+            let _ = write!(self.message, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message.insert_str(0, &format!("{:?}", value));
+        } else {
+            let _ = write!(self.message, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn entry(message: &str) -> ErrorLogEntry {
+        ErrorLogEntry {
+            timestamp: 0,
+            level: "WARN".to_string(),
+            target: "test".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let log = ErrorLog::new(3);
+        for message in ["一", "二", "三", "四"] {
+            log.push(entry(message));
+        }
+
+        let messages: Vec<String> = log.recent(10).into_iter().map(|e| e.message).collect();
+        assert_eq!(messages, ["四", "三", "二"]);
+        assert_eq!(log.recent(1)[0].message, "四");
+
+        log.clear();
+        assert!(log.recent(10).is_empty());
+    }
+
+    #[test]
+    fn test_layer_captures_warnings_and_redacts_keys() {
+        let log = ErrorLog::new(10);
+        let subscriber = tracing_subscriber::registry().with(log.layer());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("not captured");
+            tracing::warn!("Failed to copy: {}", "clipboard busy");
+            tracing::error!(status = 401, "Invalid API key sk-proj-abc123");
+        });
+
+        let entries = log.recent(10);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].level, "ERROR");
+        assert_eq!(entries[0].message, "Invalid API key [REDACTED] status=401");
+        assert_eq!(entries[1].level, "WARN");
+        assert_eq!(entries[1].message, "Failed to copy: clipboard busy");
+        assert!(entries[1].target.contains("error_log"));
+    }
+}
@@ -1,17 +1,526 @@
 //! 热键模块
 //! 处理全局热键监听和冲突检测
 
-use crate::config::Hotkey;
+use crate::config::{
+    is_dedicated_trigger_key, Hotkey, HotkeyConfig, DEFAULT_CONSECUTIVE_INTERVAL_MS,
+};
 use crate::error::{AppError, Result};
-use std::path::PathBuf;
-use std::sync::Arc;
+use crate::key_listener::{ChordState, PendingChord};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+use tauri_plugin_global_shortcut::Code;
 use tokio::sync::RwLock;
-use tracing::debug;
+use tracing::{debug, warn};
+
+/// 系统热键：(名称, 修饰键, 按键)
+type SystemHotkey = (String, Vec<String>, String);
+
+/// 系统热键配置的解析缓存，按文件修改时间失效
+struct SystemHotkeyCache {
+    path: PathBuf,
+    modified: SystemTime,
+    hotkeys: Vec<SystemHotkey>,
+}
+
+static SYSTEM_HOTKEY_CACHE: Mutex<Option<SystemHotkeyCache>> = Mutex::new(None);
+
+/// macOS 虚拟键码（kVK_*）与按键名称的对照表（ANSI 布局）
+///
+/// 同时用于解析系统快捷键和校验配置中的按键名称。
+pub const MAC_KEYCODES: &[(u32, &str)] = &[
+    (0, "a"),
+    (1, "s"),
+    (2, "d"),
+    (3, "f"),
+    (4, "h"),
+    (5, "g"),
+    (6, "z"),
+    (7, "x"),
+    (8, "c"),
+    (9, "v"),
+    (10, "§"),
+    (11, "b"),
+    (12, "q"),
+    (13, "w"),
+    (14, "e"),
+    (15, "r"),
+    (16, "y"),
+    (17, "t"),
+    (18, "1"),
+    (19, "2"),
+    (20, "3"),
+    (21, "4"),
+    (22, "6"),
+    (23, "5"),
+    (24, "="),
+    (25, "9"),
+    (26, "7"),
+    (27, "-"),
+    (28, "8"),
+    (29, "0"),
+    (30, "]"),
+    (31, "o"),
+    (32, "u"),
+    (33, "["),
+    (34, "i"),
+    (35, "p"),
+    (36, "Return"),
+    (37, "l"),
+    (38, "j"),
+    (39, "'"),
+    (40, "k"),
+    (41, ";"),
+    (42, "\\"),
+    (43, ","),
+    (44, "/"),
+    (45, "n"),
+    (46, "m"),
+    (47, "."),
+    (48, "Tab"),
+    (49, "Space"),
+    (50, "`"),
+    (51, "Backspace"),
+    (53, "Escape"),
+    (55, "Meta"),
+    (56, "Shift"),
+    (57, "CapsLock"),
+    (58, "Alt"),
+    (59, "Control"),
+    (60, "ShiftRight"),
+    (61, "AltRight"),
+    (62, "ControlRight"),
+    (63, "Fn"),
+    (64, "F17"),
+    (65, "Keypad."),
+    (67, "Keypad*"),
+    (69, "Keypad+"),
+    (71, "KeypadClear"),
+    (72, "VolumeUp"),
+    (73, "VolumeDown"),
+    (74, "Mute"),
+    (75, "Keypad/"),
+    (76, "KeypadEnter"),
+    (78, "Keypad-"),
+    (79, "F18"),
+    (80, "F19"),
+    (81, "Keypad="),
+    (82, "Keypad0"),
+    (83, "Keypad1"),
+    (84, "Keypad2"),
+    (85, "Keypad3"),
+    (86, "Keypad4"),
+    (87, "Keypad5"),
+    (88, "Keypad6"),
+    (89, "Keypad7"),
+    (90, "F20"),
+    (91, "Keypad8"),
+    (92, "Keypad9"),
+    (96, "F5"),
+    (97, "F6"),
+    (98, "F7"),
+    (99, "F3"),
+    (100, "F8"),
+    (101, "F9"),
+    (103, "F11"),
+    (105, "F13"),
+    (106, "F16"),
+    (107, "F14"),
+    (109, "F10"),
+    (111, "F12"),
+    (113, "F15"),
+    (114, "Help"),
+    (115, "Home"),
+    (116, "PageUp"),
+    (117, "Delete"),
+    (118, "F4"),
+    (119, "End"),
+    (120, "F2"),
+    (121, "PageDown"),
+    (122, "F1"),
+    (123, "Left"),
+    (124, "Right"),
+    (125, "Down"),
+    (126, "Up"),
+];
+
+/// 将按键名称规范化，使不同写法的同一按键可以比较
+///
+/// 配置、rdev 和系统快捷键各自使用不同的按键名称（如 " "、"space"、"Space"），
+/// 小键盘数字视为与主键盘数字相同。
+pub fn normalize_key_name(key: &str) -> String {
+    if key == " " {
+        return "space".to_string();
+    }
+
+    let lower = key.trim().to_lowercase();
+    let canonical = match lower.as_str() {
+        "space" | "spacebar" => "space",
+        "return" | "enter" | "keypadenter" | "numpadenter" => "return",
+        "esc" | "escape" => "escape",
+        "backspace" => "backspace",
+        "delete" | "del" | "forwarddelete" => "delete",
+        "up" | "arrowup" | "uparrow" => "up",
+        "down" | "arrowdown" | "downarrow" => "down",
+        "left" | "arrowleft" | "leftarrow" => "left",
+        "right" | "arrowright" | "rightarrow" => "right",
+        "pageup" | "pgup" => "pageup",
+        "pagedown" | "pgdn" => "pagedown",
+        "home" => "home",
+        "end" => "end",
+        // Mac 键盘在 Insert 的位置上是 Help 键
+        "insert" | "ins" | "help" => "insert",
+        "capslock" | "caps" => "capslock",
+        "fn" | "function" => "fn",
+        // rdev 和 KeyboardEvent.code 对标点键使用英文名称
+        "dot" | "period" => ".",
+        "comma" => ",",
+        "minus" => "-",
+        "equal" => "=",
+        "semicolon" => ";",
+        "quote" => "'",
+        "slash" => "/",
+        "backslash" => "\\",
+        "leftbracket" | "bracketleft" => "[",
+        "rightbracket" | "bracketright" => "]",
+        "backquote" => "`",
+        other => {
+            // 小键盘数字（Keypad1 / Numpad1 / Num1 / Kp1）和 KeyboardEvent.code 格式（Digit1 / KeyA）
+            for prefix in ["keypad", "numpad", "num", "kp", "digit", "key"] {
+                if let Some(rest) = other.strip_prefix(prefix) {
+                    if rest.len() == 1 && rest.chars().all(|c| c.is_ascii_alphanumeric()) {
+                        return rest.to_string();
+                    }
+                }
+            }
+            other
+        }
+    };
+
+    canonical.to_string()
+}
+
+/// 根据按键名称查找 macOS 虚拟键码
+///
+/// 优先按名称精确匹配（不区分大小写），以区分小键盘数字等规范化后相同的按键；
+/// 找不到时再按规范化名称匹配。
+pub fn keycode_from_name(name: &str) -> Option<u32> {
+    MAC_KEYCODES
+        .iter()
+        .find(|(_, key)| key.eq_ignore_ascii_case(name))
+        .or_else(|| {
+            let normalized = normalize_key_name(name);
+            MAC_KEYCODES
+                .iter()
+                .find(|(_, key)| normalize_key_name(key) == normalized)
+        })
+        .map(|(code, _)| *code)
+}
+
+/// 将按键名称转换为全局快捷键插件的按键码
+///
+/// 插件在 macOS 上按虚拟键码注册，这里经由 [`MAC_KEYCODES`] 转换；
+/// 插件不支持的按键（如 Fn、修饰键）返回 `None`。
+pub fn shortcut_code(key: &str) -> Option<Code> {
+    let code = match keycode_from_name(key)? {
+        0 => Code::KeyA,
+        1 => Code::KeyS,
+        2 => Code::KeyD,
+        3 => Code::KeyF,
+        4 => Code::KeyH,
+        5 => Code::KeyG,
+        6 => Code::KeyZ,
+        7 => Code::KeyX,
+        8 => Code::KeyC,
+        9 => Code::KeyV,
+        11 => Code::KeyB,
+        12 => Code::KeyQ,
+        13 => Code::KeyW,
+        14 => Code::KeyE,
+        15 => Code::KeyR,
+        16 => Code::KeyY,
+        17 => Code::KeyT,
+        18 => Code::Digit1,
+        19 => Code::Digit2,
+        20 => Code::Digit3,
+        21 => Code::Digit4,
+        22 => Code::Digit6,
+        23 => Code::Digit5,
+        24 => Code::Equal,
+        25 => Code::Digit9,
+        26 => Code::Digit7,
+        27 => Code::Minus,
+        28 => Code::Digit8,
+        29 => Code::Digit0,
+        30 => Code::BracketRight,
+        31 => Code::KeyO,
+        32 => Code::KeyU,
+        33 => Code::BracketLeft,
+        34 => Code::KeyI,
+        35 => Code::KeyP,
+        36 => Code::Enter,
+        37 => Code::KeyL,
+        38 => Code::KeyJ,
+        39 => Code::Quote,
+        40 => Code::KeyK,
+        41 => Code::Semicolon,
+        42 => Code::Backslash,
+        43 => Code::Comma,
+        44 => Code::Slash,
+        45 => Code::KeyN,
+        46 => Code::KeyM,
+        47 => Code::Period,
+        48 => Code::Tab,
+        49 => Code::Space,
+        50 => Code::Backquote,
+        51 => Code::Backspace,
+        53 => Code::Escape,
+        57 => Code::CapsLock,
+        64 => Code::F17,
+        65 => Code::NumpadDecimal,
+        67 => Code::NumpadMultiply,
+        69 => Code::NumpadAdd,
+        71 => Code::NumLock,
+        72 => Code::AudioVolumeUp,
+        73 => Code::AudioVolumeDown,
+        74 => Code::AudioVolumeMute,
+        75 => Code::NumpadDivide,
+        76 => Code::NumpadEnter,
+        78 => Code::NumpadSubtract,
+        79 => Code::F18,
+        80 => Code::F19,
+        81 => Code::NumpadEqual,
+        82 => Code::Numpad0,
+        83 => Code::Numpad1,
+        84 => Code::Numpad2,
+        85 => Code::Numpad3,
+        86 => Code::Numpad4,
+        87 => Code::Numpad5,
+        88 => Code::Numpad6,
+        89 => Code::Numpad7,
+        90 => Code::F20,
+        91 => Code::Numpad8,
+        92 => Code::Numpad9,
+        96 => Code::F5,
+        97 => Code::F6,
+        98 => Code::F7,
+        99 => Code::F3,
+        100 => Code::F8,
+        101 => Code::F9,
+        103 => Code::F11,
+        105 => Code::F13,
+        106 => Code::F16,
+        107 => Code::F14,
+        109 => Code::F10,
+        111 => Code::F12,
+        113 => Code::F15,
+        114 => Code::Insert,
+        115 => Code::Home,
+        116 => Code::PageUp,
+        117 => Code::Delete,
+        118 => Code::F4,
+        119 => Code::End,
+        120 => Code::F2,
+        121 => Code::PageDown,
+        122 => Code::F1,
+        123 => Code::ArrowLeft,
+        124 => Code::ArrowRight,
+        125 => Code::ArrowDown,
+        126 => Code::ArrowUp,
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// 热键触发去抖
+///
+/// 部分环境下全局快捷键会在短时间内重复触发（按键重复或重复的按下事件），
+/// 按模式记录最近一次触发时间，窗口内的重复触发被忽略。被忽略的触发同样刷新
+/// 记录时间，按住按键产生的连续重复不会在窗口过后再次触发。
+#[derive(Debug, Default)]
+pub struct TriggerDebouncer {
+    last_triggers: HashMap<String, Instant>,
+}
+
+impl TriggerDebouncer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次触发，返回是否应当响应
+    pub fn accept(&mut self, mode: &str, now: Instant, window: Duration) -> bool {
+        let previous = self.last_triggers.insert(mode.to_string(), now);
+        match previous {
+            Some(previous) => now.saturating_duration_since(previous) >= window,
+            None => true,
+        }
+    }
+}
 
 /// 热键管理器
 pub struct HotkeyManager {
     /// 连续按键检测器状态
     consecutive_state: Arc<RwLock<ConsecutiveState>>,
+    /// 两步组合键等待第二步按键的状态，与 rdev 监听线程共享
+    pending_chord: ChordState,
+}
+
+/// 内置的常见应用快捷键（浏览器、VS Code、JetBrains、Office、Slack 等）
+const APP_SHORTCUTS_JSON: &str = include_str!("data/app_shortcuts.json");
+
+/// 常见应用的快捷键
+#[derive(Debug, Clone, Deserialize)]
+struct AppShortcut {
+    /// 应用名称
+    app: String,
+    /// 快捷键的功能
+    action: String,
+    /// 修饰键列表，"Primary" 在 macOS 上为 Meta，其他平台为 Control
+    modifiers: Vec<String>,
+    /// 主键
+    key: String,
+    /// 仅在该平台上生效（"macos"、"windows"、"linux"），未设置时适用于所有平台
+    #[serde(default)]
+    platform: Option<String>,
+}
+
+/// 平台的主修饰键：macOS 为 Cmd，其他平台为 Ctrl
+const PRIMARY_MODIFIER: &str = if cfg!(target_os = "macos") {
+    "Meta"
+} else {
+    "Control"
+};
+
+/// 加载当前平台适用的常见应用快捷键，"Primary" 替换为平台的主修饰键
+fn app_shortcuts() -> &'static [AppShortcut] {
+    static SHORTCUTS: OnceLock<Vec<AppShortcut>> = OnceLock::new();
+    SHORTCUTS.get_or_init(|| {
+        let shortcuts: Vec<AppShortcut> = match serde_json::from_str(APP_SHORTCUTS_JSON) {
+            Ok(shortcuts) => shortcuts,
+            Err(e) => {
+                warn!("Failed to parse bundled app shortcuts: {}", e);
+                return Vec::new();
+            }
+        };
+
+        shortcuts
+            .into_iter()
+            .filter(|shortcut| {
+                shortcut
+                    .platform
+                    .as_deref()
+                    .is_none_or(|platform| platform == std::env::consts::OS)
+            })
+            .map(|mut shortcut| {
+                for modifier in &mut shortcut.modifiers {
+                    if modifier == "Primary" {
+                        *modifier = PRIMARY_MODIFIER.to_string();
+                    }
+                }
+                shortcut
+            })
+            .collect()
+    })
+}
+
+/// 热键与外部快捷键的冲突检查结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ConflictReport {
+    /// 与系统快捷键的冲突
+    pub system: Vec<String>,
+    /// 可能与常见应用快捷键的冲突，只作为提示
+    pub app: Vec<String>,
+}
+
+/// 应用内部的热键冲突（两个绑定使用了相同的触发方式）
+#[derive(Debug, Clone, Serialize)]
+pub struct HotkeyConflict {
+    /// 第一个绑定的标识，如 "selected_mode"
+    pub first: String,
+    /// 第二个绑定的标识
+    pub second: String,
+    /// 冲突描述
+    pub message: String,
+}
+
+/// 热键校验问题的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyIssueCode {
+    /// 按键设置不合法（如连续次数不足、修饰键作为主键）
+    InvalidKey,
+    /// 全局快捷键插件不支持该按键
+    UnsupportedKey,
+    /// 组合键缺少必需的修饰键
+    ModifierRequired,
+    /// 与应用内其他热键的触发方式相同
+    InternalConflict,
+    /// 与系统快捷键冲突
+    SystemConflict,
+    /// 可能与常见应用的快捷键冲突
+    AppConflict,
+    /// 使用大写锁定键触发，触发时仍会切换大小写状态
+    CapsLockToggle,
+}
+
+impl HotkeyIssueCode {
+    /// 是否阻止保存配置；系统和应用快捷键冲突、大写锁定提示不阻止保存
+    pub fn is_blocking(self) -> bool {
+        !matches!(
+            self,
+            HotkeyIssueCode::SystemConflict
+                | HotkeyIssueCode::AppConflict
+                | HotkeyIssueCode::CapsLockToggle
+        )
+    }
+}
+
+/// 热键校验发现的问题
+#[derive(Debug, Clone, Serialize)]
+pub struct HotkeyIssue {
+    /// 问题所属绑定的标识，如 "selected_mode"
+    pub binding: String,
+    /// 问题类型
+    pub code: HotkeyIssueCode,
+    /// 是否阻止保存配置
+    pub blocking: bool,
+    /// 问题描述
+    pub message: String,
+    /// 内部冲突时，冲突的另一个绑定
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflicts_with: Option<String>,
+}
+
+impl HotkeyIssue {
+    /// 创建单个绑定的问题
+    pub fn new(binding: &str, code: HotkeyIssueCode, message: String) -> Self {
+        Self {
+            binding: binding.to_string(),
+            code,
+            blocking: code.is_blocking(),
+            message,
+            conflicts_with: None,
+        }
+    }
+
+    /// 是否涉及指定的绑定
+    pub fn involves(&self, binding: &str) -> bool {
+        self.binding == binding || self.conflicts_with.as_deref() == Some(binding)
+    }
+}
+
+impl From<HotkeyConflict> for HotkeyIssue {
+    fn from(conflict: HotkeyConflict) -> Self {
+        let mut issue = Self::new(
+            &conflict.first,
+            HotkeyIssueCode::InternalConflict,
+            conflict.message,
+        );
+        issue.conflicts_with = Some(conflict.second);
+        issue
+    }
 }
 
 /// 连续按键检测状态
@@ -39,18 +548,45 @@ impl HotkeyManager {
     pub fn new() -> Self {
         Self {
             consecutive_state: Arc::new(RwLock::new(ConsecutiveState::default())),
+            pending_chord: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// 两步组合键的第一步已按下，在超时前等待第二步按键
+    /// 同一时间只等待一个两步组合键，后按下的第一步覆盖之前的等待
+    pub fn begin_chord(&self, mode: &str, timeout: Duration) {
+        debug!("Waiting {:?} for {} mode chord", timeout, mode);
+        *self
+            .pending_chord
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(PendingChord {
+            mode: mode.to_string(),
+            deadline: Instant::now() + timeout,
+        });
+    }
+
+    /// 获取两步组合键的等待状态，用于 rdev 监听器检测第二步按键
+    pub fn chord_state(&self) -> ChordState {
+        self.pending_chord.clone()
+    }
+
     /// 检查按键是否触发连续按键热键
     pub async fn check_consecutive(&self, key: &str, target: &Hotkey) -> bool {
-        if let Hotkey::Consecutive { key: target_key, count: target_count } = target {
+        if let Hotkey::Consecutive {
+            key: target_key,
+            count: target_count,
+            ..
+        } = target
+        {
+            let interval_ms = target
+                .consecutive_interval_ms()
+                .unwrap_or(DEFAULT_CONSECUTIVE_INTERVAL_MS);
             let mut state = self.consecutive_state.write().await;
             let now = std::time::Instant::now();
             
-            // 检查是否是同一个键且在 500ms 内
+            // 检查是否是同一个键且在配置的间隔内
             if Some(key.to_string()) == state.last_key 
-                && now.duration_since(state.last_time).as_millis() < 500 
+                && now.duration_since(state.last_time).as_millis() < interval_ms as u128
             {
                 state.count += 1;
                 state.last_time = now;
@@ -82,7 +618,20 @@ impl HotkeyManager {
     pub fn check_system_conflicts(hotkey: &Hotkey) -> Vec<String> {
         let mut conflicts = Vec::new();
 
+        // 两步组合键只有第一步会被系统识别
+        if let Hotkey::Chord { first, .. } = hotkey {
+            return Self::check_system_conflicts(&first.to_hotkey());
+        }
+
+        // F13–F19 没有系统默认用途，单独使用时视为无冲突
         if let Hotkey::Combination { modifiers, key } = hotkey {
+            if modifiers.is_empty() && is_dedicated_trigger_key(key) {
+                return conflicts;
+            }
+        }
+
+        if let Hotkey::Combination { modifiers, key } | Hotkey::Hold { modifiers, key, .. } = hotkey
+        {
             // 读取 macOS 系统快捷键配置
             let system_hotkeys = Self::get_system_hotkeys();
 
@@ -93,19 +642,79 @@ impl HotkeyManager {
             }
         }
 
+        // 左键和右键用于正常点击，作为触发方式会影响日常操作
+        if let Hotkey::MouseButton { button, .. } = hotkey {
+            match button {
+                1 => conflicts.push("鼠标左键（正常点击）".to_string()),
+                2 => conflicts.push("鼠标右键（右键菜单）".to_string()),
+                _ => {}
+            }
+        }
+
+        conflicts
+    }
+
+    /// 检测热键是否可能与常见应用的快捷键冲突
+    /// 这些快捷键只在对应应用获得焦点时生效，返回的冲突只作为提示
+    pub fn check_app_conflicts(hotkey: &Hotkey) -> Vec<String> {
+        let (modifiers, key) = match hotkey {
+            Hotkey::Combination { modifiers, key } | Hotkey::Hold { modifiers, key, .. } => {
+                (modifiers, key)
+            }
+            Hotkey::Chord { first, .. } => (&first.modifiers, &first.key),
+            Hotkey::Consecutive { .. } | Hotkey::MouseButton { .. } => return Vec::new(),
+        };
+
+        app_shortcuts()
+            .iter()
+            .filter(|shortcut| {
+                Self::hotkeys_match(modifiers, key, &shortcut.modifiers, &shortcut.key)
+            })
+            .map(|shortcut| format!("可能与 {} 的 {} 冲突", shortcut.app, shortcut.action))
+            .collect()
+    }
+
+    /// 同时检测系统快捷键和常见应用快捷键冲突
+    pub fn check_conflicts(hotkey: &Hotkey) -> ConflictReport {
+        ConflictReport {
+            system: Self::check_system_conflicts(hotkey),
+            app: Self::check_app_conflicts(hotkey),
+        }
+    }
+
+    /// 检测应用内部各热键之间的冲突
+    pub fn check_internal_conflicts(config: &HotkeyConfig) -> Vec<HotkeyConflict> {
+        let bindings = config.bindings();
+        let mut conflicts = Vec::new();
+
+        for (i, (id1, label1, hotkey1)) in bindings.iter().enumerate() {
+            for (id2, label2, hotkey2) in &bindings[i + 1..] {
+                if hotkey1.same_trigger(hotkey2) {
+                    conflicts.push(HotkeyConflict {
+                        first: id1.to_string(),
+                        second: id2.to_string(),
+                        message: format!(
+                            "{}和{}不能使用相同的触发方式: {}",
+                            label1,
+                            label2,
+                            hotkey1.format()
+                        ),
+                    });
+                }
+            }
+        }
+
         conflicts
     }
 
     /// 获取系统热键列表
-    fn get_system_hotkeys() -> Vec<(String, Vec<String>, String)> {
+    fn get_system_hotkeys() -> Vec<SystemHotkey> {
         let mut hotkeys = Vec::new();
 
         // 尝试读取系统配置
         if let Some(home) = dirs::home_dir() {
             let plist_path = home.join("Library/Preferences/com.apple.symbolichotkeys.plist");
-            if let Ok(parsed_hotkeys) = Self::parse_symbolic_hotkeys(&plist_path) {
-                hotkeys.extend(parsed_hotkeys);
-            }
+            hotkeys.extend(Self::cached_symbolic_hotkeys(&plist_path));
         }
 
         // 添加常见的系统热键
@@ -120,8 +729,41 @@ impl HotkeyManager {
         hotkeys
     }
 
+    /// 读取系统热键配置文件，文件未修改时直接使用缓存的解析结果
+    fn cached_symbolic_hotkeys(path: &Path) -> Vec<SystemHotkey> {
+        let Ok(modified) = std::fs::metadata(path).and_then(|m| m.modified()) else {
+            return Vec::new();
+        };
+
+        let mut cache = SYSTEM_HOTKEY_CACHE
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if let Some(cached) = cache.as_ref() {
+            if cached.path == path && cached.modified == modified {
+                return cached.hotkeys.clone();
+            }
+        }
+
+        let hotkeys = match Self::parse_symbolic_hotkeys(path) {
+            Ok(hotkeys) => hotkeys,
+            Err(e) => {
+                debug!("Failed to parse system hotkeys: {}", e);
+                Vec::new()
+            }
+        };
+
+        *cache = Some(SystemHotkeyCache {
+            path: path.to_path_buf(),
+            modified,
+            hotkeys: hotkeys.clone(),
+        });
+
+        hotkeys
+    }
+
     /// 解析系统热键配置文件
-    fn parse_symbolic_hotkeys(path: &PathBuf) -> Result<Vec<(String, Vec<String>, String)>> {
+    fn parse_symbolic_hotkeys(path: &Path) -> Result<Vec<SystemHotkey>> {
         let file = std::fs::File::open(path)?;
         let plist: plist::Value = plist::from_reader(file)
             .map_err(|e| AppError::Config(format!("解析 plist 失败: {}", e)))?;
@@ -145,7 +787,7 @@ impl HotkeyManager {
     }
 
     /// 解析单个热键条目
-    fn parse_hotkey_entry(id: &str, value: &plist::Value) -> Option<(String, Vec<String>, String)> {
+    fn parse_hotkey_entry(id: &str, value: &plist::Value) -> Option<SystemHotkey> {
         let dict = value.as_dictionary()?;
         
         // 检查是否启用
@@ -178,38 +820,23 @@ impl HotkeyManager {
         Some((name, modifiers, key))
     }
 
-    /// 将 keycode 转换为字符串
+    /// 将 macOS 虚拟键码（kVK_*）转换为按键名称
     fn keycode_to_string(keycode: u32) -> String {
-        match keycode {
-            49 => " ".to_string(),    // Space
-            36 => "Return".to_string(),
-            48 => "Tab".to_string(),
-            51 => "Delete".to_string(),
-            53 => "Escape".to_string(),
-            0 => "a".to_string(),
-            1 => "s".to_string(),
-            2 => "d".to_string(),
-            3 => "f".to_string(),
-            4 => "h".to_string(),
-            5 => "g".to_string(),
-            6 => "z".to_string(),
-            7 => "x".to_string(),
-            8 => "c".to_string(),
-            9 => "v".to_string(),
-            11 => "b".to_string(),
-            12 => "q".to_string(),
-            13 => "w".to_string(),
-            14 => "e".to_string(),
-            15 => "r".to_string(),
-            16 => "y".to_string(),
-            17 => "t".to_string(),
-            _ => format!("key_{}", keycode),
-        }
+        MAC_KEYCODES
+            .iter()
+            .find(|(code, _)| *code == keycode)
+            .map(|(_, name)| name.to_string())
+            .unwrap_or_else(|| format!("key_{}", keycode))
     }
 
     /// 比较两个热键是否匹配
-    fn hotkeys_match(mods1: &[String], key1: &str, mods2: &[String], key2: &str) -> bool {
-        if key1.to_lowercase() != key2.to_lowercase() {
+    pub(crate) fn hotkeys_match(
+        mods1: &[String],
+        key1: &str,
+        mods2: &[String],
+        key2: &str,
+    ) -> bool {
+        if normalize_key_name(key1) != normalize_key_name(key2) {
             return false;
         }
 
@@ -232,6 +859,68 @@ impl Default for HotkeyManager {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_app_shortcuts_parse() {
+        let shortcuts = app_shortcuts();
+        assert!(!shortcuts.is_empty());
+        assert!(shortcuts
+            .iter()
+            .all(|shortcut| !shortcut.modifiers.iter().any(|m| m == "Primary")));
+    }
+
+    #[test]
+    fn test_check_app_conflicts() {
+        let insert_link = Hotkey::Combination {
+            modifiers: vec![PRIMARY_MODIFIER.to_string()],
+            key: "K".to_string(),
+        };
+        let conflicts = HotkeyManager::check_app_conflicts(&insert_link);
+        assert!(conflicts.contains(&"可能与 Office 的 插入链接 冲突".to_string()));
+
+        // 两步组合键按第一步检查
+        let chord = Hotkey::Chord {
+            first: crate::config::KeyCombination {
+                modifiers: vec![PRIMARY_MODIFIER.to_string()],
+                key: "k".to_string(),
+            },
+            second_key: "l".to_string(),
+            timeout_ms: 1000,
+        };
+        assert_eq!(HotkeyManager::check_app_conflicts(&chord), conflicts);
+
+        let uncommon = Hotkey::Combination {
+            modifiers: vec![
+                "Control".to_string(),
+                "Alt".to_string(),
+                "Shift".to_string(),
+            ],
+            key: "F9".to_string(),
+        };
+        assert!(HotkeyManager::check_app_conflicts(&uncommon).is_empty());
+
+        let report = HotkeyManager::check_conflicts(&insert_link);
+        assert_eq!(report.app, conflicts);
+    }
+
+    #[test]
+    fn test_trigger_debouncer() {
+        let mut debouncer = TriggerDebouncer::new();
+        let window = Duration::from_millis(400);
+        let start = Instant::now();
+
+        assert!(debouncer.accept("selected", start, window));
+        assert!(!debouncer.accept("selected", start + Duration::from_millis(50), window));
+        // 不同模式互不影响
+        assert!(debouncer.accept("full", start + Duration::from_millis(60), window));
+        // 被忽略的触发会刷新记录时间
+        assert!(!debouncer.accept("selected", start + Duration::from_millis(420), window));
+        assert!(debouncer.accept("selected", start + Duration::from_millis(900), window));
+
+        // 窗口为 0 时不去抖
+        let zero = Duration::ZERO;
+        assert!(debouncer.accept("full", start + Duration::from_millis(61), zero));
+    }
+
     #[test]
     fn test_hotkeys_match() {
         assert!(HotkeyManager::hotkeys_match(
@@ -249,12 +938,123 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_normalize_key_name() {
+        assert_eq!(normalize_key_name(" "), "space");
+        assert_eq!(normalize_key_name("Space"), "space");
+        assert_eq!(normalize_key_name("enter"), "return");
+        assert_eq!(normalize_key_name("Keypad1"), "1");
+        assert_eq!(normalize_key_name("Digit1"), "1");
+        assert_eq!(normalize_key_name("KeyK"), "k");
+        assert_eq!(normalize_key_name("F12"), "f12");
+
+        assert!(HotkeyManager::hotkeys_match(
+            &["Meta".to_string()],
+            "space",
+            &["Meta".to_string()],
+            " "
+        ));
+    }
+
+    #[test]
+    fn test_keycode_to_string() {
+        assert_eq!(HotkeyManager::keycode_to_string(49), "Space");
+        assert_eq!(HotkeyManager::keycode_to_string(18), "1");
+        assert_eq!(HotkeyManager::keycode_to_string(83), "Keypad1");
+        assert_eq!(HotkeyManager::keycode_to_string(122), "F1");
+        assert_eq!(HotkeyManager::keycode_to_string(126), "Up");
+        assert_eq!(HotkeyManager::keycode_to_string(999), "key_999");
+    }
+
+    #[test]
+    fn test_keycode_table_round_trip() {
+        for (code, name) in MAC_KEYCODES {
+            assert_eq!(HotkeyManager::keycode_to_string(*code), *name);
+            assert_eq!(keycode_from_name(name), Some(*code), "{}", name);
+        }
+
+        assert_eq!(keycode_from_name(" "), Some(49));
+        assert_eq!(keycode_from_name("Enter"), Some(36));
+        assert_eq!(keycode_from_name("T"), Some(17));
+        assert_eq!(keycode_from_name("Foo"), None);
+    }
+
+    #[test]
+    fn test_shortcut_code() {
+        assert_eq!(shortcut_code("k"), Some(Code::KeyK));
+        assert_eq!(shortcut_code(" "), Some(Code::Space));
+        assert_eq!(shortcut_code("F5"), Some(Code::F5));
+        assert_eq!(shortcut_code("Keypad1"), Some(Code::Numpad1));
+        assert_eq!(shortcut_code("Dot"), Some(Code::Period));
+        assert_eq!(shortcut_code("Fn"), None);
+        assert_eq!(shortcut_code("Foo"), None);
+    }
+
+    #[test]
+    fn test_parse_symbolic_hotkeys_fixture() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/symbolichotkeys.plist");
+        let hotkeys = HotkeyManager::parse_symbolic_hotkeys(&path).unwrap();
+
+        // 28 = Cmd+Shift+3，30 = Cmd+Shift+4，64 = Spotlight；60 已禁用
+        assert_eq!(hotkeys.len(), 3);
+        let detected = |mods: &[&str], key: &str| {
+            let mods: Vec<String> = mods.iter().map(|m| m.to_string()).collect();
+            hotkeys.iter().any(|(_, sys_mods, sys_key)| {
+                HotkeyManager::hotkeys_match(&mods, key, sys_mods, sys_key)
+            })
+        };
+        assert!(detected(&["Meta", "Shift"], "4"));
+        assert!(detected(&["Meta", "Shift"], "3"));
+        assert!(detected(&["Meta"], " "));
+        assert!(!detected(&["Control"], " "));
+    }
+
+    #[test]
+    fn test_cached_symbolic_hotkeys() {
+        let path = std::env::temp_dir().join(format!(
+            "quicktranstype-symbolichotkeys-{}.plist",
+            std::process::id()
+        ));
+        // 64 = Spotlight，Cmd + Space
+        let plist = r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>AppleSymbolicHotKeys</key>
+    <dict>
+        <key>64</key>
+        <dict>
+            <key>enabled</key><true/>
+            <key>value</key>
+            <dict>
+                <key>parameters</key>
+                <array><integer>32</integer><integer>49</integer><integer>1048576</integer></array>
+            </dict>
+        </dict>
+    </dict>
+</dict>
+</plist>"#;
+        std::fs::write(&path, plist).unwrap();
+
+        let first = HotkeyManager::cached_symbolic_hotkeys(&path);
+        let second = HotkeyManager::cached_symbolic_hotkeys(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].1, vec!["Meta".to_string()]);
+        assert_eq!(first[0].2, "Space");
+
+        // 文件不存在时返回空列表
+        assert!(HotkeyManager::cached_symbolic_hotkeys(&path).is_empty());
+    }
+
     #[tokio::test]
     async fn test_consecutive_detection() {
         let manager = HotkeyManager::new();
         let target = Hotkey::Consecutive {
             key: " ".to_string(),
             count: 3,
+            interval_ms: None,
         };
 
         // 第一次按键
@@ -274,5 +1074,44 @@ mod tests {
         let conflicts = HotkeyManager::check_system_conflicts(&hotkey);
         // Spotlight 使用 Cmd+Space，应该检测到冲突
         assert!(!conflicts.is_empty());
+
+        // 长按模式同样使用组合键，也需要检测冲突
+        let hold = Hotkey::Hold {
+            modifiers: vec!["Meta".to_string()],
+            key: " ".to_string(),
+            hold_ms: 600,
+        };
+        assert!(!HotkeyManager::check_system_conflicts(&hold).is_empty());
+
+        // 鼠标左右键会影响正常点击
+        let left_click = Hotkey::MouseButton {
+            button: 1,
+            clicks: 2,
+            interval_ms: 300,
+        };
+        assert!(!HotkeyManager::check_system_conflicts(&left_click).is_empty());
+
+        let side_button = Hotkey::MouseButton {
+            button: 4,
+            clicks: 2,
+            interval_ms: 300,
+        };
+        assert!(HotkeyManager::check_system_conflicts(&side_button).is_empty());
+    }
+
+    #[test]
+    fn test_check_internal_conflicts() {
+        assert!(HotkeyManager::check_internal_conflicts(&HotkeyConfig::default()).is_empty());
+
+        let mut config = HotkeyConfig::default();
+        config.full_mode = Hotkey::Hold {
+            modifiers: vec!["control".to_string()],
+            key: "K".to_string(),
+            hold_ms: 600,
+        };
+        let conflicts = HotkeyManager::check_internal_conflicts(&config);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].first, "selected_mode");
+        assert_eq!(conflicts[0].second, "full_mode");
     }
 }
@@ -1,11 +1,17 @@
 //! 键盘监听模块
-//! 使用 rdev 监听原始键盘输入，用于检测连续按键触发全文翻译
+//! 使用 rdev 监听原始键盘输入，用于检测连续按键和长按组合键触发翻译
 
+use crate::config::{
+    is_caps_lock_key, is_modifier_key_name, Hotkey, KeyMatching, DEFAULT_CONSECUTIVE_INTERVAL_MS,
+};
+use crate::hotkey::normalize_key_name;
+use serde::Serialize;
+use std::collections::HashSet;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 /// 连续按键配置
 #[derive(Debug, Clone)]
@@ -16,6 +22,8 @@ pub struct ConsecutiveKeyConfig {
     pub count: u8,
     /// 按键间隔阈值（毫秒）
     pub interval_ms: u64,
+    /// 按输入的字符还是物理按键匹配
+    pub matching: KeyMatching,
 }
 
 impl Default for ConsecutiveKeyConfig {
@@ -23,134 +31,1628 @@ impl Default for ConsecutiveKeyConfig {
         Self {
             key: " ".to_string(), // 空格
             count: 3,
-            interval_ms: 300,
+            interval_ms: DEFAULT_CONSECUTIVE_INTERVAL_MS,
+            matching: KeyMatching::default(),
+        }
+    }
+}
+
+/// 连续按键检测器
+///
+/// 普通键只统计真实的按下：按住时系统自动重复产生的按下事件会被忽略，
+/// 必须先松开才会再次计数。修饰键（如 Shift）只统计单击——按下后在间隔内
+/// 松开且期间没有按其他键，这样按住 Shift 输入大写字母不会被计入。
+/// 按住 Meta/Control/Alt 时按下目标键属于其他快捷键，会重置计数。
+/// 按字符匹配时使用 rdev 事件中当前布局输入的字符，没有字符的事件退回按物理按键匹配。
+pub struct ConsecutiveDetector {
+    /// 目标按键
+    key: String,
+    /// 规范化后的目标按键，用于与输入的字符比较
+    normalized_key: String,
+    /// 对应目标的 rdev 按键，事件直接按枚举比较，不为每个按键分配字符串
+    physical_targets: Vec<rdev::Key>,
+    /// 是否按输入的字符匹配
+    by_character: bool,
+    /// 按字符匹配到目标时对应的物理按键，用于识别其松开事件
+    target_physical: Option<rdev::Key>,
+    /// 目标是否为修饰键（按单击计数）
+    tap: bool,
+    /// 目标的按下/松开事件是否表示开关状态（macOS 的大写锁定键），每个事件都算一次单击
+    toggle: bool,
+    /// 需要的按键次数
+    count: u8,
+    /// 按键间隔阈值
+    interval: Duration,
+    /// 当前连续次数
+    press_count: u8,
+    /// 当前连续按键输入到文本中的字符数
+    pending_typed_chars: usize,
+    /// 最近一次触发时输入到文本中的字符数
+    triggered_typed_chars: usize,
+    /// 上次按下目标键的时间
+    last_press_time: Option<Instant>,
+    /// 目标键是否处于按下状态，用于过滤自动重复
+    key_down: bool,
+    /// 修饰键按下尚未松开的时间
+    tap_started: Option<Instant>,
+    /// 当前按住的 Meta/Control/Alt
+    held_modifiers: HashSet<&'static str>,
+    /// 目标键自身对应的修饰键，按住它不算干扰
+    own_modifier: Option<&'static str>,
+}
+
+/// 按住时会让目标键变成快捷键（如 Cmd + Space）的修饰键
+const INTERFERING_MODIFIERS: [&str; 3] = ["Meta", "Control", "Alt"];
+
+/// 判断按键是否与配置的按键名称对应
+/// "Shift" 等通用名称匹配左右两侧，"Esc" 等别名按规范化名称匹配
+fn key_matches_name(key: rdev::Key, name: &str, normalized: &str) -> bool {
+    let key_name = key_to_string(key);
+    key_name == name
+        || normalize_key_name(&key_name) == normalized
+        || modifier_name(key) == Some(name)
+}
+
+/// 判断输入的字符是否为目标按键，与 `normalize_key_name` 的结果一致但不分配内存
+fn typed_text_matches(text: &str, normalized: &str) -> bool {
+    if text == " " {
+        return normalized == "space";
+    }
+    text.trim()
+        .chars()
+        .flat_map(char::to_lowercase)
+        .eq(normalized.chars())
+}
+
+/// rdev 中有名称的全部按键，用于预先计算目标按键对应的枚举值
+const KNOWN_KEYS: &[rdev::Key] = {
+    use rdev::Key::*;
+    &[
+        Alt,
+        AltGr,
+        Backspace,
+        CapsLock,
+        ControlLeft,
+        ControlRight,
+        Delete,
+        DownArrow,
+        End,
+        Escape,
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        F11,
+        F12,
+        Home,
+        LeftArrow,
+        MetaLeft,
+        MetaRight,
+        PageDown,
+        PageUp,
+        Return,
+        RightArrow,
+        ShiftLeft,
+        ShiftRight,
+        Space,
+        Tab,
+        UpArrow,
+        PrintScreen,
+        ScrollLock,
+        Pause,
+        NumLock,
+        BackQuote,
+        Num1,
+        Num2,
+        Num3,
+        Num4,
+        Num5,
+        Num6,
+        Num7,
+        Num8,
+        Num9,
+        Num0,
+        Minus,
+        Equal,
+        KeyQ,
+        KeyW,
+        KeyE,
+        KeyR,
+        KeyT,
+        KeyY,
+        KeyU,
+        KeyI,
+        KeyO,
+        KeyP,
+        LeftBracket,
+        RightBracket,
+        KeyA,
+        KeyS,
+        KeyD,
+        KeyF,
+        KeyG,
+        KeyH,
+        KeyJ,
+        KeyK,
+        KeyL,
+        SemiColon,
+        Quote,
+        BackSlash,
+        IntlBackslash,
+        KeyZ,
+        KeyX,
+        KeyC,
+        KeyV,
+        KeyB,
+        KeyN,
+        KeyM,
+        Comma,
+        Dot,
+        Slash,
+        Insert,
+        KpReturn,
+        KpMinus,
+        KpPlus,
+        KpMultiply,
+        KpDivide,
+        Kp0,
+        Kp1,
+        Kp2,
+        Kp3,
+        Kp4,
+        Kp5,
+        Kp6,
+        Kp7,
+        Kp8,
+        Kp9,
+        KpDelete,
+        Function,
+    ]
+};
+
+/// macOS 把大写锁定键的单击报告为开关状态：打开时只有按下事件，关闭时只有松开事件
+const TOGGLE_KEY_EVENTS: bool = cfg!(target_os = "macos");
+
+impl ConsecutiveDetector {
+    /// 创建新的连续按键检测器
+    pub fn new(config: &ConsecutiveKeyConfig) -> Self {
+        let normalized_key = normalize_key_name(&config.key);
+        let physical_targets = KNOWN_KEYS
+            .iter()
+            .copied()
+            .filter(|key| key_matches_name(*key, &config.key, &normalized_key))
+            .collect();
+
+        Self {
+            key: config.key.clone(),
+            normalized_key,
+            physical_targets,
+            by_character: config.matching == KeyMatching::Character,
+            target_physical: None,
+            tap: is_modifier_key_name(&config.key),
+            toggle: TOGGLE_KEY_EVENTS && is_caps_lock_key(&config.key),
+            count: config.count,
+            interval: Duration::from_millis(config.interval_ms),
+            press_count: 0,
+            pending_typed_chars: 0,
+            triggered_typed_chars: 0,
+            last_press_time: None,
+            key_down: false,
+            tap_started: None,
+            held_modifiers: HashSet::new(),
+            own_modifier: modifier_base_name(&config.key),
+        }
+    }
+
+    /// 是否按住了目标以外的修饰键
+    fn modifier_held(&self) -> bool {
+        self.held_modifiers
+            .iter()
+            .any(|m| Some(*m) != self.own_modifier)
+    }
+
+    /// 判断按键是否为目标键
+    /// rdev 没有命名的按键（如 F13–F19）才按名称比较
+    fn matches(&self, key: rdev::Key) -> bool {
+        match key {
+            rdev::Key::Unknown(_) => key_matches_name(key, &self.key, &self.normalized_key),
+            _ => self.physical_targets.contains(&key),
+        }
+    }
+
+    /// 判断按下的按键是否为目标键，按字符匹配时优先比较输入的字符
+    fn matches_press(&mut self, key: rdev::Key, name: Option<&str>) -> bool {
+        if self.by_character && !self.tap {
+            if let Some(text) = name.filter(|text| is_typed_text(text)) {
+                let is_target = typed_text_matches(text, &self.normalized_key);
+                if is_target {
+                    self.target_physical = Some(key);
+                }
+                return is_target;
+            }
+        }
+        self.matches(key)
+    }
+
+    /// 判断松开的按键是否为目标键
+    fn matches_release(&self, key: rdev::Key) -> bool {
+        self.target_physical == Some(key) || self.matches(key)
+    }
+
+    /// 处理按键按下事件，返回是否达到目标次数
+    pub fn on_press(&mut self, key: rdev::Key, now: Instant) -> bool {
+        self.on_key_press(key, None, now)
+    }
+
+    /// 处理带输入字符的按键按下事件（rdev 的 `Event.name`），返回是否达到目标次数
+    pub fn on_key_press(&mut self, key: rdev::Key, name: Option<&str>, now: Instant) -> bool {
+        if let Some(modifier) = modifier_name(key) {
+            if INTERFERING_MODIFIERS.contains(&modifier) {
+                self.held_modifiers.insert(modifier);
+            }
+        }
+
+        let is_target = self.matches_press(key, name);
+
+        // 按住修饰键时的目标键属于其他快捷键（如 Cmd + Space），不计数
+        if is_target && self.modifier_held() {
+            self.key_down = !self.tap;
+            self.tap_started = None;
+            self.reset("Target key pressed with modifier held");
+            return false;
+        }
+
+        if self.tap {
+            if is_target {
+                // 等待松开，按住期间的重复事件不重新计时
+                self.tap_started.get_or_insert(now);
+            } else {
+                // 按住修饰键时按了其他键，不算单击
+                self.tap_started = None;
+                self.reset("Different key pressed");
+            }
+            return false;
+        }
+
+        if is_target && self.toggle {
+            return self.record(now, false);
+        }
+
+        if is_target {
+            // 按住不放时的自动重复事件
+            if self.key_down {
+                return false;
+            }
+            self.key_down = true;
+            return self.record(now, types_character(key, name));
+        }
+
+        // 修饰键不打断普通键的计数
+        if !is_modifier_key(key) {
+            self.reset("Different key pressed");
+        }
+        false
+    }
+
+    /// 处理按键松开事件，返回是否达到目标次数
+    pub fn on_release(&mut self, key: rdev::Key, now: Instant) -> bool {
+        if let Some(modifier) = modifier_name(key) {
+            self.held_modifiers.remove(modifier);
+        }
+
+        if !self.matches_release(key) {
+            return false;
+        }
+        if self.toggle {
+            // 关闭大写锁定的单击只产生松开事件
+            return self.record(now, false);
+        }
+        if !self.tap {
+            self.key_down = false;
+            return false;
+        }
+
+        match self.tap_started.take() {
+            Some(start) if now.duration_since(start) <= self.interval => self.record(now, false),
+            Some(_) => {
+                // 长按修饰键不算单击
+                self.reset("Modifier held too long");
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// 记录一次有效按键，`typed` 表示这次按键是否向文本中输入了字符
+    fn record(&mut self, now: Instant, typed: bool) -> bool {
+        // 检查是否在时间间隔内
+        match self.last_press_time {
+            Some(last) if now.duration_since(last) <= self.interval => {
+                self.press_count += 1;
+                debug!(
+                    "Consecutive key press detected: {} (count: {})",
+                    self.key, self.press_count
+                );
+            }
+            Some(_) => {
+                // 超时，重新计数
+                self.press_count = 1;
+                self.pending_typed_chars = 0;
+                debug!("Key press timeout, resetting count");
+            }
+            None => {
+                self.press_count = 1;
+                self.pending_typed_chars = 0;
+            }
+        }
+
+        self.last_press_time = Some(now);
+        self.pending_typed_chars += usize::from(typed);
+
+        // 检查是否达到目标次数
+        if self.press_count >= self.count {
+            info!("Consecutive key trigger activated!");
+            self.triggered_typed_chars = self.pending_typed_chars;
+            self.press_count = 0;
+            self.pending_typed_chars = 0;
+            self.last_press_time = None;
+            return true;
+        }
+
+        false
+    }
+
+    /// 最近一次触发时输入到文本中的字符数，修饰键、Esc、方向键等不输入字符
+    pub fn typed_chars(&self) -> usize {
+        self.triggered_typed_chars
+    }
+
+    /// 重置计数
+    fn reset(&mut self, reason: &str) {
+        if self.press_count > 0 {
+            debug!("{}, resetting count", reason);
+        }
+        self.press_count = 0;
+        self.pending_typed_chars = 0;
+        self.last_press_time = None;
+    }
+}
+
+/// 鼠标按键连击配置
+#[derive(Debug, Clone)]
+pub struct MouseClickConfig {
+    /// 按键编号：1 左键、2 右键、3 中键、4/5 侧键
+    pub button: u8,
+    /// 需要的点击次数
+    pub clicks: u8,
+    /// 点击间隔阈值（毫秒）
+    pub interval_ms: u64,
+}
+
+/// 鼠标按键连击检测器
+///
+/// 在间隔内连续按下目标按键达到次数时触发，按下其他鼠标按键会重置计数。
+pub struct MouseClickDetector {
+    /// 目标按键编号
+    button: u8,
+    /// 需要的点击次数
+    clicks: u8,
+    /// 点击间隔阈值
+    interval: Duration,
+    /// 当前连续次数
+    click_count: u8,
+    /// 上次点击时间
+    last_click_time: Option<Instant>,
+}
+
+impl MouseClickDetector {
+    /// 创建新的鼠标连击检测器
+    pub fn new(config: &MouseClickConfig) -> Self {
+        Self {
+            button: config.button,
+            clicks: config.clicks,
+            interval: Duration::from_millis(config.interval_ms),
+            click_count: 0,
+            last_click_time: None,
+        }
+    }
+
+    /// 处理鼠标按键按下事件，返回是否达到目标次数
+    pub fn on_button_press(&mut self, button: rdev::Button, now: Instant) -> bool {
+        if button_number(button) != Some(self.button) {
+            self.click_count = 0;
+            self.last_click_time = None;
+            return false;
+        }
+
+        self.click_count = match self.last_click_time {
+            Some(last) if now.duration_since(last) <= self.interval => self.click_count + 1,
+            _ => 1,
+        };
+        self.last_click_time = Some(now);
+        debug!(
+            "Mouse button {} click detected (count: {})",
+            self.button, self.click_count
+        );
+
+        if self.click_count >= self.clicks {
+            info!("Mouse button trigger activated!");
+            self.click_count = 0;
+            self.last_click_time = None;
+            return true;
+        }
+
+        false
+    }
+}
+
+/// 正在等待第二步按键的两步组合键
+#[derive(Debug, Clone)]
+pub struct PendingChord {
+    /// 绑定的模式标签
+    pub mode: String,
+    /// 等待截止时间
+    pub deadline: Instant,
+}
+
+/// 两步组合键的等待状态，第一步的全局快捷键回调写入，rdev 监听线程读取
+pub type ChordState = Arc<Mutex<Option<PendingChord>>>;
+
+/// 两步组合键第二步的检测配置
+#[derive(Debug, Clone)]
+pub struct ChordKeyConfig {
+    /// 第一步的修饰键，用于识别第一步组合键本身的按键事件
+    pub first_modifiers: Vec<String>,
+    /// 第一步的主键
+    pub first_key: String,
+    /// 第二步按键
+    pub second_key: String,
+    /// 按输入的字符还是按物理按键匹配第二步按键
+    pub matching: KeyMatching,
+    /// 共享的等待状态
+    pub pending: ChordState,
+}
+
+/// 两步组合键第二步检测器
+///
+/// 第一步由全局快捷键触发后进入等待状态，等待期间的第一个非修饰键决定结果：
+/// 是第二步按键则触发，否则结束等待。按键照常传递给当前应用，不做拦截。
+pub struct ChordDetector {
+    /// 绑定的模式标签，只响应该模式的等待状态
+    mode: String,
+    /// 第一步的修饰键
+    first_modifiers: Vec<String>,
+    /// 规范化后的第一步主键
+    first_key: String,
+    /// 规范化后的第二步按键
+    second_key: String,
+    /// 是否按输入的字符匹配
+    by_character: bool,
+    /// 共享的等待状态
+    pending: ChordState,
+    /// 当前按住的修饰键
+    held_modifiers: HashSet<&'static str>,
+    /// 最近一次第二步按键是否输入了字符
+    typed: bool,
+}
+
+impl ChordDetector {
+    /// 创建新的两步组合键检测器
+    pub fn new(config: &ChordKeyConfig, mode: &str) -> Self {
+        Self {
+            mode: mode.to_string(),
+            first_modifiers: config.first_modifiers.clone(),
+            first_key: normalize_key_name(&config.first_key),
+            second_key: normalize_key_name(&config.second_key),
+            by_character: config.matching == KeyMatching::Character,
+            pending: config.pending.clone(),
+            held_modifiers: HashSet::new(),
+            typed: false,
+        }
+    }
+
+    /// 判断按键是否为第二步按键
+    fn matches_second(&self, key: rdev::Key, name: Option<&str>) -> bool {
+        if self.by_character {
+            if let Some(text) = name.filter(|text| is_typed_text(text)) {
+                return normalize_key_name(text) == self.second_key;
+            }
+        }
+        normalize_key_name(&key_to_string(key)) == self.second_key
+    }
+
+    /// 是否为第一步组合键本身（或其自动重复）的按键事件
+    /// 全局快捷键回调与 rdev 事件的先后顺序不确定，等待开始后仍可能收到第一步的按键
+    fn is_first_step(&self, key: rdev::Key) -> bool {
+        normalize_key_name(&key_to_string(key)) == self.first_key
+            && self
+                .first_modifiers
+                .iter()
+                .all(|m| self.held_modifiers.contains(m.as_str()))
+    }
+
+    /// 处理按键按下事件，返回是否完成两步组合键
+    pub fn on_key_press(&mut self, key: rdev::Key, name: Option<&str>, now: Instant) -> bool {
+        if let Some(modifier) = modifier_name(key) {
+            self.held_modifiers.insert(modifier);
+        }
+        // 等待期间按下或松开修饰键不影响结果
+        if is_modifier_key(key) {
+            return false;
+        }
+
+        let mut pending = lock(&self.pending);
+        let Some(chord) = pending.as_ref().filter(|chord| chord.mode == self.mode) else {
+            return false;
+        };
+        if now > chord.deadline {
+            debug!("{} mode chord timed out", self.mode);
+            *pending = None;
+            return false;
+        }
+        if self.is_first_step(key) {
+            return false;
+        }
+
+        *pending = None;
+        let matched = self.matches_second(key, name);
+        if !matched {
+            debug!("{} mode chord cancelled by a different key", self.mode);
+        }
+        // 按住 Cmd/Ctrl 时第二步按键不输入字符
+        self.typed = !self.held_modifiers.contains("Meta")
+            && !self.held_modifiers.contains("Control")
+            && types_character(key, name);
+        matched
+    }
+
+    /// 最近一次完成的第二步按键是否向文本中输入了字符
+    pub fn typed_chars(&self) -> usize {
+        usize::from(self.typed)
+    }
+
+    /// 处理按键松开事件
+    pub fn on_release(&mut self, key: rdev::Key) {
+        if let Some(modifier) = modifier_name(key) {
+            self.held_modifiers.remove(modifier);
+        }
+    }
+}
+
+/// 监听器检测到的一次触发
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trigger {
+    /// 绑定的模式标签
+    pub mode: String,
+    /// 触发过程中输入到文本中的字符数，如三击空格输入的三个空格
+    pub typed_chars: usize,
+}
+
+/// rdev 监听器检测的触发方式
+#[derive(Debug, Clone)]
+pub enum ListenerTrigger {
+    /// 连续按键
+    Consecutive(ConsecutiveKeyConfig),
+    /// 鼠标按键连击
+    MouseClick(MouseClickConfig),
+    /// 两步组合键的第二步
+    Chord(ChordKeyConfig),
+    /// 长按组合键
+    Hold(HoldKeyConfig),
+}
+
+/// 单个触发绑定的检测器
+enum TriggerDetector {
+    Consecutive(ConsecutiveDetector),
+    MouseClick(MouseClickDetector),
+    Chord(ChordDetector),
+    Hold(HoldDetector),
+}
+
+impl TriggerDetector {
+    fn new(trigger: &ListenerTrigger, mode: &str) -> Self {
+        match trigger {
+            ListenerTrigger::Consecutive(config) => {
+                Self::Consecutive(ConsecutiveDetector::new(config))
+            }
+            ListenerTrigger::MouseClick(config) => {
+                Self::MouseClick(MouseClickDetector::new(config))
+            }
+            ListenerTrigger::Chord(config) => Self::Chord(ChordDetector::new(config, mode)),
+            ListenerTrigger::Hold(config) => Self::Hold(HoldDetector::new(config)),
+        }
+    }
+
+    /// 处理输入事件，触发时返回触发过程中输入到文本中的字符数
+    fn on_event(&mut self, event: &rdev::Event, now: Instant) -> Option<usize> {
+        let name = event.name.as_deref();
+        match (self, &event.event_type) {
+            (Self::Consecutive(detector), rdev::EventType::KeyPress(key)) => detector
+                .on_key_press(*key, name, now)
+                .then(|| detector.typed_chars()),
+            (Self::Consecutive(detector), rdev::EventType::KeyRelease(key)) => detector
+                .on_release(*key, now)
+                .then(|| detector.typed_chars()),
+            (Self::MouseClick(detector), rdev::EventType::ButtonPress(button)) => {
+                detector.on_button_press(*button, now).then_some(0)
+            }
+            (Self::Chord(detector), rdev::EventType::KeyPress(key)) => detector
+                .on_key_press(*key, name, now)
+                .then(|| detector.typed_chars()),
+            (Self::Chord(detector), rdev::EventType::KeyRelease(key)) => {
+                detector.on_release(*key);
+                None
+            }
+            (Self::Hold(detector), rdev::EventType::KeyPress(key)) => {
+                detector.on_press(*key, now);
+                None
+            }
+            (Self::Hold(detector), rdev::EventType::KeyRelease(key)) => {
+                detector.on_release(*key, now).then_some(0)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 长按组合键配置
+#[derive(Debug, Clone)]
+pub struct HoldKeyConfig {
+    /// 修饰键列表 (Meta, Control, Alt, Shift)
+    pub modifiers: Vec<String>,
+    /// 主键
+    pub key: String,
+    /// 需要按住的时长（毫秒）
+    pub hold_ms: u64,
+}
+
+/// 长按组合键检测器
+///
+/// 从主键按下开始计时，在主键或任一修饰键松开时判断按住时长是否达到阈值。
+/// 按住期间的自动重复按键事件会被忽略，中途按下其他键则取消本次计时。
+pub struct HoldDetector {
+    /// 目标修饰键集合
+    modifiers: HashSet<&'static str>,
+    /// 目标主键
+    key: String,
+    /// 规范化后的目标主键
+    normalized_key: String,
+    /// 对应目标主键的 rdev 按键，事件直接按枚举比较，不为每个按键分配字符串
+    targets: Vec<rdev::Key>,
+    /// 按住阈值
+    hold: Duration,
+    /// 当前按下的修饰键
+    pressed_modifiers: HashSet<&'static str>,
+    /// 组合键完整按下的时间
+    pressed_at: Option<Instant>,
+}
+
+impl HoldDetector {
+    /// 创建新的长按检测器
+    pub fn new(config: &HoldKeyConfig) -> Self {
+        let normalized_key = normalize_key_name(&config.key);
+        let targets = KNOWN_KEYS
+            .iter()
+            .copied()
+            .filter(|key| key_matches_name(*key, &config.key, &normalized_key))
+            .collect();
+
+        Self {
+            modifiers: config
+                .modifiers
+                .iter()
+                .filter_map(|m| modifier_base_name(m))
+                .collect(),
+            key: config.key.clone(),
+            normalized_key,
+            targets,
+            hold: Duration::from_millis(config.hold_ms),
+            pressed_modifiers: HashSet::new(),
+            pressed_at: None,
+        }
+    }
+
+    /// 判断按键是否为目标主键，rdev 没有命名的按键才按名称比较
+    fn matches(&self, key: rdev::Key) -> bool {
+        match key {
+            rdev::Key::Unknown(_) => key_matches_name(key, &self.key, &self.normalized_key),
+            _ => self.targets.contains(&key),
+        }
+    }
+
+    /// 处理按键按下事件
+    pub fn on_press(&mut self, key: rdev::Key, now: Instant) {
+        if let Some(modifier) = modifier_name(key) {
+            if self.pressed_at.is_some() && !self.modifiers.contains(modifier) {
+                debug!("Extra modifier pressed during hold, cancelling");
+                self.pressed_at = None;
+            }
+            self.pressed_modifiers.insert(modifier);
+            return;
+        }
+
+        if self.matches(key) {
+            // 按住期间的自动重复事件
+            if self.pressed_at.is_some() {
+                return;
+            }
+            if self.pressed_modifiers == self.modifiers {
+                debug!("Hold chord pressed, start timing");
+                self.pressed_at = Some(now);
+            }
+        } else if self.pressed_at.take().is_some() {
+            debug!("Different key pressed during hold, cancelling");
+        }
+    }
+
+    /// 处理按键松开事件，返回是否触发
+    pub fn on_release(&mut self, key: rdev::Key, now: Instant) -> bool {
+        let chord_part = match modifier_name(key) {
+            Some(modifier) => {
+                self.pressed_modifiers.remove(modifier);
+                self.modifiers.contains(modifier)
+            }
+            None => self.matches(key),
+        };
+
+        if !chord_part {
+            return false;
+        }
+
+        match self.pressed_at.take() {
+            Some(start) if now.duration_since(start) >= self.hold => {
+                info!("Hold trigger activated!");
+                true
+            }
+            Some(_) => {
+                debug!("Hold released before threshold");
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+/// 热键录制过程中的事件
+#[derive(Debug, Clone)]
+pub enum CaptureEvent {
+    /// 录制到可用的组合键，录制结束
+    Captured(Hotkey),
+    /// 按下的按键不能用作热键，继续录制
+    Invalid(InvalidCapture),
+}
+
+/// 不能用作热键的录制结果，通过 hotkey-capture-invalid 事件发送给前端
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InvalidCapture {
+    /// 录制到的组合键的显示文本
+    pub hotkey: String,
+    /// 不能使用的原因
+    pub reason: &'static str,
+}
+
+/// 热键录制检测器
+///
+/// 跟踪修饰键状态，在第一个非修饰键按下时生成对应的组合键；
+/// 按键不能用作热键（如无法注册的按键）时返回原因，继续等待下一个按键。
+pub struct CaptureDetector {
+    /// 当前按下的修饰键
+    pressed_modifiers: HashSet<&'static str>,
+}
+
+impl CaptureDetector {
+    /// 创建新的录制检测器
+    pub fn new() -> Self {
+        Self {
+            pressed_modifiers: HashSet::new(),
+        }
+    }
+
+    /// 处理按键按下事件，按下非修饰键时返回录制结果
+    pub fn on_press(&mut self, key: rdev::Key) -> Option<CaptureEvent> {
+        if let Some(modifier) = modifier_name(key) {
+            self.pressed_modifiers.insert(modifier);
+            return None;
+        }
+
+        // 按固定顺序输出修饰键，与配置中的写法保持一致
+        let modifiers = ["Meta", "Control", "Alt", "Shift"]
+            .into_iter()
+            .filter(|m| self.pressed_modifiers.contains(m))
+            .map(str::to_string)
+            .collect();
+
+        let hotkey = Hotkey::Combination {
+            modifiers,
+            key: key_to_string(key),
+        };
+        Some(match hotkey.key_error() {
+            Some(reason) => CaptureEvent::Invalid(InvalidCapture {
+                hotkey: hotkey.format(),
+                reason,
+            }),
+            None => CaptureEvent::Captured(hotkey),
+        })
+    }
+
+    /// 处理按键松开事件
+    pub fn on_release(&mut self, key: rdev::Key) {
+        if let Some(modifier) = modifier_name(key) {
+            self.pressed_modifiers.remove(modifier);
+        }
+    }
+}
+
+impl Default for CaptureDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 键盘监听器的运行状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ListenerStatus {
+    /// 没有需要 rdev 监听的热键
+    Stopped,
+    /// 监听中
+    Running,
+    /// 启动失败或意外退出
+    Failed {
+        /// 失败原因
+        reason: String,
+    },
+}
+
+/// 监听器意外退出后第一次重启前的等待时间，之后每次翻倍
+const RESTART_BASE_DELAY: Duration = Duration::from_secs(1);
+/// 连续重启的最大次数，超过后保持失败状态
+const RESTART_MAX_ATTEMPTS: u32 = 5;
+/// 监听器持续运行超过该时长后，再次失败时重新计数
+const RESTART_STABLE_AFTER: Duration = Duration::from_secs(60);
+
+/// 监听器自动重启的退避策略
+pub struct RestartBackoff {
+    /// 连续重启的次数
+    attempts: u32,
+    /// 最近一次启动监听的时间
+    last_start: Option<Instant>,
+    /// 是否已有等待中的重启
+    pending: bool,
+}
+
+impl RestartBackoff {
+    /// 创建新的退避策略
+    pub fn new() -> Self {
+        Self {
+            attempts: 0,
+            last_start: None,
+            pending: false,
+        }
+    }
+
+    /// 记录监听器启动，等待中的重启随之完成
+    pub fn started(&mut self, now: Instant) {
+        self.last_start = Some(now);
+        self.pending = false;
+    }
+
+    /// 监听器失败时调用，返回重启前的等待时间
+    /// 已有等待中的重启或重启次数用尽时返回 `None`
+    pub fn schedule(&mut self, now: Instant) -> Option<Duration> {
+        if self.pending {
+            return None;
+        }
+
+        // 稳定运行一段时间后的失败视为新的故障
+        if let Some(start) = self.last_start {
+            if now.duration_since(start) >= RESTART_STABLE_AFTER {
+                self.attempts = 0;
+            }
+        }
+
+        if self.exhausted() {
+            return None;
+        }
+
+        let delay = RESTART_BASE_DELAY * 2u32.pow(self.attempts);
+        self.attempts += 1;
+        self.pending = true;
+        Some(delay)
+    }
+
+    /// 重启次数是否已经用尽
+    pub fn exhausted(&self) -> bool {
+        self.attempts >= RESTART_MAX_ATTEMPTS
+    }
+}
+
+impl Default for RestartBackoff {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 重建监听线程时重试终止旧事件循环的间隔
+const STOP_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+/// 重建监听线程时重试终止旧事件循环的最大次数
+const STOP_RETRY_ATTEMPTS: u32 = 100;
+
+/// 监听会话处理事件的回调
+type EventCallback = Box<dyn FnMut(rdev::Event) + Send>;
+/// 监听线程意外退出时通知会话的回调，参数为退出原因
+type ExitCallback = Box<dyn FnOnce(String) + Send>;
+
+/// 进程内共用的 rdev 监听
+///
+/// rdev 0.5 的 `listen` 结束时不会释放 macOS 上创建的 CGEventTap 和 mach port，
+/// 每次启停都会泄漏一份。因此监听线程只在第一个会话开始时启动，之后的会话只替换回调，
+/// 停止会话不会终止 `rdev::listen`；只有系统唤醒后才通过 [`restart_event_source`] 重建。
+struct SharedListener {
+    /// 当前监听线程的编号，旧线程退出时据此判断自己是否已被替换
+    generation: u64,
+    /// 当前监听线程，线程退出后清空
+    thread: Option<std::thread::JoinHandle<()>>,
+    /// 当前监听线程的事件循环句柄，用于重建时终止旧的 rdev::listen
+    event_loop: Arc<Mutex<Option<event_loop::LoopHandle>>>,
+    /// 上一个分配的会话编号
+    last_session: u64,
+    /// 当前会话的编号
+    session: Option<u64>,
+    /// 当前会话的回调，处理事件期间暂时取出
+    callback: Option<EventCallback>,
+    /// 当前会话的退出通知
+    on_exit: Option<ExitCallback>,
+}
+
+static SHARED_LISTENER: Mutex<Option<SharedListener>> = Mutex::new(None);
+
+/// 获取共用的 rdev 监听状态，第一次使用时初始化
+fn shared_listener(guard: &mut Option<SharedListener>) -> &mut SharedListener {
+    guard.get_or_insert_with(|| SharedListener {
+        generation: 0,
+        thread: None,
+        event_loop: Arc::new(Mutex::new(None)),
+        last_session: 0,
+        session: None,
+        callback: None,
+        on_exit: None,
+    })
+}
+
+/// 将回调登记为当前会话，监听线程未运行时启动，返回会话编号
+fn attach_session(callback: EventCallback, on_exit: ExitCallback) -> u64 {
+    let mut guard = lock(&SHARED_LISTENER);
+    let shared = shared_listener(&mut guard);
+    shared.last_session += 1;
+    let id = shared.last_session;
+    shared.session = Some(id);
+    shared.callback = Some(callback);
+    shared.on_exit = Some(on_exit);
+
+    if shared.thread.is_none() {
+        spawn_listen_thread(shared);
+    }
+    id
+}
+
+/// 注销编号为 `id` 的会话，会话已被替换时不做任何事
+fn detach_session(id: u64) {
+    let mut guard = lock(&SHARED_LISTENER);
+    let shared = shared_listener(&mut guard);
+    if shared.session == Some(id) {
+        shared.session = None;
+        shared.callback = None;
+        shared.on_exit = None;
+    }
+}
+
+/// 重建 rdev 监听，当前会话保持不变
+///
+/// macOS 休眠唤醒后事件监听可能失效，而 rdev 无法检测这种情况，唤醒后调用。
+/// 旧的事件监听会泄漏一份，但只在唤醒时发生；无法终止 `rdev::listen` 的平台不做任何事。
+pub fn restart_event_source() {
+    if !event_loop::STOPPABLE {
+        return;
+    }
+
+    let mut guard = lock(&SHARED_LISTENER);
+    let shared = shared_listener(&mut guard);
+    if shared.thread.is_some() {
+        info!("Restarting rdev listener");
+        spawn_listen_thread(shared);
+    }
+}
+
+/// 启动新的监听线程，替换仍在运行的旧线程
+/// 新线程先终止旧线程的 rdev::listen 并等待其退出，rdev 同一时间只能运行一个监听
+fn spawn_listen_thread(shared: &mut SharedListener) {
+    shared.generation += 1;
+    let generation = shared.generation;
+    let previous = shared
+        .thread
+        .take()
+        .map(|thread| (thread, shared.event_loop.clone()));
+    let loop_handle = Arc::new(Mutex::new(None));
+    shared.event_loop = loop_handle.clone();
+
+    shared.thread = Some(std::thread::spawn(move || {
+        if let Some((thread, previous_loop)) = previous {
+            stop_listen_thread(thread, &previous_loop);
+        }
+
+        // 记录本线程的事件循环，供重建时终止 rdev::listen
+        *lock(&loop_handle) = Some(event_loop::LoopHandle::current());
+        let result = run_listener(dispatch_event);
+        *lock(&loop_handle) = None;
+
+        // 已被重建替换的线程不通知会话
+        let on_exit = {
+            let mut guard = lock(&SHARED_LISTENER);
+            let shared = shared_listener(&mut guard);
+            if shared.generation != generation {
+                None
+            } else {
+                shared.thread = None;
+                shared.on_exit.take()
+            }
+        };
+        if let Some(on_exit) = on_exit {
+            let reason = match result {
+                Ok(()) => "键盘监听意外结束".to_string(),
+                Err(e) => e,
+            };
+            on_exit(reason);
+        }
+        debug!("Key listener thread exited");
+    }));
+}
+
+/// 终止旧监听线程的 rdev::listen 并等待线程退出
+/// 线程进入事件循环之前收到的停止请求会丢失，退出前持续重试
+fn stop_listen_thread(
+    thread: std::thread::JoinHandle<()>,
+    loop_handle: &Mutex<Option<event_loop::LoopHandle>>,
+) {
+    for _ in 0..STOP_RETRY_ATTEMPTS {
+        if thread.is_finished() {
+            break;
+        }
+        if let Some(handle) = lock(loop_handle).as_ref() {
+            event_loop::stop(handle);
+        }
+        std::thread::sleep(STOP_RETRY_INTERVAL);
+    }
+    if !thread.is_finished() {
+        warn!("Previous key listener thread did not exit, waiting for it");
+    }
+    let _ = thread.join();
+}
+
+/// rdev 的全局回调，将事件交给当前会话
+/// 处理事件期间不持有锁，会话回调中可以阻塞发送触发信号
+fn dispatch_event(event: rdev::Event) {
+    let taken = {
+        let mut guard = lock(&SHARED_LISTENER);
+        let shared = shared_listener(&mut guard);
+        shared.session.zip(shared.callback.take())
+    };
+    let Some((id, mut callback)) = taken else {
+        return;
+    };
+
+    callback(event);
+
+    // 处理期间会话被停止或替换时丢弃旧回调
+    let mut guard = lock(&SHARED_LISTENER);
+    let shared = shared_listener(&mut guard);
+    if shared.session == Some(id) && shared.callback.is_none() {
+        shared.callback = Some(callback);
+    }
+}
+
+/// 键盘监听器
+///
+/// 每次 `start` 都会开启一个新的监听会话，`stop` 会注销会话的回调并关闭对应的接收端，
+/// 之后可以使用新的配置再次启动。所有会话共用一个 rdev 监听线程。
+pub struct KeyListener {
+    /// 当前监听会话
+    session: KeyListenerHandle,
+    /// 监听线程启动失败时记录的错误信息
+    error: Arc<Mutex<Option<String>>>,
+    /// 外部设置的抑制标志，置位期间不检测触发（热键录制不受影响）
+    suppressed: Arc<AtomicBool>,
+    /// 外部设置的启用标志，暂停期间只检测 `always_active` 中的模式
+    enabled: Arc<AtomicBool>,
+    /// 暂停期间仍然检测的模式（如暂停热键）
+    always_active: Vec<String>,
+}
+
+/// 监听会话的控制句柄
+///
+/// 可以克隆后交给其他模块，用于在监听线程之外停止当前会话。
+#[derive(Clone)]
+pub struct KeyListenerHandle {
+    /// 是否正在运行
+    running: Arc<AtomicBool>,
+    /// 触发事件发送器，发送触发的翻译模式；停止时清空以关闭接收端
+    trigger_tx: Arc<Mutex<Option<mpsc::Sender<Trigger>>>>,
+    /// 热键录制结果发送器；停止时清空以关闭接收端
+    capture_tx: Arc<Mutex<Option<mpsc::Sender<CaptureEvent>>>>,
+    /// 在共用的 rdev 监听中登记的会话编号
+    session_id: Arc<Mutex<Option<u64>>>,
+}
+
+impl KeyListenerHandle {
+    /// 创建未运行的会话
+    fn idle() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+            trigger_tx: Arc::new(Mutex::new(None)),
+            capture_tx: Arc::new(Mutex::new(None)),
+            session_id: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// 停止监听会话
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+
+        // 丢弃发送器，接收端随之关闭
+        lock(&self.trigger_tx).take();
+        lock(&self.capture_tx).take();
+
+        // 注销回调，共用的 rdev 监听保持运行
+        if let Some(id) = lock(&self.session_id).take() {
+            detach_session(id);
+        }
+
+        info!("Key listener stopped");
+    }
+
+    /// 检查是否正在运行
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+}
+
+impl KeyListener {
+    /// 创建新的键盘监听器
+    pub fn new() -> Self {
+        Self {
+            session: KeyListenerHandle::idle(),
+            error: Arc::new(Mutex::new(None)),
+            suppressed: Arc::new(AtomicBool::new(false)),
+            enabled: Arc::new(AtomicBool::new(true)),
+            always_active: Vec::new(),
+        }
+    }
+
+    /// 使用外部的抑制标志（例如设置窗口获得焦点时置位），需在启动前设置
+    pub fn set_suppressed_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.suppressed = flag;
+    }
+
+    /// 使用外部的启用标志，暂停期间除 `always_active` 中的模式外不处理按键事件，需在启动前设置
+    pub fn set_enabled_flag(&mut self, flag: Arc<AtomicBool>, always_active: Vec<String>) {
+        self.enabled = flag;
+        self.always_active = always_active;
+    }
+
+    /// 启动监听器
+    /// 同一个监听器可以同时检测多个连续按键/鼠标连击/长按绑定，每个绑定独立计数；
+    /// 返回一个接收器，当检测到触发时会收到该绑定的模式标签和输入的字符数
+    pub fn start(&mut self, bindings: Vec<(ListenerTrigger, String)>) -> mpsc::Receiver<Trigger> {
+        let rx = self.begin_session();
+
+        let running = self.session.running.clone();
+        let trigger_tx = self.session.trigger_tx.clone();
+        let suppressed = self.suppressed.clone();
+
+        for (trigger, mode) in &bindings {
+            info!("Starting key listener for {:?} ({} mode)", trigger, mode);
+        }
+
+        let enabled = self.enabled.clone();
+        let always_active = self.always_active.clone();
+        let any_always_active = bindings
+            .iter()
+            .any(|(_, mode)| always_active.contains(mode));
+
+        let new_detectors = move || -> Vec<(TriggerDetector, String, bool)> {
+            bindings
+                .iter()
+                .map(|(trigger, mode)| {
+                    (
+                        TriggerDetector::new(trigger, mode),
+                        mode.clone(),
+                        always_active.contains(mode),
+                    )
+                })
+                .collect()
+        };
+        let mut detectors = new_detectors();
+        let mut was_suppressed = false;
+        let mut was_paused = false;
+
+        let callback = move |event: rdev::Event| {
+            if !running.load(Ordering::SeqCst) {
+                return;
+            }
+
+            // 暂停期间没有需要检测的绑定时，在任何处理之前返回
+            let is_enabled = enabled.load(Ordering::Relaxed);
+            if !is_enabled {
+                was_paused = true;
+                if !any_always_active {
+                    return;
+                }
+            } else if was_paused {
+                // 暂停期间错过的松开事件会让按键状态过期，恢复后重新检测
+                debug!("Key listener resumed, counters reset");
+                detectors = new_detectors();
+                was_paused = false;
+            }
+
+            // 抑制期间不计数，结束后从头开始检测
+            if suppressed.load(Ordering::SeqCst) {
+                if !was_suppressed {
+                    debug!("Key listener suppressed, consecutive counters paused");
+                    was_suppressed = true;
+                }
+                return;
+            }
+            if was_suppressed {
+                debug!("Key listener suppression lifted, counters reset");
+                detectors = new_detectors();
+                was_suppressed = false;
+            }
+
+            let now = Instant::now();
+            for (detector, mode, always_active) in &mut detectors {
+                if !is_enabled && !*always_active {
+                    continue;
+                }
+                if let Some(typed_chars) = detector.on_event(&event, now) {
+                    // 发送触发信号
+                    send_trigger(&trigger_tx, mode, typed_chars);
+                }
+            }
+        };
+
+        self.attach(callback);
+
+        rx
+    }
+
+    /// 启动热键录制
+    /// 返回一个接收器，用户按下的按键不能用作热键时收到 `Invalid` 并继续录制，
+    /// 按下可用的组合键时收到 `Captured`，之后录制结束
+    pub fn start_capture(&mut self) -> mpsc::Receiver<CaptureEvent> {
+        // 录制会话不使用触发通道
+        drop(self.begin_session());
+
+        let (tx, rx) = mpsc::channel(8);
+        *lock(&self.session.capture_tx) = Some(tx);
+
+        let running = self.session.running.clone();
+        let capture_tx = self.session.capture_tx.clone();
+        let mut detector = CaptureDetector::new();
+
+        info!("Starting key listener for hotkey capture");
+
+        let callback = move |event: rdev::Event| {
+            if !running.load(Ordering::SeqCst) {
+                return;
+            }
+
+            let captured = match event.event_type {
+                rdev::EventType::KeyPress(key) => detector.on_press(key),
+                rdev::EventType::KeyRelease(key) => {
+                    detector.on_release(key);
+                    None
+                }
+                _ => None,
+            };
+
+            match captured {
+                Some(CaptureEvent::Invalid(invalid)) => {
+                    debug!(
+                        "Ignoring invalid capture {}: {}",
+                        invalid.hotkey, invalid.reason
+                    );
+                    if let Some(tx) = lock(&capture_tx).as_ref() {
+                        if let Err(e) = tx.try_send(CaptureEvent::Invalid(invalid)) {
+                            warn!("Failed to send invalid capture: {}", e);
+                        }
+                    }
+                }
+                Some(captured) => {
+                    // 只录制一次，之后的按键不再处理
+                    running.store(false, Ordering::SeqCst);
+                    if let Some(tx) = lock(&capture_tx).take() {
+                        if let Err(e) = tx.try_send(captured) {
+                            error!("Failed to send captured hotkey: {}", e);
+                        }
+                    }
+                }
+                None => {}
+            }
+        };
+
+        self.attach(callback);
+
+        rx
+    }
+
+    /// 开始新的监听会话：停止旧会话并创建新的共享状态
+    fn begin_session(&mut self) -> mpsc::Receiver<Trigger> {
+        if self.is_running() {
+            self.stop();
+        }
+
+        // 使用全新的共享状态，旧线程的回调不会再影响新会话
+        let (tx, rx) = mpsc::channel(10);
+        self.session = KeyListenerHandle::idle();
+        self.session.running.store(true, Ordering::SeqCst);
+        *lock(&self.session.trigger_tx) = Some(tx);
+        self.error = Arc::new(Mutex::new(None));
+
+        rx
+    }
+
+    /// 将回调登记到共用的 rdev 监听
+    fn attach<F>(&self, callback: F)
+    where
+        F: FnMut(rdev::Event) + Send + 'static,
+    {
+        let session = self.session.clone();
+        let error = self.error.clone();
+        let on_exit = move |reason: String| {
+            // 记录失败原因并关闭接收端，让调用方得知监听已结束
+            *lock(&error) = Some(reason);
+            lock(&session.trigger_tx).take();
+            lock(&session.capture_tx).take();
+            session.running.store(false, Ordering::SeqCst);
+        };
+
+        let id = attach_session(Box::new(callback), Box::new(on_exit));
+        *lock(&self.session.session_id) = Some(id);
+    }
+
+    /// 获取监听线程启动失败的错误信息（例如缺少输入监控权限）
+    pub fn last_error(&self) -> Option<String> {
+        lock(&self.error).clone()
+    }
+
+    /// 获取当前监听会话的控制句柄
+    pub fn handle(&self) -> KeyListenerHandle {
+        self.session.clone()
+    }
+
+    /// 停止监听器
+    pub fn stop(&self) {
+        self.session.stop();
+    }
+
+    /// 检查是否正在运行
+    pub fn is_running(&self) -> bool {
+        self.session.is_running()
+    }
+}
+
+impl Drop for KeyListener {
+    fn drop(&mut self) {
+        if self.is_running() {
+            self.stop();
         }
     }
 }
 
-/// 键盘监听器
-pub struct KeyListener {
-    /// 是否正在运行
-    running: Arc<AtomicBool>,
-    /// 触发事件发送器
-    trigger_tx: Option<mpsc::Sender<()>>,
+impl Default for KeyListener {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl KeyListener {
-    /// 创建新的键盘监听器
-    pub fn new() -> Self {
-        Self {
-            running: Arc::new(AtomicBool::new(false)),
-            trigger_tx: None,
+/// 发送触发信号
+fn send_trigger(trigger_tx: &Mutex<Option<mpsc::Sender<Trigger>>>, mode: &str, typed_chars: usize) {
+    let tx = lock(trigger_tx).clone();
+    if let Some(tx) = tx {
+        let trigger = Trigger {
+            mode: mode.to_string(),
+            typed_chars,
+        };
+        if let Err(e) = tx.blocking_send(trigger) {
+            error!("Failed to send trigger signal: {}", e);
         }
     }
+}
 
-    /// 启动监听器
-    /// 返回一个接收器，当检测到连续按键时会收到通知
-    pub fn start(&mut self, config: ConsecutiveKeyConfig) -> mpsc::Receiver<()> {
-        let (tx, rx) = mpsc::channel(10);
-        self.trigger_tx = Some(tx.clone());
-        self.running.store(true, Ordering::SeqCst);
+/// 是否为可见的输入字符（按住 Control 等时 rdev 给出的是控制字符，
+/// macOS 的方向键、功能键给出的是私有区字符）
+fn is_typed_text(text: &str) -> bool {
+    !text.is_empty()
+        && !text
+            .chars()
+            .any(|c| c.is_control() || ('\u{e000}'..='\u{f8ff}').contains(&c))
+}
 
-        let running = self.running.clone();
-        let target_key = config.key.clone();
-        let target_count = config.count;
-        let interval = Duration::from_millis(config.interval_ms);
+/// 按键是否向文本中输入了字符
+/// 只有字母、数字、空格和标点等字符键会输入内容，rdev 没有命名的按键按事件中的字符判断
+fn types_character(key: rdev::Key, name: Option<&str>) -> bool {
+    use rdev::Key::*;
 
-        info!(
-            "Starting key listener for consecutive key: '{}' x {}",
-            target_key, target_count
-        );
+    if name.is_some_and(|text| !is_typed_text(text)) {
+        return false;
+    }
+    match key {
+        Unknown(_) => name.is_some(),
+        KeyA | KeyB | KeyC | KeyD | KeyE | KeyF | KeyG | KeyH | KeyI | KeyJ | KeyK | KeyL
+        | KeyM | KeyN | KeyO | KeyP | KeyQ | KeyR | KeyS | KeyT | KeyU | KeyV | KeyW | KeyX
+        | KeyY | KeyZ | Num0 | Num1 | Num2 | Num3 | Num4 | Num5 | Num6 | Num7 | Num8 | Num9
+        | Kp0 | Kp1 | Kp2 | Kp3 | Kp4 | Kp5 | Kp6 | Kp7 | Kp8 | Kp9 | KpMinus | KpPlus
+        | KpMultiply | KpDivide | Space | BackQuote | Minus | Equal | LeftBracket
+        | RightBracket | SemiColon | Quote | BackSlash | IntlBackslash | Comma | Dot | Slash => {
+            true
+        }
+        _ => false,
+    }
+}
 
-        // 在单独的线程中运行 rdev 监听器
-        std::thread::spawn(move || {
-            let mut last_press_time: Option<Instant> = None;
-            let mut press_count: u8 = 0;
-            let tx = tx;
+/// 获取互斥锁，忽略锁中毒
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
 
-            let callback = move |event: rdev::Event| {
-                if !running.load(Ordering::SeqCst) {
-                    return;
-                }
+/// 平台相关的事件循环控制，用于重建时终止阻塞中的 rdev::listen
+#[cfg(target_os = "macos")]
+mod event_loop {
+    use std::ffi::c_void;
 
-                if let rdev::EventType::KeyPress(key) = event.event_type {
-                    let key_str = key_to_string(key);
-
-                    if key_str == target_key {
-                        let now = Instant::now();
-
-                        // 检查是否在时间间隔内
-                        if let Some(last) = last_press_time {
-                            if now.duration_since(last) <= interval {
-                                press_count += 1;
-                                debug!(
-                                    "Consecutive key press detected: {} (count: {})",
-                                    key_str, press_count
-                                );
-                            } else {
-                                // 超时，重新计数
-                                press_count = 1;
-                                debug!("Key press timeout, resetting count");
-                            }
-                        } else {
-                            press_count = 1;
-                        }
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFRunLoopGetCurrent() -> *mut c_void;
+        fn CFRunLoopStop(run_loop: *mut c_void);
+    }
+
+    /// 监听线程的 CFRunLoop
+    pub struct LoopHandle(usize);
+
+    impl LoopHandle {
+        pub fn current() -> Self {
+            Self(unsafe { CFRunLoopGetCurrent() } as usize)
+        }
+    }
 
-                        last_press_time = Some(now);
+    /// 是否能终止 rdev::listen
+    pub const STOPPABLE: bool = true;
 
-                        // 检查是否达到目标次数
-                        if press_count >= target_count {
-                            info!("Consecutive key trigger activated!");
-                            press_count = 0;
-                            last_press_time = None;
+    /// 停止 CFRunLoopRun，rdev::listen 随之返回
+    pub fn stop(handle: &LoopHandle) {
+        unsafe { CFRunLoopStop(handle.0 as *mut c_void) }
+    }
+}
 
-                            // 发送触发信号
-                            if let Err(e) = tx.blocking_send(()) {
-                                error!("Failed to send trigger signal: {}", e);
-                            }
-                        }
-                    } else {
-                        // 按了其他键，重置计数
-                        if press_count > 0 {
-                            debug!("Different key pressed, resetting count");
-                        }
-                        press_count = 0;
-                        last_press_time = None;
-                    }
-                }
-            };
+/// 平台相关的事件循环控制，用于重建时终止阻塞中的 rdev::listen
+#[cfg(target_os = "windows")]
+mod event_loop {
+    const WM_QUIT: u32 = 0x0012;
 
-            // 启动 rdev 监听
-            // 注意：macOS 需要"输入监控"权限，否则会失败
-            info!("Starting rdev listener (requires Input Monitoring permission on macOS)");
-            match rdev::listen(callback) {
-                Ok(_) => info!("rdev listener stopped normally"),
-                Err(e) => {
-                    error!("Failed to start key listener: {:?}", e);
-                    error!("On macOS, please grant Input Monitoring permission in:");
-                    error!("System Settings > Privacy & Security > Input Monitoring");
-                }
-            }
-        });
+    extern "system" {
+        fn GetCurrentThreadId() -> u32;
+    }
 
-        rx
+    #[link(name = "user32")]
+    extern "system" {
+        fn PostThreadMessageW(thread_id: u32, msg: u32, w_param: usize, l_param: isize) -> i32;
     }
 
-    /// 停止监听器
-    pub fn stop(&self) {
-        self.running.store(false, Ordering::SeqCst);
-        info!("Key listener stopped");
+    /// 监听线程的线程 ID
+    pub struct LoopHandle(u32);
+
+    impl LoopHandle {
+        pub fn current() -> Self {
+            Self(unsafe { GetCurrentThreadId() })
+        }
     }
 
-    /// 检查是否正在运行
-    pub fn is_running(&self) -> bool {
-        self.running.load(Ordering::SeqCst)
+    /// 是否能终止 rdev::listen
+    pub const STOPPABLE: bool = true;
+
+    /// 向监听线程发送 WM_QUIT，GetMessage 随之返回
+    pub fn stop(handle: &LoopHandle) {
+        unsafe {
+            PostThreadMessageW(handle.0, WM_QUIT, 0, 0);
+        }
     }
 }
 
-impl Default for KeyListener {
-    fn default() -> Self {
-        Self::new()
+/// 其他平台无法终止 rdev::listen，不重建监听
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod event_loop {
+    pub struct LoopHandle;
+
+    impl LoopHandle {
+        pub fn current() -> Self {
+            Self
+        }
+    }
+
+    /// 是否能终止 rdev::listen
+    pub const STOPPABLE: bool = false;
+
+    pub fn stop(_handle: &LoopHandle) {}
+}
+
+/// 运行 rdev 监听（阻塞当前线程）
+fn run_listener<F>(callback: F) -> Result<(), String>
+where
+    F: FnMut(rdev::Event) + 'static,
+{
+    // 注意：macOS 需要"输入监控"权限，否则会失败
+    info!("Starting rdev listener (requires Input Monitoring permission on macOS)");
+    match rdev::listen(callback) {
+        Ok(_) => {
+            info!("rdev listener stopped normally");
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to start key listener: {:?}", e);
+            error!("On macOS, please grant Input Monitoring permission in:");
+            error!("System Settings > Privacy & Security > Input Monitoring");
+            Err(format!("键盘监听启动失败: {:?}", e))
+        }
+    }
+}
+
+/// 将 rdev 鼠标按键转换为配置中的按键编号
+///
+/// 注意：rdev 0.5 在 macOS 上只上报左右键，中键和侧键无法检测。
+fn button_number(button: rdev::Button) -> Option<u8> {
+    match button {
+        rdev::Button::Left => Some(1),
+        rdev::Button::Right => Some(2),
+        rdev::Button::Middle => Some(3),
+        // Windows: XBUTTON1/XBUTTON2
+        #[cfg(target_os = "windows")]
+        rdev::Button::Unknown(n) => n.checked_add(3),
+        // X11: 4-7 为滚轮，8/9 为侧键
+        #[cfg(not(target_os = "windows"))]
+        rdev::Button::Unknown(n) => n.checked_sub(4).filter(|b| *b >= 4),
+    }
+}
+
+/// 获取修饰键名称对应的通用名称，如 "MetaLeft" -> "Meta"
+fn modifier_base_name(key: &str) -> Option<&'static str> {
+    let base = key
+        .strip_suffix("Left")
+        .or_else(|| key.strip_suffix("Right"))
+        .unwrap_or(key);
+    ["Meta", "Control", "Alt", "Shift"]
+        .into_iter()
+        .find(|m| *m == base)
+}
+
+/// 是否为修饰键（包括 Fn）
+fn is_modifier_key(key: rdev::Key) -> bool {
+    modifier_name(key).is_some() || key == rdev::Key::Function
+}
+
+/// 将修饰键映射为配置中使用的名称
+fn modifier_name(key: rdev::Key) -> Option<&'static str> {
+    match key {
+        rdev::Key::MetaLeft | rdev::Key::MetaRight => Some("Meta"),
+        rdev::Key::ControlLeft | rdev::Key::ControlRight => Some("Control"),
+        rdev::Key::Alt | rdev::Key::AltGr => Some("Alt"),
+        rdev::Key::ShiftLeft | rdev::Key::ShiftRight => Some("Shift"),
+        _ => None,
     }
 }
 
@@ -162,6 +1664,22 @@ fn key_to_string(key: rdev::Key) -> String {
         rdev::Key::Tab => "Tab".to_string(),
         rdev::Key::Backspace => "Backspace".to_string(),
         rdev::Key::Escape => "Escape".to_string(),
+        rdev::Key::CapsLock => "CapsLock".to_string(),
+        rdev::Key::Insert => "Insert".to_string(),
+        rdev::Key::Delete => "Delete".to_string(),
+        rdev::Key::Home => "Home".to_string(),
+        rdev::Key::End => "End".to_string(),
+        rdev::Key::PageUp => "PageUp".to_string(),
+        rdev::Key::PageDown => "PageDown".to_string(),
+        rdev::Key::ShiftLeft => "ShiftLeft".to_string(),
+        rdev::Key::ShiftRight => "ShiftRight".to_string(),
+        rdev::Key::ControlLeft => "ControlLeft".to_string(),
+        rdev::Key::ControlRight => "ControlRight".to_string(),
+        rdev::Key::Alt => "AltLeft".to_string(),
+        rdev::Key::AltGr => "AltRight".to_string(),
+        rdev::Key::MetaLeft => "MetaLeft".to_string(),
+        rdev::Key::MetaRight => "MetaRight".to_string(),
+        rdev::Key::Function => "Fn".to_string(),
         rdev::Key::KeyA => "a".to_string(),
         rdev::Key::KeyB => "b".to_string(),
         rdev::Key::KeyC => "c".to_string(),
@@ -198,10 +1716,40 @@ fn key_to_string(key: rdev::Key) -> String {
         rdev::Key::Num7 => "7".to_string(),
         rdev::Key::Num8 => "8".to_string(),
         rdev::Key::Num9 => "9".to_string(),
+        rdev::Key::Unknown(code) => unknown_key_name(code)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{:?}", key)),
         _ => format!("{:?}", key),
     }
 }
 
+/// rdev 没有对应枚举值的按键（如 F13–F19），按 macOS 虚拟键码转换为名称
+#[cfg(target_os = "macos")]
+fn unknown_key_name(code: u32) -> Option<&'static str> {
+    crate::hotkey::MAC_KEYCODES
+        .iter()
+        .find(|(keycode, _)| *keycode == code)
+        .map(|(_, name)| *name)
+}
+
+/// rdev 没有对应枚举值的按键（如 F13–F19），按 Windows 虚拟键码转换为名称
+#[cfg(target_os = "windows")]
+fn unknown_key_name(code: u32) -> Option<&'static str> {
+    // VK_F13 (0x7C) 到 VK_F24 (0x87)
+    const NAMES: [&str; 12] = [
+        "F13", "F14", "F15", "F16", "F17", "F18", "F19", "F20", "F21", "F22", "F23", "F24",
+    ];
+    code.checked_sub(0x7C)
+        .and_then(|i| NAMES.get(i as usize))
+        .copied()
+}
+
+/// 其他平台的键码没有统一表，保留 rdev 的默认名称
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn unknown_key_name(_code: u32) -> Option<&'static str> {
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -211,6 +1759,8 @@ mod tests {
         assert_eq!(key_to_string(rdev::Key::Space), " ");
         assert_eq!(key_to_string(rdev::Key::KeyA), "a");
         assert_eq!(key_to_string(rdev::Key::Return), "Enter");
+        assert_eq!(key_to_string(rdev::Key::CapsLock), "CapsLock");
+        assert_eq!(key_to_string(rdev::Key::PageDown), "PageDown");
     }
 
     #[test]
@@ -220,4 +1770,653 @@ mod tests {
         assert_eq!(config.count, 3);
         assert_eq!(config.interval_ms, 300);
     }
+
+    #[test]
+    fn test_restart_backoff() {
+        let start = Instant::now();
+        let mut backoff = RestartBackoff::new();
+        backoff.started(start);
+
+        assert_eq!(backoff.schedule(start), Some(Duration::from_secs(1)));
+        // 等待中的重启不会重复安排
+        assert_eq!(backoff.schedule(start), None);
+
+        let mut now = start;
+        for expected in [2, 4, 8, 16] {
+            backoff.started(now);
+            now += Duration::from_millis(100);
+            assert_eq!(backoff.schedule(now), Some(Duration::from_secs(expected)));
+        }
+        backoff.started(now);
+        assert_eq!(backoff.schedule(now), None);
+        assert!(backoff.exhausted());
+
+        // 稳定运行后再次失败，重新计数
+        backoff.started(now);
+        now += RESTART_STABLE_AFTER;
+        assert_eq!(backoff.schedule(now), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    #[ignore = "starts a real rdev::listen, needs input monitoring permission"]
+    fn test_listener_stop_and_restart() {
+        let mut listener = KeyListener::new();
+        let mut first = listener.start(vec![(
+            ListenerTrigger::Consecutive(ConsecutiveKeyConfig::default()),
+            "full".to_string(),
+        )]);
+
+        listener.stop();
+        assert!(!listener.is_running());
+        // 停止后旧的接收端关闭
+        assert!(first.blocking_recv().is_none());
+
+        // 再次启动得到新的可用接收端
+        let mut second = listener.start(vec![(
+            ListenerTrigger::Consecutive(ConsecutiveKeyConfig::default()),
+            "full".to_string(),
+        )]);
+        assert!(matches!(
+            second.try_recv(),
+            Err(mpsc::error::TryRecvError::Empty)
+        ));
+
+        listener.stop();
+        assert!(!listener.is_running());
+        assert!(second.blocking_recv().is_none());
+    }
+
+    fn ctrl_space_hold() -> HoldDetector {
+        HoldDetector::new(&HoldKeyConfig {
+            modifiers: vec!["Control".to_string()],
+            key: " ".to_string(),
+            hold_ms: 600,
+        })
+    }
+
+    fn consecutive_detector(key: &str, count: u8) -> ConsecutiveDetector {
+        ConsecutiveDetector::new(&ConsecutiveKeyConfig {
+            key: key.to_string(),
+            count,
+            interval_ms: 300,
+            matching: KeyMatching::Physical,
+        })
+    }
+
+    /// 模拟一次单击（按下 30ms 后松开），返回是否触发
+    fn tap(detector: &mut ConsecutiveDetector, key: rdev::Key, at: Instant) -> bool {
+        let pressed = detector.on_press(key, at);
+        let released = detector.on_release(key, at + Duration::from_millis(30));
+        pressed || released
+    }
+
+    #[test]
+    fn test_consecutive_detector_matches_typed_character() {
+        // AZERTY 布局：物理位置 Q 输入 "a"，物理位置 A 输入 "q"
+        let mut detector = ConsecutiveDetector::new(&ConsecutiveKeyConfig {
+            key: "a".to_string(),
+            count: 2,
+            interval_ms: 300,
+            matching: KeyMatching::Character,
+        });
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut type_key = |key, name, ms| {
+            let pressed = detector.on_key_press(key, Some(name), at(ms));
+            let released = detector.on_release(key, at(ms + 30));
+            pressed || released
+        };
+
+        assert!(!type_key(rdev::Key::KeyA, "q", 0));
+        assert!(!type_key(rdev::Key::KeyQ, "a", 100));
+        assert!(type_key(rdev::Key::KeyQ, "a", 200));
+
+        // 按物理按键匹配时使用 QWERTY 位置
+        let mut physical = consecutive_detector("a", 2);
+        assert!(!physical.on_key_press(rdev::Key::KeyQ, Some("a"), at(0)));
+        physical.on_release(rdev::Key::KeyQ, at(30));
+        assert!(!physical.on_key_press(rdev::Key::KeyQ, Some("a"), at(100)));
+    }
+
+    #[test]
+    fn test_consecutive_detectors_count_independently() {
+        let mut space = ConsecutiveDetector::new(&ConsecutiveKeyConfig::default());
+        let mut letter = consecutive_detector("a", 2);
+
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        assert!(!tap(&mut space, rdev::Key::Space, at(0)));
+        assert!(!tap(&mut space, rdev::Key::Space, at(100)));
+        assert!(tap(&mut space, rdev::Key::Space, at(200)));
+
+        assert!(!tap(&mut letter, rdev::Key::KeyA, at(300)));
+        assert!(tap(&mut letter, rdev::Key::KeyA, at(400)));
+
+        // 超过间隔后重新计数
+        assert!(!tap(&mut letter, rdev::Key::KeyA, at(1000)));
+        assert!(!tap(&mut letter, rdev::Key::KeyA, at(1500)));
+    }
+
+    /// 统计当前线程的内存分配次数，用于验证按键检测的快速路径不分配内存
+    /// 只统计当前线程在 `count` 范围内的内存分配，其他测试线程的分配不受影响
+    mod alloc_counter {
+        use std::alloc::{GlobalAlloc, Layout, System};
+        use std::cell::Cell;
+
+        thread_local! {
+            static COUNTING: Cell<bool> = const { Cell::new(false) };
+            static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        struct CountingAllocator;
+
+        unsafe impl GlobalAlloc for CountingAllocator {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                if COUNTING.try_with(Cell::get).unwrap_or(false) {
+                    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+                }
+                System.alloc(layout)
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                System.dealloc(ptr, layout)
+            }
+        }
+
+        #[global_allocator]
+        static GLOBAL: CountingAllocator = CountingAllocator;
+
+        /// 运行 `f` 并返回其间当前线程的分配次数
+        pub fn count(f: impl FnOnce()) -> usize {
+            ALLOCATIONS.with(|count| count.set(0));
+            COUNTING.with(|counting| counting.set(true));
+            f();
+            COUNTING.with(|counting| counting.set(false));
+            ALLOCATIONS.with(Cell::get)
+        }
+    }
+
+    #[test]
+    fn test_non_matching_keys_do_not_allocate() {
+        let mut physical = ConsecutiveDetector::new(&ConsecutiveKeyConfig {
+            matching: KeyMatching::Physical,
+            ..Default::default()
+        });
+        let mut by_character = ConsecutiveDetector::new(&ConsecutiveKeyConfig::default());
+        let mut hold = ctrl_space_hold();
+        let now = Instant::now();
+        // 修饰键集合首次插入时分配空间，之后的按下/松开复用
+        hold.on_press(rdev::Key::ControlLeft, now);
+        hold.on_release(rdev::Key::ControlLeft, now);
+
+        let allocations = alloc_counter::count(|| {
+            for key in [rdev::Key::KeyA, rdev::Key::Num1, rdev::Key::Return] {
+                assert!(!physical.on_key_press(key, None, now));
+                assert!(!physical.on_release(key, now));
+                assert!(!by_character.on_key_press(key, Some("a"), now));
+                assert!(!by_character.on_release(key, now));
+                hold.on_press(key, now);
+                assert!(!hold.on_release(key, now));
+            }
+            hold.on_press(rdev::Key::ControlLeft, now);
+            assert!(!hold.on_release(rdev::Key::ControlLeft, now));
+        });
+        assert_eq!(allocations, 0);
+    }
+
+    #[test]
+    fn test_chord_detector() {
+        let pending: ChordState = Arc::new(Mutex::new(None));
+        let config = ChordKeyConfig {
+            first_modifiers: vec!["Control".to_string()],
+            first_key: "k".to_string(),
+            second_key: "l".to_string(),
+            matching: KeyMatching::Physical,
+            pending: pending.clone(),
+        };
+        let mut detector = ChordDetector::new(&config, "selected");
+        let mut other = ChordDetector::new(&config, "full");
+
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let begin = |at: Instant| {
+            *lock(&pending) = Some(PendingChord {
+                mode: "selected".to_string(),
+                deadline: at + Duration::from_millis(1000),
+            });
+        };
+
+        // 未进入等待状态时第二步按键不触发
+        assert!(!detector.on_key_press(rdev::Key::KeyL, None, at(0)));
+
+        // 第一步组合键本身的事件晚于等待开始到达时被忽略
+        begin(at(0));
+        assert!(!detector.on_key_press(rdev::Key::ControlLeft, None, at(0)));
+        assert!(!detector.on_key_press(rdev::Key::KeyK, None, at(5)));
+        detector.on_release(rdev::Key::KeyK);
+        detector.on_release(rdev::Key::ControlLeft);
+        // 其他模式的检测器不响应
+        assert!(!other.on_key_press(rdev::Key::KeyL, None, at(100)));
+        assert!(detector.on_key_press(rdev::Key::KeyL, None, at(200)));
+        assert!(lock(&pending).is_none());
+
+        // 其他按键结束等待
+        begin(at(1000));
+        assert!(!detector.on_key_press(rdev::Key::KeyM, None, at(1100)));
+        assert!(!detector.on_key_press(rdev::Key::KeyL, None, at(1200)));
+
+        // 超时后不再触发
+        begin(at(3000));
+        assert!(!detector.on_key_press(rdev::Key::KeyL, None, at(4500)));
+        assert!(lock(&pending).is_none());
+    }
+
+    #[test]
+    fn test_consecutive_non_character_keys() {
+        let config = |key: &str| ConsecutiveKeyConfig {
+            key: key.to_string(),
+            count: 2,
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        // 配置中的别名按规范化名称匹配
+        let mut escape = ConsecutiveDetector::new(&config("Esc"));
+        assert!(!tap(&mut escape, rdev::Key::Escape, at(0)));
+        assert!(tap(&mut escape, rdev::Key::Escape, at(100)));
+
+        let mut page_up = ConsecutiveDetector::new(&config("pgup"));
+        assert!(!tap(&mut page_up, rdev::Key::PageUp, at(0)));
+        assert!(tap(&mut page_up, rdev::Key::PageUp, at(100)));
+
+        // 按下/松开成对出现的平台上，大写锁定键和普通按键一样计数
+        let mut caps = ConsecutiveDetector::new(&config("CapsLock"));
+        caps.toggle = false;
+        assert!(!tap(&mut caps, rdev::Key::CapsLock, at(0)));
+        assert!(tap(&mut caps, rdev::Key::CapsLock, at(100)));
+    }
+
+    /// 构造带输入字符的 rdev 事件
+    fn key_event(event_type: rdev::EventType, name: Option<&str>) -> rdev::Event {
+        rdev::Event {
+            time: std::time::SystemTime::now(),
+            name: name.map(str::to_string),
+            event_type,
+        }
+    }
+
+    /// 通过监听器的检测器模拟一次单击，返回触发时输入的字符数
+    fn tap_trigger(
+        detector: &mut TriggerDetector,
+        key: rdev::Key,
+        name: Option<&str>,
+        at: Instant,
+    ) -> Option<usize> {
+        let pressed = detector.on_event(&key_event(rdev::EventType::KeyPress(key), name), at);
+        let released = detector.on_event(
+            &key_event(rdev::EventType::KeyRelease(key), None),
+            at + Duration::from_millis(30),
+        );
+        pressed.or(released)
+    }
+
+    #[test]
+    fn test_typed_chars_count_only_character_keys() {
+        let consecutive = |key: &str, count| {
+            TriggerDetector::new(
+                &ListenerTrigger::Consecutive(ConsecutiveKeyConfig {
+                    key: key.to_string(),
+                    count,
+                    interval_ms: 300,
+                    matching: KeyMatching::Character,
+                }),
+                "full",
+            )
+        };
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        let mut space = consecutive(" ", 3);
+        assert_eq!(
+            tap_trigger(&mut space, rdev::Key::Space, Some(" "), at(0)),
+            None
+        );
+        assert_eq!(
+            tap_trigger(&mut space, rdev::Key::Space, Some(" "), at(100)),
+            None
+        );
+        // 中途按下导航键重新计数，之前输入的空格不属于这次触发
+        assert_eq!(
+            tap_trigger(&mut space, rdev::Key::Home, None, at(150)),
+            None
+        );
+        assert_eq!(
+            tap_trigger(&mut space, rdev::Key::Space, Some(" "), at(200)),
+            None
+        );
+        assert_eq!(
+            tap_trigger(&mut space, rdev::Key::Space, Some(" "), at(300)),
+            None
+        );
+        assert_eq!(
+            tap_trigger(&mut space, rdev::Key::Space, Some(" "), at(400)),
+            Some(3)
+        );
+
+        // Esc、导航键和功能键不输入字符，macOS 给出的是控制字符或私有区字符
+        for (name, key, text) in [
+            ("Escape", rdev::Key::Escape, Some("\u{1b}")),
+            ("Home", rdev::Key::Home, None),
+            ("Left", rdev::Key::LeftArrow, Some("\u{f702}")),
+            ("F5", rdev::Key::F5, Some("\u{f708}")),
+            ("CapsLock", rdev::Key::CapsLock, None),
+        ] {
+            let mut detector = consecutive(name, 2);
+            if let TriggerDetector::Consecutive(inner) = &mut detector {
+                inner.toggle = false;
+            }
+            assert_eq!(tap_trigger(&mut detector, key, text, at(1000)), None);
+            assert_eq!(
+                tap_trigger(&mut detector, key, text, at(1100)),
+                Some(0),
+                "{}",
+                name
+            );
+        }
+
+        // 两步组合键的第二步只有字符键输入内容，按住 Control 时不输入
+        let pending: ChordState = Arc::new(Mutex::new(None));
+        let chord = |second_key: &str| {
+            TriggerDetector::new(
+                &ListenerTrigger::Chord(ChordKeyConfig {
+                    first_modifiers: vec!["Control".to_string()],
+                    first_key: "k".to_string(),
+                    second_key: second_key.to_string(),
+                    matching: KeyMatching::Physical,
+                    pending: pending.clone(),
+                }),
+                "selected",
+            )
+        };
+        let begin = || {
+            *lock(&pending) = Some(PendingChord {
+                mode: "selected".to_string(),
+                deadline: at(10_000),
+            });
+        };
+        let mut letter = chord("l");
+        begin();
+        assert_eq!(
+            tap_trigger(&mut letter, rdev::Key::KeyL, Some("l"), at(2000)),
+            Some(1)
+        );
+        begin();
+        letter.on_event(
+            &key_event(rdev::EventType::KeyPress(rdev::Key::ControlLeft), None),
+            at(2100),
+        );
+        assert_eq!(
+            tap_trigger(&mut letter, rdev::Key::KeyL, Some("\u{c}"), at(2200)),
+            Some(0)
+        );
+        let mut arrow = chord("Left");
+        begin();
+        assert_eq!(
+            tap_trigger(&mut arrow, rdev::Key::LeftArrow, None, at(3000)),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_consecutive_caps_lock_toggle_events() {
+        let mut caps = ConsecutiveDetector::new(&ConsecutiveKeyConfig {
+            key: "CapsLock".to_string(),
+            count: 2,
+            ..Default::default()
+        });
+        caps.toggle = true;
+
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        // 第一次单击打开大写锁定（按下），第二次关闭（松开）
+        assert!(!caps.on_press(rdev::Key::CapsLock, at(0)));
+        assert!(caps.on_release(rdev::Key::CapsLock, at(150)));
+
+        // 两次之间超过间隔时重新计数
+        assert!(!caps.on_press(rdev::Key::CapsLock, at(1000)));
+        assert!(!caps.on_release(rdev::Key::CapsLock, at(2000)));
+        assert!(caps.on_press(rdev::Key::CapsLock, at(2100)));
+    }
+
+    #[test]
+    fn test_consecutive_ignores_auto_repeat() {
+        let mut space = ConsecutiveDetector::new(&ConsecutiveKeyConfig::default());
+
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        // 按住空格：系统每隔一段时间重复发送按下事件
+        for ms in [0, 100, 130, 160, 190, 220] {
+            assert!(!space.on_press(rdev::Key::Space, at(ms)));
+        }
+        assert!(!space.on_release(rdev::Key::Space, at(250)));
+
+        // 松开后重新开始计数
+        assert!(!tap(&mut space, rdev::Key::Space, at(400)));
+        assert!(!tap(&mut space, rdev::Key::Space, at(500)));
+        assert!(tap(&mut space, rdev::Key::Space, at(600)));
+    }
+
+    #[test]
+    fn test_consecutive_modifier_taps() {
+        let mut shift = consecutive_detector("Shift", 3);
+
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        // 按住 Shift 输入大写字母不算单击
+        assert!(!shift.on_press(rdev::Key::ShiftLeft, at(0)));
+        assert!(!tap(&mut shift, rdev::Key::KeyA, at(20)));
+        assert!(!shift.on_release(rdev::Key::ShiftLeft, at(60)));
+
+        // 左右 Shift 的单击都计数
+        assert!(!tap(&mut shift, rdev::Key::ShiftLeft, at(200)));
+        assert!(!tap(&mut shift, rdev::Key::ShiftRight, at(300)));
+        assert!(tap(&mut shift, rdev::Key::ShiftLeft, at(400)));
+    }
+
+    #[test]
+    fn test_modifier_does_not_reset_character_target() {
+        let mut letter = consecutive_detector("a", 2);
+
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        assert!(!tap(&mut letter, rdev::Key::KeyA, at(0)));
+        assert!(!tap(&mut letter, rdev::Key::ShiftLeft, at(50)));
+        assert!(tap(&mut letter, rdev::Key::KeyA, at(100)));
+    }
+
+    #[test]
+    fn test_consecutive_resets_when_modifier_held() {
+        let mut space = ConsecutiveDetector::new(&ConsecutiveKeyConfig::default());
+
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        // Cmd + Space 连按三次不触发
+        for ms in [0, 100, 200] {
+            assert!(!space.on_press(rdev::Key::MetaLeft, at(ms)));
+            assert!(!tap(&mut space, rdev::Key::Space, at(ms + 10)));
+            assert!(!space.on_release(rdev::Key::MetaLeft, at(ms + 50)));
+        }
+
+        // 中间夹杂 Ctrl + Space 时重新计数
+        assert!(!tap(&mut space, rdev::Key::Space, at(300)));
+        assert!(!tap(&mut space, rdev::Key::Space, at(400)));
+        assert!(!space.on_press(rdev::Key::ControlLeft, at(450)));
+        assert!(!tap(&mut space, rdev::Key::Space, at(470)));
+        assert!(!space.on_release(rdev::Key::ControlLeft, at(520)));
+        assert!(!tap(&mut space, rdev::Key::Space, at(600)));
+        assert!(!tap(&mut space, rdev::Key::Space, at(700)));
+        assert!(tap(&mut space, rdev::Key::Space, at(800)));
+    }
+
+    #[test]
+    fn test_modifier_target_ignores_own_modifier() {
+        let mut meta = consecutive_detector("Meta", 2);
+
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        assert!(!tap(&mut meta, rdev::Key::MetaLeft, at(0)));
+        assert!(tap(&mut meta, rdev::Key::MetaRight, at(100)));
+
+        // 按住 Ctrl 时单击 Cmd 不计数
+        assert!(!meta.on_press(rdev::Key::ControlLeft, at(500)));
+        assert!(!tap(&mut meta, rdev::Key::MetaLeft, at(510)));
+        assert!(!tap(&mut meta, rdev::Key::MetaLeft, at(600)));
+        assert!(!meta.on_release(rdev::Key::ControlLeft, at(650)));
+    }
+
+    #[test]
+    fn test_mouse_click_detector() {
+        let mut detector = MouseClickDetector::new(&MouseClickConfig {
+            button: 3,
+            clicks: 2,
+            interval_ms: 300,
+        });
+
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        assert!(!detector.on_button_press(rdev::Button::Middle, at(0)));
+        assert!(detector.on_button_press(rdev::Button::Middle, at(150)));
+
+        // 其他按键打断连击
+        assert!(!detector.on_button_press(rdev::Button::Middle, at(500)));
+        assert!(!detector.on_button_press(rdev::Button::Left, at(550)));
+        assert!(!detector.on_button_press(rdev::Button::Middle, at(600)));
+
+        // 超过间隔重新计数
+        assert!(!detector.on_button_press(rdev::Button::Middle, at(1000)));
+        assert!(detector.on_button_press(rdev::Button::Middle, at(1100)));
+    }
+
+    #[test]
+    fn test_capture_detector() {
+        let mut detector = CaptureDetector::new();
+        assert!(detector.on_press(rdev::Key::ShiftLeft).is_none());
+        assert!(detector.on_press(rdev::Key::MetaLeft).is_none());
+
+        match detector.on_press(rdev::Key::KeyT) {
+            Some(CaptureEvent::Captured(Hotkey::Combination { modifiers, key })) => {
+                assert_eq!(modifiers, vec!["Meta".to_string(), "Shift".to_string()]);
+                assert_eq!(key, "t");
+            }
+            other => panic!("unexpected capture result: {:?}", other),
+        }
+
+        // 松开修饰键后只记录主键
+        detector.on_release(rdev::Key::ShiftLeft);
+        detector.on_release(rdev::Key::MetaLeft);
+        assert!(matches!(
+            detector.on_press(rdev::Key::F1),
+            Some(CaptureEvent::Captured(Hotkey::Combination { modifiers, .. })) if modifiers.is_empty()
+        ));
+
+        // 无法注册的按键返回原因，不结束录制
+        detector.on_press(rdev::Key::ControlLeft);
+        match detector.on_press(rdev::Key::Unknown(179)) {
+            Some(CaptureEvent::Invalid(invalid)) => assert_eq!(invalid.reason, "不支持的按键"),
+            other => panic!("unexpected capture result: {:?}", other),
+        }
+        assert!(matches!(
+            detector.on_press(rdev::Key::Space),
+            Some(CaptureEvent::Captured(Hotkey::Combination { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_hold_detector_triggers_after_threshold() {
+        let mut detector = ctrl_space_hold();
+        let start = Instant::now();
+
+        detector.on_press(rdev::Key::ControlLeft, start);
+        detector.on_press(rdev::Key::Space, start);
+        // 自动重复事件不应重置计时
+        detector.on_press(rdev::Key::Space, start + Duration::from_millis(300));
+        assert!(detector.on_release(rdev::Key::Space, start + Duration::from_millis(650)));
+    }
+
+    #[test]
+    fn test_hold_detector_early_release() {
+        let mut detector = ctrl_space_hold();
+        let start = Instant::now();
+
+        detector.on_press(rdev::Key::ControlLeft, start);
+        detector.on_press(rdev::Key::Space, start);
+        assert!(!detector.on_release(rdev::Key::Space, start + Duration::from_millis(200)));
+    }
+
+    #[test]
+    fn test_hold_detector_interleaved_key_cancels() {
+        let mut detector = ctrl_space_hold();
+        let start = Instant::now();
+
+        detector.on_press(rdev::Key::ControlLeft, start);
+        detector.on_press(rdev::Key::Space, start);
+        detector.on_press(rdev::Key::KeyA, start + Duration::from_millis(100));
+        assert!(!detector.on_release(rdev::Key::Space, start + Duration::from_millis(700)));
+
+        // 缺少修饰键时不计时
+        let mut detector = ctrl_space_hold();
+        detector.on_press(rdev::Key::Space, start);
+        assert!(!detector.on_release(rdev::Key::Space, start + Duration::from_millis(700)));
+    }
+
+    #[test]
+    fn test_hold_binding_shares_listener_events() {
+        let hold = ListenerTrigger::Hold(HoldKeyConfig {
+            modifiers: vec!["Control".to_string()],
+            key: " ".to_string(),
+            hold_ms: 600,
+        });
+        let consecutive = ListenerTrigger::Consecutive(ConsecutiveKeyConfig {
+            key: "Space".to_string(),
+            count: 2,
+            interval_ms: 300,
+            matching: KeyMatching::Physical,
+        });
+        let mut detectors = [
+            TriggerDetector::new(&hold, "selected"),
+            TriggerDetector::new(&consecutive, "full"),
+        ];
+        let event = |event_type| rdev::Event {
+            time: std::time::SystemTime::now(),
+            name: None,
+            event_type,
+        };
+        let start = Instant::now();
+        let mut feed = |event_type, at| -> Vec<Option<usize>> {
+            detectors
+                .iter_mut()
+                .map(|detector| detector.on_event(&event(event_type), at))
+                .collect()
+        };
+
+        // 同一组事件依次交给每个检测器，长按触发，按住 Control 的 Space 不计入连续按键
+        feed(rdev::EventType::KeyPress(rdev::Key::ControlLeft), start);
+        feed(rdev::EventType::KeyPress(rdev::Key::Space), start);
+        assert_eq!(
+            feed(
+                rdev::EventType::KeyRelease(rdev::Key::Space),
+                start + Duration::from_millis(650)
+            ),
+            [Some(0), None]
+        );
+    }
 }
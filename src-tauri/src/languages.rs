@@ -0,0 +1,243 @@
+//! 语言模块
+//! 内置的语言列表，供设置界面选择目标语言，并校验配置中的语言代码；按文字检测原文语言
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tracing::warn;
+
+/// 内置语言列表
+const LANGUAGES_JSON: &str = include_str!("data/languages.json");
+
+/// 语言列表中的一项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguageInfo {
+    /// 语言代码 (如 en-US)
+    pub code: String,
+    /// 英文名称 (如 Japanese)
+    pub english_name: String,
+    /// 本地名称 (如 日本語)
+    pub native_name: String,
+}
+
+/// 全部内置语言，按英文名称排序
+pub fn all_languages() -> &'static [LanguageInfo] {
+    static LANGUAGES: OnceLock<Vec<LanguageInfo>> = OnceLock::new();
+    LANGUAGES.get_or_init(|| {
+        let mut languages: Vec<LanguageInfo> = match serde_json::from_str(LANGUAGES_JSON) {
+            Ok(languages) => languages,
+            Err(e) => {
+                warn!("Failed to parse bundled language list: {}", e);
+                return Vec::new();
+            }
+        };
+        languages.sort_by(|a, b| a.english_name.cmp(&b.english_name));
+        languages
+    })
+}
+
+/// 按语言代码查找，忽略大小写
+pub fn find_language(code: &str) -> Option<&'static LanguageInfo> {
+    all_languages()
+        .iter()
+        .find(|language| language.code.eq_ignore_ascii_case(code))
+}
+
+/// 查找英文名称或本地名称以 `query` 开头的语言（忽略大小写），空查询返回全部
+pub fn search_languages(query: &str) -> Vec<LanguageInfo> {
+    let query = query.trim().to_lowercase();
+    all_languages()
+        .iter()
+        .filter(|language| {
+            query.is_empty()
+                || language.english_name.to_lowercase().starts_with(&query)
+                || language.native_name.to_lowercase().starts_with(&query)
+        })
+        .cloned()
+        .collect()
+}
+
+/// 判断英文时使用的常见词，不包含在其他拉丁字母语言中也常见的词
+const ENGLISH_WORDS: &[&str] = &[
+    "the", "and", "of", "to", "is", "are", "were", "been", "that", "this", "these", "those",
+    "have", "has", "had", "you", "your", "with", "what", "which", "would", "should", "could", "it",
+    "its", "they", "their", "there", "not", "can", "from", "about", "how", "why", "when", "our",
+    "she", "does", "did",
+];
+
+/// 根据文字判断原文语言，只识别有把握的情况，无法判断时返回 `None`
+///
+/// 含有假名的文本判断为日语，其他文本按占多数的文字判断：谚文为韩语，汉字为中文，
+/// 泰文、希腊文、希伯来文、阿拉伯文、天城文各对应一种语言。拉丁字母的文本只在没有
+/// 非 ASCII 字母且常见英文词足够多时判断为英语。返回内置语言列表中的代码。
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let mut counts: HashMap<Script, usize> = HashMap::new();
+    let mut letters = 0;
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        if let Some(script) = Script::of(c) {
+            *counts.entry(script).or_default() += 1;
+        }
+    }
+    if letters == 0 {
+        return None;
+    }
+
+    let count = |script| counts.get(&script).copied().unwrap_or_default();
+    let kana = count(Script::Kana);
+    if kana > 0 && (kana + count(Script::Han)) * 2 > letters {
+        return Some("ja-JP");
+    }
+    let (script, dominant) = counts.iter().max_by_key(|(_, count)| **count)?;
+    if dominant * 10 < letters * 6 {
+        return None;
+    }
+    match script {
+        Script::Kana => None,
+        Script::Hangul => Some("ko-KR"),
+        Script::Han => Some("zh-CN"),
+        Script::Thai => Some("th-TH"),
+        Script::Greek => Some("el-GR"),
+        Script::Hebrew => Some("he-IL"),
+        Script::Arabic => Some("ar-SA"),
+        Script::Devanagari => Some("hi-IN"),
+        Script::Latin => is_english(text).then_some("en-US"),
+    }
+}
+
+/// 至少四分之一的词是常见英文词，且没有非 ASCII 字母
+fn is_english(text: &str) -> bool {
+    if text.chars().any(|c| c.is_alphabetic() && !c.is_ascii()) {
+        return false;
+    }
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_ascii_alphabetic() && c != '\'')
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+        .collect();
+    let common = words
+        .iter()
+        .filter(|word| ENGLISH_WORDS.contains(&word.as_str()))
+        .count();
+    common > 0 && common * 4 >= words.len()
+}
+
+/// 检测语言时区分的文字
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Script {
+    Latin,
+    Kana,
+    Han,
+    Hangul,
+    Thai,
+    Greek,
+    Hebrew,
+    Arabic,
+    Devanagari,
+}
+
+impl Script {
+    fn of(c: char) -> Option<Self> {
+        Some(match c {
+            'a'..='z' | 'A'..='Z' | '\u{c0}'..='\u{24f}' => Self::Latin,
+            '\u{3040}'..='\u{30ff}' | '\u{31f0}'..='\u{31ff}' | '\u{ff66}'..='\u{ff9f}' => {
+                Self::Kana
+            }
+            '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}' => {
+                Self::Han
+            }
+            '\u{1100}'..='\u{11ff}' | '\u{3130}'..='\u{318f}' | '\u{ac00}'..='\u{d7af}' => {
+                Self::Hangul
+            }
+            '\u{e00}'..='\u{e7f}' => Self::Thai,
+            '\u{370}'..='\u{3ff}' => Self::Greek,
+            '\u{590}'..='\u{5ff}' => Self::Hebrew,
+            '\u{600}'..='\u{6ff}' => Self::Arabic,
+            '\u{900}'..='\u{97f}' => Self::Devanagari,
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_language_table() {
+        let languages = all_languages();
+        assert!(languages.len() >= 100);
+
+        let codes: HashSet<String> = languages
+            .iter()
+            .map(|language| language.code.to_lowercase())
+            .collect();
+        assert_eq!(codes.len(), languages.len());
+        assert!(languages
+            .windows(2)
+            .all(|pair| pair[0].english_name <= pair[1].english_name));
+
+        // 默认的常用语言都在列表中
+        for code in ["en-US", "zh-CN", "ja-JP", "ko-KR", "fr-FR", "es-ES"] {
+            assert!(find_language(code).is_some(), "{}", code);
+        }
+        assert_eq!(find_language("ja-jp").unwrap().native_name, "日本語");
+        assert!(find_language("xx-XX").is_none());
+    }
+
+    #[test]
+    fn test_search_languages() {
+        let codes = |query: &str| -> Vec<String> {
+            search_languages(query)
+                .into_iter()
+                .map(|language| language.code)
+                .collect()
+        };
+
+        assert_eq!(codes("japan"), ["ja-JP"]);
+        assert_eq!(codes("日本"), ["ja-JP"]);
+        assert_eq!(codes(" DEUTSCH "), ["de-DE"]);
+        assert_eq!(codes("portuguese").len(), 2);
+        // 只匹配名称开头
+        assert!(codes("anese").is_empty());
+        assert_eq!(search_languages("").len(), all_languages().len());
+    }
+
+    #[test]
+    fn test_detect_language() {
+        for (text, expected) in [
+            ("How are you doing today?", "en-US"),
+            ("Please review this change before it is merged.", "en-US"),
+            ("今天天气很好，我们去公园吧。", "zh-CN"),
+            ("今日はいい天気ですね。", "ja-JP"),
+            ("東京タワー", "ja-JP"),
+            ("안녕하세요, 만나서 반갑습니다.", "ko-KR"),
+            ("สวัสดีครับ", "th-TH"),
+            ("Καλημέρα σας", "el-GR"),
+            ("شكرا جزيلا", "ar-SA"),
+            // 少量英文词不影响判断
+            ("这个 PR 需要 review 一下，然后合并到主分支", "zh-CN"),
+        ] {
+            assert_eq!(detect_language(text), Some(expected), "{}", text);
+        }
+
+        // 没有把握时不判断
+        for text in [
+            "",
+            "12345 !?",
+            "Hello world",
+            "Bonjour, comment ça va ?",
+            "Het is een goed idee",
+            "Привет, как дела?",
+            "PR review 这个 change",
+        ] {
+            assert_eq!(detect_language(text), None, "{}", text);
+        }
+        for code in [
+            "en-US", "zh-CN", "ja-JP", "ko-KR", "th-TH", "el-GR", "he-IL", "ar-SA", "hi-IN",
+        ] {
+            assert!(find_language(code).is_some(), "{}", code);
+        }
+    }
+}
@@ -3,24 +3,37 @@
 //! 一个基于 Tauri 的 macOS 翻译应用，支持全局热键触发翻译
 
 pub mod config;
+pub mod crypto;
 pub mod database;
 pub mod error;
+pub mod error_log;
 pub mod hotkey;
 pub mod key_listener;
+pub mod languages;
 pub mod llm;
+pub mod login_item;
+pub mod redaction;
+pub mod secrets;
 pub mod text_handler;
 
 mod commands;
 mod state;
 
-use config::Hotkey;
-use key_listener::{ConsecutiveKeyConfig, KeyListener};
-use state::AppState;
+use config::{Hotkey, HotkeyConfig, KeyMatching, TriggerKeyHandling};
+use database::{MetricRecord, TranslationMetadata};
+use futures_util::future::Either;
+use key_listener::{
+    ChordKeyConfig, ConsecutiveKeyConfig, HoldKeyConfig, KeyListener, ListenerStatus,
+    ListenerTrigger, MouseClickConfig, Trigger,
+};
+use redaction::Screening;
+use state::{AppState, HotkeyRegistration, LastTranslation};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use tauri::{Emitter, Manager};
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
 use tracing::{debug, error, info, warn};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer as _};
 
 /// 构建托盘菜单
 pub(crate) async fn build_tray_menu(
@@ -32,6 +45,7 @@ pub(crate) async fn build_tray_menu(
     let config = state.config.read().await;
     let current_target = config.language.current_target.clone();
     let is_enabled = *state.is_enabled.read().await;
+    let privacy_mode = !config.history_mode.records_text();
 
     info!("构建托盘菜单，当前目标语言: {}", current_target);
     info!("当前启用状态: {}", is_enabled);
@@ -57,6 +71,17 @@ pub(crate) async fn build_tray_menu(
     }
     let lang_menu = lang_submenu.build().map_err(|e| e.to_string())?;
 
+    // 键盘监听异常时在菜单顶部显示提示
+    let listener_warning = match state.listener_status() {
+        ListenerStatus::Failed { reason } => Some(
+            MenuItemBuilder::with_id("listener_warning", format!("⚠ 热键监听异常: {}", reason))
+                .enabled(false)
+                .build(app)
+                .map_err(|e| e.to_string())?,
+        ),
+        _ => None,
+    };
+
     let toggle_label = if is_enabled {
         "✓ 已启用"
     } else {
@@ -65,19 +90,36 @@ pub(crate) async fn build_tray_menu(
     let toggle = MenuItemBuilder::with_id("toggle", toggle_label)
         .build(app)
         .map_err(|e| e.to_string())?;
+    let privacy_label = if privacy_mode {
+        "✓ 隐私模式（不保存文本）"
+    } else {
+        "  隐私模式（不保存文本）"
+    };
+    let privacy = MenuItemBuilder::with_id("privacy", privacy_label)
+        .build(app)
+        .map_err(|e| e.to_string())?;
     let settings = MenuItemBuilder::with_id("settings", "打开设置")
         .build(app)
         .map_err(|e| e.to_string())?;
+    let history = MenuItemBuilder::with_id("history", "翻译历史")
+        .build(app)
+        .map_err(|e| e.to_string())?;
     let quit = MenuItemBuilder::with_id("quit", "退出")
         .build(app)
         .map_err(|e| e.to_string())?;
 
-    let menu = MenuBuilder::new(app)
+    let mut menu = MenuBuilder::new(app);
+    if let Some(warning) = &listener_warning {
+        menu = menu.item(warning).separator();
+    }
+    let menu = menu
         .item(&lang_menu)
         .separator()
         .item(&toggle)
+        .item(&privacy)
         .separator()
         .item(&settings)
+        .item(&history)
         .separator()
         .item(&quit)
         .build()
@@ -86,9 +128,107 @@ pub(crate) async fn build_tray_menu(
     Ok(menu)
 }
 
-/// 检查 macOS 辅助功能权限
+/// 独立历史记录窗口的 label
+const HISTORY_WINDOW_LABEL: &str = "history";
+
+/// 打开独立的历史记录窗口，已存在时显示并聚焦
+///
+/// 检查和创建在同一把锁内完成，快速连续调用不会创建重复的窗口。
+/// 创建窗口需要主线程处理，因此不能在主线程上调用。
+pub(crate) fn open_history_window(app: &tauri::AppHandle) -> tauri::Result<()> {
+    static OPENING: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    let _guard = OPENING
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    if let Some(window) = app.get_webview_window(HISTORY_WINDOW_LABEL) {
+        window.unminimize()?;
+        window.show()?;
+        return window.set_focus();
+    }
+
+    info!("Creating history window");
+    tauri::WebviewWindowBuilder::new(
+        app,
+        HISTORY_WINDOW_LABEL,
+        tauri::WebviewUrl::App("index.html#history".into()),
+    )
+    .title("QuickTransType - 翻译历史")
+    .inner_size(960.0, 720.0)
+    .min_inner_size(600.0, 400.0)
+    .resizable(true)
+    .center()
+    .build()?;
+    Ok(())
+}
+
+/// 切换翻译的启用/暂停状态，托盘菜单和暂停热键共用
+pub(crate) async fn toggle_enabled(app: &tauri::AppHandle) -> bool {
+    let state = app.state::<Arc<AppState>>();
+    let new_status = state.toggle_enabled().await;
+
+    info!("Translation monitoring toggled to: {}", new_status);
+    notify_enabled_status(app, &state, new_status).await;
+    new_status
+}
+
+/// 切换隐私模式，开启后翻译历史只保存性能指标，不保存原文和译文
+pub(crate) async fn toggle_privacy_mode(app: &tauri::AppHandle) {
+    let state = app.state::<Arc<AppState>>();
+    let saved = state
+        .update_config(|config| {
+            config.history_mode = config.history_mode.toggle_privacy();
+            Ok::<(), error::AppError>(())
+        })
+        .await;
+    match saved {
+        Ok(config) => info!("History mode toggled to: {:?}", config.history_mode),
+        Err(e) => {
+            error!("Failed to save history mode: {}", e);
+            return;
+        }
+    }
+    refresh_tray_menu(app, &state).await;
+    if let Err(e) = app.emit("config-updated", ()) {
+        error!("Failed to emit config-updated event: {}", e);
+    }
+}
+
+/// 启用状态变化后重新注册热键、更新托盘菜单，并发送 enabled-status-changed 事件通知前端
+pub(crate) async fn notify_enabled_status(
+    app: &tauri::AppHandle,
+    state: &Arc<AppState>,
+    enabled: bool,
+) {
+    reregister_hotkeys(app).await;
+    refresh_tray_menu(app, state).await;
+
+    // 发送事件通知前端
+    if let Err(e) = app.emit("enabled-status-changed", enabled) {
+        error!("Failed to emit enabled-status-changed event: {}", e);
+    }
+}
+
+/// 按当前状态重新构建托盘菜单
+pub(crate) async fn refresh_tray_menu(app: &tauri::AppHandle, state: &Arc<AppState>) {
+    #[cfg(desktop)]
+    {
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        if let Ok(new_menu) = build_tray_menu(app, state).await {
+            if let Some(tray) = app.tray_by_id("main") {
+                let _ = tray.set_menu(None::<tauri::menu::Menu<tauri::Wry>>);
+                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                if let Err(e) = tray.set_menu(Some(new_menu)) {
+                    error!("Failed to update tray menu: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// 检查 macOS 辅助功能权限，`prompt` 为 true 时未授权会弹出系统授权提示
 #[cfg(target_os = "macos")]
-fn check_accessibility_permission() -> bool {
+pub(crate) fn check_accessibility_permission(prompt: bool) -> bool {
     use std::ffi::c_void;
 
     #[link(name = "ApplicationServices", kind = "framework")]
@@ -109,6 +249,7 @@ fn check_accessibility_permission() -> bool {
         fn CFRelease(cf: *const c_void);
 
         static kCFBooleanTrue: *const c_void;
+        static kCFBooleanFalse: *const c_void;
         static kCFTypeDictionaryKeyCallBacks: c_void;
         static kCFTypeDictionaryValueCallBacks: c_void;
     }
@@ -133,7 +274,11 @@ fn check_accessibility_permission() -> bool {
         );
 
         let keys = [key];
-        let values = [kCFBooleanTrue];
+        let values = [if prompt {
+            kCFBooleanTrue
+        } else {
+            kCFBooleanFalse
+        }];
 
         let options = CFDictionaryCreate(
             std::ptr::null(),
@@ -154,10 +299,172 @@ fn check_accessibility_permission() -> bool {
 }
 
 #[cfg(not(target_os = "macos"))]
-fn check_accessibility_permission() -> bool {
+pub(crate) fn check_accessibility_permission(_prompt: bool) -> bool {
+    true
+}
+
+/// IOHIDRequestType: kIOHIDRequestTypeListenEvent
+#[cfg(target_os = "macos")]
+const K_IOHID_REQUEST_TYPE_LISTEN_EVENT: u32 = 1;
+
+#[cfg(target_os = "macos")]
+#[link(name = "IOKit", kind = "framework")]
+extern "C" {
+    fn IOHIDCheckAccess(request_type: u32) -> u32;
+    fn IOHIDRequestAccess(request_type: u32) -> bool;
+}
+
+/// 检查 macOS 输入监控权限（连续按键/长按热键依赖 rdev 监听全局键盘事件）
+#[cfg(target_os = "macos")]
+pub(crate) fn check_input_monitoring_permission() -> bool {
+    // kIOHIDAccessTypeGranted
+    const K_IOHID_ACCESS_TYPE_GRANTED: u32 = 0;
+
+    unsafe { IOHIDCheckAccess(K_IOHID_REQUEST_TYPE_LISTEN_EVENT) == K_IOHID_ACCESS_TYPE_GRANTED }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn check_input_monitoring_permission() -> bool {
+    true
+}
+
+/// 请求 macOS 输入监控权限，首次调用时系统会弹出授权提示
+#[cfg(target_os = "macos")]
+fn request_input_monitoring_permission() -> bool {
+    unsafe { IOHIDRequestAccess(K_IOHID_REQUEST_TYPE_LISTEN_EVENT) }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn request_input_monitoring_permission() -> bool {
     true
 }
 
+/// 系统权限的授权状态
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub(crate) struct PermissionStatus {
+    /// 辅助功能权限（键盘模拟）
+    accessibility: bool,
+    /// 输入监控权限（连续按键/长按热键）
+    input_monitoring: bool,
+}
+
+/// 获取当前的权限授权状态，不会弹出授权提示
+pub(crate) fn permission_status() -> PermissionStatus {
+    PermissionStatus {
+        accessibility: check_accessibility_permission(false),
+        input_monitoring: check_input_monitoring_permission(),
+    }
+}
+
+/// 热键注册状态
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct HotkeyStatus {
+    /// 当前生效的热键及注册结果，暂停期间只包含暂停热键
+    hotkeys: Vec<HotkeyRegistration>,
+    /// 连续按键/长按/鼠标热键使用的键盘监听器状态
+    listener: ListenerStatus,
+    /// 是否已授予键盘监听所需的输入监控权限
+    input_monitoring: bool,
+}
+
+/// 获取最近一次热键注册的结果和键盘监听器状态
+pub(crate) fn hotkey_status(state: &AppState) -> HotkeyStatus {
+    HotkeyStatus {
+        hotkeys: state.hotkey_registrations(),
+        listener: state.listener_status(),
+        input_monitoring: check_input_monitoring_permission(),
+    }
+}
+
+/// 诊断信息，用于反馈问题，不包含 API Key 等敏感配置
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct AppInfo {
+    /// 应用版本
+    version: String,
+    /// 操作系统及版本
+    os: String,
+    config_path: String,
+    /// 数据库文件路径，内存数据库为空
+    database_path: Option<String>,
+    /// 数据库文件大小（字节）
+    database_size: Option<u64>,
+    /// 选中翻译是否启用流式输出
+    stream_selected: bool,
+    /// 全文翻译是否启用流式输出
+    stream_full: bool,
+    /// 超过该字符数的原文不使用流式输出
+    stream_min_chars: Option<usize>,
+    model: String,
+    /// 已配置的热键：标识 -> 显示形式
+    hotkeys: std::collections::BTreeMap<&'static str, String>,
+    permissions: PermissionStatus,
+}
+
+/// 收集诊断信息
+pub(crate) async fn app_info(app: &tauri::AppHandle, state: &AppState) -> AppInfo {
+    let config = state.get_config().await;
+    let database_path = state.database.path();
+    let database_size = database_path
+        .as_ref()
+        .and_then(|path| std::fs::metadata(path).ok())
+        .map(|metadata| metadata.len());
+
+    AppInfo {
+        version: app.package_info().version.to_string(),
+        os: os_version(),
+        config_path: state.config_path().display().to_string(),
+        database_path: database_path.map(|path| path.display().to_string()),
+        database_size,
+        stream_selected: config.llm.stream_selected,
+        stream_full: config.llm.stream_full,
+        stream_min_chars: config.llm.stream_min_chars,
+        model: config.llm.model.clone(),
+        hotkeys: config
+            .hotkey
+            .bindings()
+            .into_iter()
+            .map(|(id, _, hotkey)| (id, hotkey.format()))
+            .collect(),
+        permissions: permission_status(),
+    }
+}
+
+/// 操作系统名称、版本和架构，如 `macos 14.5 (aarch64)`
+fn os_version() -> String {
+    #[cfg(target_os = "macos")]
+    let version = std::process::Command::new("sw_vers")
+        .arg("-productVersion")
+        .output()
+        .ok()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|version| !version.is_empty());
+    #[cfg(not(target_os = "macos"))]
+    let version: Option<String> = None;
+
+    match version {
+        Some(version) => format!(
+            "{} {} ({})",
+            std::env::consts::OS,
+            version,
+            std::env::consts::ARCH
+        ),
+        None => format!("{} ({})", std::env::consts::OS, std::env::consts::ARCH),
+    }
+}
+
+/// 权限类型对应的系统设置页面
+pub(crate) fn permission_settings_url(kind: &str) -> Option<&'static str> {
+    match kind {
+        "accessibility" => {
+            Some("x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility")
+        }
+        "input_monitoring" => {
+            Some("x-apple.systempreferences:com.apple.preference.security?Privacy_ListenEvent")
+        }
+        _ => None,
+    }
+}
+
 /// 将配置中的热键转换为 Shortcut
 fn hotkey_to_shortcut(hotkey: &Hotkey) -> Option<Shortcut> {
     match hotkey {
@@ -174,45 +481,17 @@ fn hotkey_to_shortcut(hotkey: &Hotkey) -> Option<Shortcut> {
             }
 
             // 解析按键码
-            let code = match key.to_lowercase().as_str() {
-                "a" => tauri_plugin_global_shortcut::Code::KeyA,
-                "b" => tauri_plugin_global_shortcut::Code::KeyB,
-                "c" => tauri_plugin_global_shortcut::Code::KeyC,
-                "d" => tauri_plugin_global_shortcut::Code::KeyD,
-                "e" => tauri_plugin_global_shortcut::Code::KeyE,
-                "f" => tauri_plugin_global_shortcut::Code::KeyF,
-                "g" => tauri_plugin_global_shortcut::Code::KeyG,
-                "h" => tauri_plugin_global_shortcut::Code::KeyH,
-                "i" => tauri_plugin_global_shortcut::Code::KeyI,
-                "j" => tauri_plugin_global_shortcut::Code::KeyJ,
-                "k" => tauri_plugin_global_shortcut::Code::KeyK,
-                "l" => tauri_plugin_global_shortcut::Code::KeyL,
-                "m" => tauri_plugin_global_shortcut::Code::KeyM,
-                "n" => tauri_plugin_global_shortcut::Code::KeyN,
-                "o" => tauri_plugin_global_shortcut::Code::KeyO,
-                "p" => tauri_plugin_global_shortcut::Code::KeyP,
-                "q" => tauri_plugin_global_shortcut::Code::KeyQ,
-                "r" => tauri_plugin_global_shortcut::Code::KeyR,
-                "s" => tauri_plugin_global_shortcut::Code::KeyS,
-                "t" => tauri_plugin_global_shortcut::Code::KeyT,
-                "u" => tauri_plugin_global_shortcut::Code::KeyU,
-                "v" => tauri_plugin_global_shortcut::Code::KeyV,
-                "w" => tauri_plugin_global_shortcut::Code::KeyW,
-                "x" => tauri_plugin_global_shortcut::Code::KeyX,
-                "y" => tauri_plugin_global_shortcut::Code::KeyY,
-                "z" => tauri_plugin_global_shortcut::Code::KeyZ,
-                " " => tauri_plugin_global_shortcut::Code::Space,
-                "space" => tauri_plugin_global_shortcut::Code::Space,
-                _ => {
-                    warn!("Unsupported key: {}", key);
-                    return None;
-                }
+            let Some(code) = hotkey::shortcut_code(key) else {
+                warn!("Unsupported key: {}", key);
+                return None;
             };
 
             Some(Shortcut::new(Some(mods), code))
         }
-        Hotkey::Consecutive { .. } => {
-            // 连续按键不使用全局快捷键，需要单独处理
+        // 两步组合键只有第一步注册为全局快捷键
+        Hotkey::Chord { first, .. } => hotkey_to_shortcut(&first.to_hotkey()),
+        Hotkey::Consecutive { .. } | Hotkey::Hold { .. } | Hotkey::MouseButton { .. } => {
+            // 连续按键、长按和鼠标按键不使用全局快捷键，需要单独处理
             None
         }
     }
@@ -224,119 +503,859 @@ fn register_global_shortcuts(
     state: &Arc<AppState>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let config = tauri::async_runtime::block_on(async { state.get_config().await });
+    let enabled = tauri::async_runtime::block_on(state.is_enabled());
+
+    // 注册翻译热键和暂停热键，所有连续按键绑定共用一个监听器
+    let bindings = active_bindings(&config.hotkey, enabled);
+    update_idle_listener_status(app.handle(), &bindings);
+    register_bindings(app.handle(), bindings, config.hotkey.key_matching);
+
+    Ok(())
+}
+
+/// 按启用状态重新注册所有热键
+/// 暂停期间注销翻译热键并停止 rdev 监听，让其他应用重新收到这些按键，只保留暂停热键
+pub(crate) async fn reregister_hotkeys(app: &tauri::AppHandle) {
+    let state = app.state::<Arc<AppState>>();
+    if let Err(e) = app.global_shortcut().unregister_all() {
+        error!("Failed to unregister global shortcuts: {}", e);
+    }
+
+    // 录制热键期间监听器已停止，录制结束后会按启用状态恢复
+    let capturing = state.is_capturing_hotkey();
+    if !capturing {
+        state.stop_key_listener();
+    }
+
+    let config = state.get_config().await;
+    let enabled = state.is_enabled().await;
+    let bindings: Vec<_> = active_bindings(&config.hotkey, enabled)
+        .into_iter()
+        .filter(|(hotkey, _)| !capturing || !hotkey.uses_key_listener())
+        .collect();
+    if !capturing {
+        update_idle_listener_status(app, &bindings);
+    }
+
+    register_bindings(app, bindings, config.hotkey.key_matching);
+    info!(
+        "Hotkeys re-registered ({})",
+        if enabled { "enabled" } else { "paused" }
+    );
+}
+
+/// 注册一组热键并启动共用的 rdev 监听器，单个热键失败不影响其他热键，结果记录到 `AppState`
+fn register_bindings(
+    app: &tauri::AppHandle,
+    bindings: Vec<(&Hotkey, &'static str)>,
+    key_matching: KeyMatching,
+) {
+    let mut listener_triggers = Vec::new();
+    let mut registrations = Vec::with_capacity(bindings.len());
+    for (hotkey, mode) in bindings {
+        let error = register_hotkey(app, hotkey, mode, key_matching, &mut listener_triggers)
+            .err()
+            .map(|e| {
+                error!("Failed to register {} mode hotkey: {}", mode, e);
+                e.to_string()
+            });
+        registrations.push(HotkeyRegistration {
+            mode: mode.to_string(),
+            hotkey: hotkey.clone(),
+            label: hotkey.format(),
+            registered: error.is_none(),
+            error,
+        });
+    }
+    start_trigger_listener(app.clone(), listener_triggers);
+    app.state::<Arc<AppState>>()
+        .set_hotkey_registrations(registrations);
+}
+
+/// 暂停热键在触发通道中使用的模式名
+const TOGGLE_ENABLED_MODE: &str = "toggle_enabled";
+/// 翻译到剪贴板的模式名，同时用于历史记录和性能指标
+const CLIPBOARD_MODE: &str = "clipboard";
+/// 剪贴板翻译通知中译文预览的最大字符数
+const CLIPBOARD_PREVIEW_CHARS: usize = 80;
+
+/// 翻译到剪贴板完成后发送给前端的通知
+#[derive(Clone, serde::Serialize)]
+struct ClipboardTranslation {
+    /// 目标语言
+    target_lang: String,
+    /// 译文预览，过长时截断
+    preview: String,
+}
+
+/// 原文包含不能发送给 LLM 的内容、取消翻译时发送给前端的通知，不包含匹配的内容
+#[derive(Clone, serde::Serialize)]
+struct BlockedTranslation {
+    /// 翻译模式
+    mode: String,
+    /// 匹配的位置数
+    matches: usize,
+}
+
+/// 所有热键与对应的模式：翻译模式或暂停热键
+fn hotkey_bindings(config: &HotkeyConfig) -> Vec<(&Hotkey, &'static str)> {
+    let mut bindings = vec![
+        (&config.selected_mode, "selected"),
+        (&config.full_mode, "full"),
+    ];
+    if let Some(clipboard) = &config.clipboard_mode {
+        bindings.push((clipboard, CLIPBOARD_MODE));
+    }
+    if let Some(toggle) = &config.toggle_enabled {
+        bindings.push((toggle, TOGGLE_ENABLED_MODE));
+    }
+    bindings
+}
+
+/// 当前应生效的热键：暂停期间只保留暂停热键
+fn active_bindings(config: &HotkeyConfig, enabled: bool) -> Vec<(&Hotkey, &'static str)> {
+    hotkey_bindings(config)
+        .into_iter()
+        .filter(|(_, mode)| enabled || *mode == TOGGLE_ENABLED_MODE)
+        .collect()
+}
+
+/// 没有需要 rdev 监听的热键时，将监听状态标记为已停止
+fn update_idle_listener_status(app: &tauri::AppHandle, bindings: &[(&Hotkey, &'static str)]) {
+    if !bindings
+        .iter()
+        .any(|(hotkey, _)| hotkey.uses_key_listener())
+    {
+        app.state::<Arc<AppState>>()
+            .set_listener_status(ListenerStatus::Stopped);
+    }
+}
+
+/// 重新启动基于 rdev 的热键监听（连续按键和长按），暂停期间只启动暂停热键的监听
+pub(crate) fn restart_key_listeners(app: &tauri::AppHandle, config: &HotkeyConfig, enabled: bool) {
+    let bindings = active_bindings(config, enabled);
+    update_idle_listener_status(app, &bindings);
+    let mut listener_triggers = Vec::new();
+    for (hotkey, mode) in bindings {
+        if hotkey.uses_key_listener() {
+            if let Err(e) = register_hotkey(
+                app,
+                hotkey,
+                mode,
+                config.key_matching,
+                &mut listener_triggers,
+            ) {
+                error!("Failed to restart {} mode key listener: {}", mode, e);
+            }
+        }
+    }
+    start_trigger_listener(app.clone(), listener_triggers);
+}
+
+/// 按热键类型注册单个触发器
+/// 连续按键、长按、鼠标连击和两步组合键绑定只收集到 `listener_triggers` 中，由调用方统一启动监听器
+fn register_hotkey(
+    app: &tauri::AppHandle,
+    hotkey: &Hotkey,
+    mode: &'static str,
+    key_matching: KeyMatching,
+    listener_triggers: &mut Vec<(ListenerTrigger, String)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match hotkey {
+        Hotkey::Combination { key, .. } => {
+            // 组合键模式
+            let Some(shortcut) = hotkey_to_shortcut(hotkey) else {
+                return Err(format!("Unsupported key: {}", key).into());
+            };
+            let app_handle = app.clone();
+
+            app.global_shortcut()
+                .on_shortcut(shortcut, move |_app, _shortcut, event| {
+                    if event.state == ShortcutState::Pressed {
+                        debug!("{} mode hotkey triggered", mode);
+                        let handle = app_handle.clone();
+                        let pressed_at = Instant::now();
+                        tauri::async_runtime::spawn(async move {
+                            if !accept_trigger(&handle, mode, pressed_at).await {
+                                return;
+                            }
+                            if let Err(e) = dispatch_trigger(&handle, mode, 0).await {
+                                error!("Translation failed: {}", e);
+                            }
+                        });
+                    }
+                })?;
+
+            info!("Registered {} mode hotkey: {:?}", mode, hotkey);
+        }
+        Hotkey::Consecutive { key, count, .. } => {
+            // 连续按键模式 - 使用 rdev 监听器
+            let key_config = ConsecutiveKeyConfig {
+                key: key.clone(),
+                count: *count,
+                interval_ms: hotkey
+                    .consecutive_interval_ms()
+                    .unwrap_or(config::DEFAULT_CONSECUTIVE_INTERVAL_MS),
+                matching: key_matching,
+            };
+
+            listener_triggers.push((ListenerTrigger::Consecutive(key_config), mode.to_string()));
+            info!(
+                "Registered {} mode consecutive key: '{}' x {}",
+                mode, key, count
+            );
+        }
+        Hotkey::Hold {
+            modifiers,
+            key,
+            hold_ms,
+        } => {
+            // 长按模式 - 与连续按键共用 rdev 监听器
+            let hold_config = HoldKeyConfig {
+                modifiers: modifiers.clone(),
+                key: key.clone(),
+                hold_ms: *hold_ms,
+            };
+
+            listener_triggers.push((ListenerTrigger::Hold(hold_config), mode.to_string()));
+            info!("Registered {} mode hold hotkey: {}", mode, hotkey.format());
+        }
+        Hotkey::Chord {
+            first,
+            second_key,
+            timeout_ms,
+        } => {
+            // 两步组合键模式 - 第一步注册为全局快捷键，第二步由 rdev 监听器检测
+            let Some(shortcut) = hotkey_to_shortcut(hotkey) else {
+                return Err(format!("Unsupported key: {}", first.key).into());
+            };
+            let app_handle = app.clone();
+            let timeout = Duration::from_millis(*timeout_ms);
+
+            app.global_shortcut()
+                .on_shortcut(shortcut, move |_app, _shortcut, event| {
+                    if event.state == ShortcutState::Pressed {
+                        app_handle
+                            .state::<Arc<AppState>>()
+                            .hotkey_manager
+                            .begin_chord(mode, timeout);
+                    }
+                })?;
+
+            let chord_config = ChordKeyConfig {
+                first_modifiers: first.modifiers.clone(),
+                first_key: first.key.clone(),
+                second_key: second_key.clone(),
+                matching: key_matching,
+                pending: app.state::<Arc<AppState>>().hotkey_manager.chord_state(),
+            };
+            listener_triggers.push((ListenerTrigger::Chord(chord_config), mode.to_string()));
+            info!("Registered {} mode chord: {}", mode, hotkey.format());
+        }
+        Hotkey::MouseButton {
+            button,
+            clicks,
+            interval_ms,
+        } => {
+            // 鼠标按键模式 - 与连续按键共用 rdev 监听器
+            let click_config = MouseClickConfig {
+                button: *button,
+                clicks: *clicks,
+                interval_ms: *interval_ms,
+            };
+
+            listener_triggers.push((ListenerTrigger::MouseClick(click_config), mode.to_string()));
+            info!(
+                "Registered {} mode mouse trigger: {}",
+                mode,
+                hotkey.format()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// 启动连续按键/长按/鼠标连击监听器，所有绑定共用一个 rdev 监听
+/// rdev 只保存一个全局回调，同时运行两个 `rdev::listen` 会互相覆盖
+fn start_trigger_listener(app_handle: tauri::AppHandle, bindings: Vec<(ListenerTrigger, String)>) {
+    if bindings.is_empty() {
+        return;
+    }
+
+    // 启动和登记在同一把锁内完成，并发的重启不会同时跑两个 rdev 监听
+    let state = app_handle.state::<Arc<AppState>>().inner().clone();
+    let mut listener = KeyListener::new();
+    listener.set_suppressed_flag(state.hotkey_suppression_flag());
+    listener.set_enabled_flag(state.enabled_flag(), vec![TOGGLE_ENABLED_MODE.to_string()]);
+    let started = state.start_key_listener(|| {
+        let rx = listener.start(bindings);
+        state.set_listener_status(ListenerStatus::Running);
+        state.listener_backoff().started(Instant::now());
+        (listener.handle(), rx)
+    });
+    let Some((id, rx)) = started else {
+        debug!("Hotkey capture in progress, key listener not started");
+        return;
+    };
+
+    // 在单独的线程中转发触发信号，会话已被新会话替换时不再上报失败
+    std::thread::spawn(move || {
+        forward_triggers(&app_handle, rx);
+        if state.release_key_listener(id) {
+            report_listener_failure(&app_handle, &listener);
+        }
+    });
+}
+
+/// 将 rdev 监听器的触发信号转发为对应模式的翻译（阻塞当前线程）
+fn forward_triggers(app_handle: &tauri::AppHandle, mut rx: tokio::sync::mpsc::Receiver<Trigger>) {
+    // 使用 tokio 运行时处理接收到的触发信号
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create tokio runtime");
+
+    rt.block_on(async {
+        while let Some(Trigger { mode, typed_chars }) = rx.recv().await {
+            debug!("Key listener trigger received for {} mode", mode);
+            let handle = app_handle.clone();
+            if !accept_trigger(&handle, &mode, Instant::now()).await {
+                continue;
+            }
+
+            if let Err(e) = dispatch_trigger(&handle, &mode, typed_chars).await {
+                error!("{} translation failed: {}", mode, e);
+            }
+        }
+    });
+}
+
+/// 按配置删除触发热键时输入到文本中的字符
+/// 只在热键确实生效时调用，未生效的触发保留输入
+async fn erase_trigger_keys(state: &AppState, typed_chars: usize) {
+    if typed_chars == 0 {
+        return;
+    }
+
+    let erased = match state.get_config().await.hotkey.trigger_keys {
+        TriggerKeyHandling::Backspace => state.text_handler.delete_chars(typed_chars).await,
+        TriggerKeyHandling::Keep => Ok(()),
+    };
+    if let Err(e) = erased {
+        warn!("Failed to erase trigger keys: {}", e);
+    }
+}
+
+/// 监听线程启动失败时，将错误记录到应用状态并通知前端
+fn report_listener_failure(app_handle: &tauri::AppHandle, listener: &KeyListener) {
+    let Some(err) = listener.last_error() else {
+        return;
+    };
+
+    let state = app_handle.state::<Arc<AppState>>();
+    tauri::async_runtime::block_on(state.set_key_listener_error(Some(err.clone())));
+    state.set_listener_status(ListenerStatus::Failed {
+        reason: err.clone(),
+    });
+
+    if let Err(e) = app_handle.emit("key-listener-error", err) {
+        error!("Failed to emit key-listener-error event: {}", e);
+    }
+
+    schedule_listener_restart(app_handle);
+}
+
+/// 监听器意外退出后，按退避策略自动重启
+fn schedule_listener_restart(app_handle: &tauri::AppHandle) {
+    let state = app_handle.state::<Arc<AppState>>();
+    let mut backoff = state.listener_backoff();
+    let Some(delay) = backoff.schedule(Instant::now()) else {
+        if backoff.exhausted() {
+            warn!("Key listener keeps failing, automatic restart given up");
+        }
+        return;
+    };
+    drop(backoff);
+
+    info!("Restarting key listeners in {:?}", delay);
+    let app = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(delay).await;
+        reload_key_listeners(&app).await;
+    });
+}
+
+/// 停止所有 rdev 监听器，并按当前配置重新启动
+async fn reload_key_listeners(app: &tauri::AppHandle) {
+    let state = app.state::<Arc<AppState>>();
+
+    // 录制热键结束后会自行恢复监听
+    if state.is_capturing_hotkey() {
+        debug!("Hotkey capture in progress, skipping key listener reload");
+        return;
+    }
+
+    state.stop_key_listener();
+    let config = state.get_config().await;
+    restart_key_listeners(app, &config.hotkey, state.is_enabled().await);
+}
+
+/// 检测系统唤醒的轮询间隔
+const WAKE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// 系统时钟比单调时钟多走出该时长时，视为刚从休眠中唤醒
+const WAKE_DETECT_THRESHOLD: Duration = Duration::from_secs(10);
+
+/// 监控系统休眠唤醒，唤醒后主动重建 rdev 监听并重启监听会话
+/// macOS 休眠后事件监听可能失效，而 rdev 无法检测这种情况；
+/// 休眠期间单调时钟暂停、系统时钟继续走，据此判断刚刚唤醒
+fn spawn_wake_monitor(app_handle: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        let mut last_instant = Instant::now();
+        let mut last_wall = SystemTime::now();
+
+        loop {
+            std::thread::sleep(WAKE_CHECK_INTERVAL);
+
+            let elapsed = last_instant.elapsed();
+            let wall_elapsed = last_wall.elapsed().unwrap_or_default();
+            last_instant = Instant::now();
+            last_wall = SystemTime::now();
+
+            if wall_elapsed <= elapsed + WAKE_DETECT_THRESHOLD {
+                continue;
+            }
+
+            info!("System wake detected, reloading key listeners");
+            tauri::async_runtime::block_on(async {
+                let state = app_handle.state::<Arc<AppState>>();
+                if state.get_config().await.hotkey.uses_key_listener() {
+                    key_listener::restart_event_source();
+                    reload_key_listeners(&app_handle).await;
+                }
+            });
+        }
+    });
+}
+
+/// 检查是否需要自动备份数据库的间隔
+const BACKUP_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 定期检查并备份数据库，长时间运行时也能保持每天一份备份
+fn spawn_backup_scheduler(state: &Arc<AppState>) {
+    let database = state.database.clone();
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(BACKUP_CHECK_INTERVAL).await;
+            if let Err(e) = database.backup_if_due().await {
+                warn!("Failed to back up database: {}", e);
+            }
+        }
+    });
+}
+
+/// 监听器状态变化时更新托盘菜单，并发送 listener-status-changed 事件通知前端
+fn spawn_listener_status_watcher(app_handle: tauri::AppHandle, state: &Arc<AppState>) {
+    let mut status_rx = state.subscribe_listener_status();
+    let state = state.clone();
+
+    tauri::async_runtime::spawn(async move {
+        while status_rx.changed().await.is_ok() {
+            let status = status_rx.borrow_and_update().clone();
+            refresh_tray_menu(&app_handle, &state).await;
+
+            if let Err(e) = app_handle.emit("listener-status-changed", status) {
+                error!("Failed to emit listener-status-changed event: {}", e);
+            }
+        }
+    });
+}
+
+/// 按配置的去抖窗口过滤同一热键的重复触发，返回是否应当响应
+async fn accept_trigger(app: &tauri::AppHandle, mode: &str, now: Instant) -> bool {
+    let state = app.state::<Arc<AppState>>();
+    let window = Duration::from_millis(state.config.read().await.hotkey.debounce_ms);
+    let accepted = state.accept_trigger(mode, now, window);
+    if !accepted {
+        debug!("Ignoring duplicate {} trigger within {:?}", mode, window);
+    }
+    accepted
+}
+
+/// 执行热键对应的操作：暂停热键切换启用状态，其余触发翻译
+/// 暂停热键不受启用状态限制，暂停期间仍可用于恢复
+///
+/// `typed_chars` 为触发热键时输入到文本中的字符数，热键生效时按配置删除。
+async fn dispatch_trigger(
+    app: &tauri::AppHandle,
+    mode: &str,
+    typed_chars: usize,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if mode != TOGGLE_ENABLED_MODE {
+        return trigger_translation(app, mode, typed_chars).await;
+    }
+
+    let state = app.state::<Arc<AppState>>();
+    if state.is_capturing_hotkey() {
+        debug!("Hotkey capture in progress, skipping");
+        return Ok(());
+    }
+    erase_trigger_keys(&state, typed_chars).await;
+    toggle_enabled(app).await;
+    Ok(())
+}
+
+/// 热键翻译不执行的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TriggerBlock {
+    /// 翻译已暂停
+    Disabled,
+    /// 正在录制热键
+    CapturingHotkey,
+    /// 设置窗口获得焦点
+    SettingsFocused,
+}
+
+impl TriggerBlock {
+    /// 显示给用户的说明
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Disabled => "翻译已暂停，热键不会触发翻译",
+            Self::CapturingHotkey => "正在录制热键，热键不会触发翻译",
+            Self::SettingsFocused => "设置窗口获得焦点时热键不会触发翻译",
+        }
+    }
+}
+
+/// 检查当前状态是否允许热键翻译，`check_settings_focus` 为 false 时不检查设置窗口焦点
+async fn trigger_block(state: &AppState, check_settings_focus: bool) -> Option<TriggerBlock> {
+    if !state.is_enabled().await {
+        return Some(TriggerBlock::Disabled);
+    }
+    if state.is_capturing_hotkey() {
+        return Some(TriggerBlock::CapturingHotkey);
+    }
+    if check_settings_focus && state.hotkeys_suppressed() {
+        return Some(TriggerBlock::SettingsFocused);
+    }
+    None
+}
+
+/// 发送给 LLM 之前的检查结果
+enum Preparation {
+    /// 原文包含不能发送给 LLM 的内容，取消翻译
+    Blocked { matches: usize },
+    /// 可以发送
+    Ready(PreparedTranslation),
+}
+
+/// 通过检查、可以发送给 LLM 的翻译
+struct PreparedTranslation {
+    /// 代替了敏感内容的原文，收到译文后放回原来的内容
+    masked: Option<redaction::Masked>,
+    /// 检测到的原文语言
+    source_lang: Option<&'static str>,
+    /// 本次翻译使用的目标语言
+    target_lang: String,
+    /// 是否流式输出
+    use_stream: bool,
+}
+
+impl PreparedTranslation {
+    /// 实际发送给 LLM 的文本
+    fn llm_text<'a>(&'a self, text: &'a str) -> &'a str {
+        self.masked.as_ref().map_or(text, |m| m.text.as_str())
+    }
+}
+
+/// 发送给 LLM 之前的检查：过滤不能发送的内容，按原文语言选择目标语言并决定是否流式输出
+///
+/// 目标语言只写入本次翻译使用的配置副本，历史记录和指标都记录实际使用的目标语言。
+fn prepare_translation(
+    config: &mut config::AppConfig,
+    mode: &str,
+    text: &str,
+) -> error::Result<Preparation> {
+    // 规则无效时不发送
+    let masked = match redaction::screen(config.redaction_mode, &config.redaction_patterns, text)? {
+        Screening::Clean => None,
+        Screening::Blocked { matches } => {
+            warn!(
+                "Text has {} redaction matches, skipping {} translation",
+                matches, mode
+            );
+            return Ok(Preparation::Blocked { matches });
+        }
+        Screening::Masked(masked) => {
+            info!("Masked {} redaction matches", masked.originals.len());
+            Some(masked)
+        }
+    };
+
+    let source_lang = languages::detect_language(text);
+    let target_lang = config.language.effective_target(source_lang).to_string();
+    if target_lang != config.language.current_target {
+        info!(
+            "Detected {} source text, translating to {} instead of {}",
+            source_lang.unwrap_or_default(),
+            target_lang,
+            config.language.current_target
+        );
+        config.language.current_target = target_lang.clone();
+    }
+
+    // 按翻译模式选择是否流式输出，翻译到剪贴板和过长的原文一次性输出；
+    // 代替了敏感内容时需要完整的译文才能放回原来的内容，也一次性输出
+    let use_stream = masked.is_none() && config.llm.use_stream(mode, text.chars().count());
+
+    Ok(Preparation::Ready(PreparedTranslation {
+        masked,
+        source_lang,
+        target_lang,
+        use_stream,
+    }))
+}
+
+/// 译文的输出方式：热键翻译输出到当前应用，流程诊断只收集译文
+trait TranslationOutput {
+    /// 流式输出开始前调用，删除将被译文替换的原文
+    async fn begin_stream(&mut self) -> Result<(), String>;
+
+    /// 输出一段流式增量
+    async fn push_delta(&mut self, delta: &str);
+
+    /// 一次性输出完整的译文
+    async fn deliver(&mut self, translated_text: &str) -> Result<(), String>;
+
+    /// 流式输出中途失败或被取消，`typed` 为已经输出的部分
+    async fn abort_stream(&mut self, typed: &str);
+}
+
+/// 热键翻译的输出：替换当前应用中的原文，或写入剪贴板
+struct AppOutput<'a> {
+    app: &'a tauri::AppHandle,
+    state: &'a AppState,
+    to_clipboard: bool,
+    target_lang: &'a str,
+    /// 流式输出失败或取消时放回的原文
+    original_text: &'a str,
+}
 
-    // 注册选中翻译热键
-    if let Some(shortcut) = hotkey_to_shortcut(&config.hotkey.selected_mode) {
-        let app_handle = app.handle().clone();
-
-        app.global_shortcut()
-            .on_shortcut(shortcut, move |_app, _shortcut, event| {
-                if event.state == ShortcutState::Pressed {
-                    debug!("Selected mode hotkey triggered");
-                    let handle = app_handle.clone();
-                    tauri::async_runtime::spawn(async move {
-                        if let Err(e) = trigger_translation(&handle, "selected").await {
-                            error!("Translation failed: {}", e);
-                        }
-                    });
-                }
-            })?;
+impl TranslationOutput for AppOutput<'_> {
+    async fn begin_stream(&mut self) -> Result<(), String> {
+        // 流式模式：删除选中的文本，逐字输入
+        self.state
+            .text_handler
+            .delete_selection()
+            .await
+            .map_err(|e| format!("Failed to delete selection: {}", e))
+    }
 
-        info!(
-            "Registered selected mode hotkey: {:?}",
-            config.hotkey.selected_mode
-        );
+    async fn push_delta(&mut self, delta: &str) {
+        if let Err(e) = self.state.text_handler.type_chunk(delta).await {
+            error!("Failed to type chunk: {}", e);
+        }
     }
 
-    // 注册全文翻译热键
-    match &config.hotkey.full_mode {
-        Hotkey::Combination { .. } => {
-            // 组合键模式
-            if let Some(shortcut) = hotkey_to_shortcut(&config.hotkey.full_mode) {
-                let app_handle = app.handle().clone();
+    async fn deliver(&mut self, translated_text: &str) -> Result<(), String> {
+        deliver_translation(
+            self.app,
+            self.state,
+            self.to_clipboard,
+            self.target_lang,
+            translated_text,
+        )
+        .await
+        .map_err(|e| e.to_string())
+    }
 
-                app.global_shortcut()
-                    .on_shortcut(shortcut, move |_app, _shortcut, event| {
-                        if event.state == ShortcutState::Pressed {
-                            debug!("Full mode hotkey triggered");
-                            let handle = app_handle.clone();
-                            tauri::async_runtime::spawn(async move {
-                                if let Err(e) = trigger_translation(&handle, "full").await {
-                                    error!("Translation failed: {}", e);
-                                }
-                            });
-                        }
-                    })?;
+    async fn abort_stream(&mut self, typed: &str) {
+        restore_original_text(self.state, typed, self.original_text).await;
+    }
+}
 
-                info!("Registered full mode hotkey: {:?}", config.hotkey.full_mode);
-            }
-        }
-        Hotkey::Consecutive { key, count } => {
-            // 连续按键模式 - 使用 rdev 监听器
-            let app_handle = app.handle().clone();
-            let key_config = ConsecutiveKeyConfig {
-                key: key.clone(),
-                count: *count,
-                interval_ms: 300,
-            };
+/// LLM 请求或输出失败的原因
+enum TranslationFailure {
+    /// LLM 请求失败，需要记录失败的指标
+    Llm {
+        error_type: &'static str,
+        message: String,
+    },
+    /// 输出译文失败
+    Output(String),
+}
 
-            start_consecutive_key_listener(app_handle, key_config);
-            info!(
-                "Registered full mode consecutive key: '{}' x {}",
-                key, count
-            );
+/// 调用 LLM 并按 `output` 输出译文，热键翻译和流程诊断共用
+///
+/// `cancelled` 完成时停止输出并返回 `Ok(None)`。代替了敏感内容时一次性输出，译文中的占位符换回原来的内容。
+async fn request_translation(
+    llm_client: &llm::LLMClient,
+    llm: &config::LLMConfig,
+    prepared: &PreparedTranslation,
+    text: &str,
+    output: &mut impl TranslationOutput,
+    cancelled: impl std::future::Future<Output = ()>,
+) -> Result<Option<llm::TranslationResult>, TranslationFailure> {
+    use crate::llm::StreamEvent;
+
+    tokio::pin!(cancelled);
+    let llm_text = prepared.llm_text(text);
+    let target_lang = prepared.target_lang.as_str();
+
+    if !prepared.use_stream {
+        // 非流式模式：等待完成后一次性替换
+        let result = tokio::select! {
+            biased;
+            _ = &mut cancelled => return Ok(None),
+            result = llm_client.translate(llm, llm_text, target_lang) => result,
+        };
+        let mut result = result.map_err(|e| TranslationFailure::Llm {
+            error_type: e.metric_type(),
+            message: e.to_string(),
+        })?;
+        if let Some(masked) = &prepared.masked {
+            result.translated_text = masked.restore(&result.translated_text);
         }
+        output
+            .deliver(&result.translated_text)
+            .await
+            .map_err(TranslationFailure::Output)?;
+        return Ok(Some(result));
     }
 
-    Ok(())
-}
+    output
+        .begin_stream()
+        .await
+        .map_err(TranslationFailure::Output)?;
 
-/// 启动连续按键监听器
-fn start_consecutive_key_listener(app_handle: tauri::AppHandle, config: ConsecutiveKeyConfig) {
-    std::thread::spawn(move || {
-        let mut listener = KeyListener::new();
-        let mut rx = listener.start(config);
-
-        // 使用 tokio 运行时处理接收到的触发信号
-        let rt = tokio::runtime::Builder::new_current_thread()
-            .enable_all()
-            .build()
-            .expect("Failed to create tokio runtime");
-
-        rt.block_on(async {
-            while let Some(()) = rx.recv().await {
-                debug!("Consecutive key trigger received");
-                let handle = app_handle.clone();
-
-                if let Err(e) = trigger_translation(&handle, "full").await {
-                    error!("Full translation failed: {}", e);
-                }
+    let connected = tokio::select! {
+        biased;
+        _ = &mut cancelled => {
+            output.abort_stream("").await;
+            return Ok(None);
+        }
+        connected = llm_client.translate_stream(llm, llm_text, target_lang) => connected,
+    };
+    let mut stream = connected.map_err(|e| TranslationFailure::Llm {
+        error_type: e.metric_type(),
+        message: e.to_string(),
+    })?;
+
+    let mut result = llm::TranslationResult {
+        translated_text: String::new(),
+        completion_tokens: None,
+        duration_ms: 0,
+        tokens_per_second: None,
+        prompt_tokens: None,
+        cost_usd: None,
+    };
+    loop {
+        // 优先检查取消，取消后不再输入后续片段
+        let event = tokio::select! {
+            biased;
+            _ = &mut cancelled => {
+                output.abort_stream(&result.translated_text).await;
+                return Ok(None);
             }
-        });
+            event = stream.recv() => event,
+        };
+        let Some(event) = event else {
+            break;
+        };
+        match event {
+            StreamEvent::Delta(delta) => {
+                // 流式输入每个增量文本
+                output.push_delta(&delta).await;
+                result.translated_text.push_str(&delta);
+            }
+            StreamEvent::Done {
+                completion_tokens,
+                prompt_tokens,
+                duration_ms,
+                cost_usd,
+            } => {
+                result.completion_tokens = completion_tokens;
+                result.prompt_tokens = prompt_tokens;
+                result.duration_ms = duration_ms;
+                result.cost_usd = cost_usd;
+                debug!(
+                    "Stream completed: {} tokens, {}ms",
+                    completion_tokens.unwrap_or(0),
+                    duration_ms
+                );
+            }
+            StreamEvent::Error(err) => {
+                error!("Stream error: {}", err);
+                let error_type = if err.starts_with("API 错误") {
+                    "api"
+                } else {
+                    "network"
+                };
+                // 发生错误时，尝试恢复原文
+                output.abort_stream(&result.translated_text).await;
+                return Err(TranslationFailure::Llm {
+                    error_type,
+                    message: err,
+                });
+            }
+        }
+    }
+
+    result.tokens_per_second = result.completion_tokens.map(|t| {
+        if result.duration_ms > 0 {
+            (t as f64) / (result.duration_ms as f64 / 1000.0)
+        } else {
+            0.0
+        }
     });
+    Ok(Some(result))
 }
 
 /// 触发翻译（流式传输版本）
 async fn trigger_translation(
     app: &tauri::AppHandle,
     mode: &str,
+    typed_chars: usize,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     info!("Triggering {} translation", mode);
 
     let state = app.state::<Arc<AppState>>();
 
-    // 检查是否启用
-    let is_enabled = *state.is_enabled.read().await;
-    if !is_enabled {
-        debug!("Translation is disabled, skipping");
+    // 暂停、录制热键或设置窗口获得焦点时不触发翻译
+    if let Some(block) = trigger_block(&state, true).await {
+        debug!("Skipping {} translation: {:?}", mode, block);
         return Ok(());
     }
 
-    let config = state.get_config().await;
+    let mut config = state.get_config().await;
+
+    // 在复制文本的同时检测前台应用，不增加翻译延迟；设置了排除应用时先检测，
+    // 避免向排除的应用模拟复制
+    let frontmost = tokio::task::spawn_blocking(text_handler::frontmost_app);
+    let source_app = if config.excluded_apps.is_empty() {
+        Either::Left(async { frontmost.await.ok().flatten() })
+    } else {
+        let app = frontmost.await.ok().flatten();
+        if let Some(excluded) = app.as_deref().and_then(|app| config.excluded_app(app)) {
+            info!(
+                "Frontmost app {} is excluded by {}, skipping {} translation",
+                app.as_deref().unwrap_or_default(),
+                excluded.identifier,
+                mode
+            );
+            return Ok(());
+        }
+        Either::Right(std::future::ready(app))
+    };
+
+    // 确定要翻译后再删除触发键，必须在全文模式的全选之前删除
+    erase_trigger_keys(&state, typed_chars).await;
 
     // 获取文本
-    let text = if mode == "selected" {
-        // 选中翻译：复制当前选中的文本
+    let to_clipboard = mode == CLIPBOARD_MODE;
+    let text = if mode == "selected" || to_clipboard {
+        // 选中翻译和翻译到剪贴板：复制当前选中的文本
         match state.text_handler.translate_selected().await {
             Ok(t) => t,
             Err(e) => {
@@ -364,147 +1383,723 @@ async fn trigger_translation(
     let char_count = text.len();
     info!("Translating {} characters", char_count);
 
+    let prepared = match prepare_translation(&mut config, mode, &text) {
+        Ok(Preparation::Ready(prepared)) => prepared,
+        Ok(Preparation::Blocked { matches }) => {
+            let notification = BlockedTranslation {
+                mode: mode.to_string(),
+                matches,
+            };
+            if let Err(e) = app.emit("translation-blocked", notification) {
+                error!("Failed to emit translation-blocked event: {}", e);
+            }
+            return Ok(());
+        }
+        Err(e) => return Err(format!("Failed to check redaction patterns: {}", e).into()),
+    };
+    if let Some(source_lang) = prepared.source_lang {
+        state.set_last_source_lang(source_lang);
+    }
+    let target_lang = prepared.target_lang.clone();
+    let use_stream = prepared.use_stream;
+
+    // 复用最近相同原文的历史译文，不调用 API
+    if config.reuse_history {
+        match state
+            .database
+            .find_recent_translation(&text, &target_lang, config::HISTORY_REUSE_MAX_AGE_SECS)
+            .await
+        {
+            Ok(Some(record)) => {
+                info!("Reusing translation {} from history", record.id);
+                deliver_translation(
+                    app,
+                    &state,
+                    to_clipboard,
+                    &target_lang,
+                    &record.translated_text,
+                )
+                .await?;
+                finish_clipboard(&state, &config, to_clipboard, &record.translated_text).await;
+                record_cached_translation(&state, &config, mode, char_count).await;
+                state.set_last_translation(LastTranslation {
+                    original_text: text,
+                    translated_text: record.translated_text,
+                    target_lang,
+                    mode: mode.to_string(),
+                    model: record.model,
+                    completion_tokens: None,
+                    duration_ms: 0,
+                    cached: true,
+                    timestamp: chrono::Utc::now().timestamp(),
+                });
+                return Ok(());
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to look up translation history: {}", e),
+        }
+    }
+
     let llm_client = state.get_llm_client().await;
-    let target_lang = config.language.current_target.clone();
-    let use_stream = config.llm.stream_mode;
+    let llm =
+        llm::with_prompt_override(&config.llm, &config.language.prompt_overrides, &target_lang);
+
+    let started = Instant::now();
+    // 通过 cancel_translation 命令取消，令牌释放时自动注销
+    let ticket = state.begin_translation(mode, use_stream);
+    let mut output = AppOutput {
+        app,
+        state: &state,
+        to_clipboard,
+        target_lang: &target_lang,
+        original_text: &original_text,
+    };
+    let result = request_translation(
+        &llm_client,
+        &llm,
+        &prepared,
+        &text,
+        &mut output,
+        ticket.cancelled(),
+    )
+    .await;
+    let llm::TranslationResult {
+        translated_text,
+        completion_tokens,
+        duration_ms,
+        tokens_per_second,
+        cost_usd,
+        ..
+    } = match result {
+        Ok(Some(result)) => result,
+        Ok(None) => {
+            return finish_cancelled_translation(&state, &config, mode, use_stream, started).await;
+        }
+        Err(TranslationFailure::Llm {
+            error_type,
+            message,
+        }) => {
+            record_failed_translation(
+                &state, &config, mode, use_stream, started, error_type, &message,
+            )
+            .await;
+            return Err(format!("Translation API error: {}", message).into());
+        }
+        Err(TranslationFailure::Output(message)) => return Err(message.into()),
+    };
+    // 译文已经输出，之后不能再取消
+    drop(ticket);
+    finish_clipboard(&state, &config, to_clipboard, &translated_text).await;
 
-    let translated_text: String;
-    let mut completion_tokens: Option<u32> = None;
-    let mut duration_ms: u64 = 0;
-    let mut tokens_per_second: Option<f64> = None;
+    info!(
+        "Translation completed: {} chars -> {} chars, {} tokens, {}ms, {:.1} tokens/s",
+        original_text.len(),
+        translated_text.len(),
+        completion_tokens.unwrap_or(0),
+        duration_ms,
+        tokens_per_second.unwrap_or(0.0)
+    );
 
-    if use_stream {
-        // 流式模式：删除选中的文本，逐字输入
-        state
-            .text_handler
-            .delete_selection()
-            .await
-            .map_err(|e| format!("Failed to delete selection: {}", e))?;
+    state.set_last_translation(LastTranslation {
+        original_text: original_text.clone(),
+        translated_text: translated_text.clone(),
+        target_lang: target_lang.clone(),
+        mode: mode.to_string(),
+        model: Some(config.llm.model.clone()),
+        completion_tokens,
+        duration_ms,
+        cached: false,
+        timestamp: chrono::Utc::now().timestamp(),
+    });
 
-        let mut stream = llm_client
-            .translate_stream(&config.llm, &text, &target_lang)
+    // 保存翻译历史，隐私模式下不保存文本
+    if config.history_mode.records_text() {
+        let source_app = source_app.await;
+        if let Err(e) = state
+            .database
+            .insert_translation(
+                &original_text,
+                &translated_text,
+                prepared.source_lang,
+                &target_lang,
+                mode,
+                &TranslationMetadata {
+                    model: Some(config.llm.model.clone()),
+                    completion_tokens,
+                    duration_ms: Some(duration_ms as i64),
+                    source_app,
+                    dedup_window_secs: config.history_dedup_secs,
+                    retranslated_from: None,
+                },
+            )
             .await
-            .map_err(|e| format!("Translation API error: {}", e))?;
-
-        let mut result_text = String::new();
-
-        // 处理流式响应
-        use crate::llm::StreamEvent;
-        while let Some(event) = stream.recv().await {
-            match event {
-                StreamEvent::Delta(delta) => {
-                    // 流式输入每个增量文本
-                    if let Err(e) = state.text_handler.type_chunk(&delta).await {
-                        error!("Failed to type chunk: {}", e);
-                    }
-                    result_text.push_str(&delta);
-                }
-                StreamEvent::Done {
-                    completion_tokens: tokens,
-                    duration_ms: dur,
-                } => {
-                    completion_tokens = tokens;
-                    duration_ms = dur;
-                    debug!(
-                        "Stream completed: {} tokens, {}ms",
-                        tokens.unwrap_or(0),
-                        dur
-                    );
-                }
-                StreamEvent::Error(err) => {
-                    error!("Stream error: {}", err);
-                    // 发生错误时，尝试恢复原文
-                    if let Some(backup) = state.text_handler.get_backup().await {
-                        state.text_handler.paste(&backup).await.ok();
-                    }
-                    return Err(err.into());
-                }
-            }
+        {
+            error!("Failed to save translation history: {}", e);
         }
+    }
 
-        translated_text = result_text;
-        tokens_per_second = completion_tokens.map(|t| {
-            if duration_ms > 0 {
-                (t as f64) / (duration_ms as f64 / 1000.0)
-            } else {
-                0.0
-            }
-        });
-    } else {
-        // 非流式模式：等待完成后一次性替换
-        let result = llm_client
-            .translate(&config.llm, &text, &target_lang)
+    // 保存性能指标（使用实际的操作模式）
+    if config.history_mode.records_metrics() {
+        if let Err(e) = state
+            .database
+            .insert_metric(&MetricRecord {
+                operation_type: mode, // "selected"、"full" 或 "clipboard"
+                duration_ms: duration_ms as i64,
+                success: true,
+                error_type: None,
+                error_message: None,
+                char_count: char_count as i64,
+                target_lang: Some(target_lang.as_str()),
+                completion_tokens,
+                tokens_per_second,
+                model: Some(config.llm.model.as_str()),
+                cost_usd,
+                stream: Some(use_stream),
+                cached: false,
+            })
             .await
-            .map_err(|e| format!("Translation API error: {}", e))?;
+        {
+            error!("Failed to save performance metric: {}", e);
+        }
+    }
 
-        translated_text = result.translated_text;
-        completion_tokens = result.completion_tokens;
-        duration_ms = result.duration_ms;
-        tokens_per_second = result.tokens_per_second;
+    Ok(())
+}
 
-        // 替换选中的文本
+/// 输出完整的译文：翻译到剪贴板时写入剪贴板并通知前端，否则替换选中的文本
+async fn deliver_translation(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    to_clipboard: bool,
+    target_lang: &str,
+    translated_text: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if !to_clipboard {
         state
             .text_handler
-            .paste(&translated_text)
+            .paste(translated_text)
             .await
             .map_err(|e| format!("Failed to paste translation: {}", e))?;
+        return Ok(());
     }
 
-    info!(
-        "Translation completed: {} chars -> {} chars, {} tokens, {}ms, {:.1} tokens/s",
-        original_text.len(),
-        translated_text.len(),
-        completion_tokens.unwrap_or(0),
-        duration_ms,
-        tokens_per_second.unwrap_or(0.0)
-    );
-
-    // 保存翻译历史
-    if let Err(e) = state
-        .database
-        .insert_translation(
-            &original_text,
-            &translated_text,
-            None, // source_lang 自动检测
-            &target_lang,
-            mode,
-        )
+    // 不删除或替换原文
+    state
+        .text_handler
+        .copy_to_clipboard(translated_text)
         .await
-    {
-        error!("Failed to save translation history: {}", e);
+        .map_err(|e| format!("Failed to copy translation: {}", e))?;
+
+    let notification = ClipboardTranslation {
+        target_lang: target_lang.to_string(),
+        preview: clipboard_preview(translated_text),
+    };
+    if let Err(e) = app.emit("translation-copied", notification) {
+        error!("Failed to emit translation-copied event: {}", e);
     }
+    Ok(())
+}
 
-    // 保存性能指标（使用实际的操作模式）
+/// 译文输出后按配置恢复、保留或定时清除剪贴板，失败时只记录日志
+async fn finish_clipboard(
+    state: &AppState,
+    config: &config::AppConfig,
+    to_clipboard: bool,
+    translated_text: &str,
+) {
     if let Err(e) = state
-        .database
-        .insert_metric(
-            mode, // "selected" 或 "full"
-            duration_ms as i64,
-            true,
-            None,
-            char_count as i64,
-            completion_tokens,
-            tokens_per_second,
-        )
+        .text_handler
+        .finish_clipboard(&config.clipboard, to_clipboard, translated_text)
         .await
     {
+        warn!("Failed to update clipboard after translation: {}", e);
+    }
+}
+
+/// 记录复用历史译文的指标，没有调用 API，耗时记为 0
+async fn record_cached_translation(
+    state: &AppState,
+    config: &config::AppConfig,
+    mode: &str,
+    char_count: usize,
+) {
+    if !config.history_mode.records_metrics() {
+        return;
+    }
+    let metric = MetricRecord {
+        operation_type: mode,
+        success: true,
+        char_count: char_count as i64,
+        target_lang: Some(config.language.current_target.as_str()),
+        cached: true,
+        ..Default::default()
+    };
+    if let Err(e) = state.database.insert_metric(&metric).await {
         error!("Failed to save performance metric: {}", e);
     }
+}
+
+/// 流式翻译被取消时原文已经删除：删除已经输入的部分译文，粘贴回原文
+async fn restore_original_text(state: &AppState, typed: &str, original: &str) {
+    if let Err(e) = state.text_handler.delete_chars(typed.chars().count()).await {
+        warn!("Failed to remove partial translation: {}", e);
+        return;
+    }
+    if let Err(e) = state.text_handler.paste(original).await {
+        warn!("Failed to restore original text: {}", e);
+    }
+}
 
+/// 结束被取消的翻译：恢复剪贴板并记录取消的指标
+///
+/// 流式模式下需要先调用 `restore_original_text` 放回原文，之后再恢复剪贴板。
+async fn finish_cancelled_translation(
+    state: &AppState,
+    config: &config::AppConfig,
+    mode: &str,
+    stream: bool,
+    started: Instant,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    info!("{} translation cancelled", mode);
+    if let Err(e) = state.text_handler.restore_clipboard().await {
+        warn!("Failed to restore clipboard: {}", e);
+    }
+    record_failed_translation(
+        state,
+        config,
+        mode,
+        stream,
+        started,
+        "cancelled",
+        "翻译已取消",
+    )
+    .await;
     Ok(())
 }
 
+/// 记录失败的翻译指标
+async fn record_failed_translation(
+    state: &AppState,
+    config: &config::AppConfig,
+    mode: &str,
+    stream: bool,
+    started: Instant,
+    error_type: &str,
+    message: &str,
+) {
+    if !config.history_mode.records_metrics() {
+        return;
+    }
+    let metric = MetricRecord {
+        operation_type: mode,
+        duration_ms: started.elapsed().as_millis() as i64,
+        success: false,
+        error_type: Some(error_type),
+        error_message: Some(message),
+        target_lang: Some(config.language.current_target.as_str()),
+        model: Some(config.llm.model.as_str()),
+        stream: Some(stream),
+        ..Default::default()
+    };
+    if let Err(e) = state.database.insert_metric(&metric).await {
+        error!("Failed to save performance metric: {}", e);
+    }
+}
+
+/// 截取译文开头作为通知预览
+fn clipboard_preview(text: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.chars().count() <= CLIPBOARD_PREVIEW_CHARS {
+        return trimmed.to_string();
+    }
+    let mut preview: String = trimmed.chars().take(CLIPBOARD_PREVIEW_CHARS).collect();
+    preview.push('…');
+    preview
+}
+
+/// 翻译流程诊断中单个步骤的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PipelineStepStatus {
+    Passed,
+    Failed,
+    /// 诊断时不执行的步骤（如读取选中文本、输出译文）
+    Skipped,
+}
+
+/// 翻译流程诊断中的一个步骤
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct PipelineStep {
+    /// 步骤名称："state"、"config"、"excluded_app"、"capture"、"redaction"、"history_reuse"、"llm"、"metrics"、"output"
+    name: &'static str,
+    status: PipelineStepStatus,
+    /// 耗时（毫秒）
+    duration_ms: u64,
+    /// 结果说明或失败原因
+    detail: Option<String>,
+}
+
+/// 翻译流程诊断报告
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct PipelineReport {
+    mode: String,
+    target_lang: String,
+    /// 是否使用流式请求
+    stream: bool,
+    steps: Vec<PipelineStep>,
+    /// 译文，LLM 请求失败时为空
+    translated_text: Option<String>,
+    /// 所有执行的步骤是否都成功
+    success: bool,
+}
+
+impl PipelineReport {
+    fn push(
+        &mut self,
+        name: &'static str,
+        status: PipelineStepStatus,
+        started: Instant,
+        detail: Option<String>,
+    ) {
+        if status == PipelineStepStatus::Failed {
+            self.success = false;
+        }
+        self.steps.push(PipelineStep {
+            name,
+            status,
+            duration_ms: started.elapsed().as_millis() as u64,
+            detail,
+        });
+    }
+
+    /// 记录诊断结果并返回报告
+    fn finish(self, started: Instant) -> Self {
+        info!(
+            "Translation pipeline test finished: success={}, {}ms",
+            self.success,
+            started.elapsed().as_millis()
+        );
+        self
+    }
+}
+
+/// 流程诊断的输出：不模拟键盘输入也不写入剪贴板，译文只写入诊断报告
+struct DryRunOutput;
+
+impl TranslationOutput for DryRunOutput {
+    async fn begin_stream(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn push_delta(&mut self, _delta: &str) {}
+
+    async fn deliver(&mut self, _translated_text: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn abort_stream(&mut self, _typed: &str) {}
+}
+
+/// 使用示例文本走一遍热键翻译的流程，不读取选中文本也不模拟键盘输出
+///
+/// 与 `trigger_translation` 共用状态检查、敏感内容过滤、目标语言选择和 LLM 请求，
+/// 返回每一步的耗时和结果，便于定位是哪一步出了问题。不保存翻译历史。
+pub(crate) async fn test_translation_pipeline(
+    state: &AppState,
+    sample_text: &str,
+    mode: &str,
+) -> Result<PipelineReport, error::CommandError> {
+    use PipelineStepStatus::{Failed, Passed, Skipped};
+
+    if !["selected", "full", CLIPBOARD_MODE].contains(&mode) {
+        return Err(error::CommandError::validation(format!(
+            "未知的翻译模式: {}",
+            mode
+        )));
+    }
+    if sample_text.trim().is_empty() {
+        return Err(error::CommandError::validation("示例文本不能为空"));
+    }
+
+    let started = Instant::now();
+    let mut config = state.get_config().await;
+    let to_clipboard = mode == CLIPBOARD_MODE;
+    let mut report = PipelineReport {
+        mode: mode.to_string(),
+        target_lang: config.language.current_target.clone(),
+        stream: false,
+        steps: Vec::new(),
+        translated_text: None,
+        success: true,
+    };
+
+    // 设置窗口获得焦点时热键也会暂停，但诊断本身就从设置窗口发起，不作为失败
+    let step = Instant::now();
+    match trigger_block(state, false).await {
+        Some(block) => report.push("state", Failed, step, Some(block.describe().to_string())),
+        None => report.push("state", Passed, step, None),
+    }
+
+    let config_detail = format!("模型 {}，目标语言 {}", config.llm.model, report.target_lang);
+    if config.llm.api_key.is_empty() {
+        report.push(
+            "config",
+            Failed,
+            started,
+            Some("API Key 未配置".to_string()),
+        );
+    } else {
+        report.push("config", Passed, started, Some(config_detail));
+    }
+
+    // 诊断从设置窗口发起时前台应用通常是本应用
+    let step = Instant::now();
+    let frontmost = tokio::task::spawn_blocking(text_handler::frontmost_app)
+        .await
+        .ok()
+        .flatten();
+    match frontmost.as_deref() {
+        Some(app) => match config.excluded_app(app) {
+            Some(excluded) => report.push(
+                "excluded_app",
+                Failed,
+                step,
+                Some(format!(
+                    "前台应用 {} 被 {} 排除，热键不会触发翻译",
+                    app, excluded.identifier
+                )),
+            ),
+            None => report.push(
+                "excluded_app",
+                Passed,
+                step,
+                Some(format!("前台应用 {} 未被排除", app)),
+            ),
+        },
+        None => report.push(
+            "excluded_app",
+            Skipped,
+            step,
+            Some("无法检测前台应用".to_string()),
+        ),
+    }
+
+    report.push(
+        "capture",
+        Skipped,
+        Instant::now(),
+        Some(format!("使用示例文本（{} 字符）", sample_text.len())),
+    );
+
+    let step = Instant::now();
+    let prepared = match prepare_translation(&mut config, mode, sample_text) {
+        Ok(Preparation::Ready(prepared)) => prepared,
+        Ok(Preparation::Blocked { matches }) => {
+            report.push(
+                "redaction",
+                Failed,
+                step,
+                Some(format!("原文包含 {} 处敏感内容，热键翻译会被取消", matches)),
+            );
+            return Ok(report.finish(started));
+        }
+        Err(e) => {
+            report.push("redaction", Failed, step, Some(e.to_string()));
+            return Ok(report.finish(started));
+        }
+    };
+    match &prepared.masked {
+        Some(masked) => report.push(
+            "redaction",
+            Passed,
+            step,
+            Some(format!("代替了 {} 处敏感内容", masked.originals.len())),
+        ),
+        None if config.redaction_mode == config::RedactionMode::Off => report.push(
+            "redaction",
+            Skipped,
+            step,
+            Some("未启用敏感内容过滤".to_string()),
+        ),
+        None => report.push("redaction", Passed, step, None),
+    }
+    report.target_lang = prepared.target_lang.clone();
+    report.stream = prepared.use_stream;
+
+    let step = Instant::now();
+    if config.reuse_history {
+        match state
+            .database
+            .find_recent_translation(
+                sample_text,
+                &report.target_lang,
+                config::HISTORY_REUSE_MAX_AGE_SECS,
+            )
+            .await
+        {
+            Ok(Some(record)) => report.push(
+                "history_reuse",
+                Passed,
+                step,
+                Some(format!("将复用历史译文 {}，诊断仍会调用 LLM", record.id)),
+            ),
+            Ok(None) => report.push(
+                "history_reuse",
+                Passed,
+                step,
+                Some("没有可复用的历史译文".to_string()),
+            ),
+            Err(e) => report.push("history_reuse", Failed, step, Some(e.to_string())),
+        }
+    } else {
+        report.push(
+            "history_reuse",
+            Skipped,
+            step,
+            Some("未启用历史复用".to_string()),
+        );
+    }
+
+    let llm_client = state.get_llm_client().await;
+    let llm = llm::with_prompt_override(
+        &config.llm,
+        &config.language.prompt_overrides,
+        &report.target_lang,
+    );
+    let step = Instant::now();
+    // 诊断不会取消，输出也不会失败
+    let result = match request_translation(
+        &llm_client,
+        &llm,
+        &prepared,
+        sample_text,
+        &mut DryRunOutput,
+        std::future::pending(),
+    )
+    .await
+    {
+        Ok(Some(result)) => Ok(result),
+        Ok(None) => Err(("cancelled", "翻译已取消".to_string())),
+        Err(TranslationFailure::Llm {
+            error_type,
+            message,
+        }) => Err((error_type, message)),
+        Err(TranslationFailure::Output(message)) => Err(("output", message)),
+    };
+    let duration_ms = step.elapsed().as_millis() as i64;
+    let metric_started = Instant::now();
+    let metric_result = match &result {
+        Ok(result) => {
+            report.push(
+                "llm",
+                Passed,
+                step,
+                Some(format!("{} tokens", result.completion_tokens.unwrap_or(0))),
+            );
+            report.translated_text = Some(result.translated_text.clone());
+            if config.history_mode.records_metrics() {
+                let tokens_per_second = result
+                    .completion_tokens
+                    .filter(|_| duration_ms > 0)
+                    .map(|t| t as f64 / (duration_ms as f64 / 1000.0));
+                let metric = MetricRecord {
+                    operation_type: mode,
+                    duration_ms,
+                    success: true,
+                    char_count: sample_text.len() as i64,
+                    target_lang: Some(report.target_lang.as_str()),
+                    completion_tokens: result.completion_tokens,
+                    tokens_per_second,
+                    model: Some(config.llm.model.as_str()),
+                    cost_usd: result.cost_usd,
+                    stream: Some(report.stream),
+                    ..Default::default()
+                };
+                Some(state.database.insert_metric(&metric).await)
+            } else {
+                None
+            }
+        }
+        Err((error_type, message)) => {
+            report.push("llm", Failed, step, Some(message.clone()));
+            if config.history_mode.records_metrics() {
+                let metric = MetricRecord {
+                    operation_type: mode,
+                    duration_ms,
+                    success: false,
+                    error_type: Some(error_type),
+                    error_message: Some(message),
+                    target_lang: Some(report.target_lang.as_str()),
+                    model: Some(config.llm.model.as_str()),
+                    stream: Some(report.stream),
+                    ..Default::default()
+                };
+                Some(state.database.insert_metric(&metric).await)
+            } else {
+                None
+            }
+        }
+    };
+    match metric_result {
+        Some(Ok(())) => report.push("metrics", Passed, metric_started, None),
+        Some(Err(e)) => report.push("metrics", Failed, metric_started, Some(e.to_string())),
+        None => report.push(
+            "metrics",
+            Skipped,
+            metric_started,
+            Some("当前历史模式不记录性能指标".to_string()),
+        ),
+    }
+
+    report.push(
+        "output",
+        Skipped,
+        Instant::now(),
+        Some(if to_clipboard {
+            "诊断时不写入剪贴板".to_string()
+        } else {
+            "诊断时不模拟键盘输入".to_string()
+        }),
+    );
+
+    Ok(report.finish(started))
+}
+
+/// 运行时替换日志过滤器的句柄
+static LOG_FILTER: std::sync::OnceLock<
+    tracing_subscriber::reload::Handle<tracing_subscriber::EnvFilter, tracing_subscriber::Registry>,
+> = std::sync::OnceLock::new();
+
 /// 初始化日志系统
+/// 可调整的过滤器只作用于输出层，错误日志始终收集 WARN 及以上的日志
 fn init_logging() {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| config::LogLevel::default().filter_directive().into());
+    let (filter, handle) = tracing_subscriber::reload::Layer::new(filter);
+    let _ = LOG_FILTER.set(handle);
+
     tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_filter(filter))
         .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "quick_trans_type=debug,tauri=info".into()),
+            error_log::ErrorLog::global()
+                .layer()
+                .with_filter(tracing_subscriber::filter::LevelFilter::WARN),
         )
-        .with(tracing_subscriber::fmt::layer())
         .init();
 }
 
+/// 调整本应用的日志级别，立即生效
+pub(crate) fn set_log_level(level: config::LogLevel) -> error::Result<()> {
+    let handle = LOG_FILTER
+        .get()
+        .ok_or_else(|| error::AppError::Other("日志系统尚未初始化".to_string()))?;
+    handle
+        .reload(tracing_subscriber::EnvFilter::new(level.filter_directive()))
+        .map_err(|e| error::AppError::Other(format!("无法调整日志级别: {}", e)))?;
+    info!("Log level set to {}", level.as_str());
+    Ok(())
+}
+
 /// 应用程序入口
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -512,7 +2107,7 @@ pub fn run() {
     info!("Starting QuickTransType...");
 
     // 检查辅助功能权限
-    if !check_accessibility_permission() {
+    if !check_accessibility_permission(true) {
         warn!("辅助功能权限未授权，键盘模拟功能可能无法正常工作");
         warn!("请在 系统设置 > 隐私与安全性 > 辅助功能 中授权本应用");
     } else {
@@ -537,6 +2132,28 @@ pub fn run() {
             app.manage(state.clone());
             info!("Application state initialized");
 
+            // 设置了 RUST_LOG 时以环境变量为准
+            if std::env::var_os("RUST_LOG").is_none() {
+                let log_level =
+                    tauri::async_runtime::block_on(async { state.get_config().await.log_level });
+                if let Err(e) = set_log_level(log_level) {
+                    warn!("Failed to apply log level: {}", e);
+                }
+            }
+
+            // 连续按键/长按热键依赖 rdev，需要输入监控权限
+            let hotkey_config =
+                tauri::async_runtime::block_on(async { state.get_config().await.hotkey });
+            if hotkey_config.uses_key_listener() {
+                if check_input_monitoring_permission() || request_input_monitoring_permission() {
+                    info!("输入监控权限已授权");
+                } else {
+                    warn!("输入监控权限未授权，连续按键/长按热键将无法使用");
+                    warn!("请在 系统设置 > 隐私与安全性 > 输入监控 中授权本应用");
+                    // 此时前端尚未加载，由前端通过 get_input_monitoring_required 查询后提示
+                }
+            }
+
             // 注册全局热键
             if let Err(e) = register_global_shortcuts(app, &state) {
                 error!("Failed to register global shortcuts: {}", e);
@@ -573,7 +2190,7 @@ pub fn run() {
                                     "托盘点击前，当前目标语言: {}",
                                     config.language.current_target
                                 );
-                                config.language.current_target = lang.clone();
+                                config.language.set_target(lang.clone());
                                 info!("准备保存新的目标语言: {}", lang);
                                 if let Err(e) = state.save_config(&config).await {
                                     error!("Failed to save language config: {}", e);
@@ -618,43 +2235,15 @@ pub fn run() {
                         match event_id {
                             "toggle" => {
                                 info!("Toggle translation monitoring");
-                                let state = app_state.clone();
                                 let app_clone = app_handle.clone();
                                 tauri::async_runtime::spawn(async move {
-                                    let mut is_enabled = state.is_enabled.write().await;
-                                    *is_enabled = !*is_enabled;
-                                    let new_status = *is_enabled;
-                                    drop(is_enabled);
-
-                                    info!("Translation monitoring toggled to: {}", new_status);
-
-                                    // 更新托盘菜单
-                                    tokio::time::sleep(tokio::time::Duration::from_millis(50))
-                                        .await;
-                                    if let Ok(new_menu) = build_tray_menu(&app_clone, &state).await
-                                    {
-                                        if let Some(tray) = app_clone.tray_by_id("main") {
-                                            let _ = tray
-                                                .set_menu(None::<tauri::menu::Menu<tauri::Wry>>);
-                                            tokio::time::sleep(tokio::time::Duration::from_millis(
-                                                100,
-                                            ))
-                                            .await;
-                                            if let Err(e) = tray.set_menu(Some(new_menu)) {
-                                                error!("Failed to update tray menu: {}", e);
-                                            }
-                                        }
-                                    }
-
-                                    // 发送事件通知前端
-                                    if let Err(e) =
-                                        app_clone.emit("enabled-status-changed", new_status)
-                                    {
-                                        error!(
-                                            "Failed to emit enabled-status-changed event: {}",
-                                            e
-                                        );
-                                    }
+                                    toggle_enabled(&app_clone).await;
+                                });
+                            }
+                            "privacy" => {
+                                let app_clone = app_handle.clone();
+                                tauri::async_runtime::spawn(async move {
+                                    toggle_privacy_mode(&app_clone).await;
                                 });
                             }
                             "settings" => {
@@ -664,6 +2253,15 @@ pub fn run() {
                                     let _ = window.set_focus();
                                 }
                             }
+                            "history" => {
+                                info!("Opening history window");
+                                let app_clone = app_handle.clone();
+                                tauri::async_runtime::spawn(async move {
+                                    if let Err(e) = open_history_window(&app_clone) {
+                                        error!("Failed to open history window: {}", e);
+                                    }
+                                });
+                            }
                             "quit" => {
                                 info!("Quitting application");
                                 app.exit(0);
@@ -674,21 +2272,113 @@ pub fn run() {
                     .build(app)?;
             }
 
+            // 监听器健康状态：变化时通知，唤醒后主动重启
+            spawn_listener_status_watcher(app.handle().clone(), &state);
+            spawn_wake_monitor(app.handle().clone());
+            spawn_backup_scheduler(&state);
+
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // 跟踪设置窗口焦点，获得焦点时暂停热键
+            if let tauri::WindowEvent::Focused(focused) = event {
+                if window.label() != "main" {
+                    return;
+                }
+                let Some(state) = window.try_state::<Arc<AppState>>() else {
+                    return;
+                };
+                let state = state.inner().clone();
+                let focused = *focused;
+                tauri::async_runtime::spawn(async move {
+                    state.set_settings_focused(focused).await;
+                });
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             commands::get_config,
             commands::save_config,
+            commands::set_api_key,
+            commands::list_profiles,
+            commands::set_active_profile,
+            commands::save_profile,
+            commands::delete_profile,
+            commands::export_config,
+            commands::import_config,
+            commands::reset_config,
+            commands::import_legacy_config,
+            commands::validate_prompt_template,
             commands::get_enabled_status,
             commands::set_enabled_status,
             commands::test_llm_connection,
+            commands::test_llm_connection_detailed,
             commands::get_history,
+            commands::get_history_cursor,
+            commands::get_history_facets,
+            commands::get_translation_by_id,
+            commands::get_adjacent_ids,
+            commands::set_translation_pinned,
+            commands::purge_history,
             commands::clear_history,
+            commands::get_recent_translations,
+            commands::open_history_window,
+            commands::retranslate_record,
+            commands::get_last_translation,
+            commands::copy_last_translation,
+            commands::estimate_tokens,
+            commands::preview_prompt,
+            commands::get_error_log,
+            commands::clear_error_log,
+            commands::get_log_level,
+            commands::set_log_level,
+            commands::import_history,
+            commands::set_history_encryption,
+            commands::get_history_encryption_error,
+            commands::get_db_info,
+            commands::compact_database,
+            commands::list_backups,
+            commands::restore_from_backup,
+            commands::move_database,
             commands::get_performance_stats,
+            commands::export_metrics,
+            commands::cancel_translation,
+            commands::get_cost_stats,
+            commands::get_model_performance_comparison,
+            commands::get_daily_stats,
             commands::check_hotkey_conflicts,
+            commands::validate_hotkey_config,
+            commands::validate_hotkey,
+            commands::start_hotkey_capture,
+            commands::cancel_hotkey_capture,
+            commands::check_input_monitoring_permission,
+            commands::get_input_monitoring_required,
+            commands::get_permission_status,
+            commands::open_permission_settings,
+            commands::get_app_info,
+            commands::get_hotkey_status,
+            commands::test_translation_pipeline,
+            commands::get_key_listener_error,
+            commands::get_listener_status,
+            commands::get_languages,
+            commands::add_favorite_language,
+            commands::remove_favorite_language,
+            commands::reorder_favorite_languages,
             commands::switch_language,
+            commands::swap_languages,
+            commands::add_excluded_app,
+            commands::remove_excluded_app,
+            commands::list_running_apps,
             commands::translate_text,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // 退出时停止键盘监听会话
+            if let tauri::RunEvent::Exit = event {
+                if let Some(state) = app.try_state::<Arc<AppState>>() {
+                    info!("Stopping key listeners before exit");
+                    state.shutdown_key_listeners();
+                }
+            }
+        });
 }
@@ -1,11 +1,13 @@
 //! LLM 客户端模块
 //! 处理与 LLM API 的通信，支持流式传输
 
-use crate::config::LLMConfig;
+use crate::config::{LLMConfig, ModelPrice, PromptOverride};
 use crate::error::{AppError, Result};
 use futures_util::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tracing::{debug, info};
@@ -26,6 +28,10 @@ pub struct TranslationResult {
     pub duration_ms: u64,
     /// 输出速率 (tokens/s)
     pub tokens_per_second: Option<f64>,
+    /// 提示 tokens 数量
+    pub prompt_tokens: Option<u32>,
+    /// 按价格表计算的费用（美元），未配置价格或缺少 usage 时为空
+    pub cost_usd: Option<f64>,
 }
 
 /// 流式传输的事件
@@ -36,7 +42,9 @@ pub enum StreamEvent {
     /// 完成，包含统计信息
     Done {
         completion_tokens: Option<u32>,
+        prompt_tokens: Option<u32>,
         duration_ms: u64,
+        cost_usd: Option<f64>,
     },
     /// 错误
     Error(String),
@@ -62,16 +70,20 @@ struct StreamOptions {
 }
 
 /// 消息结构
-#[derive(Debug, Serialize)]
-struct Message {
-    role: String,
-    content: String,
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Message {
+    /// "system" 或 "user"
+    pub role: String,
+    pub content: String,
 }
 
 /// OpenAI API 响应体 (非流式)
 #[derive(Debug, Deserialize)]
 struct ChatCompletionResponse {
     choices: Vec<Choice>,
+    /// 实际处理请求的模型
+    #[serde(default)]
+    model: Option<String>,
     #[serde(default)]
     usage: Option<Usage>,
 }
@@ -133,6 +145,107 @@ struct ApiError {
     message: String,
 }
 
+/// 按 HTTP 状态码区分 API 错误类型
+fn status_error(status: reqwest::StatusCode, message: String) -> AppError {
+    match status.as_u16() {
+        401 | 403 => AppError::LlmAuth(message),
+        429 => AppError::LlmRateLimit(message),
+        _ => AppError::LlmApi(message),
+    }
+}
+
+/// 连接测试默认的示例文本
+const CONNECTION_TEST_TEXT: &str = "Hello";
+/// 连接测试翻译的目标语言
+const CONNECTION_TEST_TARGET: &str = "中文";
+
+/// 连接测试失败的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionStage {
+    /// 配置不完整
+    Config,
+    /// 无法连接或读取响应（DNS、网络、超时等）
+    Connect,
+    /// 认证失败（401/403）
+    Auth,
+    /// API 返回其他错误状态，如模型名不存在
+    Api,
+    /// 响应不是有效的 Chat Completions 格式
+    Parse,
+    /// 响应中没有译文
+    EmptyResponse,
+}
+
+impl ConnectionStage {
+    fn label(self) -> &'static str {
+        match self {
+            ConnectionStage::Config => "配置错误",
+            ConnectionStage::Connect => "连接失败",
+            ConnectionStage::Auth => "认证失败",
+            ConnectionStage::Api => "请求失败",
+            ConnectionStage::Parse => "响应解析失败",
+            ConnectionStage::EmptyResponse => "空响应",
+        }
+    }
+}
+
+/// LLM 连接测试的诊断报告
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionReport {
+    pub success: bool,
+    /// 请求的地址
+    pub endpoint: String,
+    /// HTTP 状态码，未收到响应时为空
+    pub status: Option<u16>,
+    /// 请求耗时（毫秒）
+    pub latency_ms: u64,
+    /// 响应中返回的模型名
+    pub model: Option<String>,
+    pub prompt_tokens: Option<u32>,
+    pub completion_tokens: Option<u32>,
+    pub tokens_per_second: Option<f64>,
+    /// 测试使用的示例文本
+    pub sample_text: String,
+    /// 示例文本的译文
+    pub translated_text: Option<String>,
+    /// 失败的阶段
+    pub failed_stage: Option<ConnectionStage>,
+    /// 失败原因
+    pub error: Option<String>,
+}
+
+impl ConnectionReport {
+    fn fail(mut self, stage: ConnectionStage, message: &str) -> Self {
+        self.failed_stage = Some(stage);
+        self.error = Some(message.to_string());
+        self
+    }
+
+    /// 一句话描述测试结果
+    pub fn summary(&self) -> String {
+        if let Some(stage) = self.failed_stage {
+            let status = self
+                .status
+                .map(|status| format!(" (HTTP {})", status))
+                .unwrap_or_default();
+            return format!(
+                "{}{}: {}",
+                stage.label(),
+                status,
+                self.error.as_deref().unwrap_or_default()
+            );
+        }
+        format!(
+            "连接成功！测试翻译: {} → {} ({}ms, {:.1} tokens/s)",
+            self.sample_text,
+            self.translated_text.as_deref().unwrap_or_default(),
+            self.latency_ms,
+            self.tokens_per_second.unwrap_or(0.0)
+        )
+    }
+}
+
 impl LLMClient {
     /// 创建新的 LLM 客户端
     pub fn new() -> Result<Self> {
@@ -144,28 +257,130 @@ impl LLMClient {
         Ok(Self { client })
     }
 
-    /// 测试 LLM 连接
+    /// 测试 LLM 连接，返回一句话的结果
     pub async fn test_connection(&self, config: &LLMConfig) -> Result<String> {
+        let report = self.test_connection_detailed(config, None).await;
+        if report.success {
+            return Ok(report.summary());
+        }
+        let message = report.error.clone().unwrap_or_default();
+        Err(match report.failed_stage {
+            Some(ConnectionStage::Config) => AppError::Config(message),
+            Some(ConnectionStage::Auth) => AppError::LlmAuth(report.summary()),
+            _ => AppError::LlmApi(report.summary()),
+        })
+    }
+
+    /// 测试 LLM 连接，返回请求地址、状态码、耗时、模型和 usage 等诊断信息
+    ///
+    /// 失败时 `failed_stage` 标明失败的阶段，便于区分网络、认证和模型名等问题。
+    pub async fn test_connection_detailed(
+        &self,
+        config: &LLMConfig,
+        sample_text: Option<&str>,
+    ) -> ConnectionReport {
         info!("Testing LLM connection...");
-        
+        let sample_text = sample_text
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .unwrap_or(CONNECTION_TEST_TEXT);
+        let mut report = ConnectionReport {
+            success: false,
+            endpoint: format!("{}/chat/completions", config.base_url.trim_end_matches('/')),
+            status: None,
+            latency_ms: 0,
+            model: None,
+            prompt_tokens: None,
+            completion_tokens: None,
+            tokens_per_second: None,
+            sample_text: sample_text.to_string(),
+            translated_text: None,
+            failed_stage: None,
+            error: None,
+        };
+
         if config.api_key.is_empty() {
-            return Err(AppError::Config("API Key 不能为空".to_string()));
+            return report.fail(ConnectionStage::Config, "API Key 不能为空");
         }
         if config.base_url.is_empty() {
-            return Err(AppError::Config("Base URL 不能为空".to_string()));
+            return report.fail(ConnectionStage::Config, "Base URL 不能为空");
+        }
+
+        let request_body = ChatCompletionRequest {
+            model: config.model.clone(),
+            messages: build_messages(config, CONNECTION_TEST_TARGET, sample_text),
+            temperature: config.temperature,
+            top_p: config.top_p,
+            stream: None,
+            stream_options: None,
+        };
+        let start_time = Instant::now();
+        let response = match self
+            .client
+            .post(&report.endpoint)
+            .header("Authorization", format!("Bearer {}", config.api_key))
+            .header("Content-Type", "application/json")
+            .json(&request_body)
+            .send()
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                report.latency_ms = start_time.elapsed().as_millis() as u64;
+                return report.fail(ConnectionStage::Connect, &e.to_string());
+            }
+        };
+
+        let status = response.status();
+        report.status = Some(status.as_u16());
+        let body = response.text().await;
+        report.latency_ms = start_time.elapsed().as_millis() as u64;
+        let body = match body {
+            Ok(body) => body,
+            Err(e) => return report.fail(ConnectionStage::Connect, &e.to_string()),
+        };
+
+        if !status.is_success() {
+            let message = serde_json::from_str::<ApiErrorResponse>(&body)
+                .map(|api_error| api_error.error.message)
+                .unwrap_or_else(|_| format!("请求失败 ({})", status));
+            let stage = if matches!(status.as_u16(), 401 | 403) {
+                ConnectionStage::Auth
+            } else {
+                ConnectionStage::Api
+            };
+            return report.fail(stage, &message);
         }
 
-        let test_text = "Hello";
-        let result = self.translate(config, test_text, "中文").await?;
+        let result: ChatCompletionResponse = match serde_json::from_str(&body) {
+            Ok(result) => result,
+            Err(e) => {
+                return report.fail(ConnectionStage::Parse, &format!("解析响应失败: {}", e));
+            }
+        };
+        report.model = result.model.clone();
+        if let Some(usage) = &result.usage {
+            report.prompt_tokens = Some(usage.prompt_tokens).filter(|&t| t > 0);
+            report.completion_tokens = Some(usage.completion_tokens).filter(|&t| t > 0);
+        }
+        report.tokens_per_second = report
+            .completion_tokens
+            .filter(|_| report.latency_ms > 0)
+            .map(|t| t as f64 / (report.latency_ms as f64 / 1000.0));
+
+        let translated = result
+            .choices
+            .first()
+            .map(|choice| choice.message.content.trim().to_string())
+            .filter(|text| !text.is_empty());
+        let Some(translated) = translated else {
+            return report.fail(ConnectionStage::EmptyResponse, "API 返回空响应");
+        };
 
         info!("LLM connection test successful");
-        Ok(format!(
-            "连接成功！测试翻译: {} → {} ({}ms, {:.1} tokens/s)",
-            test_text,
-            result.translated_text.trim(),
-            result.duration_ms,
-            result.tokens_per_second.unwrap_or(0.0)
-        ))
+        report.translated_text = Some(translated);
+        report.success = true;
+        report
     }
 
     /// 翻译文本（非流式）
@@ -181,21 +396,11 @@ impl LLMClient {
             return Err(AppError::Config("API Key 未配置".to_string()));
         }
 
-        let user_prompt = build_user_prompt(&config.user_prompt_template, target_language, text);
         let start_time = Instant::now();
 
         let request_body = ChatCompletionRequest {
             model: config.model.clone(),
-            messages: vec![
-                Message {
-                    role: "system".to_string(),
-                    content: config.system_prompt.clone(),
-                },
-                Message {
-                    role: "user".to_string(),
-                    content: user_prompt,
-                },
-            ],
+            messages: build_messages(config, target_language, text),
             temperature: config.temperature,
             top_p: config.top_p,
             stream: None,
@@ -220,10 +425,10 @@ impl LLMClient {
             let error_text = response.text().await.unwrap_or_default();
             
             if let Ok(api_error) = serde_json::from_str::<ApiErrorResponse>(&error_text) {
-                return Err(AppError::LlmApi(api_error.error.message));
+                return Err(status_error(status, api_error.error.message));
             }
             
-            return Err(AppError::LlmApi(format!("翻译请求失败 ({})", status)));
+            return Err(status_error(status, format!("翻译请求失败 ({})", status)));
         }
 
         // 解析完整响应以获取 usage
@@ -253,6 +458,13 @@ impl LLMClient {
             }
         });
 
+        let prompt_tokens = result
+            .usage
+            .as_ref()
+            .map(|u| u.prompt_tokens)
+            .filter(|&t| t > 0);
+        let cost_usd = request_cost(config.price(), prompt_tokens, completion_tokens);
+
         debug!(
             "Translation completed: {} chars, {} tokens, {}ms, {:.1} tokens/s",
             translated.len(),
@@ -266,6 +478,8 @@ impl LLMClient {
             completion_tokens,
             duration_ms,
             tokens_per_second,
+            prompt_tokens,
+            cost_usd,
         })
     }
 
@@ -284,20 +498,9 @@ impl LLMClient {
 
         let (tx, rx) = mpsc::channel(100);
 
-        let user_prompt = build_user_prompt(&config.user_prompt_template, target_language, text);
-
         let request_body = ChatCompletionRequest {
             model: config.model.clone(),
-            messages: vec![
-                Message {
-                    role: "system".to_string(),
-                    content: config.system_prompt.clone(),
-                },
-                Message {
-                    role: "user".to_string(),
-                    content: user_prompt,
-                },
-            ],
+            messages: build_messages(config, target_language, text),
             temperature: config.temperature,
             top_p: config.top_p,
             stream: Some(true),
@@ -309,11 +512,13 @@ impl LLMClient {
         let url = format!("{}/chat/completions", config.base_url.trim_end_matches('/'));
         let client = self.client.clone();
         let api_key = config.api_key.clone();
+        let price = config.price();
 
         // 在后台任务中处理流式响应
         tokio::spawn(async move {
             let start_time = Instant::now();
             let mut total_tokens = 0u32;
+            let mut prompt_tokens = 0u32;
 
             let response = match client
                 .post(&url)
@@ -365,6 +570,7 @@ impl LLMClient {
                                 // 检查 usage (某些 API 在流式响应的最后一块包含 usage)
                                 if let Some(usage) = &chunk_data.usage {
                                     total_tokens = usage.completion_tokens;
+                                    prompt_tokens = usage.prompt_tokens;
                                     debug!("Received usage info: {} completion_tokens", total_tokens);
                                 }
 
@@ -385,9 +591,13 @@ impl LLMClient {
             }
 
             let duration_ms = start_time.elapsed().as_millis() as u64;
+            let completion_tokens = if total_tokens > 0 { Some(total_tokens) } else { None };
+            let prompt_tokens = if prompt_tokens > 0 { Some(prompt_tokens) } else { None };
             let _ = tx.send(StreamEvent::Done {
-                completion_tokens: if total_tokens > 0 { Some(total_tokens) } else { None },
+                completion_tokens,
+                prompt_tokens,
                 duration_ms,
+                cost_usd: request_cost(price, prompt_tokens, completion_tokens),
             }).await;
         });
 
@@ -408,6 +618,131 @@ fn build_user_prompt(template: &str, target_language: &str, text: &str) -> Strin
         .replace("{text}", text)
 }
 
+/// 使用目标语言提示词的 LLM 配置
+///
+/// `overrides` 中没有目标语言，或对应的提示词为空时使用 `config` 中的全局提示词。
+pub fn with_prompt_override<'a>(
+    config: &'a LLMConfig,
+    overrides: &HashMap<String, PromptOverride>,
+    target_language: &str,
+) -> Cow<'a, LLMConfig> {
+    let Some(prompt) = overrides.get(target_language) else {
+        return Cow::Borrowed(config);
+    };
+    let non_empty = |value: &Option<String>| value.clone().filter(|v| !v.trim().is_empty());
+    let system_prompt = non_empty(&prompt.system_prompt);
+    let user_prompt_template = non_empty(&prompt.user_prompt_template);
+    if system_prompt.is_none() && user_prompt_template.is_none() {
+        return Cow::Borrowed(config);
+    }
+
+    let mut config = config.clone();
+    if let Some(system_prompt) = system_prompt {
+        config.system_prompt = system_prompt;
+    }
+    if let Some(user_prompt_template) = user_prompt_template {
+        config.user_prompt_template = user_prompt_template;
+    }
+    Cow::Owned(config)
+}
+
+/// 翻译请求的消息：系统提示词和渲染后的用户提示词
+///
+/// `translate`、`translate_stream` 和提示词预览共用，保证预览与实际请求一致。
+pub fn build_messages(config: &LLMConfig, target_language: &str, text: &str) -> Vec<Message> {
+    vec![
+        Message {
+            role: "system".to_string(),
+            content: config.system_prompt.clone(),
+        },
+        Message {
+            role: "user".to_string(),
+            content: build_user_prompt(&config.user_prompt_template, target_language, text),
+        },
+    ]
+}
+
+/// 预估的 prompt tokens 上限，超过时大多数模型的上下文可能放不下
+pub const MAX_PROMPT_TOKENS_EST: u32 = 16_000;
+/// 每条消息的格式开销（tokens）
+const MESSAGE_OVERHEAD_TOKENS: u32 = 4;
+/// 回复的起始开销（tokens）
+const REPLY_PRIMING_TOKENS: u32 = 3;
+
+/// 翻译请求的 tokens 和费用预估
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenEstimate {
+    /// 预估的 prompt tokens
+    pub prompt_tokens_est: u32,
+    /// 预估的 completion tokens，按译文与原文长度相当估算
+    pub completion_tokens_est: u32,
+    /// 按价格表估算的费用（美元），未配置价格时为空
+    pub cost_est_usd: Option<f64>,
+    /// prompt 是否超过 `MAX_PROMPT_TOKENS_EST`
+    pub exceeds_max_tokens: bool,
+}
+
+/// 在本地粗略估算文本的 tokens 数量，不调用 API
+///
+/// 中日韩字符大约每字一个 token，其他文字大约每 4 个字符一个 token。
+pub fn estimate_tokens(text: &str) -> u32 {
+    let mut cjk = 0u32;
+    let mut other = 0u32;
+    for c in text.chars() {
+        if is_cjk(c) {
+            cjk += 1;
+        } else {
+            other += 1;
+        }
+    }
+    cjk + other.div_ceil(4)
+}
+
+/// 中日韩文字（汉字、假名、谚文及全角标点）
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3000}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FF00}'..='\u{FFEF}'
+        | '\u{20000}'..='\u{2FFFF}')
+}
+
+/// 预估翻译 `text` 的请求大小和费用，消息与实际发送的请求一致
+pub fn estimate_translation(
+    config: &LLMConfig,
+    text: &str,
+    target_language: &str,
+) -> TokenEstimate {
+    let prompt_tokens_est = build_messages(config, target_language, text)
+        .iter()
+        .map(|message| estimate_tokens(&message.content) + MESSAGE_OVERHEAD_TOKENS)
+        .sum::<u32>()
+        + REPLY_PRIMING_TOKENS;
+    let completion_tokens_est = estimate_tokens(text);
+    TokenEstimate {
+        prompt_tokens_est,
+        completion_tokens_est,
+        cost_est_usd: request_cost(
+            config.price(),
+            Some(prompt_tokens_est),
+            Some(completion_tokens_est),
+        ),
+        exceeds_max_tokens: prompt_tokens_est > MAX_PROMPT_TOKENS_EST,
+    }
+}
+
+/// 计算一次请求的费用，价格或任一 tokens 数量未知时返回 `None`
+fn request_cost(
+    price: Option<ModelPrice>,
+    prompt_tokens: Option<u32>,
+    completion_tokens: Option<u32>,
+) -> Option<f64> {
+    Some(price?.cost_usd(prompt_tokens?, completion_tokens?))
+}
+
 /// 从响应文本中提取 completion_tokens
 fn extract_completion_tokens(response_text: &str) -> Option<u32> {
     // 尝试用正则或简单搜索找 completion_tokens
@@ -435,6 +770,245 @@ fn extract_completion_tokens(response_text: &str) -> Option<u32> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 只响应一次请求的模拟 LLM 服务，返回服务地址和收到的请求体
+    async fn mock_llm_server(response: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        mock_llm_server_with_status("200 OK", response).await
+    }
+
+    /// 以指定状态码响应一次请求的模拟 LLM 服务
+    async fn mock_llm_server_with_status(
+        status: &'static str,
+        response: &'static str,
+    ) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            let body_start = loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+            };
+            let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+            let content_length: usize = headers
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .and_then(|value| value.trim().parse().ok())
+                .unwrap_or(0);
+            while request.len() < body_start + content_length {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+
+            let reply = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                response.len(),
+                response
+            );
+            socket.write_all(reply.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request[body_start..]).into_owned()
+        });
+        (base_url, handle)
+    }
+
+    #[tokio::test]
+    async fn test_translate_uses_given_target_language() {
+        let (base_url, request) = mock_llm_server(
+            r#"{"choices":[{"message":{"content":" Bonjour "}}],"usage":{"prompt_tokens":12,"completion_tokens":3,"total_tokens":15}}"#,
+        )
+        .await;
+        let config = LLMConfig {
+            base_url,
+            api_key: "test-key".to_string(),
+            user_prompt_template: "Translate to {target_language}: {text}".to_string(),
+            ..Default::default()
+        };
+
+        let result = LLMClient::new()
+            .unwrap()
+            .translate(&config, "你好", "fr-FR")
+            .await
+            .unwrap();
+        assert_eq!(result.translated_text, "Bonjour");
+        assert_eq!(result.completion_tokens, Some(3));
+
+        let body: serde_json::Value = serde_json::from_str(&request.await.unwrap()).unwrap();
+        assert_eq!(body["messages"][1]["content"], "Translate to fr-FR: 你好");
+    }
+
+    #[tokio::test]
+    async fn test_preview_matches_sent_messages() {
+        let (base_url, request) =
+            mock_llm_server(r#"{"choices":[{"message":{"content":"Hallo"}}]}"#).await;
+        let config = LLMConfig {
+            system_prompt: "You are a translator.".to_string(),
+            user_prompt_template: "To {target_language}:\n{text}".to_string(),
+            ..test_config(base_url)
+        };
+        let preview = build_messages(&config, "de-DE", "你好");
+        assert_eq!(preview.len(), 2);
+        assert_eq!(preview[1].content, "To de-DE:\n你好");
+
+        let client = LLMClient::new().unwrap();
+        client.translate(&config, "你好", "de-DE").await.unwrap();
+        let body: serde_json::Value = serde_json::from_str(&request.await.unwrap()).unwrap();
+        assert_eq!(body["messages"], serde_json::to_value(&preview).unwrap());
+
+        // 流式请求发送相同的消息
+        let (base_url, request) = mock_llm_server("data: [DONE]\n\n").await;
+        let config = LLMConfig { base_url, ..config };
+        let mut events = client
+            .translate_stream(&config, "你好", "de-DE")
+            .await
+            .unwrap();
+        while events.recv().await.is_some() {}
+        let body: serde_json::Value = serde_json::from_str(&request.await.unwrap()).unwrap();
+        assert_eq!(body["messages"], serde_json::to_value(&preview).unwrap());
+        assert_eq!(body["stream"], true);
+    }
+
+    #[test]
+    fn test_prompt_override_fallback() {
+        let config = LLMConfig {
+            system_prompt: "You are a translator.".to_string(),
+            user_prompt_template: "To {target_language}: {text}".to_string(),
+            ..Default::default()
+        };
+        let mut overrides = HashMap::new();
+        overrides.insert(
+            "ja-JP".to_string(),
+            PromptOverride {
+                system_prompt: Some("です/ます調で翻訳してください。".to_string()),
+                user_prompt_template: None,
+            },
+        );
+        overrides.insert(
+            "de-DE".to_string(),
+            PromptOverride {
+                system_prompt: Some("  ".to_string()),
+                user_prompt_template: Some("Ins Deutsche (Sie-Form): {text}".to_string()),
+            },
+        );
+        overrides.insert("fr-FR".to_string(), PromptOverride::default());
+
+        // 只覆盖 System Prompt，User Prompt 使用全局模板
+        let ja = build_messages(
+            &with_prompt_override(&config, &overrides, "ja-JP"),
+            "ja-JP",
+            "你好",
+        );
+        assert_eq!(ja[0].content, "です/ます調で翻訳してください。");
+        assert_eq!(ja[1].content, "To ja-JP: 你好");
+
+        // 空白的 System Prompt 视为未设置
+        let de = build_messages(
+            &with_prompt_override(&config, &overrides, "de-DE"),
+            "de-DE",
+            "你好",
+        );
+        assert_eq!(de[0].content, "You are a translator.");
+        assert_eq!(de[1].content, "Ins Deutsche (Sie-Form): 你好");
+
+        // 没有覆盖的语言使用全局提示词，不复制配置
+        assert!(matches!(
+            with_prompt_override(&config, &overrides, "fr-FR"),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(
+            with_prompt_override(&config, &overrides, "en-US"),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    fn test_config(base_url: String) -> LLMConfig {
+        LLMConfig {
+            base_url,
+            api_key: "test-key".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connection_report() {
+        let client = LLMClient::new().unwrap();
+        let (base_url, request) = mock_llm_server(
+            r#"{"model":"gpt-4o-mini-2024-07-18","choices":[{"message":{"content":"早上好"}}],"usage":{"prompt_tokens":20,"completion_tokens":4,"total_tokens":24}}"#,
+        )
+        .await;
+        let report = client
+            .test_connection_detailed(&test_config(base_url.clone()), Some("Good morning"))
+            .await;
+        assert!(report.success, "{:?}", report);
+        assert_eq!(report.endpoint, format!("{}/chat/completions", base_url));
+        assert_eq!(report.status, Some(200));
+        assert_eq!(report.model.as_deref(), Some("gpt-4o-mini-2024-07-18"));
+        assert_eq!(report.prompt_tokens, Some(20));
+        assert_eq!(report.completion_tokens, Some(4));
+        assert_eq!(report.translated_text.as_deref(), Some("早上好"));
+        assert!(report.summary().starts_with("连接成功！测试翻译: Good morning → 早上好"));
+        assert!(request.await.unwrap().contains("Good morning"));
+    }
+
+    #[tokio::test]
+    async fn test_connection_report_failure_stages() {
+        let client = LLMClient::new().unwrap();
+
+        let (base_url, _) = mock_llm_server_with_status(
+            "401 Unauthorized",
+            r#"{"error":{"message":"Incorrect API key provided"}}"#,
+        )
+        .await;
+        let report = client
+            .test_connection_detailed(&test_config(base_url), None)
+            .await;
+        assert_eq!(report.failed_stage, Some(ConnectionStage::Auth));
+        assert_eq!(report.status, Some(401));
+        assert_eq!(report.error.as_deref(), Some("Incorrect API key provided"));
+        assert_eq!(report.sample_text, CONNECTION_TEST_TEXT);
+
+        let (base_url, _) = mock_llm_server("not json").await;
+        let report = client
+            .test_connection_detailed(&test_config(base_url), None)
+            .await;
+        assert_eq!(report.failed_stage, Some(ConnectionStage::Parse));
+
+        let (base_url, _) = mock_llm_server(r#"{"choices":[]}"#).await;
+        let report = client
+            .test_connection_detailed(&test_config(base_url), None)
+            .await;
+        assert_eq!(report.failed_stage, Some(ConnectionStage::EmptyResponse));
+
+        // 端口上没有服务
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let report = client
+            .test_connection_detailed(&test_config(base_url), None)
+            .await;
+        assert_eq!(report.failed_stage, Some(ConnectionStage::Connect));
+        assert_eq!(report.status, None);
+
+        let report = client
+            .test_connection_detailed(&test_config(String::new()), None)
+            .await;
+        assert_eq!(report.failed_stage, Some(ConnectionStage::Config));
+        assert!(matches!(
+            status_error(reqwest::StatusCode::TOO_MANY_REQUESTS, String::new()),
+            AppError::LlmRateLimit(_)
+        ));
+        assert!(matches!(
+            client.test_connection(&test_config(String::new())).await,
+            Err(AppError::Config(_))
+        ));
+    }
 
     #[test]
     fn test_build_user_prompt() {
@@ -443,9 +1017,68 @@ mod tests {
         assert_eq!(result, "将下列文本翻译为English：你好");
     }
 
+    #[test]
+    fn test_estimate_tokens() {
+        // 中文约每字一个 token
+        assert_eq!(estimate_tokens("今天天气很好"), 6);
+        assert_eq!(estimate_tokens("こんにちは、世界！"), 9);
+        // 英文约每 4 个字符一个 token
+        assert_eq!(estimate_tokens("The quick brown fox"), 5);
+        assert_eq!(estimate_tokens("Hi 你好"), 3);
+        assert_eq!(estimate_tokens(""), 0);
+    }
+
+    #[test]
+    fn test_estimate_translation() {
+        let mut config = LLMConfig {
+            system_prompt: "You are a translator.".to_string(),
+            user_prompt_template: "Translate to {target_language}: {text}".to_string(),
+            ..Default::default()
+        };
+        let cjk = "机器翻译".repeat(1000);
+        let latin = "word ".repeat(1000);
+
+        let cjk_estimate = estimate_translation(&config, &cjk, "English");
+        let latin_estimate = estimate_translation(&config, &latin, "中文");
+        // 同样长度的文本，中文的 tokens 远多于英文
+        assert!(cjk_estimate.prompt_tokens_est > 4000);
+        assert!(latin_estimate.prompt_tokens_est < 1400);
+        assert_eq!(cjk_estimate.completion_tokens_est, 4000);
+        assert_eq!(latin_estimate.completion_tokens_est, 1250);
+        assert!(!cjk_estimate.exceeds_max_tokens);
+        assert!(cjk_estimate.cost_est_usd.is_none());
+
+        config.pricing.insert(
+            config.model.clone(),
+            ModelPrice {
+                input_per_million: 1.0,
+                output_per_million: 2.0,
+            },
+        );
+        let estimate = estimate_translation(&config, &latin, "中文");
+        let expected = (estimate.prompt_tokens_est as f64 + 2.0 * 1250.0) / 1_000_000.0;
+        assert!((estimate.cost_est_usd.unwrap() - expected).abs() < 1e-12);
+
+        let huge = "长".repeat(MAX_PROMPT_TOKENS_EST as usize);
+        assert!(estimate_translation(&config, &huge, "English").exceeds_max_tokens);
+    }
+
     #[test]
     fn test_extract_completion_tokens() {
         let response = r#"{"usage":{"completion_tokens":92,"prompt_tokens":10}}"#;
         assert_eq!(extract_completion_tokens(response), Some(92));
     }
+
+    #[test]
+    fn test_request_cost() {
+        let price = ModelPrice {
+            input_per_million: 1.0,
+            output_per_million: 2.0,
+        };
+        let cost = request_cost(Some(price), Some(1000), Some(500)).unwrap();
+        assert!((cost - 0.002).abs() < 1e-12);
+        // 缺少价格或 usage 时不记为零费用
+        assert_eq!(request_cost(None, Some(1000), Some(500)), None);
+        assert_eq!(request_cost(Some(price), None, Some(500)), None);
+    }
 }
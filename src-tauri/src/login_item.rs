@@ -0,0 +1,242 @@
+//! 开机启动模块
+//! 将应用注册为系统登录项
+//!
+//! 支持平台:
+//! - macOS: 使用 SMAppService (需要 macOS 13 或更高版本)
+//! - Windows: 使用当前用户的 Run 注册表项
+
+pub use platform::{is_enabled, set_enabled};
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use crate::error::{AppError, Result};
+    use objc::runtime::{Class, Object, BOOL, NO};
+    use objc::{msg_send, sel, sel_impl};
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    #[link(name = "ServiceManagement", kind = "framework")]
+    extern "C" {}
+
+    /// SMAppServiceStatusEnabled
+    const STATUS_ENABLED: isize = 1;
+    /// SMAppServiceStatusRequiresApproval
+    const STATUS_REQUIRES_APPROVAL: isize = 2;
+
+    /// 当前应用对应的登录项服务
+    fn main_app_service() -> Result<*mut Object> {
+        let class = Class::get("SMAppService")
+            .ok_or_else(|| AppError::Other("开机启动需要 macOS 13 或更高版本".to_string()))?;
+        Ok(unsafe { msg_send![class, mainAppService] })
+    }
+
+    fn status(service: *mut Object) -> isize {
+        unsafe { msg_send![service, status] }
+    }
+
+    /// NSError 的描述
+    fn describe(error: *mut Object) -> String {
+        if error.is_null() {
+            return "未知错误".to_string();
+        }
+        unsafe {
+            let description: *mut Object = msg_send![error, localizedDescription];
+            let chars: *const c_char = msg_send![description, UTF8String];
+            if chars.is_null() {
+                return "未知错误".to_string();
+            }
+            CStr::from_ptr(chars).to_string_lossy().into_owned()
+        }
+    }
+
+    /// 登录项是否已启用，等待用户在系统设置中允许时返回 false
+    pub fn is_enabled() -> Result<bool> {
+        Ok(status(main_app_service()?) == STATUS_ENABLED)
+    }
+
+    /// 添加或移除登录项，添加后需要用户在系统设置中允许时返回 `Permission` 错误
+    pub fn set_enabled(enabled: bool) -> Result<()> {
+        let service = main_app_service()?;
+        let mut error: *mut Object = std::ptr::null_mut();
+        let error_ptr = &mut error as *mut *mut Object;
+        let ok: BOOL = unsafe {
+            if enabled {
+                msg_send![service, registerAndReturnError: error_ptr]
+            } else {
+                msg_send![service, unregisterAndReturnError: error_ptr]
+            }
+        };
+        if ok == NO {
+            return Err(AppError::Permission(format!(
+                "无法{}登录项: {}",
+                if enabled { "添加" } else { "移除" },
+                describe(error)
+            )));
+        }
+        if enabled && status(service) == STATUS_REQUIRES_APPROVAL {
+            return Err(AppError::Permission(
+                "已添加登录项，需要在系统设置 > 通用 > 登录项中允许本应用".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use crate::error::{AppError, Result};
+    use std::ffi::{c_void, OsStr};
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr;
+
+    /// HKEY_CURRENT_USER，预定义句柄按 32 位有符号数扩展
+    const HKEY_CURRENT_USER: isize = 0x8000_0001u32 as i32 as isize;
+    const KEY_QUERY_VALUE: u32 = 0x0001;
+    const KEY_SET_VALUE: u32 = 0x0002;
+    const REG_SZ: u32 = 1;
+    const ERROR_FILE_NOT_FOUND: i32 = 2;
+
+    const RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+    const VALUE_NAME: &str = "QuickTransType";
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        fn RegOpenKeyExW(
+            key: *mut c_void,
+            sub_key: *const u16,
+            options: u32,
+            desired: u32,
+            result: *mut *mut c_void,
+        ) -> i32;
+        fn RegQueryValueExW(
+            key: *mut c_void,
+            value_name: *const u16,
+            reserved: *mut u32,
+            value_type: *mut u32,
+            data: *mut u8,
+            data_len: *mut u32,
+        ) -> i32;
+        fn RegSetValueExW(
+            key: *mut c_void,
+            value_name: *const u16,
+            reserved: u32,
+            value_type: u32,
+            data: *const u8,
+            data_len: u32,
+        ) -> i32;
+        fn RegDeleteValueW(key: *mut c_void, value_name: *const u16) -> i32;
+        fn RegCloseKey(key: *mut c_void) -> i32;
+    }
+
+    fn wide(text: &str) -> Vec<u16> {
+        OsStr::new(text)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect()
+    }
+
+    fn registry_error(code: i32) -> AppError {
+        AppError::Other(format!(
+            "无法访问开机启动注册表项: {}",
+            std::io::Error::from_raw_os_error(code)
+        ))
+    }
+
+    /// 打开的 Run 注册表项，释放时关闭
+    struct RunKey(*mut c_void);
+
+    impl RunKey {
+        fn open(access: u32) -> Result<Self> {
+            let sub_key = wide(RUN_KEY);
+            let mut key = ptr::null_mut();
+            let code = unsafe {
+                RegOpenKeyExW(
+                    HKEY_CURRENT_USER as *mut c_void,
+                    sub_key.as_ptr(),
+                    0,
+                    access,
+                    &mut key,
+                )
+            };
+            if code != 0 {
+                return Err(registry_error(code));
+            }
+            Ok(Self(key))
+        }
+    }
+
+    impl Drop for RunKey {
+        fn drop(&mut self) {
+            unsafe {
+                RegCloseKey(self.0);
+            }
+        }
+    }
+
+    /// Run 注册表项中是否有本应用
+    pub fn is_enabled() -> Result<bool> {
+        let key = RunKey::open(KEY_QUERY_VALUE)?;
+        let name = wide(VALUE_NAME);
+        let code = unsafe {
+            RegQueryValueExW(
+                key.0,
+                name.as_ptr(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        match code {
+            0 => Ok(true),
+            ERROR_FILE_NOT_FOUND => Ok(false),
+            code => Err(registry_error(code)),
+        }
+    }
+
+    /// 在 Run 注册表项中写入或删除当前可执行文件的路径
+    pub fn set_enabled(enabled: bool) -> Result<()> {
+        let key = RunKey::open(KEY_SET_VALUE)?;
+        let name = wide(VALUE_NAME);
+        let code = if enabled {
+            let command = wide(&format!("\"{}\"", std::env::current_exe()?.display()));
+            unsafe {
+                RegSetValueExW(
+                    key.0,
+                    name.as_ptr(),
+                    0,
+                    REG_SZ,
+                    command.as_ptr() as *const u8,
+                    (command.len() * 2) as u32,
+                )
+            }
+        } else {
+            match unsafe { RegDeleteValueW(key.0, name.as_ptr()) } {
+                ERROR_FILE_NOT_FOUND => 0,
+                code => code,
+            }
+        };
+        if code != 0 {
+            return Err(registry_error(code));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use crate::error::{AppError, Result};
+
+    /// 其他平台不支持开机启动
+    pub fn is_enabled() -> Result<bool> {
+        Ok(false)
+    }
+
+    /// 其他平台不支持开机启动，关闭时不做处理
+    pub fn set_enabled(enabled: bool) -> Result<()> {
+        if enabled {
+            return Err(AppError::Other("当前平台不支持开机启动".to_string()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,495 @@
+//! 密钥存储模块
+//! 将 API Key 和历史加密密钥保存在系统的凭据存储中：macOS 使用钥匙串，Windows 使用凭据管理器
+//!
+//! 其他平台没有可用的凭据存储，API Key 仍保存在配置文件中，历史加密密钥保存在配置目录下的密钥文件中。
+
+use crate::error::Result;
+
+/// 凭据存储中使用的服务名
+pub const SERVICE: &str = "com.QuickTransType.app";
+/// 默认方案的 API Key 在凭据存储中的账户名
+pub const API_KEY_ACCOUNT: &str = "llm_api_key";
+/// 翻译历史加密密钥在凭据存储中的账户名
+pub const HISTORY_KEY_ACCOUNT: &str = "history_key";
+/// API Key 保存在凭据存储中时，配置文件里代替 API Key 的占位文本
+pub const STORED_API_KEY: &str = "<stored-in-credential-store>";
+
+/// 系统凭据存储
+pub trait SecretStore: Send + Sync {
+    /// 读取密钥，不存在时返回 `None`
+    fn get(&self, account: &str) -> Result<Option<String>>;
+    /// 写入密钥，已存在时覆盖
+    fn set(&self, account: &str, secret: &str) -> Result<()>;
+    /// 删除密钥，不存在时不报错
+    fn delete(&self, account: &str) -> Result<()>;
+}
+
+/// 当前平台的凭据存储，没有可用的凭据存储时返回 `None`
+pub fn system_store() -> Option<Box<dyn SecretStore>> {
+    #[cfg(target_os = "macos")]
+    {
+        Some(Box::new(keychain::KeychainStore))
+    }
+    #[cfg(target_os = "windows")]
+    {
+        Some(Box::new(credential_manager::CredentialStore))
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        None
+    }
+}
+
+/// LLM 配置方案的 API Key 在凭据存储中的账户名
+///
+/// 默认方案沿用 `API_KEY_ACCOUNT`，升级前保存的 API Key 不需要迁移。
+pub fn api_key_account(profile: &str) -> String {
+    if profile == crate::config::DEFAULT_PROFILE {
+        API_KEY_ACCOUNT.to_string()
+    } else {
+        format!("{}:{}", API_KEY_ACCOUNT, profile)
+    }
+}
+
+/// 配置文件中读到的 API Key 对应的实际值
+///
+/// 返回实际的 API Key 和凭据存储中的已知值；读取失败时已知值为 `None`，
+/// 此时不能认为凭据存储中没有 API Key。
+pub fn load_api_key(
+    store: &dyn SecretStore,
+    account: &str,
+    file_value: &str,
+) -> (String, Option<String>) {
+    if file_value != STORED_API_KEY {
+        return (file_value.to_string(), None);
+    }
+    match store.get(account) {
+        Ok(secret) => {
+            let secret = secret.unwrap_or_default();
+            (secret.clone(), Some(secret))
+        }
+        Err(e) => {
+            tracing::warn!("Failed to read API key from credential store: {}", e);
+            (String::new(), None)
+        }
+    }
+}
+
+/// 将 API Key 写入凭据存储，返回配置文件中应保存的值
+///
+/// `known` 为凭据存储中的已知值，相同时不重复写入。API Key 为空时删除凭据；
+/// 凭据存储的内容未知时保留占位文本，避免读取失败后保存配置导致 API Key 丢失。
+pub fn store_api_key(
+    store: &dyn SecretStore,
+    account: &str,
+    known: &mut Option<String>,
+    api_key: &str,
+) -> Result<String> {
+    if api_key.is_empty() {
+        return match known.as_deref() {
+            None => Ok(STORED_API_KEY.to_string()),
+            Some("") => Ok(String::new()),
+            Some(_) => {
+                store.delete(account)?;
+                *known = Some(String::new());
+                Ok(String::new())
+            }
+        };
+    }
+    if known.as_deref() != Some(api_key) {
+        store.set(account, api_key)?;
+        *known = Some(api_key.to_string());
+    }
+    Ok(STORED_API_KEY.to_string())
+}
+
+#[cfg(target_os = "macos")]
+mod keychain {
+    use super::{SecretStore, SERVICE};
+    use crate::error::{AppError, Result};
+    use std::ffi::c_void;
+    use std::ptr;
+
+    const ERR_SEC_SUCCESS: i32 = 0;
+    const ERR_SEC_ITEM_NOT_FOUND: i32 = -25300;
+
+    #[link(name = "Security", kind = "framework")]
+    extern "C" {
+        fn SecKeychainFindGenericPassword(
+            keychain_or_array: *const c_void,
+            service_name_length: u32,
+            service_name: *const u8,
+            account_name_length: u32,
+            account_name: *const u8,
+            password_length: *mut u32,
+            password_data: *mut *mut c_void,
+            item_ref: *mut *const c_void,
+        ) -> i32;
+        fn SecKeychainAddGenericPassword(
+            keychain: *const c_void,
+            service_name_length: u32,
+            service_name: *const u8,
+            account_name_length: u32,
+            account_name: *const u8,
+            password_length: u32,
+            password_data: *const c_void,
+            item_ref: *mut *const c_void,
+        ) -> i32;
+        fn SecKeychainItemModifyAttributesAndData(
+            item_ref: *const c_void,
+            attr_list: *const c_void,
+            length: u32,
+            data: *const c_void,
+        ) -> i32;
+        fn SecKeychainItemFreeContent(attr_list: *const c_void, data: *mut c_void) -> i32;
+        fn SecKeychainItemDelete(item_ref: *const c_void) -> i32;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFRelease(cf: *const c_void);
+    }
+
+    fn keychain_error(status: i32) -> AppError {
+        AppError::Keychain(format!("钥匙串操作失败 (OSStatus {})", status))
+    }
+
+    /// 查找钥匙串条目，返回的条目需要 `CFRelease`
+    fn find_item(account: &str) -> Result<Option<*const c_void>> {
+        let mut item = ptr::null();
+        let status = unsafe {
+            SecKeychainFindGenericPassword(
+                ptr::null(),
+                SERVICE.len() as u32,
+                SERVICE.as_ptr(),
+                account.len() as u32,
+                account.as_ptr(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                &mut item,
+            )
+        };
+        match status {
+            ERR_SEC_SUCCESS => Ok(Some(item)),
+            ERR_SEC_ITEM_NOT_FOUND => Ok(None),
+            status => Err(keychain_error(status)),
+        }
+    }
+
+    /// macOS 钥匙串
+    pub struct KeychainStore;
+
+    impl SecretStore for KeychainStore {
+        fn get(&self, account: &str) -> Result<Option<String>> {
+            let mut length = 0u32;
+            let mut data = ptr::null_mut();
+            let status = unsafe {
+                SecKeychainFindGenericPassword(
+                    ptr::null(),
+                    SERVICE.len() as u32,
+                    SERVICE.as_ptr(),
+                    account.len() as u32,
+                    account.as_ptr(),
+                    &mut length,
+                    &mut data,
+                    ptr::null_mut(),
+                )
+            };
+            match status {
+                ERR_SEC_SUCCESS => {}
+                ERR_SEC_ITEM_NOT_FOUND => return Ok(None),
+                status => return Err(keychain_error(status)),
+            }
+
+            let bytes =
+                unsafe { std::slice::from_raw_parts(data as *const u8, length as usize) }.to_vec();
+            unsafe {
+                SecKeychainItemFreeContent(ptr::null(), data);
+            }
+            String::from_utf8(bytes)
+                .map(Some)
+                .map_err(|_| AppError::Keychain("钥匙串中的内容不是有效文本".to_string()))
+        }
+
+        fn set(&self, account: &str, secret: &str) -> Result<()> {
+            let status = match find_item(account)? {
+                Some(item) => unsafe {
+                    let status = SecKeychainItemModifyAttributesAndData(
+                        item,
+                        ptr::null(),
+                        secret.len() as u32,
+                        secret.as_ptr() as *const c_void,
+                    );
+                    CFRelease(item);
+                    status
+                },
+                None => unsafe {
+                    SecKeychainAddGenericPassword(
+                        ptr::null(),
+                        SERVICE.len() as u32,
+                        SERVICE.as_ptr(),
+                        account.len() as u32,
+                        account.as_ptr(),
+                        secret.len() as u32,
+                        secret.as_ptr() as *const c_void,
+                        ptr::null_mut(),
+                    )
+                },
+            };
+            match status {
+                ERR_SEC_SUCCESS => Ok(()),
+                status => Err(keychain_error(status)),
+            }
+        }
+
+        fn delete(&self, account: &str) -> Result<()> {
+            let Some(item) = find_item(account)? else {
+                return Ok(());
+            };
+            let status = unsafe {
+                let status = SecKeychainItemDelete(item);
+                CFRelease(item);
+                status
+            };
+            match status {
+                ERR_SEC_SUCCESS => Ok(()),
+                status => Err(keychain_error(status)),
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod credential_manager {
+    use super::{SecretStore, SERVICE};
+    use crate::error::{AppError, Result};
+    use std::ffi::{c_void, OsStr};
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr;
+
+    const CRED_TYPE_GENERIC: u32 = 1;
+    const CRED_PERSIST_LOCAL_MACHINE: u32 = 2;
+    const ERROR_NOT_FOUND: i32 = 1168;
+
+    #[repr(C)]
+    struct FileTime {
+        low_date_time: u32,
+        high_date_time: u32,
+    }
+
+    #[repr(C)]
+    struct CredentialW {
+        flags: u32,
+        cred_type: u32,
+        target_name: *mut u16,
+        comment: *mut u16,
+        last_written: FileTime,
+        credential_blob_size: u32,
+        credential_blob: *mut u8,
+        persist: u32,
+        attribute_count: u32,
+        attributes: *mut c_void,
+        target_alias: *mut u16,
+        user_name: *mut u16,
+    }
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        fn CredReadW(
+            target_name: *const u16,
+            cred_type: u32,
+            flags: u32,
+            credential: *mut *mut CredentialW,
+        ) -> i32;
+        fn CredWriteW(credential: *const CredentialW, flags: u32) -> i32;
+        fn CredDeleteW(target_name: *const u16, cred_type: u32, flags: u32) -> i32;
+        fn CredFree(buffer: *mut c_void);
+    }
+
+    /// 凭据名称，如 com.QuickTransType.app/llm_api_key
+    fn target_name(account: &str) -> Vec<u16> {
+        OsStr::new(&format!("{}/{}", SERVICE, account))
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect()
+    }
+
+    fn last_error() -> std::io::Error {
+        std::io::Error::last_os_error()
+    }
+
+    fn credential_error(error: std::io::Error) -> AppError {
+        AppError::Keychain(format!("凭据管理器操作失败: {}", error))
+    }
+
+    /// Windows 凭据管理器
+    pub struct CredentialStore;
+
+    impl SecretStore for CredentialStore {
+        fn get(&self, account: &str) -> Result<Option<String>> {
+            let target = target_name(account);
+            let mut credential = ptr::null_mut();
+            if unsafe { CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut credential) } == 0 {
+                let error = last_error();
+                if error.raw_os_error() == Some(ERROR_NOT_FOUND) {
+                    return Ok(None);
+                }
+                return Err(credential_error(error));
+            }
+
+            let bytes = unsafe {
+                let credential = &*credential;
+                std::slice::from_raw_parts(
+                    credential.credential_blob,
+                    credential.credential_blob_size as usize,
+                )
+                .to_vec()
+            };
+            unsafe {
+                CredFree(credential as *mut c_void);
+            }
+            String::from_utf8(bytes)
+                .map(Some)
+                .map_err(|_| AppError::Keychain("凭据管理器中的内容不是有效文本".to_string()))
+        }
+
+        fn set(&self, account: &str, secret: &str) -> Result<()> {
+            let mut target = target_name(account);
+            let mut blob = secret.as_bytes().to_vec();
+            let credential = CredentialW {
+                flags: 0,
+                cred_type: CRED_TYPE_GENERIC,
+                target_name: target.as_mut_ptr(),
+                comment: ptr::null_mut(),
+                last_written: FileTime {
+                    low_date_time: 0,
+                    high_date_time: 0,
+                },
+                credential_blob_size: blob.len() as u32,
+                credential_blob: blob.as_mut_ptr(),
+                persist: CRED_PERSIST_LOCAL_MACHINE,
+                attribute_count: 0,
+                attributes: ptr::null_mut(),
+                target_alias: ptr::null_mut(),
+                user_name: ptr::null_mut(),
+            };
+            if unsafe { CredWriteW(&credential, 0) } == 0 {
+                return Err(credential_error(last_error()));
+            }
+            Ok(())
+        }
+
+        fn delete(&self, account: &str) -> Result<()> {
+            let target = target_name(account);
+            if unsafe { CredDeleteW(target.as_ptr(), CRED_TYPE_GENERIC, 0) } == 0 {
+                let error = last_error();
+                if error.raw_os_error() != Some(ERROR_NOT_FOUND) {
+                    return Err(credential_error(error));
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// 测试用的内存凭据存储，记录写入次数，`fail` 为 true 时读取失败（如钥匙串已锁定）
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct MemoryStore {
+    pub(crate) secrets: std::sync::Mutex<std::collections::HashMap<String, String>>,
+    pub(crate) writes: std::sync::Mutex<usize>,
+    pub(crate) fail: bool,
+}
+
+#[cfg(test)]
+impl SecretStore for MemoryStore {
+    fn get(&self, account: &str) -> Result<Option<String>> {
+        if self.fail {
+            return Err(crate::error::AppError::Keychain("locked".to_string()));
+        }
+        Ok(self.secrets.lock().unwrap().get(account).cloned())
+    }
+
+    fn set(&self, account: &str, secret: &str) -> Result<()> {
+        *self.writes.lock().unwrap() += 1;
+        self.secrets
+            .lock()
+            .unwrap()
+            .insert(account.to_string(), secret.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, account: &str) -> Result<()> {
+        self.secrets.lock().unwrap().remove(account);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_plaintext_api_key() {
+        let store = MemoryStore::default();
+        // 配置文件中的明文 API Key 原样使用，保存时移入凭据存储
+        let (api_key, mut known) = load_api_key(&store, API_KEY_ACCOUNT, "sk-plain");
+        assert_eq!(api_key, "sk-plain");
+        assert_eq!(known, None);
+
+        let file_value = store_api_key(&store, API_KEY_ACCOUNT, &mut known, &api_key).unwrap();
+        assert_eq!(file_value, STORED_API_KEY);
+        assert_eq!(
+            store.get(API_KEY_ACCOUNT).unwrap().as_deref(),
+            Some("sk-plain")
+        );
+
+        // 下次启动从凭据存储读取
+        assert_eq!(
+            load_api_key(&store, API_KEY_ACCOUNT, STORED_API_KEY),
+            ("sk-plain".to_string(), Some("sk-plain".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_store_api_key_updates() {
+        let store = MemoryStore::default();
+        let mut known = None;
+        store_api_key(&store, API_KEY_ACCOUNT, &mut known, "sk-1").unwrap();
+        // 未变化时不重复写入
+        store_api_key(&store, API_KEY_ACCOUNT, &mut known, "sk-1").unwrap();
+        assert_eq!(*store.writes.lock().unwrap(), 1);
+
+        store_api_key(&store, API_KEY_ACCOUNT, &mut known, "sk-2").unwrap();
+        assert_eq!(store.get(API_KEY_ACCOUNT).unwrap().as_deref(), Some("sk-2"));
+
+        // 清空 API Key 时删除凭据
+        assert_eq!(
+            store_api_key(&store, API_KEY_ACCOUNT, &mut known, "").unwrap(),
+            ""
+        );
+        assert_eq!(store.get(API_KEY_ACCOUNT).unwrap(), None);
+    }
+
+    #[test]
+    fn test_api_key_account() {
+        assert_eq!(api_key_account("default"), API_KEY_ACCOUNT);
+        assert_eq!(api_key_account("ollama"), "llm_api_key:ollama");
+    }
+
+    #[test]
+    fn test_unreadable_store_keeps_placeholder() {
+        let store = MemoryStore {
+            fail: true,
+            ..Default::default()
+        };
+        let (api_key, mut known) = load_api_key(&store, API_KEY_ACCOUNT, STORED_API_KEY);
+        assert_eq!(api_key, "");
+        assert_eq!(known, None);
+        // 读取失败后保存配置不会删除凭据，配置文件仍指向凭据存储
+        assert_eq!(
+            store_api_key(&store, API_KEY_ACCOUNT, &mut known, "").unwrap(),
+            STORED_API_KEY
+        );
+    }
+}
@@ -1,16 +1,121 @@
 //! 应用状态模块
 //! 管理全局状态和共享资源
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, Hotkey};
+use crate::crypto::FieldCipher;
 use crate::database::Database;
-use crate::error::Result;
-use crate::hotkey::HotkeyManager;
+use crate::error::{AppError, Result};
+use crate::error_log::ErrorLog;
+use crate::hotkey::{HotkeyManager, TriggerDebouncer};
+use crate::key_listener::{KeyListenerHandle, ListenerStatus, RestartBackoff};
 use crate::llm::LLMClient;
+use crate::secrets::SecretStore;
 use crate::text_handler::TextHandler;
-use std::path::PathBuf;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{debug, info};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, RwLock};
+use tracing::{debug, error, info, warn};
+
+/// 正在进行的翻译
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveTranslation {
+    /// 操作模式："selected"、"full" 或 "clipboard"
+    pub mode: String,
+    /// 是否使用流式输出
+    pub stream: bool,
+    /// 开始时间（Unix 时间戳）
+    pub started_at: i64,
+}
+
+/// 本次运行中最近一次热键翻译的结果，只保存在内存中
+#[derive(Debug, Clone, Serialize)]
+pub struct LastTranslation {
+    pub original_text: String,
+    pub translated_text: String,
+    pub target_lang: String,
+    /// 操作模式："selected"、"full" 或 "clipboard"
+    pub mode: String,
+    /// 生成译文的模型，复用历史译文时为历史记录中的模型
+    pub model: Option<String>,
+    pub completion_tokens: Option<u32>,
+    /// 翻译耗时（毫秒）
+    pub duration_ms: u64,
+    /// 是否复用了历史中的译文
+    pub cached: bool,
+    /// 完成时间（Unix 时间戳）
+    pub timestamp: i64,
+}
+
+/// 一个热键最近一次注册的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct HotkeyRegistration {
+    /// 模式名："selected"、"full"、"clipboard" 或 "toggle_enabled"
+    pub mode: String,
+    pub hotkey: Hotkey,
+    /// 热键的显示形式
+    pub label: String,
+    /// 是否注册成功，基于 rdev 监听的热键还取决于监听器状态
+    pub registered: bool,
+    /// 注册失败的原因
+    pub error: Option<String>,
+}
+
+/// 登记中的翻译及其取消信号
+struct ActiveSlot {
+    id: u64,
+    info: ActiveTranslation,
+    cancel: Arc<watch::Sender<bool>>,
+}
+
+fn lock_slot(slot: &Mutex<Option<ActiveSlot>>) -> std::sync::MutexGuard<'_, Option<ActiveSlot>> {
+    slot.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// 登记中的键盘监听会话
+struct ListenerSlot {
+    id: u64,
+    handle: KeyListenerHandle,
+    /// 是否为热键录制会话
+    capture: bool,
+}
+
+fn lock_listener(
+    slot: &Mutex<Option<ListenerSlot>>,
+) -> std::sync::MutexGuard<'_, Option<ListenerSlot>> {
+    slot.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// 一次翻译的取消令牌，释放时注销这次翻译
+pub struct TranslationTicket {
+    id: u64,
+    cancel: Arc<watch::Sender<bool>>,
+    slot: Arc<Mutex<Option<ActiveSlot>>>,
+}
+
+impl TranslationTicket {
+    /// 等待翻译被取消
+    pub async fn cancelled(&self) {
+        // 发送端由令牌持有，等待期间不会关闭
+        let _ = self
+            .cancel
+            .subscribe()
+            .wait_for(|cancelled| *cancelled)
+            .await;
+    }
+}
+
+impl Drop for TranslationTicket {
+    fn drop(&mut self) {
+        let mut slot = lock_slot(&self.slot);
+        if slot.as_ref().is_some_and(|active| active.id == self.id) {
+            *slot = None;
+        }
+    }
+}
 
 /// 应用程序全局状态
 pub struct AppState {
@@ -26,8 +131,42 @@ pub struct AppState {
     pub text_handler: Arc<TextHandler>,
     /// 是否启用翻译监听
     pub is_enabled: Arc<RwLock<bool>>,
+    /// 启用状态的副本，供键盘监听线程无锁读取
+    enabled_flag: Arc<AtomicBool>,
+    /// 键盘监听器（连续按键/长按热键）最近一次启动失败的原因
+    pub key_listener_error: Arc<RwLock<Option<String>>>,
+    /// 运行中的键盘监听会话（连续按键/长按热键或热键录制），rdev 同一时间只能有一个监听
+    key_listener: Mutex<Option<ListenerSlot>>,
+    /// 下一个键盘监听会话的编号
+    next_listener_id: AtomicU64,
+    /// 键盘监听器的运行状态，变化时通知订阅方
+    listener_status: watch::Sender<ListenerStatus>,
+    /// 键盘监听器自动重启的退避状态
+    listener_backoff: Mutex<RestartBackoff>,
+    /// 设置窗口是否获得焦点
+    settings_focused: AtomicBool,
+    /// 是否暂停热键响应（设置窗口获得焦点时），键盘监听线程共享此标志
+    hotkeys_suppressed: Arc<AtomicBool>,
+    /// 热键重复触发去抖
+    trigger_debouncer: Mutex<TriggerDebouncer>,
+    /// 正在进行的翻译
+    active_translation: Arc<Mutex<Option<ActiveSlot>>>,
+    /// 下一次翻译的编号
+    next_translation_id: AtomicU64,
+    /// 最近一次注册热键的结果
+    hotkey_registrations: Mutex<Vec<HotkeyRegistration>>,
+    /// 最近一次热键翻译的结果
+    last_translation: Mutex<Option<LastTranslation>>,
+    /// 最近一次热键翻译检测到的原文语言，互换语言时优先切换为此语言
+    last_source_lang: Mutex<Option<&'static str>>,
+    /// 最近的警告和错误日志
+    pub error_log: ErrorLog,
     /// 配置文件路径
     config_path: PathBuf,
+    /// 保存 API Key 的系统凭据存储，当前平台不支持时 API Key 保存在配置文件中
+    secrets: Option<Box<dyn SecretStore>>,
+    /// 凭据存储中各账户 API Key 的已知值，未知时为 `None`
+    stored_api_keys: Mutex<HashMap<String, Option<String>>>,
 }
 
 impl AppState {
@@ -41,11 +180,56 @@ impl AppState {
         let config_path = config_dir.join("QuickTransType").join("config.json");
 
         // 加载或创建配置
-        let config = Self::load_config(&config_path).await;
+        let mut config = Self::load_config(&config_path).await;
+        let secrets = crate::secrets::system_store();
+        let mut stored_api_keys = HashMap::new();
+        // 配置文件中仍是明文 API Key 时需要移入凭据存储
+        let mut plaintext_api_key = false;
+        if let Some(store) = &secrets {
+            for profile in &mut config.profiles {
+                let account = crate::secrets::api_key_account(&profile.name);
+                let file_value = std::mem::take(&mut profile.llm.api_key);
+                plaintext_api_key |=
+                    !file_value.is_empty() && file_value != crate::secrets::STORED_API_KEY;
+                let (api_key, known) =
+                    crate::secrets::load_api_key(store.as_ref(), &account, &file_value);
+                profile.llm.api_key = api_key;
+                stored_api_keys.insert(account, known);
+            }
+            config.resolve_active_profile();
+        }
         debug!("Config loaded: {:?}", config.llm.model);
 
         // 初始化数据库
-        let database = Database::new().await?;
+        let database = Database::new(config.database_path.as_deref()).await?;
+        if config.history_encryption {
+            // 密钥无法加载时仍然启动，但在恢复密钥前不保存任何翻译文本，避免以明文写入
+            let enabled = match load_history_cipher(&database, secrets.as_deref()).await {
+                Ok(cipher) => database.enable_encryption(cipher).await.map(|_| ()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = enabled {
+                error!("History encryption unavailable: {}", e);
+                database.mark_encryption_unavailable(e.to_string());
+            }
+        }
+        // 启动时将过期的性能指标汇总后清理
+        if let Err(e) = database.cleanup_metrics().await {
+            warn!("Failed to clean up old metrics: {}", e);
+        }
+        // 启动时按保留天数和条数限制清理历史，长时间未使用时不会保留过期的记录
+        match database
+            .apply_history_retention(config.history_limit, config.history_retention_days)
+            .await
+        {
+            Ok(0) => {}
+            Ok(deleted) => info!("Removed {} expired history records", deleted),
+            Err(e) => warn!("Failed to clean up history: {}", e),
+        }
+        // 备份失败不影响启动
+        if let Err(e) = database.backup_if_due().await {
+            warn!("Failed to back up database: {}", e);
+        }
         info!("Database initialized");
 
         // 初始化 LLM 客户端
@@ -58,56 +242,211 @@ impl AppState {
 
         // 初始化文本处理器
         let text_handler = TextHandler::new()?;
+        text_handler.set_timing(config.effective_timing());
         debug!("Text handler created");
 
-        Ok(Self {
+        let state = Self {
             config: Arc::new(RwLock::new(config)),
             database: Arc::new(database),
             llm_client: Arc::new(llm_client),
             hotkey_manager: Arc::new(hotkey_manager),
             text_handler: Arc::new(text_handler),
             is_enabled: Arc::new(RwLock::new(true)),
+            enabled_flag: Arc::new(AtomicBool::new(true)),
+            key_listener_error: Arc::new(RwLock::new(None)),
+            key_listener: Mutex::new(None),
+            next_listener_id: AtomicU64::new(0),
+            listener_status: watch::Sender::new(ListenerStatus::Stopped),
+            listener_backoff: Mutex::new(RestartBackoff::new()),
+            settings_focused: AtomicBool::new(false),
+            hotkeys_suppressed: Arc::new(AtomicBool::new(false)),
+            trigger_debouncer: Mutex::new(TriggerDebouncer::new()),
+            active_translation: Arc::new(Mutex::new(None)),
+            next_translation_id: AtomicU64::new(0),
+            hotkey_registrations: Mutex::new(Vec::new()),
+            last_translation: Mutex::new(None),
+            last_source_lang: Mutex::new(None),
+            error_log: ErrorLog::global().clone(),
             config_path,
-        })
+            secrets,
+            stored_api_keys: Mutex::new(stored_api_keys),
+        };
+        if plaintext_api_key {
+            state.migrate_api_key_to_store().await;
+        }
+        Ok(state)
+    }
+
+    /// 将配置文件中的明文 API Key 移入凭据存储，并从配置文件中清除
+    async fn migrate_api_key_to_store(&self) {
+        let config = self.config.read().await;
+        match self.write_config_file(&config) {
+            Ok(()) => info!("API key moved to the system credential store"),
+            Err(e) => warn!("Failed to move API key to the credential store: {}", e),
+        }
     }
 
     /// 加载配置文件
-    async fn load_config(path: &PathBuf) -> AppConfig {
-        if path.exists() {
-            match std::fs::read_to_string(path) {
-                Ok(content) => {
-                    match serde_json::from_str(&content) {
-                        Ok(config) => return config,
-                        Err(e) => {
-                            tracing::warn!("Failed to parse config: {}, using defaults", e);
-                        }
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to read config: {}, using defaults", e);
-                }
+    ///
+    /// 配置文件不存在时尝试迁移旧版本的配置；无法解析时先尝试按旧格式迁移，
+    /// 失败则将文件移到一旁再使用默认配置，避免下次保存时覆盖用户的配置。
+    async fn load_config(path: &Path) -> AppConfig {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                tracing::warn!("Failed to read config: {}, using defaults", e);
+                return AppConfig::default();
+            }
+        };
+
+        if let Some(content) = &content {
+            match AppConfig::from_json(content) {
+                Ok(config) => return config,
+                Err(e) => tracing::warn!("Failed to parse config: {}", e),
+            }
+            if let Some(config) = Self::migrate_legacy_config(&[path.to_path_buf()], path) {
+                return config;
+            }
+            match crate::config::stash_broken_config(path) {
+                Ok(stashed) => tracing::warn!("Unreadable config moved to {:?}", stashed),
+                Err(e) => tracing::warn!("Failed to move unreadable config aside: {}", e),
+            }
+        }
+
+        let legacy = path
+            .parent()
+            .and_then(Path::parent)
+            .map(|config_dir| crate::config::legacy_config_candidates(config_dir, path))
+            .unwrap_or_default();
+        Self::migrate_legacy_config(&legacy, path).unwrap_or_default()
+    }
+
+    /// 迁移 `sources` 中第一个能识别的旧版本配置，没有可迁移的配置时返回 `None`
+    fn migrate_legacy_config(sources: &[PathBuf], path: &Path) -> Option<AppConfig> {
+        match crate::config::migrate_legacy_config(sources, path) {
+            Ok(Some(migration)) => {
+                info!(
+                    "Migrated legacy config from {:?}, original kept at {:?}",
+                    migration.source, migration.backup
+                );
+                Some(migration.config)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                tracing::warn!("Failed to migrate legacy config: {}", e);
+                None
             }
         }
-        AppConfig::default()
     }
 
     /// 保存配置文件
     pub async fn save_config(&self, config: &AppConfig) -> Result<()> {
+        let mut config = config.clone();
+        config.sync_active_profile();
+        self.write_config_file(&config)?;
+        self.text_handler.set_timing(config.effective_timing());
+
+        // 更新内存中的配置
+        *self.config.write().await = config;
+        self.refresh_hotkey_suppression().await;
+
+        info!("Config saved to {:?}", self.config_path);
+        Ok(())
+    }
+
+    /// 在配置写锁内修改并保存配置，返回修改后的配置
+    ///
+    /// 修改函数返回错误时配置保持不变。与先读取再 `save_config` 不同，
+    /// 并发的修改不会互相覆盖。
+    pub async fn update_config<F, E>(&self, update: F) -> std::result::Result<AppConfig, E>
+    where
+        F: FnOnce(&mut AppConfig) -> std::result::Result<(), E>,
+        E: From<AppError>,
+    {
+        let mut config = self.config.write().await;
+        let mut updated = config.clone();
+        update(&mut updated)?;
+        updated.sync_active_profile();
+        self.write_config_file(&updated)?;
+        self.text_handler.set_timing(updated.effective_timing());
+        *config = updated.clone();
+        drop(config);
+        self.refresh_hotkey_suppression().await;
+
+        info!("Config updated at {:?}", self.config_path);
+        Ok(updated)
+    }
+
+    /// 在配置文件旁边保存一份带时间戳的备份，配置文件不存在时返回 `None`
+    pub fn backup_config_file(&self) -> Result<Option<PathBuf>> {
+        if !self.config_path.exists() {
+            return Ok(None);
+        }
+        let name = format!(
+            "config-{}.json",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        );
+        let backup_path = self.config_path.with_file_name(name);
+        std::fs::copy(&self.config_path, &backup_path)?;
+        info!("Config backed up to {:?}", backup_path);
+        Ok(Some(backup_path))
+    }
+
+    /// 将配置写入配置文件
+    ///
+    /// 有凭据存储时 API Key 写入凭据存储，配置文件中只保存占位文本。
+    fn write_config_file(&self, config: &AppConfig) -> Result<()> {
         // 确保目录存在
         if let Some(parent) = self.config_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
-        let content = serde_json::to_string_pretty(config)?;
+        let content = match &self.secrets {
+            Some(store) => {
+                let mut saved = config.clone();
+                let mut known = self
+                    .stored_api_keys
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                for profile in &mut saved.profiles {
+                    let account = crate::secrets::api_key_account(&profile.name);
+                    let stored = known.entry(account.clone()).or_default();
+                    profile.llm.api_key = crate::secrets::store_api_key(
+                        store.as_ref(),
+                        &account,
+                        stored,
+                        &profile.llm.api_key,
+                    )?;
+                }
+                // 删除已删除方案的 API Key
+                let accounts: Vec<String> = saved
+                    .profiles
+                    .iter()
+                    .map(|profile| crate::secrets::api_key_account(&profile.name))
+                    .collect();
+                known.retain(|account, _| {
+                    if accounts.contains(account) {
+                        return true;
+                    }
+                    if let Err(e) = store.delete(account) {
+                        warn!("Failed to delete API key of removed profile: {}", e);
+                    }
+                    false
+                });
+                saved.to_file_json()?
+            }
+            None => config.to_file_json()?,
+        };
         std::fs::write(&self.config_path, content)?;
-
-        // 更新内存中的配置
-        *self.config.write().await = config.clone();
-
-        info!("Config saved to {:?}", self.config_path);
         Ok(())
     }
 
+    /// 配置文件路径
+    pub fn config_path(&self) -> &Path {
+        &self.config_path
+    }
+
     /// 获取当前配置
     pub async fn get_config(&self) -> AppConfig {
         self.config.read().await.clone()
@@ -116,16 +455,294 @@ impl AppState {
     /// 设置启用状态
     pub async fn set_enabled(&self, enabled: bool) {
         *self.is_enabled.write().await = enabled;
+        self.enabled_flag.store(enabled, Ordering::SeqCst);
         info!("Translation monitoring {}", if enabled { "enabled" } else { "disabled" });
     }
 
+    /// 切换启用状态，返回切换后的状态
+    pub async fn toggle_enabled(&self) -> bool {
+        let mut is_enabled = self.is_enabled.write().await;
+        *is_enabled = !*is_enabled;
+        self.enabled_flag.store(*is_enabled, Ordering::SeqCst);
+        *is_enabled
+    }
+
     /// 检查是否启用
     pub async fn is_enabled(&self) -> bool {
         *self.is_enabled.read().await
     }
 
+    /// 获取启用状态标志，键盘监听线程据此在暂停期间跳过按键处理
+    pub fn enabled_flag(&self) -> Arc<AtomicBool> {
+        self.enabled_flag.clone()
+    }
+
+    /// 记录键盘监听器的启动失败信息
+    pub async fn set_key_listener_error(&self, error: Option<String>) {
+        *self.key_listener_error.write().await = error;
+    }
+
+    /// 获取键盘监听器的启动失败信息
+    pub async fn key_listener_error(&self) -> Option<String> {
+        self.key_listener_error.read().await.clone()
+    }
+
+    /// 停止仍在运行的热键监听会话，调用 `start` 启动新会话并登记，返回会话编号和 `start` 的结果
+    ///
+    /// 启动和登记在同一把锁内完成，其他线程不会在新会话登记前看到空位而再启动一个 rdev 监听。
+    /// 正在录制热键时不启动，返回 `None`，录制结束后会重新启动。
+    pub fn start_key_listener<T>(
+        &self,
+        start: impl FnOnce() -> (KeyListenerHandle, T),
+    ) -> Option<(u64, T)> {
+        self.start_listener_session(false, start)
+    }
+
+    /// 停止热键监听会话，调用 `start` 启动热键录制会话并登记，已在录制时返回 `None`
+    pub fn start_capture_listener<T>(
+        &self,
+        start: impl FnOnce() -> (KeyListenerHandle, T),
+    ) -> Option<(u64, T)> {
+        self.start_listener_session(true, start)
+    }
+
+    fn start_listener_session<T>(
+        &self,
+        capture: bool,
+        start: impl FnOnce() -> (KeyListenerHandle, T),
+    ) -> Option<(u64, T)> {
+        let mut slot = lock_listener(&self.key_listener);
+        if slot.as_ref().is_some_and(|active| active.capture) {
+            return None;
+        }
+        if let Some(previous) = slot.take() {
+            previous.handle.stop();
+        }
+
+        let (handle, started) = start();
+        let id = self.next_listener_id.fetch_add(1, Ordering::SeqCst);
+        *slot = Some(ListenerSlot {
+            id,
+            handle,
+            capture,
+        });
+        Some((id, started))
+    }
+
+    /// 编号为 `id` 的会话是否仍是登记中的会话，已被替换或停止的会话不再更新监听状态
+    pub fn is_current_key_listener(&self, id: u64) -> bool {
+        lock_listener(&self.key_listener)
+            .as_ref()
+            .is_some_and(|active| active.id == id)
+    }
+
+    /// 停止并注销编号为 `id` 的会话，返回它是否仍是登记中的会话；会话已被替换时不做任何事
+    pub fn release_key_listener(&self, id: u64) -> bool {
+        let mut slot = lock_listener(&self.key_listener);
+        if slot.as_ref().is_some_and(|active| active.id == id) {
+            if let Some(active) = slot.take() {
+                active.handle.stop();
+                return true;
+            }
+        }
+        false
+    }
+
+    /// 停止热键监听会话，热键录制会话不受影响
+    pub fn stop_key_listener(&self) {
+        let mut slot = lock_listener(&self.key_listener);
+        if slot.as_ref().is_some_and(|active| !active.capture) {
+            if let Some(active) = slot.take() {
+                active.handle.stop();
+            }
+        }
+    }
+
+    /// 停止正在进行的热键录制，返回是否有录制被停止
+    pub fn stop_hotkey_capture(&self) -> bool {
+        let mut slot = lock_listener(&self.key_listener);
+        if slot.as_ref().is_some_and(|active| active.capture) {
+            if let Some(active) = slot.take() {
+                active.handle.stop();
+                return true;
+            }
+        }
+        false
+    }
+
+    /// 退出前停止所有键盘监听会话和正在进行的热键录制，让监听线程结束
+    pub fn shutdown_key_listeners(&self) {
+        if let Some(active) = lock_listener(&self.key_listener).take() {
+            active.handle.stop();
+        }
+        self.set_listener_status(ListenerStatus::Stopped);
+    }
+
+    /// 更新键盘监听器的运行状态
+    pub fn set_listener_status(&self, status: ListenerStatus) {
+        self.listener_status.send_if_modified(|current| {
+            if *current == status {
+                return false;
+            }
+            debug!("Key listener status: {:?}", status);
+            *current = status;
+            true
+        });
+    }
+
+    /// 获取键盘监听器的运行状态
+    pub fn listener_status(&self) -> ListenerStatus {
+        self.listener_status.borrow().clone()
+    }
+
+    /// 订阅键盘监听器的状态变化
+    pub fn subscribe_listener_status(&self) -> watch::Receiver<ListenerStatus> {
+        self.listener_status.subscribe()
+    }
+
+    /// 键盘监听器自动重启的退避状态
+    pub fn listener_backoff(&self) -> std::sync::MutexGuard<'_, RestartBackoff> {
+        self.listener_backoff
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// 记录热键注册结果，替换上一次的结果
+    pub fn set_hotkey_registrations(&self, registrations: Vec<HotkeyRegistration>) {
+        *self
+            .hotkey_registrations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = registrations;
+    }
+
+    /// 最近一次注册热键的结果
+    pub fn hotkey_registrations(&self) -> Vec<HotkeyRegistration> {
+        self.hotkey_registrations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// 记录最近一次热键翻译的结果
+    pub fn set_last_translation(&self, translation: LastTranslation) {
+        *self
+            .last_translation
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(translation);
+    }
+
+    /// 本次运行中最近一次热键翻译的结果
+    pub fn last_translation(&self) -> Option<LastTranslation> {
+        self.last_translation
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// 记录热键翻译检测到的原文语言
+    pub fn set_last_source_lang(&self, code: &'static str) {
+        *self
+            .last_source_lang
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(code);
+    }
+
+    /// 本次运行中最近一次检测到的原文语言
+    pub fn last_source_lang(&self) -> Option<&'static str> {
+        *self
+            .last_source_lang
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// 记录一次热键触发，返回是否应当响应（不在上次触发的去抖窗口内）
+    pub fn accept_trigger(&self, mode: &str, now: Instant, window: Duration) -> bool {
+        self.trigger_debouncer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .accept(mode, now, window)
+    }
+
+    /// 登记新开始的翻译，返回它的取消令牌
+    ///
+    /// 同一时间只记录最近开始的一次翻译。
+    pub fn begin_translation(&self, mode: &str, stream: bool) -> TranslationTicket {
+        let id = self.next_translation_id.fetch_add(1, Ordering::SeqCst);
+        let cancel = Arc::new(watch::Sender::new(false));
+        *lock_slot(&self.active_translation) = Some(ActiveSlot {
+            id,
+            info: ActiveTranslation {
+                mode: mode.to_string(),
+                stream,
+                started_at: chrono::Utc::now().timestamp(),
+            },
+            cancel: cancel.clone(),
+        });
+        TranslationTicket {
+            id,
+            cancel,
+            slot: self.active_translation.clone(),
+        }
+    }
+
+    /// 取消正在进行的翻译，返回被取消的翻译，没有时返回 `None`
+    pub fn cancel_translation(&self) -> Option<ActiveTranslation> {
+        let slot = lock_slot(&self.active_translation).take()?;
+        slot.cancel.send_replace(true);
+        Some(slot.info)
+    }
+
+    /// 是否正在录制热键
+    pub fn is_capturing_hotkey(&self) -> bool {
+        lock_listener(&self.key_listener)
+            .as_ref()
+            .is_some_and(|active| active.capture)
+    }
+
+    /// 记录设置窗口的焦点状态，并更新热键暂停标志
+    pub async fn set_settings_focused(&self, focused: bool) {
+        self.settings_focused.store(focused, Ordering::SeqCst);
+        self.refresh_hotkey_suppression().await;
+    }
+
+    /// 根据设置窗口焦点和配置重新计算是否暂停热键响应
+    async fn refresh_hotkey_suppression(&self) {
+        let active_in_settings = self.config.read().await.hotkey.active_in_settings;
+        let suppressed = self.settings_focused.load(Ordering::SeqCst) && !active_in_settings;
+        if self.hotkeys_suppressed.swap(suppressed, Ordering::SeqCst) != suppressed {
+            debug!(
+                "Hotkeys {} while settings window is focused",
+                if suppressed { "suppressed" } else { "resumed" }
+            );
+        }
+    }
+
+    /// 是否暂停热键响应
+    pub fn hotkeys_suppressed(&self) -> bool {
+        self.hotkeys_suppressed.load(Ordering::SeqCst)
+    }
+
+    /// 热键暂停标志，交给键盘监听线程使用
+    pub fn hotkey_suppression_flag(&self) -> Arc<AtomicBool> {
+        self.hotkeys_suppressed.clone()
+    }
+
     /// 获取 LLM 客户端
     pub async fn get_llm_client(&self) -> Arc<LLMClient> {
         self.llm_client.clone()
     }
+
+    /// 加载翻译历史加密密钥
+    pub async fn history_cipher(&self) -> Result<FieldCipher> {
+        load_history_cipher(&self.database, self.secrets.as_deref()).await
+    }
+}
+
+/// 加载翻译历史加密密钥，翻译历史中已有加密记录时不生成新密钥
+async fn load_history_cipher(
+    database: &Database,
+    secrets: Option<&dyn SecretStore>,
+) -> Result<FieldCipher> {
+    let allow_create = !database.has_encrypted_history().await?;
+    FieldCipher::load_or_create(secrets, &FieldCipher::default_key_path()?, allow_create)
 }
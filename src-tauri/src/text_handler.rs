@@ -5,6 +5,7 @@
 //! - macOS: 使用 AppleScript (osascript) 模拟键盘操作
 //! - TODO:Windows: 使用 enigo 库模拟键盘操作
 
+use crate::config::{ClipboardAction, ClipboardConfig, ExcludedApp, TimingConfig};
 use crate::error::{AppError, Result};
 use arboard::Clipboard;
 use std::sync::Arc;
@@ -24,12 +25,15 @@ const CLIPBOARD_MAX_RETRIES: u32 = 3;
 /// 剪贴板重试间隔（毫秒）
 const CLIPBOARD_RETRY_DELAY_MS: u64 = 50;
 
-/// 文本处理器
+/// 文本处理器，克隆后共享剪贴板备份和互斥锁
+#[derive(Clone)]
 pub struct TextHandler {
     /// 剪贴板备份（用于错误恢复）
     clipboard_backup: Arc<RwLock<Option<String>>>,
     /// 剪贴板操作互斥锁，确保剪贴板操作的原子性
     clipboard_mutex: Arc<Mutex<()>>,
+    /// 模拟按键和剪贴板操作的等待时间，保存配置时更新
+    timing: Arc<std::sync::Mutex<TimingConfig>>,
 }
 
 impl TextHandler {
@@ -38,9 +42,20 @@ impl TextHandler {
         Ok(Self {
             clipboard_backup: Arc::new(RwLock::new(None)),
             clipboard_mutex: Arc::new(Mutex::new(())),
+            timing: Arc::new(std::sync::Mutex::new(TimingConfig::default())),
         })
     }
 
+    /// 更新等待时间，下一次操作开始生效
+    pub fn set_timing(&self, timing: TimingConfig) {
+        *self.timing.lock().unwrap() = timing;
+    }
+
+    /// 当前的等待时间
+    pub fn timing(&self) -> TimingConfig {
+        *self.timing.lock().unwrap()
+    }
+
     /// 选中模式 - 获取选中的文本
     /// 模拟 Cmd+C 复制选中文本，然后返回剪贴板内容
     pub async fn translate_selected(&self) -> Result<String> {
@@ -56,14 +71,14 @@ impl TextHandler {
 
         // 清空剪贴板以便检测复制是否成功
         self.set_clipboard_internal("").await.ok();
-        sleep(Duration::from_millis(50)).await;
+        sleep(self.timing().clipboard_settle()).await;
 
         // 模拟 Cmd+C 复制选中文本
         self.copy().await?;
 
         // 等待剪贴板更新，使用重试机制
         let text = self
-            .wait_for_clipboard_change("", CLIPBOARD_MAX_RETRIES)
+            .wait_for_clipboard_change("", self.timing().clipboard_retries)
             .await?;
 
         // 验证剪贴板内容是否已更新（非空且与备份不同）
@@ -101,20 +116,20 @@ impl TextHandler {
 
         // 清空剪贴板，用于检测复制是否成功
         self.set_clipboard_internal("").await.ok();
-        sleep(Duration::from_millis(50)).await;
+        sleep(self.timing().clipboard_settle()).await;
 
         // 模拟 Cmd+A 全选
         self.select_all().await?;
 
         // 等待全选操作完成（增加延迟）
-        sleep(Duration::from_millis(150)).await;
+        sleep(self.timing().select_all_delay()).await;
 
         // 模拟 Cmd+C 复制
         self.copy().await?;
 
         // 等待剪贴板更新
         let text = self
-            .wait_for_clipboard_change("", CLIPBOARD_MAX_RETRIES)
+            .wait_for_clipboard_change("", self.timing().clipboard_retries)
             .await?;
 
         // 验证复制是否成功
@@ -140,7 +155,7 @@ impl TextHandler {
     ) -> Result<String> {
         for attempt in 0..max_retries {
             // 每次重试前等待
-            sleep(Duration::from_millis(100 + (attempt as u64 * 50))).await;
+            sleep(self.timing().clipboard_poll(attempt)).await;
 
             match self.get_clipboard_internal().await {
                 Ok(text) if text != exclude_value => {
@@ -166,8 +181,20 @@ impl TextHandler {
     /// 删除当前选中的文本（模拟 Delete/Backspace）
     pub async fn delete_selection(&self) -> Result<()> {
         debug!("Deleting selected text");
-        self.delete_key().await?;
-        sleep(Duration::from_millis(50)).await;
+        self.delete_key(1).await?;
+        sleep(self.timing().key_delay()).await;
+        Ok(())
+    }
+
+    /// 向前删除指定数量的字符（模拟多次 Backspace），用于清除连续按键的触发键
+    pub async fn delete_chars(&self, count: usize) -> Result<()> {
+        if count == 0 {
+            return Ok(());
+        }
+
+        debug!("Deleting {} typed characters", count);
+        self.delete_key(count).await?;
+        sleep(self.timing().key_delay()).await;
         Ok(())
     }
 
@@ -177,12 +204,14 @@ impl TextHandler {
 
         // 使用剪贴板方式输入（更可靠）
         // 将文本分块输入，避免一次性输入太多
-        for chunk in text.chars().collect::<Vec<_>>().chunks(50) {
+        let timing = self.timing();
+        let chars: Vec<char> = text.chars().collect();
+        for chunk in chars.chunks(timing.type_chunk_chars.max(1)) {
             let chunk_str: String = chunk.iter().collect();
             self.set_clipboard_internal(&chunk_str).await?;
-            sleep(Duration::from_millis(10)).await;
+            sleep(timing.type_chunk_delay()).await;
             self.paste_clipboard().await?;
-            sleep(Duration::from_millis(10)).await;
+            sleep(timing.type_chunk_delay()).await;
         }
 
         Ok(())
@@ -196,7 +225,7 @@ impl TextHandler {
 
         self.set_clipboard_internal(text).await?;
         self.paste_clipboard().await?;
-        sleep(Duration::from_millis(10)).await;
+        sleep(self.timing().type_chunk_delay()).await;
 
         Ok(())
     }
@@ -208,7 +237,7 @@ impl TextHandler {
         // 设置剪贴板内容
         self.set_clipboard_internal(text).await?;
         // 等待剪贴板设置完成
-        sleep(Duration::from_millis(50)).await;
+        sleep(self.timing().clipboard_settle()).await;
 
         // 模拟 Cmd+V 粘贴
         self.paste_clipboard().await?;
@@ -216,6 +245,14 @@ impl TextHandler {
         Ok(())
     }
 
+    /// 将文本写入剪贴板，不模拟任何按键
+    pub async fn copy_to_clipboard(&self, text: &str) -> Result<()> {
+        info!("Copying translated text to clipboard: {} chars", text.len());
+
+        let _lock = self.clipboard_mutex.lock().await;
+        self.set_clipboard_internal(text).await
+    }
+
     /// 获取剪贴板内容
     async fn get_clipboard_internal(&self) -> Result<String> {
         for attempt in 0..CLIPBOARD_MAX_RETRIES {
@@ -295,7 +332,7 @@ impl TextHandler {
             ));
         }
 
-        sleep(Duration::from_millis(50)).await;
+        sleep(self.timing().key_delay()).await;
         Ok(())
     }
 
@@ -325,7 +362,7 @@ impl TextHandler {
         .join()
         .map_err(|_| AppError::Keyboard("键盘模拟线程崩溃".to_string()))??;
 
-        sleep(Duration::from_millis(50)).await;
+        sleep(self.timing().key_delay()).await;
         Ok(())
     }
 
@@ -350,7 +387,7 @@ impl TextHandler {
             ));
         }
 
-        sleep(Duration::from_millis(50)).await;
+        sleep(self.timing().key_delay()).await;
         Ok(())
     }
 
@@ -380,7 +417,7 @@ impl TextHandler {
         .join()
         .map_err(|_| AppError::Keyboard("键盘模拟线程崩溃".to_string()))??;
 
-        sleep(Duration::from_millis(50)).await;
+        sleep(self.timing().key_delay()).await;
         Ok(())
     }
 
@@ -405,7 +442,7 @@ impl TextHandler {
             ));
         }
 
-        sleep(Duration::from_millis(50)).await;
+        sleep(self.timing().key_delay()).await;
         Ok(())
     }
 
@@ -435,20 +472,24 @@ impl TextHandler {
         .join()
         .map_err(|_| AppError::Keyboard("键盘模拟线程崩溃".to_string()))??;
 
-        sleep(Duration::from_millis(50)).await;
+        sleep(self.timing().key_delay()).await;
         Ok(())
     }
 
     /// 模拟删除键 (Backspace) - macOS
     #[cfg(target_os = "macos")]
-    async fn delete_key(&self) -> Result<()> {
-        debug!("Simulating Delete via AppleScript");
+    async fn delete_key(&self, count: usize) -> Result<()> {
+        debug!("Simulating Delete x{} via AppleScript", count);
 
-        let script = r#"tell application "System Events" to key code 51"#; // 51 = Backspace
+        // 51 = Backspace
+        let script = format!(
+            "tell application \"System Events\"\nrepeat {} times\nkey code 51\nend repeat\nend tell",
+            count
+        );
 
         let output = Command::new("osascript")
             .arg("-e")
-            .arg(script)
+            .arg(&script)
             .output()
             .map_err(|e| AppError::Keyboard(format!("无法执行 osascript: {}", e)))?;
 
@@ -465,16 +506,18 @@ impl TextHandler {
 
     /// 模拟删除键 (Backspace) - Windows
     #[cfg(target_os = "windows")]
-    async fn delete_key(&self) -> Result<()> {
-        debug!("Simulating Delete via enigo");
+    async fn delete_key(&self, count: usize) -> Result<()> {
+        debug!("Simulating Delete x{} via enigo", count);
 
-        std::thread::spawn(|| -> Result<()> {
+        std::thread::spawn(move || -> Result<()> {
             let mut enigo = Enigo::new(&Settings::default())
                 .map_err(|e| AppError::Keyboard(format!("创建键盘模拟器失败: {}", e)))?;
 
-            enigo
-                .key(Key::Backspace, Direction::Click)
-                .map_err(|e| AppError::Keyboard(format!("按键失败: {}", e)))?;
+            for _ in 0..count {
+                enigo
+                    .key(Key::Backspace, Direction::Click)
+                    .map_err(|e| AppError::Keyboard(format!("按键失败: {}", e)))?;
+            }
 
             Ok(())
         })
@@ -489,10 +532,75 @@ impl TextHandler {
         self.clipboard_backup.read().await.clone()
     }
 
+    /// 将剪贴板恢复为翻译前备份的内容，没有备份时不做处理
+    pub async fn restore_clipboard(&self) -> Result<()> {
+        let Some(backup) = self.get_backup().await else {
+            return Ok(());
+        };
+        let _lock = self.clipboard_mutex.lock().await;
+        self.set_clipboard_internal(&backup).await
+    }
+
     /// 清除剪贴板备份
     pub async fn clear_backup(&self) {
         *self.clipboard_backup.write().await = None;
     }
+
+    /// 翻译结束后按配置处理剪贴板，优先级见 `ClipboardConfig`
+    ///
+    /// 需要定时清除时启动后台任务，到时剪贴板仍是本次翻译留下的内容才清除。
+    pub async fn finish_clipboard(
+        &self,
+        config: &ClipboardConfig,
+        to_clipboard: bool,
+        translation: &str,
+    ) -> Result<()> {
+        let action = config.action(to_clipboard);
+        let left = {
+            let _lock = self.clipboard_mutex.lock().await;
+            match action {
+                ClipboardAction::Leave => self.get_clipboard_internal().await.ok(),
+                ClipboardAction::KeepTranslation => {
+                    self.set_clipboard_internal(translation).await?;
+                    Some(translation.to_string())
+                }
+                ClipboardAction::RestoreOriginal => {
+                    if let Some(backup) = self.get_backup().await {
+                        self.set_clipboard_internal(&backup).await?;
+                    }
+                    None
+                }
+            }
+        };
+
+        if let (Some(delay), Some(text)) = (config.clear_after(action), left) {
+            if !text.is_empty() {
+                self.schedule_clear(text, delay);
+            }
+        }
+        Ok(())
+    }
+
+    /// `delay` 后剪贴板仍为 `text` 时清空剪贴板
+    fn schedule_clear(&self, text: String, delay: Duration) {
+        debug!("Clipboard will be cleared in {}s", delay.as_secs());
+        let handler = self.clone();
+        tokio::spawn(async move {
+            sleep(delay).await;
+            let _lock = handler.clipboard_mutex.lock().await;
+            match handler.get_clipboard_internal().await {
+                Ok(current) if current == text => {
+                    if let Err(e) = handler.set_clipboard_internal("").await {
+                        warn!("Failed to clear clipboard: {}", e);
+                    } else {
+                        info!("Cleared translation from clipboard");
+                    }
+                }
+                Ok(_) => debug!("Clipboard changed since translation, not clearing"),
+                Err(e) => warn!("Failed to read clipboard before clearing: {}", e),
+            }
+        });
+    }
 }
 
 impl Default for TextHandler {
@@ -501,6 +609,160 @@ impl Default for TextHandler {
     }
 }
 
+/// 获取当前前台应用的 bundle id，检测失败时返回 `None`
+#[cfg(target_os = "macos")]
+pub fn frontmost_app() -> Option<String> {
+    let script = r#"tell application "System Events" to get bundle identifier of first application process whose frontmost is true"#;
+
+    let output = match Command::new("osascript").arg("-e").arg(script).output() {
+        Ok(output) => output,
+        Err(e) => {
+            debug!("Failed to run osascript for frontmost app: {}", e);
+            return None;
+        }
+    };
+    if !output.status.success() {
+        debug!(
+            "Failed to get frontmost app: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return None;
+    }
+
+    let bundle_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!bundle_id.is_empty() && bundle_id != "missing value").then_some(bundle_id)
+}
+
+/// 获取当前前台应用，其他平台暂不支持
+#[cfg(not(target_os = "macos"))]
+pub fn frontmost_app() -> Option<String> {
+    None
+}
+
+/// 列出正在运行的应用，用于选择排除应用，按名称排序
+///
+/// 通过 NSWorkspace 获取，只包含出现在 Dock 中的普通应用。
+#[cfg(target_os = "macos")]
+pub fn running_apps() -> Result<Vec<ExcludedApp>> {
+    use cocoa::base::{id, nil};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    // NSApplicationActivationPolicyRegular
+    const ACTIVATION_POLICY_REGULAR: isize = 0;
+
+    unsafe fn to_string(string: id) -> Option<String> {
+        if string == nil {
+            return None;
+        }
+        let chars: *const c_char = msg_send![string, UTF8String];
+        (!chars.is_null()).then(|| CStr::from_ptr(chars).to_string_lossy().into_owned())
+    }
+
+    let mut apps = Vec::new();
+    unsafe {
+        let pool: id = msg_send![class!(NSAutoreleasePool), new];
+        let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+        let running: id = msg_send![workspace, runningApplications];
+        let count: usize = msg_send![running, count];
+        for i in 0..count {
+            let app: id = msg_send![running, objectAtIndex: i];
+            let policy: isize = msg_send![app, activationPolicy];
+            if policy != ACTIVATION_POLICY_REGULAR {
+                continue;
+            }
+            let Some(identifier) = to_string(msg_send![app, bundleIdentifier]) else {
+                continue;
+            };
+            let name =
+                to_string(msg_send![app, localizedName]).unwrap_or_else(|| identifier.clone());
+            apps.push(ExcludedApp { identifier, name });
+        }
+        let _: () = msg_send![pool, drain];
+    }
+
+    sort_running_apps(&mut apps);
+    Ok(apps)
+}
+
+/// 列出正在运行的应用，用于选择排除应用，按名称排序
+///
+/// 通过进程快照获取，标识为进程名，同名进程只保留一个。
+#[cfg(target_os = "windows")]
+pub fn running_apps() -> Result<Vec<ExcludedApp>> {
+    use std::ffi::c_void;
+
+    const TH32CS_SNAPPROCESS: u32 = 0x0000_0002;
+    const INVALID_HANDLE_VALUE: *mut c_void = -1isize as *mut c_void;
+    const MAX_PATH: usize = 260;
+
+    #[repr(C)]
+    struct ProcessEntry32W {
+        size: u32,
+        usage: u32,
+        process_id: u32,
+        default_heap_id: usize,
+        module_id: u32,
+        threads: u32,
+        parent_process_id: u32,
+        priority_class_base: i32,
+        flags: u32,
+        exe_file: [u16; MAX_PATH],
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateToolhelp32Snapshot(flags: u32, process_id: u32) -> *mut c_void;
+        fn Process32FirstW(snapshot: *mut c_void, entry: *mut ProcessEntry32W) -> i32;
+        fn Process32NextW(snapshot: *mut c_void, entry: *mut ProcessEntry32W) -> i32;
+        fn CloseHandle(handle: *mut c_void) -> i32;
+    }
+
+    let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) };
+    if snapshot == INVALID_HANDLE_VALUE {
+        return Err(AppError::Io(std::io::Error::last_os_error()));
+    }
+
+    let mut apps: Vec<ExcludedApp> = Vec::new();
+    let mut entry: ProcessEntry32W = unsafe { std::mem::zeroed() };
+    entry.size = std::mem::size_of::<ProcessEntry32W>() as u32;
+    let mut found = unsafe { Process32FirstW(snapshot, &mut entry) } != 0;
+    while found {
+        let len = entry
+            .exe_file
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(MAX_PATH);
+        let identifier = String::from_utf16_lossy(&entry.exe_file[..len]);
+        if identifier.to_ascii_lowercase().ends_with(".exe")
+            && !apps
+                .iter()
+                .any(|app| app.identifier.eq_ignore_ascii_case(&identifier))
+        {
+            let name = identifier[..identifier.len() - 4].to_string();
+            apps.push(ExcludedApp { identifier, name });
+        }
+        found = unsafe { Process32NextW(snapshot, &mut entry) } != 0;
+    }
+    unsafe { CloseHandle(snapshot) };
+
+    sort_running_apps(&mut apps);
+    Ok(apps)
+}
+
+/// 列出正在运行的应用，其他平台暂不支持
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn running_apps() -> Result<Vec<ExcludedApp>> {
+    Ok(Vec::new())
+}
+
+/// 按名称排序，名称相同时按标识排序
+#[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+fn sort_running_apps(apps: &mut [ExcludedApp]) {
+    apps.sort_by_cached_key(|app| (app.name.to_lowercase(), app.identifier.to_lowercase()));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
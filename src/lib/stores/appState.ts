@@ -22,7 +22,25 @@ export interface LLMConfig {
   top_p: number;
   system_prompt: string;
   user_prompt_template: string;
-  stream_mode: boolean;
+  stream_selected: boolean;
+  stream_full: boolean;
+  // 超过该字符数的原文不使用流式输出，为空表示不限制
+  stream_min_chars?: number | null;
+  pricing: Record<string, ModelPrice>;
+}
+
+export interface NamedLLMConfig extends LLMConfig {
+  name: string;
+}
+
+export interface ProfileList {
+  active_profile: string;
+  profiles: NamedLLMConfig[];
+}
+
+export interface ModelPrice {
+  input_per_million: number;
+  output_per_million: number;
 }
 
 export interface HotkeyConfig {
@@ -33,13 +51,105 @@ export interface HotkeyConfig {
 export interface LanguageConfig {
   current_target: string;
   favorite_languages: Language[];
+  previous_target?: string | null;
+  prompt_overrides?: Record<string, PromptOverride>;
+  language_pairs?: LanguagePair[];
+}
+
+// 检测到原文为 source 时本次翻译为 target，按主语言代码匹配，第一条匹配的规则生效
+export interface LanguagePair {
+  source: string;
+  target: string;
+}
+
+export interface PromptOverride {
+  system_prompt?: string | null;
+  user_prompt_template?: string | null;
 }
 
 export interface AppConfig {
+  config_version?: number;
   llm: LLMConfig;
+  profiles: NamedLLMConfig[];
+  active_profile: string;
   hotkey: HotkeyConfig;
   language: LanguageConfig;
   history_limit: number;
+  history_dedup_secs: number;
+  history_mode: "full" | "metrics_only" | "off";
+  history_encryption: boolean;
+  history_retention_days: number | null;
+  database_path: string | null;
+  reuse_history: boolean;
+  log_level?: "error" | "warn" | "info" | "debug" | "trace";
+  clipboard?: ClipboardConfig;
+  // 由 get_config 按系统登录项的实际状态返回
+  launch_at_login?: boolean;
+  excluded_apps?: ExcludedApp[];
+  performance_preset?: PerformancePreset;
+  timing?: TimingConfig;
+  redaction_mode?: RedactionMode;
+  // 不能发送给 LLM 的内容，每项为一个正则表达式
+  redaction_patterns?: string[];
+}
+
+// 原文匹配 redaction_patterns 时的处理：abort 取消翻译并发送 translation-blocked 事件，
+// mask 用 ⟦REDACTED⟧ 代替匹配的内容，收到译文后放回
+export type RedactionMode = "off" | "abort" | "mask";
+
+// translation-blocked 事件的内容，不包含匹配的文本
+export interface BlockedTranslation {
+  mode: string;
+  matches: number;
+}
+
+// 模拟按键和剪贴板操作的等待时间预设，custom 时使用 timing 中的设置
+export type PerformancePreset = "fast" | "balanced" | "compatible" | "custom";
+
+// 等待时间（毫秒），clipboard_poll_ms 之后每次重试增加一半
+export interface TimingConfig {
+  clipboard_settle_ms: number;
+  select_all_delay_ms: number;
+  clipboard_poll_ms: number;
+  clipboard_retries: number;
+  key_delay_ms: number;
+  type_chunk_chars: number;
+  type_chunk_delay_ms: number;
+}
+
+// 翻译结束后的剪贴板处理：翻译到剪贴板始终保留译文，restore_original 优先于
+// keep_translation_on_clipboard，clear_after_secs 只清除留在剪贴板中的译文
+export interface ClipboardConfig {
+  restore_original: boolean;
+  keep_translation_on_clipboard: boolean;
+  clear_after_secs?: number | null;
+}
+
+// 不触发翻译的应用，identifier 包含 . 时完全匹配，否则作为关键字匹配
+export interface ExcludedApp {
+  identifier: string;
+  name: string;
+}
+
+// 配置校验发现的问题，save_config 返回警告，错误时放在 CommandError.details 中
+export interface ValidationIssue {
+  field: string;
+  severity: "error" | "warning";
+  message: string;
+}
+
+// 后端命令返回的错误
+export interface CommandError {
+  code: string;
+  message: string;
+  details: string | null;
+}
+
+export function errorMessage(e: unknown): string {
+  if (typeof e === "object" && e !== null && "message" in e) {
+    return (e as CommandError).message;
+  }
+  return String(e);
 }
 
 interface AppStateData {
@@ -60,7 +170,10 @@ const defaultConfig: AppConfig = {
       "You are a professional translator. Maintain the original formatting of the text.",
     user_prompt_template:
       "将下列文本翻译为{target_language}，保持原有格式：{text}",
-    stream_mode: true,
+    stream_selected: true,
+    stream_full: true,
+    stream_min_chars: null,
+    pricing: {},
   },
   hotkey: {
     selected_mode: {
@@ -86,6 +199,12 @@ const defaultConfig: AppConfig = {
     ],
   },
   history_limit: 500,
+  history_dedup_secs: 60,
+  history_mode: "full",
+  history_encryption: false,
+  history_retention_days: null,
+  database_path: null,
+  reuse_history: false,
 };
 
 function createAppState() {
@@ -121,7 +240,7 @@ function createAppState() {
         update((state) => ({ ...state, config, isLoading: false }));
         return { success: true };
       } catch (e) {
-        const error = e as string;
+        const error = errorMessage(e);
         update((state) => ({ ...state, error, isLoading: false }));
         return { success: false, error };
       }
@@ -132,7 +251,7 @@ function createAppState() {
         const message = await invoke<string>("test_llm_connection", { config });
         return { success: true, message };
       } catch (e) {
-        return { success: false, message: e as string };
+        return { success: false, message: errorMessage(e) };
       }
     },
 
@@ -1,7 +1,11 @@
 import { mount } from "svelte";
 import App from "./App.svelte";
+import HistoryWindow from "./HistoryWindow.svelte";
 
-const app = mount(App, {
+// 独立的历史记录窗口只显示翻译历史
+const root = window.location.hash === "#history" ? HistoryWindow : App;
+
+const app = mount(root, {
   target: document.getElementById("app")!,
 });
 